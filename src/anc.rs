@@ -0,0 +1,311 @@
+//! `anc set` / `anc cycle` subcommands: switch the noise control mode from
+//! a hotkey, through the daemon or a short-lived in-process connection.
+
+use crate::bluetooth::aacp::{AACPEvent, ControlCommandIdentifiers};
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::tui::app::{AirPodsDeviceState, App, AppEvent, DeviceCommand, DeviceState};
+use std::collections::HashSet;
+use std::io;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// How long to wait for AirPods to show up; an in-process connection
+/// needs a few seconds for L2CAP setup and the handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the AirPods get to report their current mode after connecting.
+const MODE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long the AirPods get to acknowledge the new mode.
+const ACK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(clap::Subcommand)]
+pub enum AncCommand {
+    /// Switch the noise control mode
+    Set {
+        #[arg(value_enum)]
+        mode: NoiseMode,
+    },
+    /// Advance to the next mode the TUI offers (Off and Adaptive only when
+    /// available)
+    Cycle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NoiseMode {
+    Off,
+    /// Noise Cancellation
+    Nc,
+    Transparency,
+    Adaptive,
+}
+
+impl From<NoiseMode> for AirPodsNoiseControlMode {
+    fn from(mode: NoiseMode) -> Self {
+        match mode {
+            NoiseMode::Off => AirPodsNoiseControlMode::Off,
+            NoiseMode::Nc => AirPodsNoiseControlMode::NoiseCancellation,
+            NoiseMode::Transparency => AirPodsNoiseControlMode::Transparency,
+            NoiseMode::Adaptive => AirPodsNoiseControlMode::Adaptive,
+        }
+    }
+}
+
+pub async fn run(
+    app_rx: UnboundedReceiver<AppEvent>,
+    cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+    command: AncCommand,
+) -> io::Result<()> {
+    let mut app = App::new(app_rx, cmd_tx);
+    let Some((mac, mode)) = target(&mut app, command).await? else {
+        return Ok(());
+    };
+    if let Some(tx) = &app.command_tx {
+        let _ = tx.send((
+            mac.clone(),
+            DeviceCommand::ControlCommand(
+                ControlCommandIdentifiers::ListeningMode,
+                vec![mode.to_byte()],
+            ),
+        ));
+    }
+
+    // The AirPods echo the mode once applied
+    let deadline = tokio::time::Instant::now() + ACK_TIMEOUT;
+    loop {
+        let event = match tokio::time::timeout_at(deadline, app.rx.recv()).await {
+            Ok(Some(event)) => event,
+            Ok(None) | Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} did not confirm {}", mac, mode),
+                ));
+            }
+        };
+        match &event {
+            AppEvent::CommandFailed { mac: m, error } if *m == mac => {
+                return Err(io::Error::other(error.clone()));
+            }
+            _ if mode_report(&event) == Some((&mac, mode.to_byte())) => return Ok(()),
+            _ => {}
+        }
+        app.handle_event(event);
+    }
+}
+
+/// Wait for AirPods and their current mode, then pick the mode to send;
+/// None when they are already in the requested one.
+async fn target(
+    app: &mut App,
+    command: AncCommand,
+) -> io::Result<Option<(String, AirPodsNoiseControlMode)>> {
+    // MACs whose listening mode has been reported; until then the state
+    // holds a placeholder
+    let mut reported = HashSet::new();
+    let deadline = tokio::time::Instant::now() + CONNECT_TIMEOUT;
+    while airpods(app).is_none() {
+        match tokio::time::timeout_at(deadline, app.rx.recv()).await {
+            Ok(Some(event)) => apply(app, &mut reported, event),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    // Take in the rest of the connect burst (usually the mode too)
+    while let Ok(event) = app.rx.try_recv() {
+        apply(app, &mut reported, event);
+    }
+
+    let Some((mac, _)) = airpods(app) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no AirPods connected",
+        ));
+    };
+    let mac = mac.clone();
+    let deadline = tokio::time::Instant::now() + MODE_TIMEOUT;
+    while !reported.contains(&mac) {
+        match tokio::time::timeout_at(deadline, app.rx.recv()).await {
+            Ok(Some(event)) => apply(app, &mut reported, event),
+            Ok(None) | Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} did not report its noise control mode", mac),
+                ));
+            }
+        }
+    }
+    let Some((_, state)) = airpods(app).filter(|(m, _)| **m == mac) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} disconnected", mac),
+        ));
+    };
+
+    let mode = match command {
+        AncCommand::Set { mode } => {
+            let mode = AirPodsNoiseControlMode::from(mode);
+            check_supported(state, &mode)?;
+            if state.listening_mode == mode {
+                return Ok(None);
+            }
+            mode
+        }
+        AncCommand::Cycle => {
+            if !state.has_anc {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{} has no noise control", state.name),
+                ));
+            }
+            next_mode(state)
+        }
+    };
+    Ok(Some((mac, mode)))
+}
+
+fn apply(app: &mut App, reported: &mut HashSet<String>, event: AppEvent) {
+    if let Some((mac, _)) = mode_report(&event) {
+        reported.insert(mac.clone());
+    }
+    app.handle_event(event);
+}
+
+/// The MAC and mode byte if `event` is a listening-mode report.
+fn mode_report(event: &AppEvent) -> Option<(&String, u8)> {
+    match event {
+        AppEvent::AACPEvent(mac, aacp) => match &**aacp {
+            AACPEvent::ControlCommand(cmd)
+                if cmd.identifier == ControlCommandIdentifiers::ListeningMode =>
+            {
+                Some((mac, *cmd.value.first()?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn airpods(app: &App) -> Option<(&String, &AirPodsDeviceState)> {
+    match (app.selected_mac(), app.selected_device()) {
+        (Some(mac), Some(DeviceState::AirPods(s))) if !s.link_lost => Some((mac, &**s)),
+        _ => None,
+    }
+}
+
+/// The mode after the current one in the TUI's noise control list.
+fn next_mode(state: &AirPodsDeviceState) -> AirPodsNoiseControlMode {
+    let mut modes = crate::tui::ui::noise_mode_list(state.has_adaptive, state.allow_off_mode);
+    let next = modes
+        .iter()
+        .position(|m| *m == state.listening_mode)
+        .map_or(0, |i| (i + 1) % modes.len());
+    modes.swap_remove(next)
+}
+
+fn check_supported(state: &AirPodsDeviceState, mode: &AirPodsNoiseControlMode) -> io::Result<()> {
+    let unsupported = |what: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} {}", state.name, what),
+        ))
+    };
+    if !state.has_anc {
+        return unsupported("has no noise control");
+    }
+    if *mode == AirPodsNoiseControlMode::Adaptive && !state.has_adaptive {
+        return unsupported("has no Adaptive mode");
+    }
+    if *mode == AirPodsNoiseControlMode::Off && !state.allow_off_mode {
+        return unsupported("has Off disabled (enable it in the TUI settings)");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::aacp::ControlCommandStatus;
+
+    #[test]
+    fn rejects_modes_the_model_lacks() {
+        let mut s = AirPodsDeviceState::new("Pods".into());
+        assert!(check_supported(&s, &AirPodsNoiseControlMode::Transparency).is_ok());
+        let err = check_supported(&s, &AirPodsNoiseControlMode::Adaptive).unwrap_err();
+        assert_eq!(err.to_string(), "Pods has no Adaptive mode");
+        assert!(check_supported(&s, &AirPodsNoiseControlMode::Off).is_err());
+        s.has_anc = false;
+        assert!(check_supported(&s, &AirPodsNoiseControlMode::Transparency).is_err());
+        assert_eq!(AirPodsNoiseControlMode::from(NoiseMode::Nc).to_byte(), 0x02);
+    }
+
+    #[test]
+    fn cycle_skips_disabled_modes_and_wraps() {
+        let mut s = AirPodsDeviceState::new("Pods".into());
+        s.listening_mode = AirPodsNoiseControlMode::Transparency;
+        assert_eq!(next_mode(&s), AirPodsNoiseControlMode::NoiseCancellation);
+        s.listening_mode = AirPodsNoiseControlMode::NoiseCancellation;
+        assert_eq!(next_mode(&s), AirPodsNoiseControlMode::Transparency);
+
+        s.has_adaptive = true;
+        s.allow_off_mode = true;
+        s.listening_mode = AirPodsNoiseControlMode::Transparency;
+        assert_eq!(next_mode(&s), AirPodsNoiseControlMode::Adaptive);
+        s.listening_mode = AirPodsNoiseControlMode::NoiseCancellation;
+        assert_eq!(next_mode(&s), AirPodsNoiseControlMode::Off);
+        s.listening_mode = AirPodsNoiseControlMode::Off;
+        assert_eq!(next_mode(&s), AirPodsNoiseControlMode::Transparency);
+    }
+
+    fn mode_event(mac: &str, mode: AirPodsNoiseControlMode) -> AppEvent {
+        AppEvent::AACPEvent(
+            mac.into(),
+            Box::new(AACPEvent::ControlCommand(ControlCommandStatus {
+                identifier: ControlCommandIdentifiers::ListeningMode,
+                value: vec![mode.to_byte()],
+            })),
+        )
+    }
+
+    #[tokio::test]
+    async fn cycle_waits_for_the_reported_mode() {
+        const MAC: &str = "AA:BB:CC:DD:EE:FF";
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        // AirPods Pro 2: Transparency, Adaptive, Noise Cancellation
+        tx.send(AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "Pods".into(),
+            product_id: 0x2014,
+        })
+        .unwrap();
+        // The mode arrives after the connect burst; cycling from the
+        // placeholder would pick Transparency
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            tx.send(mode_event(MAC, AirPodsNoiseControlMode::Transparency))
+                .unwrap();
+            // Keep the channel open past the wait
+            tokio::time::sleep(MODE_TIMEOUT).await;
+        });
+        let (mac, mode) = target(&mut app, AncCommand::Cycle).await.unwrap().unwrap();
+        assert_eq!(mac, MAC);
+        assert_eq!(mode, AirPodsNoiseControlMode::Adaptive);
+    }
+
+    #[tokio::test]
+    async fn set_fails_without_a_reported_mode() {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        tx.send(AppEvent::DeviceConnected {
+            mac: "AA:BB:CC:DD:EE:FF".into(),
+            name: "Pods".into(),
+            product_id: 0x2014,
+        })
+        .unwrap();
+        drop(tx);
+        let command = AncCommand::Set {
+            mode: NoiseMode::Off,
+        };
+        let err = target(&mut app, command).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
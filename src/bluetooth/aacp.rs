@@ -1,6 +1,5 @@
 use crate::devices::airpods::AirPodsInformation;
 use crate::devices::enums::{DeviceData, DeviceInformation, DeviceType};
-use crate::utils::get_devices_path;
 use bluer::{
     Address, AddressType, Error, Result,
     l2cap::{Security, SecurityLevel, SeqPacket, Socket, SocketAddr},
@@ -18,6 +17,11 @@ use tokio::time::{Instant, sleep};
 const PSM: u16 = 0x1001;
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Silence after which the watchdog probes the link.
+const LINK_IDLE: Duration = Duration::from_secs(60);
+/// How long a probe may go unanswered before the link counts as dead.
+const LINK_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const WATCHDOG_TICK: Duration = Duration::from_secs(5);
 const HEADER_BYTES: [u8; 4] = [0x04, 0x00, 0x04, 0x00];
 
 pub mod opcodes {
@@ -224,7 +228,7 @@ pub enum AACPEvent {
     ConnectionLost,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AirPodsLEKeys {
     pub irk: String,
     pub enc_key: String,
@@ -246,14 +250,15 @@ pub struct AACPManagerState {
     pub airpods_mac: Option<Address>,
     /// Broadcasts the opcode of every incoming packet for strict init sequencing.
     pub opcode_tx: tokio::sync::broadcast::Sender<u8>,
+    /// When the last packet arrived, for the link watchdog.
+    last_rx: Instant,
+    /// Last battery report forwarded; repeats are dropped.
+    last_battery: Option<Vec<BatteryInfo>>,
 }
 
 impl AACPManagerState {
     fn new() -> Self {
-        let devices: HashMap<String, DeviceData> = std::fs::read_to_string(get_devices_path())
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default();
+        let devices = crate::devices::store::load();
         AACPManagerState {
             sender: None,
             control_command_status_list: Vec::new(),
@@ -266,6 +271,8 @@ impl AACPManagerState {
             devices,
             airpods_mac: None,
             opcode_tx: tokio::sync::broadcast::channel(16).0,
+            last_rx: Instant::now(),
+            last_battery: None,
         }
     }
 }
@@ -284,7 +291,9 @@ impl AACPManager {
         }
     }
 
-    pub async fn connect(&mut self, addr: Address) {
+    /// Connect to `addr`, from the adapter at `local` if given (otherwise
+    /// the kernel picks the route).
+    pub async fn connect(&mut self, addr: Address, local: Option<Address>) {
         info!("AACPManager connecting to {} on PSM {:#06X}...", addr, PSM);
         let target_sa = SocketAddr::new(addr, AddressType::BrEdr, PSM);
 
@@ -312,6 +321,13 @@ impl AACPManager {
             return;
         }
 
+        if let Some(local) = local
+            && let Err(e) = socket.bind(SocketAddr::new(local, AddressType::BrEdr, 0))
+        {
+            error!("Failed to bind L2CAP socket to {}: {}", local, e);
+            return;
+        }
+
         let seq_packet =
             match tokio::time::timeout(CONNECT_TIMEOUT, socket.connect(target_sa)).await {
                 Ok(Ok(s)) => Arc::new(s),
@@ -348,18 +364,25 @@ impl AACPManager {
         }
 
         info!("L2CAP connection established with {}", addr);
+        self.attach(seq_packet).await;
+    }
 
+    /// Run the session over an established transport: start the receive,
+    /// send and watchdog tasks and accept outgoing packets.
+    async fn attach<T: Transport>(&self, transport: Arc<T>) {
         let (tx, rx) = mpsc::channel(128);
 
         let manager_clone = self.clone();
         {
             let mut state = self.state.lock().await;
             state.sender = Some(tx);
+            state.last_rx = Instant::now();
         }
 
         let mut tasks = self.tasks.lock().await;
-        tasks.spawn(recv_thread(manager_clone, seq_packet.clone()));
-        tasks.spawn(send_thread(rx, seq_packet));
+        tasks.spawn(recv_thread(manager_clone, transport.clone()));
+        tasks.spawn(send_thread(rx, transport));
+        tasks.spawn(watchdog_thread(self.clone()));
     }
 
     /// Tear down the L2CAP session deliberately: abort the recv/send tasks
@@ -371,6 +394,12 @@ impl AACPManager {
         self.state.lock().await.sender = None;
     }
 
+    /// Time since the last packet, or None once the session is gone.
+    pub async fn idle(&self) -> Option<Duration> {
+        let state = self.state.lock().await;
+        state.sender.as_ref().map(|_| state.last_rx.elapsed())
+    }
+
     async fn send_packet(&self, data: &[u8]) -> Result<()> {
         let state = self.state.lock().await;
         if let Some(sender) = &state.sender {
@@ -495,12 +524,20 @@ impl AACPManager {
                 if let Some(p) = primary {
                     state.primary_pod = Some(p);
                 }
+                if state.last_battery.as_ref() == Some(&batteries) {
+                    debug!("Battery Info unchanged");
+                    return;
+                }
                 info!(
                     "Received Battery Info: {:?} (primary_pod={:?})",
                     batteries, state.primary_pod
                 );
+                state.last_battery = Some(batteries.clone());
                 if let Some(ref tx) = state.event_tx {
-                    let _ = tx.send(AACPEvent::BatteryInfo(batteries));
+                    let _ = tx.send(AACPEvent::BatteryInfo(batteries.clone()));
+                }
+                if remember_battery(&mut state, &batteries) {
+                    save_device(&state).await;
                 }
             }
             opcodes::CONTROL_COMMAND => {
@@ -662,13 +699,21 @@ impl AACPManager {
                     },
                 };
                 let mut state = self.state.lock().await;
-                if let Some(mac) = state.airpods_mac
-                    && let Some(device_data) = state.devices.get_mut(&mac.to_string())
-                {
+                if let Some(mac) = state.airpods_mac {
+                    let device_data = airpods_entry(&mut state.devices, mac);
                     device_data.name = info.name.clone();
-                    device_data.information = Some(DeviceInformation::AirPods(info.clone()));
+                    // Keep proximity keys that arrived before the info
+                    let le_keys = match device_data.information.take() {
+                        Some(DeviceInformation::AirPods(old)) => old.le_keys,
+                        _ => AirPodsLEKeys::default(),
+                    };
+                    device_data.information =
+                        Some(DeviceInformation::AirPods(AirPodsInformation {
+                            le_keys,
+                            ..info.clone()
+                        }));
                 }
-                save_devices(&state.devices).await;
+                save_device(&state).await;
                 info!("Received Information: {:?}", info);
                 if let Some(tx) = &state.event_tx {
                     let _ = tx.send(AACPEvent::DeviceInfo(Box::new(info)));
@@ -716,37 +761,15 @@ impl AACPManager {
                         .collect::<Vec<_>>()
                 );
                 let mut state = self.state.lock().await;
-                for (key_type, key_data) in &keys {
-                    if let Ok(kt) = ProximityKeyType::try_from(*key_type)
-                        && let Some(mac) = state.airpods_mac
-                    {
-                        let mac_str = mac.to_string();
-                        let device_data =
-                            state.devices.entry(mac_str.clone()).or_insert(DeviceData {
-                                name: mac_str.clone(),
-                                type_: DeviceType::AirPods,
-                                information: None,
-                                volume_swipe: None,
-                            });
-                        match kt {
-                            ProximityKeyType::Irk => {
-                                if let Some(DeviceInformation::AirPods(info)) =
-                                    device_data.information.as_mut()
-                                {
-                                    info.le_keys.irk = hex::encode(key_data);
-                                }
-                            }
-                            ProximityKeyType::EncKey => {
-                                if let Some(DeviceInformation::AirPods(info)) =
-                                    device_data.information.as_mut()
-                                {
-                                    info.le_keys.enc_key = hex::encode(key_data);
-                                }
-                            }
+                if let Some(mac) = state.airpods_mac {
+                    let device_data = airpods_entry(&mut state.devices, mac);
+                    for (key_type, key_data) in &keys {
+                        if let Ok(kt) = ProximityKeyType::try_from(*key_type) {
+                            set_le_key(device_data, kt, key_data);
                         }
                     }
                 }
-                save_devices(&state.devices).await;
+                save_device(&state).await;
             }
             opcodes::STEM_PRESS => {
                 let press_type = payload.get(2).and_then(|&b| match b {
@@ -895,6 +918,27 @@ impl AACPManager {
         self.send_data_packet(&packet).await
     }
 
+    /// Sink volume last remembered for the connected AirPods.
+    pub async fn remembered_volume(&self) -> Option<u32> {
+        let state = self.state.lock().await;
+        let mac = state.airpods_mac?.to_string();
+        state.devices.get(&mac).and_then(|d| d.volume)
+    }
+
+    /// Remember the sink volume used with the connected AirPods.
+    pub async fn remember_volume(&self, percent: u32) {
+        let mut state = self.state.lock().await;
+        let Some(mac) = state.airpods_mac else {
+            return;
+        };
+        let device_data = airpods_entry(&mut state.devices, mac);
+        if device_data.volume == Some(percent) {
+            return;
+        }
+        device_data.volume = Some(percent);
+        save_device(&state).await;
+    }
+
     pub async fn send_rename_packet(&self, name: &str) -> Result<()> {
         let name_bytes = name.as_bytes();
         let size = name_bytes.len();
@@ -918,15 +962,9 @@ impl AACPManager {
         if identifier == ControlCommandIdentifiers::VolumeSwipeMode {
             let mut state = self.state.lock().await;
             if let Some(mac) = state.airpods_mac {
-                let mac_str = mac.to_string();
-                let device_data = state.devices.entry(mac_str.clone()).or_insert(DeviceData {
-                    name: mac_str,
-                    type_: DeviceType::AirPods,
-                    information: None,
-                    volume_swipe: None,
-                });
+                let device_data = airpods_entry(&mut state.devices, mac);
                 device_data.volume_swipe = Some(value.first() == Some(&0x01));
-                save_devices(&state.devices).await;
+                save_device(&state).await;
             }
         }
 
@@ -946,24 +984,90 @@ impl AACPManager {
     }
 }
 
-/// Persist the device store (names, LE keys, remembered settings) to devices.json.
-async fn save_devices(devices: &HashMap<String, DeviceData>) {
-    let Ok(json) = serde_json::to_string(devices) else {
-        error!("Failed to serialize devices to JSON");
-        return;
+/// Fold a battery report into the device's stored levels; a component
+/// reporting Disconnected (closed case) keeps its last level. False when
+/// the device has no stored entry yet.
+fn remember_battery(state: &mut AACPManagerState, batteries: &[BatteryInfo]) -> bool {
+    let Some(mac) = state.airpods_mac.map(|m| m.to_string()) else {
+        return false;
     };
-    if let Some(parent) = get_devices_path().parent()
-        && let Err(e) = tokio::fs::create_dir_all(&parent).await
-    {
-        error!("Failed to create directory for devices: {}", e);
+    let Some(device) = state.devices.get_mut(&mac) else {
+        return false;
+    };
+    let stored = device.last_battery.get_or_insert_with(Default::default);
+    stored.at = crate::utils::unix_now();
+    for b in batteries {
+        if b.status != BatteryStatus::Disconnected {
+            stored.components.retain(|s| s.component != b.component);
+            stored.components.push(b.clone());
+        }
+    }
+    true
+}
+
+/// The stored entry for `mac`, created bare (name = MAC) if new.
+fn airpods_entry(devices: &mut HashMap<String, DeviceData>, mac: Address) -> &mut DeviceData {
+    let mac = mac.to_string();
+    devices.entry(mac.clone()).or_insert(DeviceData {
+        name: mac,
+        type_: DeviceType::AirPods,
+        information: None,
+        volume_swipe: None,
+        volume: None,
+        adapter: None,
+        last_battery: None,
+    })
+}
+
+/// Store one proximity key, with placeholder info if the Information
+/// packet hasn't arrived yet (it fills in the rest and keeps the keys).
+fn set_le_key(data: &mut DeviceData, key_type: ProximityKeyType, key: &[u8]) {
+    if !matches!(data.information, Some(DeviceInformation::AirPods(_))) {
+        data.information = Some(DeviceInformation::AirPods(AirPodsInformation::default()));
+    }
+    let Some(DeviceInformation::AirPods(info)) = &mut data.information else {
         return;
+    };
+    let key = hex::encode(key);
+    match key_type {
+        ProximityKeyType::Irk => info.le_keys.irk = key,
+        ProximityKeyType::EncKey => info.le_keys.enc_key = key,
     }
-    if let Err(e) = tokio::fs::write(&get_devices_path(), json).await {
+}
+
+/// Persist this session's device entry (name, LE keys, remembered
+/// settings) into devices.json, leaving other devices' entries alone.
+async fn save_device(state: &AACPManagerState) {
+    let Some(mac) = state.airpods_mac.map(|m| m.to_string()) else {
+        return;
+    };
+    let Some(data) = state.devices.get(&mac).cloned() else {
+        return;
+    };
+    if let Err(e) = crate::devices::store::save_device(mac, data).await {
         error!("Failed to save devices: {}", e);
     }
 }
 
-async fn recv_thread(manager: AACPManager, sp: Arc<SeqPacket>) {
+/// Packet-oriented link an AACP session runs over: the L2CAP socket in
+/// production, an in-memory pipe in tests.
+trait Transport: Send + Sync + 'static {
+    fn send(&self, data: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send;
+    /// Next packet into `buf`; 0 once the peer closed the link.
+    fn recv(&self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send;
+}
+
+impl Transport for SeqPacket {
+    fn send(&self, data: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        SeqPacket::send(self, data)
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+        SeqPacket::recv(self, buf)
+    }
+}
+
+async fn recv_thread<T: Transport>(manager: AACPManager, sp: Arc<T>) {
     let mut buf = vec![0u8; 1024];
     loop {
         match sp.recv(&mut buf).await {
@@ -974,6 +1078,7 @@ async fn recv_thread(manager: AACPManager, sp: Arc<SeqPacket>) {
             Ok(n) => {
                 let data = &buf[..n];
                 debug!("Received {} bytes: {}", n, hex::encode(data));
+                manager.state.lock().await.last_rx = Instant::now();
                 manager.receive_packet(data).await;
             }
             Err(e) => {
@@ -996,7 +1101,72 @@ async fn recv_thread(manager: AACPManager, sp: Arc<SeqPacket>) {
     }
 }
 
-async fn send_thread(mut rx: mpsc::Receiver<Vec<u8>>, sp: Arc<SeqPacket>) {
+/// What the watchdog does on a tick.
+#[derive(Debug, PartialEq, Eq)]
+enum LinkCheck {
+    Alive,
+    Probe,
+    Waiting,
+    Dead,
+}
+
+/// `idle`: time since the last packet; `probe_age`: time since the
+/// unanswered probe, if one was sent.
+fn check_link(idle: Duration, probe_age: Option<Duration>) -> LinkCheck {
+    match probe_age {
+        _ if idle < LINK_IDLE => LinkCheck::Alive,
+        None => LinkCheck::Probe,
+        Some(age) if age >= LINK_PROBE_TIMEOUT => LinkCheck::Dead,
+        Some(_) => LinkCheck::Waiting,
+    }
+}
+
+/// Catch an L2CAP socket that went quiet without erroring (BlueZ still
+/// reports the device connected, but nothing gets through). After a
+/// minute of silence it asks for notifications, which the AirPods answer
+/// with their current state; no answer tears the session down and emits
+/// `ConnectionLost` so the reconnect path takes over. Each tick also
+/// beats the systemd watchdog, which notices a tick that never finishes.
+async fn watchdog_thread(manager: AACPManager) {
+    let mut probed_at: Option<Instant> = None;
+    loop {
+        sleep(WATCHDOG_TICK).await;
+        let _beat = crate::systemd::AACP_HEARTBEAT.beat();
+        let idle = {
+            let state = manager.state.lock().await;
+            if state.sender.is_none() {
+                return; // recv_thread already reported the loss
+            }
+            state.last_rx.elapsed()
+        };
+        match check_link(idle, probed_at.map(|at| at.elapsed())) {
+            LinkCheck::Alive => probed_at = None,
+            LinkCheck::Probe => {
+                debug!("AACP link idle for {:?}, probing", idle);
+                if manager.send_notification_request().await.is_err() {
+                    break;
+                }
+                probed_at = Some(Instant::now());
+            }
+            LinkCheck::Waiting => {}
+            LinkCheck::Dead => break,
+        }
+    }
+    error!("AACP link unresponsive; dropping the session");
+    {
+        let mut state = manager.state.lock().await;
+        state.sender = None;
+        state.connected_devices.clear();
+        state.control_command_status_list.clear();
+        if let Some(tx) = &state.event_tx {
+            let _ = tx.send(AACPEvent::ConnectionLost);
+        }
+    }
+    // Closes the socket; this task is aborted along with the others.
+    manager.tasks.lock().await.abort_all();
+}
+
+async fn send_thread<T: Transport>(mut rx: mpsc::Receiver<Vec<u8>>, sp: Arc<T>) {
     while let Some(data) = rx.recv().await {
         if let Err(e) = sp.send(&data).await {
             error!("Failed to send data: {}", e);
@@ -1021,6 +1191,180 @@ mod tests {
         (m, rx)
     }
 
+    #[test]
+    fn watchdog_probes_then_gives_up() {
+        let quiet = LINK_IDLE + Duration::from_secs(1);
+        assert_eq!(check_link(Duration::from_secs(5), None), LinkCheck::Alive);
+        assert_eq!(check_link(quiet, None), LinkCheck::Probe);
+        assert_eq!(
+            check_link(quiet, Some(Duration::from_secs(1))),
+            LinkCheck::Waiting
+        );
+        assert_eq!(check_link(quiet, Some(LINK_PROBE_TIMEOUT)), LinkCheck::Dead);
+        // A reply resets the idle clock, probe or not
+        assert_eq!(
+            check_link(Duration::ZERO, Some(LINK_PROBE_TIMEOUT)),
+            LinkCheck::Alive
+        );
+    }
+
+    /// In-memory transport: packets pushed into `incoming` arrive as if
+    /// from the AirPods, and everything the session sends lands in `sent`.
+    struct MockTransport {
+        incoming: Mutex<UnboundedReceiver<Vec<u8>>>,
+        sent: mpsc::UnboundedSender<Vec<u8>>,
+    }
+
+    impl Transport for MockTransport {
+        fn send(&self, data: &[u8]) -> impl Future<Output = std::io::Result<usize>> + Send {
+            let result = self
+                .sent
+                .send(data.to_vec())
+                .map(|_| data.len())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe.into());
+            async move { result }
+        }
+
+        async fn recv(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.incoming.lock().await.recv().await {
+                Some(packet) => {
+                    buf[..packet.len()].copy_from_slice(&packet);
+                    Ok(packet.len())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    /// A session attached to a mock transport.
+    struct Session {
+        manager: AACPManager,
+        events: UnboundedReceiver<AACPEvent>,
+        to_host: mpsc::UnboundedSender<Vec<u8>>,
+        sent: UnboundedReceiver<Vec<u8>>,
+    }
+
+    async fn mock_session() -> Session {
+        let (manager, events) = manager_with_events().await;
+        let (to_host, incoming) = mpsc::unbounded_channel();
+        let (sent_tx, sent) = mpsc::unbounded_channel();
+        manager
+            .attach(Arc::new(MockTransport {
+                incoming: Mutex::new(incoming),
+                sent: sent_tx,
+            }))
+            .await;
+        Session {
+            manager,
+            events,
+            to_host,
+            sent,
+        }
+    }
+
+    /// Play a fixture: `< hex` lines are delivered to the session, `> hex`
+    /// lines must be the next packet it sent. `#` starts a comment.
+    async fn replay(session: &mut Session, fixture: &str) {
+        for (n, line) in fixture.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((dir, hex_bytes)) = line.split_at_checked(1) else {
+                continue;
+            };
+            let bytes: String = hex_bytes.split_whitespace().collect();
+            let packet =
+                hex::decode(&bytes).unwrap_or_else(|e| panic!("fixture line {}: {}", n + 1, e));
+            match dir {
+                "<" => session.to_host.send(packet).unwrap(),
+                ">" => {
+                    let sent = timeout(Duration::from_millis(100), session.sent.recv())
+                        .await
+                        .ok()
+                        .flatten();
+                    assert_eq!(
+                        sent.map(hex::encode),
+                        Some(hex::encode(&packet)),
+                        "fixture line {}",
+                        n + 1
+                    );
+                }
+                _ => panic!("fixture line {}: expected < or >", n + 1),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_battery_and_ear_transitions() {
+        let mut session = mock_session().await;
+        replay(&mut session, include_str!("fixtures/battery_ear.txt")).await;
+
+        match next_event(&mut session.events).await {
+            Some(AACPEvent::BatteryInfo(b)) => {
+                let levels: Vec<_> = b.iter().map(|x| (x.component, x.level)).collect();
+                assert_eq!(
+                    levels,
+                    [
+                        (BatteryComponent::Right, 100),
+                        (BatteryComponent::Left, 90),
+                        (BatteryComponent::Case, 50)
+                    ]
+                );
+                assert_eq!(b[0].status, BatteryStatus::Charging);
+            }
+            other => panic!("expected BatteryInfo, got {:?}", other),
+        }
+
+        use EarDetectionStatus::*;
+        let expected = [
+            (None, None, OutOfEar, InEar),
+            (Some(OutOfEar), Some(InEar), InEar, InEar),
+            (Some(InEar), Some(InEar), InCase, InCase),
+        ];
+        for (old_l, old_r, new_l, new_r) in expected {
+            match next_event(&mut session.events).await {
+                Some(AACPEvent::EarDetection {
+                    old_left,
+                    old_right,
+                    new_left,
+                    new_right,
+                }) => {
+                    assert_eq!((old_left, old_right), (old_l, old_r));
+                    assert_eq!((new_left, new_right), (Some(new_l), Some(new_r)));
+                }
+                other => panic!("expected EarDetection, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_control_command_round_trip() {
+        let mut session = mock_session().await;
+        session
+            .manager
+            .send_control_command(ControlCommandIdentifiers::ListeningMode, &[0x03])
+            .await
+            .unwrap();
+        replay(&mut session, include_str!("fixtures/listening_mode.txt")).await;
+
+        assert!(matches!(
+            next_event(&mut session.events).await,
+            Some(AACPEvent::ControlCommand(ControlCommandStatus { identifier, value }))
+                if identifier == ControlCommandIdentifiers::ListeningMode && value == [0x03]
+        ));
+        let state = session.manager.state.lock().await;
+        assert_eq!(state.control_command_status_list.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn closed_transport_reports_connection_lost() {
+        let mut session = mock_session().await;
+        drop(session.to_host);
+        assert!(matches!(
+            next_event(&mut session.events).await,
+            Some(AACPEvent::ConnectionLost)
+        ));
+        assert!(session.manager.idle().await.is_none());
+    }
+
     /// Helper: prepend the standard 4-byte AACP header to a payload.
     fn pkt(payload: &[u8]) -> Vec<u8> {
         let mut v = HEADER_BYTES.to_vec();
@@ -1105,6 +1449,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn repeated_battery_info_is_forwarded_once() {
+        let (m, mut rx) = manager_with_events().await;
+        let mut payload = vec![
+            opcodes::BATTERY_INFO,
+            0x00,
+            0x01,
+            0x04,
+            0x00,
+            75,
+            0x02,
+            0x00,
+        ];
+        m.receive_packet(&pkt(&payload)).await;
+        assert!(next_event(&mut rx).await.is_some());
+        m.receive_packet(&pkt(&payload)).await;
+        assert!(next_event(&mut rx).await.is_none());
+        payload[5] = 70;
+        m.receive_packet(&pkt(&payload)).await;
+        assert!(matches!(
+            next_event(&mut rx).await,
+            Some(AACPEvent::BatteryInfo(b)) if b[0].level == 70
+        ));
+    }
+
     #[tokio::test]
     async fn battery_info_skips_unknown_status_byte() {
         let (m, mut rx) = manager_with_events().await;
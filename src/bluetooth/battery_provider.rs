@@ -0,0 +1,226 @@
+//! BlueZ battery provider.
+//!
+//! Registers with `org.bluez.BatteryProviderManager1` so BlueZ publishes our
+//! AACP battery level on the device's `Battery1` interface, where every
+//! Bluetooth applet already looks. BlueZ discovers the per-device
+//! `org.bluez.BatteryProvider1` objects through the ObjectManager we serve
+//! at [`PROVIDER_PATH`].
+
+use crate::bluetooth::aacp::{AACPEvent, BatteryComponent, BatteryInfo, BatteryStatus};
+use crate::tui::app::AppEvent;
+use log::{debug, info};
+use std::collections::HashMap;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+const PROVIDER_PATH: &str = "/io/github/annoyedmilk/AirPodsTui/battery";
+
+struct DeviceBattery {
+    percentage: u8,
+    device: OwnedObjectPath,
+}
+
+#[zbus::interface(name = "org.bluez.BatteryProvider1")]
+impl DeviceBattery {
+    #[zbus(property)]
+    fn percentage(&self) -> u8 {
+        self.percentage
+    }
+
+    #[zbus(property)]
+    fn device(&self) -> OwnedObjectPath {
+        self.device.clone()
+    }
+
+    #[zbus(property)]
+    fn source(&self) -> &str {
+        "airpods-tui"
+    }
+}
+
+/// Reports the lowest bud level of each AirPods to BlueZ.
+pub struct BatteryProvider {
+    conn: zbus::Connection,
+    adapter_path: String,
+    /// Last reported level per MAC, to skip redundant property updates.
+    reported: HashMap<String, u8>,
+}
+
+impl BatteryProvider {
+    /// Serve the provider tree on the system bus and register it with the
+    /// adapter's BatteryProviderManager1.
+    pub async fn register(adapter_name: &str) -> zbus::Result<Self> {
+        let conn = zbus::Connection::system().await?;
+        conn.object_server()
+            .at(PROVIDER_PATH, zbus::fdo::ObjectManager)
+            .await?;
+        let adapter_path = format!("/org/bluez/{}", adapter_name);
+        conn.call_method(
+            Some("org.bluez"),
+            adapter_path.as_str(),
+            Some("org.bluez.BatteryProviderManager1"),
+            "RegisterBatteryProvider",
+            &(ObjectPath::try_from(PROVIDER_PATH)?,),
+        )
+        .await?;
+        info!("Registered BlueZ battery provider on {}", adapter_path);
+        Ok(Self {
+            conn,
+            adapter_path,
+            reported: HashMap::new(),
+        })
+    }
+
+    pub async fn handle(&mut self, event: &AppEvent) {
+        match provider_change(event) {
+            Some((mac, Some(level))) => {
+                if let Err(e) = self.update(mac, level).await {
+                    debug!("Battery provider update for {} failed: {}", mac, e);
+                }
+            }
+            Some((mac, None)) => self.remove(mac).await,
+            None => {}
+        }
+    }
+
+    async fn update(&mut self, mac: &str, level: u8) -> zbus::Result<()> {
+        let path = object_path(mac);
+        let server = self.conn.object_server();
+        match self.reported.get(mac) {
+            Some(&prev) if prev == level => {}
+            Some(_) => {
+                let iface = server.interface::<_, DeviceBattery>(path.as_str()).await?;
+                iface.get_mut().await.percentage = level;
+                iface
+                    .get()
+                    .await
+                    .percentage_changed(iface.signal_emitter())
+                    .await?;
+            }
+            None => {
+                let device = match super::device_path(&self.conn, mac).await {
+                    Some(path) => OwnedObjectPath::try_from(path)?,
+                    None => OwnedObjectPath::try_from(format!(
+                        "{}/dev_{}",
+                        self.adapter_path,
+                        mac.replace(':', "_")
+                    ))?,
+                };
+                server
+                    .at(
+                        path.as_str(),
+                        DeviceBattery {
+                            percentage: level,
+                            device,
+                        },
+                    )
+                    .await?;
+            }
+        }
+        self.reported.insert(mac.to_string(), level);
+        Ok(())
+    }
+
+    async fn remove(&mut self, mac: &str) {
+        if self.reported.remove(mac).is_some() {
+            let _ = self
+                .conn
+                .object_server()
+                .remove::<DeviceBattery, _>(object_path(mac).as_str())
+                .await;
+        }
+    }
+}
+
+fn object_path(mac: &str) -> String {
+    format!("{}/dev_{}", PROVIDER_PATH, mac.replace(':', "_"))
+}
+
+/// What `event` changes for BlueZ: a new level for a MAC, or None to drop
+/// the MAC's battery.
+fn provider_change(event: &AppEvent) -> Option<(&str, Option<u8>)> {
+    match event {
+        AppEvent::AACPEvent(mac, aacp_event) => match &**aacp_event {
+            AACPEvent::BatteryInfo(infos) => Some((mac, Some(min_bud_level(infos)?))),
+            _ => None,
+        },
+        AppEvent::DeviceDisconnected(mac) => Some((mac, None)),
+        _ => None,
+    }
+}
+
+/// The level BlueZ should show: the emptier of the two buds (or the
+/// headphone level on over-ear models). The case is not what the user is
+/// wearing, so it never counts.
+fn min_bud_level(infos: &[BatteryInfo]) -> Option<u8> {
+    infos
+        .iter()
+        .filter(|b| b.component != BatteryComponent::Case)
+        .filter(|b| b.status != BatteryStatus::Disconnected)
+        .map(|b| b.level)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bat(component: BatteryComponent, level: u8, status: BatteryStatus) -> BatteryInfo {
+        BatteryInfo {
+            component,
+            level,
+            status,
+        }
+    }
+
+    #[test]
+    fn min_bud_level_ignores_case_and_disconnected() {
+        let infos = vec![
+            bat(BatteryComponent::Left, 80, BatteryStatus::NotCharging),
+            bat(BatteryComponent::Right, 0, BatteryStatus::Disconnected),
+            bat(BatteryComponent::Case, 10, BatteryStatus::NotCharging),
+        ];
+        assert_eq!(min_bud_level(&infos), Some(80));
+    }
+
+    #[test]
+    fn min_bud_level_picks_lower_bud() {
+        let infos = vec![
+            bat(BatteryComponent::Left, 80, BatteryStatus::NotCharging),
+            bat(BatteryComponent::Right, 45, BatteryStatus::Charging),
+        ];
+        assert_eq!(min_bud_level(&infos), Some(45));
+    }
+
+    #[test]
+    fn min_bud_level_none_when_only_case() {
+        let infos = vec![bat(BatteryComponent::Case, 50, BatteryStatus::Charging)];
+        assert_eq!(min_bud_level(&infos), None);
+    }
+
+    #[test]
+    fn reports_bud_level_and_drops_on_disconnect() {
+        const MAC: &str = "AA:BB:CC:DD:EE:FF";
+        let battery =
+            |infos| AppEvent::AACPEvent(MAC.into(), Box::new(AACPEvent::BatteryInfo(infos)));
+        let event = battery(vec![
+            bat(BatteryComponent::Left, 40, BatteryStatus::NotCharging),
+            bat(BatteryComponent::Right, 30, BatteryStatus::NotCharging),
+            bat(BatteryComponent::Case, 10, BatteryStatus::NotCharging),
+        ]);
+        assert_eq!(provider_change(&event), Some((MAC, Some(30))));
+        let event = battery(vec![bat(
+            BatteryComponent::Case,
+            10,
+            BatteryStatus::Charging,
+        )]);
+        assert_eq!(provider_change(&event), None);
+        let event = AppEvent::DeviceDisconnected(MAC.into());
+        assert_eq!(provider_change(&event), Some((MAC, None)));
+        assert_eq!(provider_change(&AppEvent::AudioUnavailable), None);
+    }
+
+    #[test]
+    fn object_path_is_valid() {
+        assert!(ObjectPath::try_from(object_path("AA:BB:CC:DD:EE:FF").as_str()).is_ok());
+    }
+}
@@ -0,0 +1,60 @@
+//! Notice bluetoothd, or the adapter we use, going away and coming back.
+//! Our connection is to the system bus, so it outlives a BlueZ restart, but
+//! every BlueZ object and L2CAP socket from before the restart is dead.
+
+use bluer::SessionEvent;
+use futures::StreamExt;
+use zbus::fdo::DBusProxy;
+
+const BLUEZ: &str = "org.bluez";
+
+/// Resolve once org.bluez has an owner (`present`) or has none (`!present`).
+pub async fn wait_for_bluez(conn: &zbus::Connection, present: bool) -> zbus::Result<()> {
+    let proxy = DBusProxy::new(conn).await?;
+    // Subscribe before checking so a change in between isn't missed
+    let mut changes = proxy
+        .receive_name_owner_changed_with_args(&[(0, BLUEZ)])
+        .await?;
+    if proxy.name_has_owner(BLUEZ.try_into()?).await? == present {
+        return Ok(());
+    }
+    while let Some(signal) = changes.next().await {
+        if signal.args()?.new_owner().is_some() == present {
+            return Ok(());
+        }
+    }
+    Err(zbus::Error::Failure("D-Bus connection closed".into()))
+}
+
+/// Resolve once adapter `name` is gone, e.g. a USB dongle was unplugged.
+pub async fn wait_for_adapter_removal(session: &bluer::Session, name: &str) -> bluer::Result<()> {
+    let mut events = std::pin::pin!(session.events().await?);
+    if !session.adapter_names().await?.iter().any(|a| a == name) {
+        return Ok(());
+    }
+    while let Some(event) = events.next().await {
+        if matches!(event, SessionEvent::AdapterRemoved(removed) if removed == name) {
+            return Ok(());
+        }
+    }
+    // Events stop with bluetoothd; wait_for_bluez reports that
+    std::future::pending().await
+}
+
+/// Resolve once an adapter we can use is present: `wanted`, or any adapter
+/// when none is configured.
+pub async fn wait_for_adapter(session: &bluer::Session, wanted: Option<&str>) -> bluer::Result<()> {
+    let mut events = std::pin::pin!(session.events().await?);
+    let usable = |name: &str| wanted.is_none_or(|w| w == name);
+    if session.adapter_names().await?.iter().any(|a| usable(a)) {
+        return Ok(());
+    }
+    while let Some(event) = events.next().await {
+        if let SessionEvent::AdapterAdded(added) = event
+            && usable(&added)
+        {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
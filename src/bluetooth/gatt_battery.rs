@@ -0,0 +1,139 @@
+//! Battery level for earbuds without AACP (LE Audio and other brands) from
+//! the standard Battery Service, so they show up in the TUI as a basic
+//! battery-only device.
+
+use crate::tui::app::AppEvent;
+use bluer::gatt::remote::Characteristic;
+use bluer::{Adapter, Address, Device, DeviceEvent, DeviceProperty, UuidExt};
+use futures::StreamExt;
+use log::{debug, info};
+use uuid::Uuid;
+
+const BATTERY_SERVICE: u16 = 0x180f;
+const BATTERY_LEVEL: u16 = 0x2a19;
+
+/// Whether `uuids` advertise the Battery Service on a device that isn't
+/// AirPods (those report battery over AACP).
+pub fn is_candidate(uuids: &[Uuid]) -> bool {
+    let aacp = super::AIRPODS_AACP_UUID.parse::<Uuid>().ok();
+    uuids.contains(&Uuid::from_u16(BATTERY_SERVICE)) && !aacp.is_some_and(|a| uuids.contains(&a))
+}
+
+/// Start monitoring every connected candidate device.
+pub async fn scan_connected(
+    adapter: &Adapter,
+    app_tx: &tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) {
+    let Ok(addrs) = adapter.device_addresses().await else {
+        return;
+    };
+    for addr in addrs {
+        let Ok(device) = adapter.device(addr) else {
+            continue;
+        };
+        if !device.is_connected().await.unwrap_or(false) {
+            continue;
+        }
+        let uuids: Vec<Uuid> = device
+            .uuids()
+            .await
+            .ok()
+            .flatten()
+            .map(|u| u.into_iter().collect())
+            .unwrap_or_default();
+        if is_candidate(&uuids) {
+            tokio::spawn(monitor(adapter.clone(), addr, app_tx.clone()));
+        }
+    }
+}
+
+/// Report the battery level of `addr` until it disconnects.
+pub async fn monitor(
+    adapter: Adapter,
+    addr: Address,
+    app_tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) {
+    let Ok(device) = adapter.device(addr) else {
+        return;
+    };
+    let name = device.alias().await.unwrap_or_else(|_| addr.to_string());
+    let send = |level: u8| {
+        app_tx
+            .send(AppEvent::BatteryDevice {
+                mac: addr.to_string(),
+                name: name.clone(),
+                level,
+            })
+            .is_ok()
+    };
+    info!("Battery Service monitor for {} ({})", name, addr);
+
+    // BlueZ's battery plugin usually claims the service and exposes
+    // Battery1 instead; the raw characteristic only shows up without it.
+    if let Some(characteristic) = level_characteristic(&device).await {
+        if let Some(&level) = characteristic
+            .read()
+            .await
+            .ok()
+            .as_deref()
+            .and_then(<[u8]>::first)
+        {
+            send(level);
+        }
+        let Ok(updates) = characteristic.notify().await else {
+            return;
+        };
+        let mut updates = Box::pin(updates);
+        while let Some(value) = updates.next().await {
+            if let Some(&level) = value.first()
+                && !send(level)
+            {
+                break;
+            }
+        }
+    } else {
+        if let Ok(Some(level)) = device.battery_percentage().await {
+            send(level);
+        }
+        let Ok(events) = device.events().await else {
+            return;
+        };
+        let mut events = Box::pin(events);
+        while let Some(DeviceEvent::PropertyChanged(property)) = events.next().await {
+            match property {
+                DeviceProperty::BatteryPercentage(level) if !send(level) => break,
+                DeviceProperty::Connected(false) => break,
+                _ => {}
+            }
+        }
+    }
+    debug!("Battery Service monitor for {} ended", addr);
+}
+
+async fn level_characteristic(device: &Device) -> Option<Characteristic> {
+    for service in device.services().await.ok()? {
+        if service.uuid().await.ok() != Some(Uuid::from_u16(BATTERY_SERVICE)) {
+            continue;
+        }
+        for characteristic in service.characteristics().await.ok()? {
+            if characteristic.uuid().await.ok() == Some(Uuid::from_u16(BATTERY_LEVEL)) {
+                return Some(characteristic);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn airpods_are_not_candidates() {
+        let battery = Uuid::from_u16(BATTERY_SERVICE);
+        let aacp: Uuid = crate::bluetooth::AIRPODS_AACP_UUID.parse().unwrap();
+        assert!(is_candidate(&[battery]));
+        assert!(!is_candidate(&[battery, aacp]));
+        assert!(!is_candidate(&[Uuid::from_u16(0x110b)]));
+    }
+}
@@ -0,0 +1,134 @@
+//! `airpods-tui connect` / `disconnect`: drive the BlueZ link for a device
+//! named by MAC or by its name in devices.json.
+
+use crate::devices::enums::DeviceData;
+use crate::devices::store::Devices;
+use bluer::{Address, Session};
+use std::io;
+
+/// Address for `query`: a MAC as given, or the one stored device whose
+/// name matches (case-insensitively).
+pub fn resolve<'a>(
+    devices: &'a Devices,
+    query: &str,
+) -> io::Result<(Address, Option<&'a DeviceData>)> {
+    if let Ok(addr) = query.parse::<Address>() {
+        return Ok((addr, devices.get(&addr.to_string())));
+    }
+    let mut matches: Vec<_> = devices
+        .iter()
+        .filter(|(_, d)| d.name.eq_ignore_ascii_case(query))
+        .collect();
+    matches.sort_by(|a, b| a.0.cmp(b.0));
+    match matches.as_slice() {
+        [(mac, data)] => mac
+            .parse()
+            .map(|addr| (addr, Some(*data)))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("bad MAC {}", mac))),
+        [] => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no device named '{}' in devices.json", query),
+        )),
+        several => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "'{}' matches {}; use the MAC",
+                query,
+                several
+                    .iter()
+                    .map(|(mac, _)| mac.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )),
+    }
+}
+
+async fn set_connected(adapter_name: Option<&str>, query: &str, connect: bool) -> io::Result<()> {
+    let devices = crate::devices::store::load();
+    let (addr, data) = resolve(&devices, query)?;
+    let adapter_name = super::preferred_adapter(data, adapter_name);
+
+    let session = Session::new().await.map_err(io::Error::other)?;
+    let adapter = super::open_adapter(&session, adapter_name)
+        .await
+        .map_err(io::Error::other)?;
+    let device = adapter.device(addr).map_err(io::Error::other)?;
+    let name = device.alias().await.unwrap_or_else(|_| addr.to_string());
+    let result = if connect {
+        adapter.set_powered(true).await.map_err(io::Error::other)?;
+        device.connect().await
+    } else {
+        device.disconnect().await
+    };
+    result.map_err(|e| {
+        let verb = if connect {
+            "Connecting"
+        } else {
+            "Disconnecting"
+        };
+        io::Error::other(format!("{} {} failed: {}", verb, name, e))
+    })?;
+    println!(
+        "{} {}",
+        if connect { "Connected" } else { "Disconnected" },
+        name
+    );
+    Ok(())
+}
+
+/// Entry point for the `connect` subcommand.
+pub async fn connect(adapter_name: Option<&str>, query: &str) -> io::Result<()> {
+    set_connected(adapter_name, query, true).await
+}
+
+/// Entry point for the `disconnect` subcommand.
+pub async fn disconnect(adapter_name: Option<&str>, query: &str) -> io::Result<()> {
+    set_connected(adapter_name, query, false).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::enums::DeviceType;
+
+    fn device(name: &str) -> DeviceData {
+        DeviceData {
+            name: name.into(),
+            type_: DeviceType::AirPods,
+            information: None,
+            volume_swipe: None,
+            volume: None,
+            adapter: None,
+            last_battery: None,
+        }
+    }
+
+    #[test]
+    fn resolves_mac_or_unique_name() {
+        let devices = Devices::from([
+            ("AA:BB:CC:DD:EE:FF".to_string(), device("Work Pods")),
+            ("11:22:33:44:55:66".to_string(), device("Pods")),
+            ("66:55:44:33:22:11".to_string(), device("pods")),
+        ]);
+        let (addr, data) = resolve(&devices, "work pods").unwrap();
+        assert_eq!(addr.to_string(), "AA:BB:CC:DD:EE:FF");
+        assert_eq!(data.unwrap().name, "Work Pods");
+
+        // Unknown MACs are fine; BlueZ may still know them
+        let (addr, data) = resolve(&devices, "01:02:03:04:05:06").unwrap();
+        assert_eq!(addr.to_string(), "01:02:03:04:05:06");
+        assert!(data.is_none());
+
+        let err = resolve(&devices, "Pods").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(
+            err.to_string()
+                .contains("11:22:33:44:55:66, 66:55:44:33:22:11")
+        );
+        assert_eq!(
+            resolve(&devices, "Nope").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
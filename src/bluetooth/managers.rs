@@ -1,28 +1,34 @@
 use crate::bluetooth::aacp::AACPManager;
+use crate::devices::airpods::AirPodsDevice;
+use crate::media_controller::MediaController;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 
 pub struct DeviceManagers {
     aacp: Option<Arc<AACPManager>>,
+    media: Option<Arc<Mutex<MediaController>>>,
 }
 
 impl DeviceManagers {
     /// Reserve a HashMap slot before async init starts so concurrent
     /// connection events can detect the in-progress claim.
     pub fn placeholder() -> Self {
-        Self { aacp: None }
-    }
-
-    pub fn with_aacp(aacp: AACPManager) -> Self {
         Self {
-            aacp: Some(Arc::new(aacp)),
+            aacp: None,
+            media: None,
         }
     }
 
-    pub fn set_aacp(&mut self, manager: AACPManager) {
-        self.aacp = Some(Arc::new(manager));
+    pub fn set_device(&mut self, device: AirPodsDevice) {
+        self.aacp = Some(Arc::new(device.aacp_manager));
+        self.media = Some(device.media_controller);
     }
 
     pub fn get_aacp(&self) -> Option<Arc<AACPManager>> {
         self.aacp.clone()
     }
+
+    pub fn get_media(&self) -> Option<Arc<Mutex<MediaController>>> {
+        self.media.clone()
+    }
 }
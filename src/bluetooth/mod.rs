@@ -1,6 +1,94 @@
 pub mod aacp;
+pub mod battery_provider;
+pub mod bluez_watch;
 pub(crate) mod discovery;
+pub mod gatt_battery;
+pub mod link;
 pub mod managers;
+pub mod object_cache;
+pub mod pairing;
+pub mod proximity;
 
 /// AACP service UUID used by AirPods for battery/settings communication.
 pub const AIRPODS_AACP_UUID: &str = "74ec2172-0bad-4d01-8f77-997b2be0722a";
+
+/// The configured adapter (`adapter` in config.toml), else BlueZ's default.
+pub async fn open_adapter(
+    session: &bluer::Session,
+    name: Option<&str>,
+) -> bluer::Result<bluer::Adapter> {
+    match name {
+        Some(name) => session.adapter(name),
+        None => session.default_adapter().await,
+    }
+}
+
+/// Adapter for a device: its pin in devices.json, else `configured`.
+pub fn preferred_adapter<'a>(
+    device: Option<&'a crate::devices::enums::DeviceData>,
+    configured: Option<&'a str>,
+) -> Option<&'a str> {
+    device.and_then(|d| d.adapter.as_deref()).or(configured)
+}
+
+/// Whether a device seen on `adapter` is ours to handle: always, unless it
+/// is pinned, or `configured`, to a different adapter.
+pub fn serves_adapter(
+    adapter: Option<&str>,
+    device: Option<&crate::devices::enums::DeviceData>,
+    configured: Option<&str>,
+) -> bool {
+    preferred_adapter(device, configured).is_none_or(|preferred| adapter == Some(preferred))
+}
+
+/// Adapter name ("hci0") from a BlueZ object path below it.
+pub fn adapter_of_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/org/bluez/")?.split('/').next()
+}
+
+/// Device MAC from a BlueZ object path such as
+/// `/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF/sep1/fd0`.
+pub fn mac_from_bluez_path(path: &str) -> Option<String> {
+    let dev = path.split('/').find_map(|seg| seg.strip_prefix("dev_"))?;
+    (dev.len() == 17).then(|| dev.replace('_', ":").to_ascii_uppercase())
+}
+
+/// BlueZ's object tree, from its ObjectManager.
+pub async fn managed_objects(conn: &zbus::Connection) -> Option<zbus::fdo::ManagedObjects> {
+    let manager = zbus::fdo::ObjectManagerProxy::builder(conn)
+        .destination("org.bluez")
+        .ok()?
+        .path("/")
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+    manager.get_managed_objects().await.ok()
+}
+
+/// Object path of the Device1 with this MAC, on whichever adapter has it.
+pub async fn device_path(conn: &zbus::Connection, mac: &str) -> Option<String> {
+    let objects = managed_objects(conn).await?;
+    let mac = mac.to_ascii_uppercase();
+    objects.into_iter().find_map(|(path, ifaces)| {
+        (ifaces.contains_key("org.bluez.Device1")
+            && mac_from_bluez_path(path.as_str()).as_deref() == Some(mac.as_str()))
+        .then(|| path.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devices_on_other_adapters_are_skipped() {
+        let path = "/org/bluez/hci1/dev_AA_BB_CC_DD_EE_FF";
+        assert_eq!(adapter_of_path(path), Some("hci1"));
+        let on_hci1 = adapter_of_path(path);
+        assert!(serves_adapter(on_hci1, None, None));
+        assert!(serves_adapter(on_hci1, None, Some("hci1")));
+        assert!(!serves_adapter(on_hci1, None, Some("hci0")));
+        assert!(!serves_adapter(None, None, Some("hci0")));
+    }
+}
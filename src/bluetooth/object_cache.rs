@@ -0,0 +1,135 @@
+//! Local copy of BlueZ's object tree, so the connection listener can answer
+//! Address/UUIDs/Modalias lookups without a D-Bus round-trip per signal.
+//!
+//! Seeded once from `GetManagedObjects`, then kept current from the
+//! `InterfacesAdded`, `InterfacesRemoved` and `PropertiesChanged` signals the
+//! listener already receives.
+
+use std::collections::HashMap;
+use zbus::zvariant::OwnedValue;
+
+type Properties = HashMap<String, OwnedValue>;
+
+#[derive(Default)]
+pub struct ObjectCache {
+    /// Object path -> interface -> property -> value.
+    objects: HashMap<String, HashMap<String, Properties>>,
+}
+
+impl ObjectCache {
+    /// Snapshot BlueZ's current objects. Empty if BlueZ isn't reachable;
+    /// the signals fill it in from there.
+    pub async fn seed(conn: &zbus::Connection) -> Self {
+        let mut cache = Self::default();
+        for (path, ifaces) in super::managed_objects(conn).await.unwrap_or_default() {
+            cache.interfaces_added(
+                path.as_str(),
+                ifaces
+                    .into_iter()
+                    .map(|(iface, props)| (iface.to_string(), props))
+                    .collect(),
+            );
+        }
+        cache
+    }
+
+    pub fn interfaces_added(&mut self, path: &str, ifaces: HashMap<String, Properties>) {
+        self.objects
+            .entry(path.to_string())
+            .or_default()
+            .extend(ifaces);
+    }
+
+    pub fn interfaces_removed(&mut self, path: &str, ifaces: &[String]) {
+        let Some(object) = self.objects.get_mut(path) else {
+            return;
+        };
+        for iface in ifaces {
+            object.remove(iface);
+        }
+        if object.is_empty() {
+            self.objects.remove(path);
+        }
+    }
+
+    pub fn properties_changed(
+        &mut self,
+        path: &str,
+        iface: &str,
+        changed: &HashMap<String, OwnedValue>,
+        invalidated: &[String],
+    ) {
+        let props = self
+            .objects
+            .entry(path.to_string())
+            .or_default()
+            .entry(iface.to_string())
+            .or_default();
+        for (name, value) in changed {
+            if let Ok(value) = value.try_clone() {
+                props.insert(name.clone(), value);
+            }
+        }
+        for name in invalidated {
+            props.remove(name);
+        }
+    }
+
+    /// A cached property, converted to `T`.
+    pub fn get<T: TryFrom<OwnedValue>>(&self, path: &str, iface: &str, prop: &str) -> Option<T> {
+        let value = self.objects.get(path)?.get(iface)?.get(prop)?;
+        T::try_from(value.try_clone().ok()?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    const PATH: &str = "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF";
+    const DEVICE: &str = "org.bluez.Device1";
+
+    fn value(v: Value<'_>) -> OwnedValue {
+        v.try_into().unwrap()
+    }
+
+    fn props(entries: &[(&str, Value<'_>)]) -> Properties {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), value(v.try_clone().unwrap())))
+            .collect()
+    }
+
+    #[test]
+    fn signals_keep_cache_current() {
+        let mut cache = ObjectCache::default();
+        cache.interfaces_added(
+            PATH,
+            HashMap::from([(
+                DEVICE.to_string(),
+                props(&[
+                    ("Address", Value::from("AA:BB:CC:DD:EE:FF")),
+                    ("Connected", Value::from(false)),
+                ]),
+            )]),
+        );
+        assert_eq!(
+            cache.get::<String>(PATH, DEVICE, "Address").as_deref(),
+            Some("AA:BB:CC:DD:EE:FF")
+        );
+
+        cache.properties_changed(
+            PATH,
+            DEVICE,
+            &props(&[("Connected", Value::from(true))]),
+            &["Address".to_string()],
+        );
+        assert_eq!(cache.get::<bool>(PATH, DEVICE, "Connected"), Some(true));
+        assert_eq!(cache.get::<String>(PATH, DEVICE, "Address"), None);
+
+        cache.interfaces_removed(PATH, &[DEVICE.to_string()]);
+        assert_eq!(cache.get::<bool>(PATH, DEVICE, "Connected"), None);
+        assert!(cache.objects.is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+//! `airpods-tui pair`: pair, trust and connect AirPods without bluetoothctl.
+//!
+//! Registers a BlueZ agent for the duration of the pairing that accepts
+//! the Secure Simple Pairing confirmation for the target device only, then
+//! drives discovery and `Pair()` itself. Afterwards it asks the AirPods
+//! for their proximity keys (IRK/EncKey) so devices.json comes out
+//! complete without hand-editing.
+
+use super::aacp::{AACPManager, ProximityKeyType, opcodes};
+use crate::devices::enums::{DeviceData, DeviceInformation};
+use bluer::agent::{Agent, ReqError, ReqResult};
+use bluer::{Adapter, AdapterEvent, Address, Session};
+use futures::StreamExt;
+use std::io;
+use std::time::Duration;
+
+/// How long to wait for the device to show up in discovery.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long each step of the key exchange may take to be answered.
+const KEY_STEP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long a running daemon gets to store the keys from its own session.
+const DAEMON_KEYS_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// PIN sent if a device falls back to legacy (pre-2.1) pairing.
+const LEGACY_PIN: &str = "0000";
+
+/// Only answer agent requests for the device being paired.
+fn check_target(target: Address, device: Address) -> ReqResult<()> {
+    if device == target {
+        Ok(())
+    } else {
+        Err(ReqError::Rejected)
+    }
+}
+
+fn pairing_agent(target: Address) -> Agent {
+    Agent {
+        request_default: true,
+        request_pin_code: Some(Box::new(move |req| {
+            Box::pin(async move { check_target(target, req.device).map(|_| LEGACY_PIN.into()) })
+        })),
+        request_passkey: Some(Box::new(move |req| {
+            Box::pin(async move { check_target(target, req.device).map(|_| 0) })
+        })),
+        request_confirmation: Some(Box::new(move |req| {
+            Box::pin(async move {
+                println!("Confirming passkey {:06} for {}", req.passkey, req.device);
+                check_target(target, req.device)
+            })
+        })),
+        request_authorization: Some(Box::new(move |req| {
+            Box::pin(async move { check_target(target, req.device) })
+        })),
+        authorize_service: Some(Box::new(move |req| {
+            Box::pin(async move { check_target(target, req.device) })
+        })),
+        ..Default::default()
+    }
+}
+
+/// Wait until BlueZ knows `target`, discovering if it doesn't yet. With no
+/// target, take the first unpaired AirPods that show up.
+async fn discover(adapter: &Adapter, target: Option<Address>) -> bluer::Result<Address> {
+    if let Some(target) = target
+        && adapter.device_addresses().await?.contains(&target)
+    {
+        return Ok(target);
+    }
+    println!("Open the case and hold the setup button until the light flashes white...");
+    let mut events = Box::pin(adapter.discover_devices().await?);
+    let found = tokio::time::timeout(DISCOVERY_TIMEOUT, async {
+        while let Some(event) = events.next().await {
+            let AdapterEvent::DeviceAdded(addr) = event else {
+                continue;
+            };
+            match target {
+                Some(target) if addr == target => return Some(addr),
+                Some(_) => {}
+                None => {
+                    if let Ok(device) = adapter.device(addr)
+                        && !device.is_paired().await.unwrap_or(true)
+                        && looks_like_airpods(&device).await
+                    {
+                        return Some(addr);
+                    }
+                }
+            }
+        }
+        None
+    })
+    .await;
+    match found {
+        Ok(Some(addr)) => Ok(addr),
+        _ => Err(bluer::Error {
+            kind: bluer::ErrorKind::NotFound,
+            message: match target {
+                Some(target) => format!("{} not seen within {:?}", target, DISCOVERY_TIMEOUT),
+                None => format!("no AirPods in pairing mode within {:?}", DISCOVERY_TIMEOUT),
+            },
+        }),
+    }
+}
+
+/// AirPods advertise the AACP service; fall back to the name for models
+/// that leave it out of their inquiry response.
+async fn looks_like_airpods(device: &bluer::Device) -> bool {
+    let aacp = super::AIRPODS_AACP_UUID.parse::<uuid::Uuid>().ok();
+    if let Ok(Some(uuids)) = device.uuids().await
+        && aacp.is_some_and(|u| uuids.contains(&u))
+    {
+        return true;
+    }
+    device
+        .name()
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|n| n.contains("AirPods"))
+}
+
+async fn pair_device(
+    adapter_name: Option<&str>,
+    target: Option<Address>,
+) -> bluer::Result<Address> {
+    let session = Session::new().await?;
+    let adapter = super::open_adapter(&session, adapter_name).await?;
+    adapter.set_powered(true).await?;
+
+    let target = discover(&adapter, target).await?;
+    let _agent = session.register_agent(pairing_agent(target)).await?;
+    let device = adapter.device(target)?;
+    if device.is_paired().await? {
+        println!("{} is already paired", target);
+    } else {
+        println!("Pairing with {}...", target);
+        device.pair().await?;
+    }
+    device.set_trusted(true).await?;
+    if !device.is_connected().await? {
+        device.connect().await?;
+    }
+    let name = device.alias().await.unwrap_or_else(|_| target.to_string());
+    println!("Paired and connected: {}", name);
+    Ok(target)
+}
+
+/// Open a short AACP session and ask for the proximity keys; the AACP
+/// manager stores them (and the device info) in devices.json as they
+/// arrive.
+async fn request_keys(adapter_name: Option<&str>, target: Address) -> bluer::Result<()> {
+    let session = Session::new().await?;
+    let adapter = super::open_adapter(&session, adapter_name).await?;
+    let local = adapter.address().await.ok();
+
+    let mut aacp = AACPManager::new();
+    aacp.connect(target, local).await;
+    if aacp.idle().await.is_none() {
+        return Err(bluer::Error {
+            kind: bluer::ErrorKind::ConnectionAttemptFailed,
+            message: "AACP channel did not open".into(),
+        });
+    }
+    let mut opcode_rx = aacp.state.lock().await.opcode_tx.subscribe();
+    let mut wait_for = async |expected: Option<u8>| {
+        let _ = tokio::time::timeout(KEY_STEP_TIMEOUT, async {
+            while let Ok(opcode) = opcode_rx.recv().await {
+                if expected.is_none_or(|e| e == opcode) {
+                    break;
+                }
+            }
+        })
+        .await;
+    };
+
+    let result = async {
+        aacp.send_handshake().await?;
+        wait_for(None).await;
+        aacp.send_set_feature_flags_packet().await?;
+        wait_for(Some(opcodes::SET_FEATURE_FLAGS)).await;
+        aacp.send_notification_request().await?;
+        wait_for(Some(opcodes::INFORMATION)).await;
+        aacp.send_proximity_keys_request(vec![ProximityKeyType::Irk, ProximityKeyType::EncKey])
+            .await?;
+        wait_for(Some(opcodes::PROXIMITY_KEYS_RSP)).await;
+        Ok(())
+    }
+    .await;
+    aacp.disconnect().await;
+    result
+}
+
+/// Whether the stored entry has both proximity keys.
+fn has_le_keys(data: Option<&DeviceData>) -> bool {
+    matches!(data.and_then(|d| d.information.as_ref()),
+        Some(DeviceInformation::AirPods(info))
+            if !info.le_keys.irk.is_empty() && !info.le_keys.enc_key.is_empty())
+}
+
+/// Wait for another instance (which opens its own AACP session on
+/// connect) to store the keys.
+async fn wait_for_stored_keys(target: Address) -> bool {
+    let mac = target.to_string();
+    let deadline = tokio::time::Instant::now() + DAEMON_KEYS_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if has_le_keys(crate::devices::store::load().get(&mac)) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    false
+}
+
+/// Entry point for the `pair` subcommand. `instance_running` says whether
+/// a daemon already handles AirPods sessions; it then stores the keys and
+/// this only waits for them.
+pub async fn run(
+    adapter_name: Option<&str>,
+    target: Option<Address>,
+    instance_running: bool,
+) -> io::Result<()> {
+    let what = target.map_or_else(|| "AirPods".to_string(), |t| t.to_string());
+    let target = pair_device(adapter_name, target)
+        .await
+        .map_err(|e| io::Error::other(format!("Pairing {} failed: {}", what, e)))?;
+
+    println!("Requesting proximity keys...");
+    let stored = if instance_running {
+        wait_for_stored_keys(target).await
+    } else {
+        if let Err(e) = request_keys(adapter_name, target).await {
+            log::warn!("Proximity key request to {} failed: {}", target, e);
+        }
+        has_le_keys(crate::devices::store::load().get(&target.to_string()))
+    };
+    if stored {
+        println!(
+            "Saved {} with its IRK and EncKey to {}",
+            target,
+            crate::utils::get_devices_path().display()
+        );
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!(
+                "{} is paired but sent no proximity keys; reconnect it and run `pair {}` again",
+                target, target
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_only_answers_for_target() {
+        let target: Address = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+        let other: Address = "11:22:33:44:55:66".parse().unwrap();
+        assert!(check_target(target, target).is_ok());
+        assert_eq!(check_target(target, other), Err(ReqError::Rejected));
+    }
+
+    #[test]
+    fn keys_count_only_when_both_present() {
+        use crate::devices::airpods::AirPodsInformation;
+        use crate::devices::enums::DeviceType;
+
+        let mut data = DeviceData {
+            name: "Pods".into(),
+            type_: DeviceType::AirPods,
+            information: None,
+            volume_swipe: None,
+            volume: None,
+            adapter: None,
+            last_battery: None,
+        };
+        assert!(!has_le_keys(None));
+        assert!(!has_le_keys(Some(&data)));
+        let mut info = AirPodsInformation::default();
+        info.le_keys.irk = "00".repeat(16);
+        data.information = Some(DeviceInformation::AirPods(info.clone()));
+        assert!(!has_le_keys(Some(&data)));
+        info.le_keys.enc_key = "11".repeat(16);
+        data.information = Some(DeviceInformation::AirPods(info));
+        assert!(has_le_keys(Some(&data)));
+    }
+}
@@ -0,0 +1,815 @@
+//! Apple proximity-pairing adverts.
+//!
+//! AirPods with the case open broadcast manufacturer data (company 0x004C,
+//! type 0x07) with the model and coarse battery levels in the clear. The
+//! advertising address is a resolvable private address: with the IRK saved
+//! in devices.json it maps back to one of our devices, whose EncKey then
+//! decrypts the trailing 16 bytes into exact battery levels. The nearby
+//! scan lists them in the TUI; it never connects to anything.
+
+use crate::config::AutoConnect;
+use crate::devices::apple_models::APPLE_VENDOR_ID;
+use crate::devices::enums::DeviceInformation;
+use crate::devices::store::Devices;
+use crate::tui::app::AppEvent;
+use aes::Aes128;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use bluer::{Adapter, AdapterEvent, Address, DiscoveryFilter, DiscoveryTransport};
+use futures::StreamExt;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const PROXIMITY_PAIRING: u8 = 0x07;
+
+/// Adverts older than this drop out of the list.
+const NEARBY_TTL: Duration = Duration::from_secs(30);
+
+/// How often the list is re-published.
+const NEARBY_REFRESH: Duration = Duration::from_secs(2);
+
+/// Weight of a new RSSI reading in the moving average.
+const RSSI_ALPHA: f32 = 0.25;
+
+/// How often the scan re-reads devices.json for keys saved since.
+const KEYS_RELOAD: Duration = Duration::from_secs(60);
+
+/// Least time between auto-connect attempts for one device.
+const AUTO_CONNECT_RETRY: Duration = Duration::from_secs(30);
+
+/// Length of the AES block at the end of the advert.
+const ENCRYPTED_LEN: usize = 16;
+
+/// What a proximity-pairing advert says about the device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProximityAdvert {
+    pub product_id: u16,
+    /// Battery in percent, 10% steps; None when the bud/case isn't reported.
+    pub left: Option<u8>,
+    pub right: Option<u8>,
+    pub case: Option<u8>,
+    pub left_charging: bool,
+    pub right_charging: bool,
+    pub case_charging: bool,
+    pub left_in_ear: bool,
+    pub right_in_ear: bool,
+    /// The bud sending the advert sits in the case.
+    #[serde(default)]
+    pub in_case: bool,
+    #[serde(default)]
+    pub lid_open: bool,
+    /// Wraps at 8; a new value means the lid was opened again.
+    #[serde(default)]
+    pub lid_count: u8,
+    /// Levels are exact, from the decrypted block, rather than 10% steps.
+    #[serde(default)]
+    pub exact: bool,
+    /// Housing color code, when the advert is long enough to carry it.
+    pub color: Option<u8>,
+}
+
+impl ProximityAdvert {
+    /// Model name plus color, e.g. "AirPods Max (Sky Blue)".
+    pub fn display_name(&self) -> String {
+        let model = crate::devices::apple_models::model_info(self.product_id).name;
+        match self.color.and_then(color_name) {
+            Some(color) => format!("{} ({})", model, color),
+            None => model.to_string(),
+        }
+    }
+}
+
+/// Color byte of the proximity advert; None for codes we don't know.
+fn color_name(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "White",
+        0x01 => "Black",
+        0x02 => "Red",
+        0x03 => "Blue",
+        0x04 => "Pink",
+        0x05 => "Gray",
+        0x06 => "Silver",
+        0x07 => "Gold",
+        0x08 => "Rose Gold",
+        0x09 => "Space Gray",
+        0x0a => "Dark Blue",
+        0x0b => "Light Blue",
+        0x0c => "Yellow",
+        _ => return None,
+    })
+}
+
+/// A device seen advertising, keyed by its (usually random) address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NearbyAirPods {
+    pub address: String,
+    /// MAC and name in devices.json, when the address resolves to one of
+    /// our devices.
+    #[serde(default)]
+    pub mac: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub advert: ProximityAdvert,
+    /// From smoothed RSSI; None until BlueZ reports signal strength.
+    #[serde(default)]
+    pub distance: Option<Distance>,
+    /// Smoothed RSSI in dBm, for the TUI's locate meter.
+    #[serde(default)]
+    pub rssi: Option<i16>,
+}
+
+/// Rough distance band from smoothed RSSI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Distance {
+    /// About arm's length.
+    Close,
+    /// Same room.
+    Near,
+    Far,
+}
+
+impl Distance {
+    fn from_rssi(rssi: f32) -> Self {
+        if rssi >= -60.0 {
+            Distance::Close
+        } else if rssi >= -75.0 {
+            Distance::Near
+        } else {
+            Distance::Far
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Distance::Close => "close",
+            Distance::Near => "near",
+            Distance::Far => "far",
+        }
+    }
+
+    /// The locate screen's word for the band.
+    pub fn temperature(self) -> &'static str {
+        match self {
+            Distance::Close => "hot",
+            Distance::Near => "warm",
+            Distance::Far => "cold",
+        }
+    }
+}
+
+/// Smoothed RSSI as 0-100 for the locate meter: -100 dBm and weaker is 0,
+/// -40 dBm (right next to the adapter) and stronger is 100.
+pub fn warmth(rssi: i16) -> u8 {
+    ((i32::from(rssi) + 100) * 100 / 60).clamp(0, 100) as u8
+}
+
+/// Exponential moving average of RSSI; single readings jump by 10 dB
+/// or more between adverts.
+#[derive(Debug, Clone, Copy, Default)]
+struct RssiFilter {
+    avg: Option<f32>,
+}
+
+impl RssiFilter {
+    fn update(&mut self, rssi: i16) -> f32 {
+        let rssi = f32::from(rssi);
+        let avg = match self.avg {
+            Some(avg) => avg + RSSI_ALPHA * (rssi - avg),
+            None => rssi,
+        };
+        self.avg = Some(avg);
+        avg
+    }
+
+    fn distance(&self) -> Option<Distance> {
+        self.avg.map(Distance::from_rssi)
+    }
+
+    fn dbm(&self) -> Option<i16> {
+        self.avg.map(|avg| avg.round() as i16)
+    }
+}
+
+/// LE keys of a device in devices.json, as stored (least significant
+/// byte first, the way AACP and BlueZ hand them out).
+#[derive(Debug, Clone)]
+pub struct DeviceKeys {
+    pub mac: String,
+    pub name: String,
+    irk: [u8; 16],
+    enc_key: Option<[u8; 16]>,
+}
+
+/// Keys of every stored device that has an IRK.
+pub fn stored_keys(devices: &Devices) -> Vec<DeviceKeys> {
+    let mut keys: Vec<DeviceKeys> = devices
+        .iter()
+        .filter_map(|(mac, data)| {
+            let Some(DeviceInformation::AirPods(info)) = &data.information else {
+                return None;
+            };
+            Some(DeviceKeys {
+                mac: mac.clone(),
+                name: data.name.clone(),
+                irk: key_bytes(&info.le_keys.irk)?,
+                enc_key: key_bytes(&info.le_keys.enc_key),
+            })
+        })
+        .collect();
+    keys.sort_by(|a, b| a.mac.cmp(&b.mac));
+    keys
+}
+
+fn key_bytes(hex: &str) -> Option<[u8; 16]> {
+    hex::decode(hex).ok()?.try_into().ok()
+}
+
+/// Whether `address` is a resolvable private address generated from
+/// `irk`: the top 24 bits are random (tagged 0b01), the low 24 a hash of
+/// them under the IRK (Core spec Vol 3 Part H 2.2.2).
+pub fn resolves(irk: &[u8; 16], address: Address) -> bool {
+    let addr = address.0;
+    if addr[0] >> 6 != 0b01 {
+        return false;
+    }
+    let mut key = *irk;
+    key.reverse();
+    let mut block = [0u8; 16];
+    block[13..].copy_from_slice(&addr[..3]);
+    let mut block = block.into();
+    Aes128::new(&key.into()).encrypt_block(&mut block);
+    block[13..] == addr[3..]
+}
+
+/// The stored device `address` belongs to.
+pub fn resolve(keys: &[DeviceKeys], address: Address) -> Option<&DeviceKeys> {
+    keys.iter().find(|k| resolves(&k.irk, address))
+}
+
+/// The advert's trailing block decrypted with the device's EncKey.
+fn decrypt(enc_key: &[u8; 16], data: &[u8]) -> Option<[u8; 16]> {
+    // The clear fields end at the lid byte; the block comes after
+    if data.len() < 9 + ENCRYPTED_LEN {
+        return None;
+    }
+    let tail: [u8; ENCRYPTED_LEN] = data[data.len() - ENCRYPTED_LEN..].try_into().ok()?;
+    let mut block = tail.into();
+    Aes128::new(enc_key.into()).decrypt_block(&mut block);
+    Some(block.into())
+}
+
+/// Parse an advert, taking the exact battery levels from the encrypted
+/// block when the device's EncKey is known.
+pub fn parse_with_key(data: &[u8], enc_key: Option<&[u8; 16]>) -> Option<ProximityAdvert> {
+    let mut advert = parse(data)?;
+    let Some(block) = enc_key.and_then(|key| decrypt(key, data)) else {
+        return Some(advert);
+    };
+    // Same bud order as the clear nibbles
+    let (left, right) = if flipped(data[5]) {
+        (block[1], block[2])
+    } else {
+        (block[2], block[1])
+    };
+    (advert.left, advert.left_charging) = exact_level(left);
+    (advert.right, advert.right_charging) = exact_level(right);
+    (advert.case, advert.case_charging) = exact_level(block[3]);
+    advert.exact = true;
+    Some(advert)
+}
+
+/// Decrypted battery byte: percent in the low 7 bits, bit 7 set while
+/// charging, 0xff when not reported.
+fn exact_level(byte: u8) -> (Option<u8>, bool) {
+    let level = byte & 0x7f;
+    if byte == 0xff || level > 100 {
+        return (None, false);
+    }
+    (Some(level), byte & 0x80 != 0)
+}
+
+/// Bit 5 of the status byte swaps the order of the bud battery fields.
+fn flipped(status: u8) -> bool {
+    status & 0x20 == 0
+}
+
+/// Parse Apple manufacturer data (company ID already stripped).
+pub fn parse(data: &[u8]) -> Option<ProximityAdvert> {
+    if data.len() < 9 || data[0] != PROXIMITY_PAIRING {
+        return None;
+    }
+    let product_id = u16::from_le_bytes([data[3], data[4]]);
+    let flipped = flipped(data[5]);
+    let (hi, lo) = (data[6] >> 4, data[6] & 0x0f);
+    let (left, right) = if flipped { (hi, lo) } else { (lo, hi) };
+    let charging = data[7] >> 4;
+    let (left_bit, right_bit) = if flipped { (0x02, 0x01) } else { (0x01, 0x02) };
+    let (left_ear, right_ear) = if flipped { (0x08, 0x02) } else { (0x02, 0x08) };
+    Some(ProximityAdvert {
+        product_id,
+        left: battery_level(left),
+        right: battery_level(right),
+        case: battery_level(data[7] & 0x0f),
+        left_charging: charging & left_bit != 0,
+        right_charging: charging & right_bit != 0,
+        case_charging: charging & 0x04 != 0,
+        left_in_ear: data[5] & left_ear != 0,
+        right_in_ear: data[5] & right_ear != 0,
+        in_case: data[5] & 0x40 != 0,
+        lid_open: data[8] & 0x08 == 0,
+        lid_count: data[8] & 0x07,
+        exact: false,
+        color: data.get(9).copied(),
+    })
+}
+
+/// Battery nibble: 0-10 in tens of percent, 15 when unknown.
+fn battery_level(nibble: u8) -> Option<u8> {
+    (nibble <= 10).then_some(nibble * 10)
+}
+
+/// What the scan knows about an advertising address.
+struct Seen {
+    nearby: NearbyAirPods,
+    at: Instant,
+    rssi: RssiFilter,
+}
+
+/// Last lid state seen for one of our devices.
+#[derive(Debug, Clone, Copy)]
+struct Lid {
+    open: bool,
+    count: u8,
+    at: Instant,
+}
+
+/// Whether `advert` shows the lid opened since `prev`, or open at all when
+/// the device hasn't been heard for `NEARBY_TTL`.
+fn lid_opened(prev: Option<&Lid>, advert: &ProximityAdvert) -> bool {
+    if !advert.lid_open {
+        return false;
+    }
+    match prev {
+        Some(lid) if lid.at.elapsed() < NEARBY_TTL => !lid.open || lid.count != advert.lid_count,
+        _ => true,
+    }
+}
+
+/// Whether `advert` meets the `auto_connect` condition.
+fn wants_connect(policy: AutoConnect, advert: &ProximityAdvert) -> bool {
+    let in_ear = advert.left_in_ear || advert.right_in_ear;
+    match policy {
+        AutoConnect::Never => false,
+        AutoConnect::OutOfCase => !advert.in_case || in_ear,
+        AutoConnect::InEar => in_ear,
+    }
+}
+
+/// Connect a stored device by its own address in the background, if it is
+/// paired here; the BlueZ connection listener starts AACP as usual.
+fn spawn_connect(adapter: &Adapter, known: &DeviceKeys) {
+    let Ok(addr) = known.mac.parse::<Address>() else {
+        return;
+    };
+    let Ok(device) = adapter.device(addr) else {
+        return;
+    };
+    let name = known.name.clone();
+    tokio::spawn(async move {
+        if !device.is_paired().await.unwrap_or(false) {
+            return;
+        }
+        info!("Auto-connecting {}: the advert shows it in use", name);
+        if let Err(e) = device.connect().await {
+            warn!("Auto-connect to {} failed: {}", name, e);
+        }
+    });
+}
+
+/// Whether the advertiser is connected to this host, under the advertising
+/// address or (for our own AirPods) the stored one. AACP reports those;
+/// paired AirPods in use with a phone are only seen through adverts.
+async fn connected_here(adapter: &Adapter, address: Address, known: Option<&DeviceKeys>) -> bool {
+    let stored = known.and_then(|k| k.mac.parse::<Address>().ok());
+    for addr in std::iter::once(address).chain(stored) {
+        if let Ok(device) = adapter.device(addr)
+            && device.is_connected().await.unwrap_or(false)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Scan for proximity adverts from devices not connected to this host and
+/// publish them as `AppEvent::Nearby` until the adapter stops discovering.
+/// Adverts that resolve to a device in devices.json also send
+/// `AppEvent::LidOpened` when its case opens, and connect it as
+/// `auto_connect` says.
+pub async fn scan(
+    adapter: Adapter,
+    app_tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+    auto_connect: AutoConnect,
+) {
+    let filter = DiscoveryFilter {
+        transport: DiscoveryTransport::Le,
+        duplicate_data: true,
+        ..Default::default()
+    };
+    if let Err(e) = adapter.set_discovery_filter(filter).await {
+        warn!("Nearby scan: could not set LE discovery filter: {}", e);
+    }
+    let mut events = match adapter.discover_devices_with_changes().await {
+        Ok(events) => Box::pin(events),
+        Err(e) => {
+            warn!("Nearby scan unavailable: {}", e);
+            return;
+        }
+    };
+    info!("Scanning for nearby AirPods on {}", adapter.name());
+
+    // Filled by the first reload tick
+    let mut keys = Vec::new();
+    let mut seen: HashMap<String, Seen> = HashMap::new();
+    // By stored MAC, since the advertising address rotates
+    let mut lids: HashMap<String, Lid> = HashMap::new();
+    let mut connect_attempts: HashMap<String, Instant> = HashMap::new();
+    let mut published: Vec<NearbyAirPods> = Vec::new();
+    let mut refresh = tokio::time::interval(NEARBY_REFRESH);
+    let mut reload = tokio::time::interval(KEYS_RELOAD);
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(AdapterEvent::DeviceAdded(addr)) = event else {
+                    if event.is_none() {
+                        break;
+                    }
+                    continue;
+                };
+                let Ok(device) = adapter.device(addr) else { continue };
+                let Some(data) = device
+                    .manufacturer_data()
+                    .await
+                    .ok()
+                    .flatten()
+                    .and_then(|mut m| m.remove(&APPLE_VENDOR_ID))
+                else {
+                    continue;
+                };
+                let known = resolve(&keys, addr);
+                if connected_here(&adapter, addr, known).await {
+                    continue;
+                }
+                let Some(advert) = parse_with_key(&data, known.and_then(|k| k.enc_key.as_ref()))
+                else {
+                    continue;
+                };
+                let address = addr.to_string();
+                let mut rssi = seen.get(&address).map(|s| s.rssi).unwrap_or_default();
+                if let Ok(Some(reading)) = device.rssi().await {
+                    rssi.update(reading);
+                }
+                let nearby = NearbyAirPods {
+                    address: address.clone(),
+                    mac: known.map(|k| k.mac.clone()),
+                    name: known.map(|k| k.name.clone()),
+                    advert,
+                    distance: rssi.distance(),
+                    rssi: rssi.dbm(),
+                };
+                if let Some(known) = known {
+                    if lid_opened(lids.get(&known.mac), &nearby.advert) {
+                        info!("Case of {} opened nearby", known.name);
+                        let _ = app_tx.send(AppEvent::LidOpened(nearby.clone()));
+                    }
+                    let lid = Lid {
+                        open: nearby.advert.lid_open,
+                        count: nearby.advert.lid_count,
+                        at: Instant::now(),
+                    };
+                    lids.insert(known.mac.clone(), lid);
+                    if wants_connect(auto_connect, &nearby.advert)
+                        && !crate::session_lock::is_locked()
+                        && connect_attempts
+                            .get(&known.mac)
+                            .is_none_or(|at| at.elapsed() >= AUTO_CONNECT_RETRY)
+                    {
+                        connect_attempts.insert(known.mac.clone(), Instant::now());
+                        spawn_connect(&adapter, known);
+                    }
+                }
+                seen.insert(address, Seen { nearby, at: Instant::now(), rssi });
+            }
+            _ = refresh.tick() => {
+                seen.retain(|_, s| s.at.elapsed() < NEARBY_TTL);
+                let mut nearby: Vec<NearbyAirPods> =
+                    seen.values().map(|s| s.nearby.clone()).collect();
+                nearby.sort_by(|a, b| a.address.cmp(&b.address));
+                if nearby != published {
+                    debug!("Nearby AirPods: {}", nearby.len());
+                    published = nearby.clone();
+                    if app_tx.send(AppEvent::Nearby(nearby)).is_err() {
+                        break;
+                    }
+                }
+            }
+            _ = reload.tick() => {
+                keys = stored_keys(&crate::devices::store::load());
+            }
+        }
+    }
+    info!("Nearby scan stopped");
+}
+
+/// Print every Apple advert (raw manufacturer data, plus the decoded
+/// fields for proximity pairing) until interrupted. Paired devices are
+/// included; this is for bug reports, not for the TUI. With `decrypt`,
+/// adverts that resolve to a device in devices.json also get its MAC and
+/// the decrypted block.
+pub async fn dump(adapter: Adapter, decrypt: bool) -> bluer::Result<()> {
+    let keys = if decrypt {
+        stored_keys(&crate::devices::store::load())
+    } else {
+        Vec::new()
+    };
+    if decrypt && keys.is_empty() {
+        eprintln!(
+            "No IRK in {}; printing adverts undecrypted",
+            crate::utils::get_devices_path().display()
+        );
+    }
+    let filter = DiscoveryFilter {
+        transport: DiscoveryTransport::Le,
+        duplicate_data: true,
+        ..Default::default()
+    };
+    adapter.set_discovery_filter(filter).await?;
+    let mut events = Box::pin(adapter.discover_devices_with_changes().await?);
+    eprintln!(
+        "Dumping Apple adverts on {} (Ctrl+C to stop)",
+        adapter.name()
+    );
+    let start = Instant::now();
+    while let Some(event) = events.next().await {
+        let AdapterEvent::DeviceAdded(addr) = event else {
+            continue;
+        };
+        let Ok(device) = adapter.device(addr) else {
+            continue;
+        };
+        let Some(data) = device
+            .manufacturer_data()
+            .await
+            .ok()
+            .flatten()
+            .and_then(|mut m| m.remove(&APPLE_VENDOR_ID))
+        else {
+            continue;
+        };
+        let rssi = device.rssi().await.ok().flatten();
+        let mut line = dump_line(start.elapsed(), &addr.to_string(), rssi, &data);
+        if let Some(known) = resolve(&keys, addr) {
+            line.push_str(&decrypted_line(known, &data));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// The device an advert resolved to, and its decrypted block and levels.
+fn decrypted_line(known: &DeviceKeys, data: &[u8]) -> String {
+    let mut line = format!("\n           = {} ({})", known.mac, known.name);
+    let Some(enc_key) = &known.enc_key else {
+        line.push_str(", no EncKey stored");
+        return line;
+    };
+    let Some(block) = decrypt(enc_key, data) else {
+        return line;
+    };
+    line.push_str(&format!("\n           decrypted {}", hex::encode(block)));
+    if let Some(advert) = parse_with_key(data, Some(enc_key)) {
+        line.push_str(&format!(
+            " L={:?} R={:?} case={:?}",
+            advert.left, advert.right, advert.case
+        ));
+    }
+    line
+}
+
+fn dump_line(elapsed: Duration, address: &str, rssi: Option<i16>, data: &[u8]) -> String {
+    let rssi = rssi.map_or("   ?".to_string(), |r| format!("{:4}", r));
+    let mut line = format!(
+        "{:9.3}s {} {} dBm {}",
+        elapsed.as_secs_f64(),
+        address,
+        rssi,
+        hex::encode(data)
+    );
+    if let Some(advert) = parse(data) {
+        line.push_str(&format!(
+            "\n           {} (0x{:04x}) L={:?} R={:?} case={:?} in-ear L={} R={}",
+            advert.display_name(),
+            advert.product_id,
+            advert.left,
+            advert.right,
+            advert.case,
+            advert.left_in_ear,
+            advert.right_in_ear
+        ));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // AirPods Pro: left 70%, right 80%, case 50% and charging
+    const ADVERT: [u8; 27] = [
+        0x07, 0x19, 0x01, 0x0e, 0x20, 0x2b, 0x87, 0x45, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn parses_model_and_battery() {
+        let advert = parse(&ADVERT).unwrap();
+        assert_eq!(advert.product_id, 0x200e);
+        assert_eq!(advert.left, Some(70));
+        assert_eq!(advert.right, Some(80));
+        assert_eq!(advert.case, Some(50));
+        assert!(advert.case_charging);
+        assert!(!advert.left_charging && !advert.right_charging);
+        // Status 0x2b: 0x02 (left, unflipped) and 0x08 (right) set
+        assert!(advert.left_in_ear && advert.right_in_ear);
+    }
+
+    #[test]
+    fn model_and_color_name() {
+        let mut data = ADVERT;
+        assert_eq!(parse(&data).unwrap().display_name(), "AirPods Pro (White)");
+        data[9] = 0xee;
+        assert_eq!(parse(&data).unwrap().display_name(), "AirPods Pro");
+        assert_eq!(parse(&data[..9]).unwrap().color, None);
+    }
+
+    #[test]
+    fn flip_bit_swaps_buds() {
+        let mut data = ADVERT;
+        data[5] &= !0x20;
+        let advert = parse(&data).unwrap();
+        assert_eq!(advert.left, Some(80));
+        assert_eq!(advert.right, Some(70));
+        data[5] = 0x02;
+        let advert = parse(&data).unwrap();
+        assert!(!advert.left_in_ear && advert.right_in_ear);
+    }
+
+    #[test]
+    fn rssi_average_damps_jumps() {
+        let mut rssi = RssiFilter::default();
+        assert_eq!(rssi.distance(), None);
+        assert_eq!(rssi.update(-80), -80.0);
+        // One strong reading only nudges the average
+        assert_eq!(rssi.update(-50), -72.5);
+        assert_eq!(rssi.distance(), Some(Distance::Near));
+        for _ in 0..10 {
+            rssi.update(-50);
+        }
+        assert_eq!(rssi.distance(), Some(Distance::Close));
+        assert_eq!(rssi.dbm(), Some(-51));
+    }
+
+    #[test]
+    fn warmth_spans_the_rssi_range() {
+        assert_eq!(warmth(-110), 0);
+        assert_eq!(warmth(-70), 50);
+        assert_eq!(warmth(-40), 100);
+        assert_eq!(warmth(-20), 100);
+    }
+
+    #[test]
+    fn dump_line_decodes_proximity_pairing() {
+        let line = dump_line(
+            Duration::from_millis(1500),
+            "AA:BB",
+            Some(-61),
+            &ADVERT[..10],
+        );
+        assert!(line.starts_with("    1.500s AA:BB  -61 dBm 0719010e202b8745"));
+        assert!(line.contains("AirPods Pro (White) (0x200e) L=Some(70)"));
+        let other = dump_line(Duration::ZERO, "AA:BB", None, &[0x10, 0x05]);
+        assert_eq!(other, "    0.000s AA:BB    ? dBm 1005");
+    }
+
+    #[test]
+    fn resolves_addresses_made_from_the_irk() {
+        // Core spec Vol 6 Part C 1.1 sample: IRK ec0234a3...7d9b, prand
+        // 708194, hash 0dfbaa; stored least significant byte first
+        let mut irk: [u8; 16] = hex::decode("ec0234a357c8ad05341010a60a397d9b")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        irk.reverse();
+        let addr = |s: &str| s.parse::<Address>().unwrap();
+        assert!(resolves(&irk, addr("70:81:94:0D:FB:AA")));
+        assert!(!resolves(&irk, addr("70:81:94:0D:FB:AB")));
+        // Top bits 0b11: a static address, never resolvable
+        assert!(!resolves(&irk, addr("F0:81:94:0D:FB:AA")));
+    }
+
+    #[test]
+    fn enc_key_gives_exact_levels() {
+        let key = [0x42; 16];
+        let mut block = [0u8; 16];
+        // Unflipped: right first, then left, then the case
+        block[1] = 0x80 | 55;
+        block[2] = 93;
+        block[3] = 0xff;
+        let mut block = block.into();
+        Aes128::new(&key.into()).encrypt_block(&mut block);
+        let mut data = ADVERT;
+        data[27 - ENCRYPTED_LEN..].copy_from_slice(&block);
+
+        let advert = parse_with_key(&data, Some(&key)).unwrap();
+        assert!(advert.exact);
+        assert_eq!((advert.left, advert.left_charging), (Some(93), false));
+        assert_eq!((advert.right, advert.right_charging), (Some(55), true));
+        assert_eq!((advert.case, advert.case_charging), (None, false));
+        // Without the key, or too short to carry the block: clear fields
+        assert_eq!(parse_with_key(&data, None).unwrap().left, Some(70));
+        assert!(!parse_with_key(&data[..10], Some(&key)).unwrap().exact);
+    }
+
+    #[test]
+    fn dump_shows_the_resolved_device_and_block() {
+        let mut known = DeviceKeys {
+            mac: "AA:BB:CC:DD:EE:FF".into(),
+            name: "Pods".into(),
+            irk: [0; 16],
+            enc_key: None,
+        };
+        assert_eq!(
+            decrypted_line(&known, &ADVERT),
+            "\n           = AA:BB:CC:DD:EE:FF (Pods), no EncKey stored"
+        );
+        let key = [0x42; 16];
+        let mut block = [0u8; 16];
+        block[1..4].copy_from_slice(&[55, 93, 0xff]);
+        let mut encrypted = block.into();
+        Aes128::new(&key.into()).encrypt_block(&mut encrypted);
+        let mut data = ADVERT;
+        data[27 - ENCRYPTED_LEN..].copy_from_slice(&encrypted);
+        known.enc_key = Some(key);
+        let line = decrypted_line(&known, &data);
+        assert!(line.ends_with(&format!(
+            "decrypted {} L=Some(93) R=Some(55) case=None",
+            hex::encode(block)
+        )));
+    }
+
+    #[test]
+    fn auto_connect_waits_for_the_buds() {
+        // ADVERT: both buds in ear, sender not in the case
+        let mut advert = parse(&ADVERT).unwrap();
+        assert!(!wants_connect(AutoConnect::Never, &advert));
+        assert!(wants_connect(AutoConnect::InEar, &advert));
+        // Lid open with both buds still in the case
+        advert.left_in_ear = false;
+        advert.right_in_ear = false;
+        advert.in_case = true;
+        assert!(!wants_connect(AutoConnect::OutOfCase, &advert));
+        assert!(!wants_connect(AutoConnect::InEar, &advert));
+        // Taken out, not yet worn
+        advert.in_case = false;
+        assert!(wants_connect(AutoConnect::OutOfCase, &advert));
+        assert!(!wants_connect(AutoConnect::InEar, &advert));
+    }
+
+    #[test]
+    fn lid_opening_is_reported_once() {
+        let mut advert = parse(&ADVERT).unwrap();
+        assert!(advert.lid_open && advert.lid_count == 1);
+        assert!(lid_opened(None, &advert));
+        let lid = Lid {
+            open: true,
+            count: 1,
+            at: Instant::now(),
+        };
+        assert!(!lid_opened(Some(&lid), &advert));
+        advert.lid_count = 2;
+        assert!(lid_opened(Some(&lid), &advert));
+        advert.lid_open = false;
+        assert!(!lid_opened(None, &advert));
+    }
+
+    #[test]
+    fn unknown_levels_and_other_types() {
+        let mut data = ADVERT;
+        data[7] = 0x0f;
+        assert_eq!(parse(&data).unwrap().case, None);
+        data[0] = 0x10; // nearby-info, not proximity pairing
+        assert!(parse(&data).is_none());
+        assert!(parse(&ADVERT[..5]).is_none());
+    }
+}
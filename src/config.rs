@@ -1,4 +1,6 @@
-use log::info;
+use crossterm::event::{KeyCode, KeyModifiers};
+use log::{info, warn};
+use ratatui::style::Color;
 use serde::Deserialize;
 use std::path::PathBuf;
 
@@ -9,15 +11,679 @@ pub struct Config {
     /// with "+0" (display only; `volume_set_command` applies the volume).
     pub volume_osd_command: Vec<String>,
     /// Command to set absolute volume. `{}` is replaced with a 0.0-1.0 fraction.
+    /// `--click volume-up/-down` passes a wpctl-style step like "0.05+".
     pub volume_set_command: Vec<String>,
     /// Optional command to restart the audio server (e.g. WirePlumber).
     /// Set to `None` (the default) to disable the automatic restart.
     pub restart_audio_server: Option<Vec<String>>,
+    /// Audio server API for profile switching, sink volume and routing.
+    pub audio_backend: AudioBackend,
     /// Command to send a battery-low desktop notification. Fired by the
     /// daemon at 20% and 10% while discharging; `{}` is replaced with the
     /// component label and level, e.g. "Left battery: 18%".
     /// Set to `[]` to disable notifications.
     pub battery_alert_command: Vec<String>,
+    /// Emit D-Bus signals on battery and noise-mode changes (daemon mode
+    /// only), for bars and widgets that prefer push over polling.
+    pub dbus_signals: bool,
+    /// Report the lowest bud level to BlueZ as a battery provider (daemon
+    /// mode only), so it shows up in every Bluetooth applet.
+    pub battery_provider: bool,
+    /// Bluetooth adapter to use, e.g. "hci1". Unset uses BlueZ's default
+    /// adapter and watches devices on every adapter.
+    pub adapter: Option<String>,
+    /// Scan for AirPods advertising nearby (case open, or in use with a
+    /// phone) and list the ones not connected to this machine, with their
+    /// battery and in-ear state (daemon mode only). Our own AirPods are
+    /// recognised by the IRK in devices.json; their case opening pops up
+    /// the battery levels.
+    pub nearby_scan: bool,
+    /// Connect our paired AirPods when their adverts show them in use
+    /// (needs `nearby_scan` and the IRK). Waiting until they leave the case
+    /// or go in an ear means opening the lid near this machine doesn't pull
+    /// them away from the phone. Held off while the session is locked
+    /// (`pause_automation_when_locked`).
+    pub auto_connect: AutoConnect,
+    /// Also show other earbuds that expose the standard Battery Service,
+    /// as battery-only devices (daemon mode only).
+    pub gatt_battery: bool,
+    /// Record connections, battery reports and noise-mode changes in
+    /// `$XDG_STATE_HOME/airpods-tui/history.sqlite3` (daemon only).
+    pub history: bool,
+    /// Terminal used by the tray's "Open TUI" entry. `{}` is replaced with
+    /// the path of the airpods-tui binary.
+    pub tray_terminal_command: Vec<String>,
+    /// File mode of the IPC socket. Connecting clients are also checked
+    /// with SO_PEERCRED, so a wider mode alone never admits other users.
+    pub ipc_socket_mode: u32,
+    /// Group whose members may use the IPC socket (name or gid). Pair it
+    /// with `ipc_socket_mode = 0o660`.
+    pub ipc_socket_group: Option<String>,
+    /// Optional TCP address the daemon also serves IPC on; a bare port such
+    /// as "7625" binds loopback. Ignored unless `ipc_tcp_token` is set.
+    pub ipc_tcp_listen: Option<String>,
+    /// PEM certificate the TCP listener serves over TLS, required to bind a
+    /// non-loopback address. `--remote` clients trust exactly this
+    /// certificate when it is set in their own config.
+    pub ipc_tcp_tls_cert: Option<PathBuf>,
+    /// PEM private key for `ipc_tcp_tls_cert` (daemon only).
+    pub ipc_tcp_tls_key: Option<PathBuf>,
+    /// Shared secret remote clients must present before seeing any state.
+    /// Also used by `--remote` when AIRPODS_TUI_TOKEN is unset.
+    pub ipc_tcp_token: Option<String>,
+    /// Inhibit screen idle/lock while media is playing and both buds are in
+    /// ear, via org.freedesktop.ScreenSaver. Released as soon as either
+    /// condition ends.
+    pub idle_inhibit: bool,
+    /// Follow the logind session lock and skip ear-detection resume, A2DP
+    /// activation and `auto_connect` while locked, so the buds at the lock
+    /// screen don't start audio. Removal still pauses.
+    pub pause_automation_when_locked: bool,
+    /// MPRIS players to leave alone entirely (never paused, resumed or sent
+    /// stem commands). Entries match the bus name after
+    /// `org.mpris.MediaPlayer2.`, case-insensitively, so "firefox" also
+    /// covers "firefox.instance_1_42".
+    pub mpris_ignore: Vec<String>,
+    /// Players that receive stem play/pause/next/previous first, in order.
+    /// Unlisted players follow in bus order.
+    pub mpris_priority: Vec<String>,
+    /// Players ear detection never pauses or resumes, matched like
+    /// `mpris_ignore` or against the player's MPRIS `Identity` (e.g.
+    /// "Spotify"). They still get stem commands.
+    pub auto_pause_exceptions: Vec<String>,
+    /// When taking buds out pauses playback.
+    pub ear_pause: EarPausePolicy,
+    /// Putting the buds in also starts a player when nothing was paused by
+    /// taking them out. Off: only what ear detection paused resumes.
+    pub play_on_wear: bool,
+    /// Card profile to set when another device takes the audio. Unset (the
+    /// default) restores the profile that was active before we switched to
+    /// A2DP, or "off" if there wasn't one; set "off" to always turn it off.
+    pub release_profile: Option<String>,
+    /// A2DP codecs to try, best first, as card profile suffixes
+    /// (`a2dp-sink-<codec>`). The server's default profile is the fallback.
+    pub a2dp_codecs: Vec<String>,
+    /// On takeover, make the AirPods sink the default output and move
+    /// playing streams onto it.
+    pub switch_default_sink: bool,
+    /// Put the previous default output (and its streams) back when the
+    /// AirPods are released, go in the case, or disconnect.
+    pub restore_default_sink: bool,
+    /// Remember the sink volume per device and restore it when the
+    /// device's sink appears.
+    pub remember_volume: bool,
+    /// Switch the card to the headset (HFP) profile while an application
+    /// records from a microphone, and back to A2DP when it stops.
+    pub auto_headset_profile: bool,
+    /// What each stem press does.
+    pub stem: StemConfig,
+    /// AACP reconnect backoff after a dropped or failed session.
+    pub reconnect: ReconnectConfig,
+    /// Scripts the daemon runs on device events.
+    pub hooks: HooksConfig,
+    /// `--waybar` output settings.
+    pub waybar: WaybarConfig,
+    /// TUI key bindings.
+    pub keys: KeysConfig,
+    /// TUI color overrides.
+    pub colors: ColorsConfig,
+    /// Log level and destination.
+    pub log: LogConfig,
+}
+
+/// Which audio server API the audio thread uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBackend {
+    /// PipeWire when it manages audio, PulseAudio otherwise.
+    #[default]
+    Auto,
+    /// Always libpulse (PulseAudio, or PipeWire's pulse layer).
+    PulseAudio,
+    /// Always native PipeWire; needs the `pipewire` build feature.
+    PipeWire,
+}
+
+/// When ear detection pauses playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EarPausePolicy {
+    /// Pause as soon as either bud comes out.
+    AnyOut,
+    /// Pause only once both buds are out.
+    BothOut,
+    /// Never pause.
+    Never,
+}
+
+/// When the nearby scan connects our AirPods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoConnect {
+    /// Leave connecting to BlueZ and the user.
+    Never,
+    /// Once a bud is out of the case.
+    OutOfCase,
+    /// Once a bud is in an ear.
+    InEar,
+}
+
+/// What a stem press does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StemAction {
+    None,
+    PlayPause,
+    Next,
+    Previous,
+    /// Seek 30 seconds forward / back in the current track.
+    SkipForward,
+    SkipBack,
+    /// Mute/unmute the AirPods microphone (headset profile only).
+    MicMute,
+}
+
+/// `[stem]` table: action per press type.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct StemConfig {
+    /// Off: ignore AACP stem presses and leave media keys to BlueZ AVRCP.
+    pub enabled: bool,
+    pub single: StemAction,
+    pub double: StemAction,
+    pub triple: StemAction,
+    pub long: StemAction,
+}
+
+impl Default for StemConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            single: StemAction::PlayPause,
+            double: StemAction::Next,
+            triple: StemAction::Previous,
+            long: StemAction::None,
+        }
+    }
+}
+
+/// `[reconnect]` table: retry policy when an AACP session can't be
+/// (re-)established while BlueZ still reports the device connected.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    /// Delay before the first retry, in seconds; doubles on each failure.
+    pub initial_delay_secs: u64,
+    /// Upper bound for the delay between retries, in seconds.
+    pub max_delay_secs: u64,
+    /// Give up after this many failed attempts (0 retries forever).
+    pub max_attempts: u32,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_secs: 2,
+            max_delay_secs: 30,
+            max_attempts: 10,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Delay before the given (1-based) attempt.
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        let secs = self.initial_delay_secs.max(1).saturating_mul(factor);
+        std::time::Duration::from_secs(secs.min(self.max_delay_secs.max(1)))
+    }
+
+    /// Whether another attempt is allowed after `attempt` failed ones.
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts == 0 || attempt < self.max_attempts
+    }
+}
+
+/// `[waybar]` table: glyphs used in the module's `text`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WaybarConfig {
+    pub icon_left: String,
+    pub icon_right: String,
+    pub icon_case: String,
+    pub icon_headphone: String,
+    /// Appended to a component's level while it charges.
+    pub icon_charging: String,
+    /// Add class "warning" when any discharging component is at or below
+    /// this level.
+    pub warning: u8,
+    /// Add class "critical" at or below this level.
+    pub critical: u8,
+}
+
+impl Default for WaybarConfig {
+    fn default() -> Self {
+        Self {
+            icon_left: "L ".into(),
+            icon_right: "R ".into(),
+            icon_case: "C ".into(),
+            icon_headphone: String::new(),
+            icon_charging: "\u{26a1}".into(),
+            warning: 20,
+            critical: 10,
+        }
+    }
+}
+
+/// `[hooks]` table: argv vectors run by the daemon with event details in
+/// `AIRPODS_*` environment variables. Empty (the default) disables a hook.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub on_connect: Vec<String>,
+    pub on_disconnect: Vec<String>,
+    /// Fires at the same 20%/10% thresholds as `battery_alert_command`.
+    pub on_low_battery: Vec<String>,
+    pub on_ear_out: Vec<String>,
+    pub on_noise_mode_change: Vec<String>,
+    /// Needs `nearby_scan` and the device's IRK in devices.json.
+    pub on_lid_open: Vec<String>,
+}
+
+/// `[keys]` table: the key for each TUI shortcut, as a chord such as `"x"`,
+/// `"ctrl+t"` or `"f2"`. Navigation (arrows, Tab, Space/Enter) and Ctrl+C
+/// are fixed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub quit: String,
+    pub transparency: String,
+    /// Adaptive, or Noise Cancellation on models without Adaptive.
+    pub adaptive: String,
+    /// Noise Cancellation on models with Adaptive.
+    pub noise_cancellation: String,
+    pub conversation_awareness: String,
+    pub rename: String,
+    pub mic_mute: String,
+    pub info: String,
+    pub history: String,
+    /// Hot/cold meter for AirPods advertising nearby (`nearby_scan`).
+    pub locate: String,
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self {
+            quit: "q".into(),
+            transparency: "1".into(),
+            adaptive: "2".into(),
+            noise_cancellation: "3".into(),
+            conversation_awareness: "c".into(),
+            rename: "r".into(),
+            mic_mute: "m".into(),
+            info: "i".into(),
+            history: "h".into(),
+            locate: "l".into(),
+        }
+    }
+}
+
+/// TUI shortcuts that can be rebound in the `[keys]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Quit,
+    Transparency,
+    Adaptive,
+    NoiseCancellation,
+    ConversationAwareness,
+    Rename,
+    MicMute,
+    Info,
+    History,
+    Locate,
+}
+
+/// A `[keys]` entry that passed validation.
+#[derive(Debug, Clone)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    pub action: KeyAction,
+    /// The chord as written, for key hints.
+    pub chord: String,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers, action: KeyAction, chord: &str) -> Self {
+        Self {
+            code,
+            modifiers,
+            action,
+            chord: chord.to_string(),
+        }
+    }
+}
+
+impl KeysConfig {
+    /// The resolved bindings plus what was wrong with the table. A chord that
+    /// doesn't parse, is shadowed by a fixed key, or is already bound to an
+    /// earlier action keeps the action's default key; if that is taken too
+    /// the action is left unbound.
+    pub fn bindings(&self) -> (Vec<KeyBinding>, Vec<String>) {
+        let defaults = KeysConfig::default();
+        let entries = [
+            (&self.quit, &defaults.quit, KeyAction::Quit),
+            (
+                &self.transparency,
+                &defaults.transparency,
+                KeyAction::Transparency,
+            ),
+            (&self.adaptive, &defaults.adaptive, KeyAction::Adaptive),
+            (
+                &self.noise_cancellation,
+                &defaults.noise_cancellation,
+                KeyAction::NoiseCancellation,
+            ),
+            (
+                &self.conversation_awareness,
+                &defaults.conversation_awareness,
+                KeyAction::ConversationAwareness,
+            ),
+            (&self.rename, &defaults.rename, KeyAction::Rename),
+            (&self.mic_mute, &defaults.mic_mute, KeyAction::MicMute),
+            (&self.info, &defaults.info, KeyAction::Info),
+            (&self.history, &defaults.history, KeyAction::History),
+            (&self.locate, &defaults.locate, KeyAction::Locate),
+        ];
+        let mut bindings: Vec<KeyBinding> = Vec::new();
+        let mut problems = Vec::new();
+        for (chord, default, action) in entries {
+            let problem = match parse_chord(chord) {
+                None => format!("invalid key {:?} for {:?}", chord, action),
+                Some((code, mods)) => match clash(&bindings, code, mods) {
+                    None => {
+                        bindings.push(KeyBinding::new(code, mods, action, chord));
+                        continue;
+                    }
+                    Some(owner) => format!(
+                        "key {:?} for {:?} is already used by {}",
+                        chord, action, owner
+                    ),
+                },
+            };
+            match parse_chord(default)
+                .filter(|&(code, mods)| clash(&bindings, code, mods).is_none())
+            {
+                Some((code, mods)) => {
+                    problems.push(format!("{}, using {:?}", problem, default));
+                    bindings.push(KeyBinding::new(code, mods, action, default));
+                }
+                None => problems.push(format!("{}, leaving it unbound", problem)),
+            }
+        }
+        (bindings, problems)
+    }
+}
+
+/// What already holds `code`+`mods`: a fixed key or an earlier action.
+fn clash(bindings: &[KeyBinding], code: KeyCode, mods: KeyModifiers) -> Option<String> {
+    if is_fixed_key(code, mods) {
+        return Some("a fixed key".to_string());
+    }
+    bindings
+        .iter()
+        .find(|b| b.code == code && b.modifiers == mods)
+        .map(|b| format!("{:?}", b.action))
+}
+
+/// Keys the TUI handles before the rebindable shortcuts.
+fn is_fixed_key(code: KeyCode, mods: KeyModifiers) -> bool {
+    match code {
+        KeyCode::Tab
+        | KeyCode::BackTab
+        | KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Enter
+        | KeyCode::Char(' ') => true,
+        KeyCode::Char('c') => mods.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+/// Parse a chord such as `"x"`, `"ctrl+t"`, `"alt+enter"` or `"f2"`.
+fn parse_chord(chord: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut parts: Vec<&str> = chord.split('+').collect();
+    let key = parts.pop()?;
+    let mut modifiers = KeyModifiers::NONE;
+    for part in parts {
+        modifiers |= match part.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            _ => return None,
+        };
+    }
+    let lower = key.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "esc" => KeyCode::Esc,
+        "enter" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        f if f.len() > 1 && f.starts_with('f') => KeyCode::F(f[1..].parse().ok()?),
+        _ => {
+            let mut chars = key.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            KeyCode::Char(c)
+        }
+    };
+    Some((code, modifiers))
+}
+
+/// `[colors]` table: a named `theme`, plus overrides for single colors as
+/// names ("cyan", "darkgray"), `#rrggbb` or a 0-255 palette index. Unset
+/// entries keep the theme's colors.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ColorsConfig {
+    /// One of `THEMES`; unset is "default".
+    pub theme: Option<String>,
+    /// Selection markers, active values and popup borders.
+    pub accent: Option<String>,
+    /// Border of the focused section.
+    pub focus: Option<String>,
+    /// Section titles.
+    pub header: Option<String>,
+    /// Regular text.
+    pub fg: Option<String>,
+    /// Labels, hints and unfocused borders.
+    pub dim: Option<String>,
+    /// Battery bars and the "[charging]" tag while charging.
+    pub charging: Option<String>,
+}
+
+/// TUI colors resolved from `[colors]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub accent: Color,
+    pub focus: Color,
+    pub header: Color,
+    pub fg: Color,
+    pub dim: Color,
+    pub charging: Color,
+}
+
+/// Named themes for `[colors] theme`; the first is the default.
+pub const THEMES: &[(&str, Palette)] = &[
+    (
+        "default",
+        Palette {
+            accent: Color::Cyan,
+            focus: Color::Green,
+            header: Color::Yellow,
+            fg: Color::White,
+            dim: Color::DarkGray,
+            charging: Color::Cyan,
+        },
+    ),
+    (
+        "light",
+        Palette {
+            accent: Color::Blue,
+            focus: Color::Green,
+            header: Color::Magenta,
+            fg: Color::Black,
+            dim: Color::Gray,
+            charging: Color::Blue,
+        },
+    ),
+    (
+        "solarized",
+        Palette {
+            accent: Color::Rgb(0x26, 0x8b, 0xd2),
+            focus: Color::Rgb(0x85, 0x99, 0x00),
+            header: Color::Rgb(0xb5, 0x89, 0x00),
+            fg: Color::Rgb(0x93, 0xa1, 0xa1),
+            dim: Color::Rgb(0x58, 0x6e, 0x75),
+            charging: Color::Rgb(0x2a, 0xa1, 0x98),
+        },
+    ),
+    (
+        "gruvbox",
+        Palette {
+            accent: Color::Rgb(0x83, 0xa5, 0x98),
+            focus: Color::Rgb(0xb8, 0xbb, 0x26),
+            header: Color::Rgb(0xfa, 0xbd, 0x2f),
+            fg: Color::Rgb(0xeb, 0xdb, 0xb2),
+            dim: Color::Rgb(0x92, 0x83, 0x74),
+            charging: Color::Rgb(0x8e, 0xc0, 0x7c),
+        },
+    ),
+    (
+        "mono",
+        Palette {
+            accent: Color::Reset,
+            focus: Color::Reset,
+            header: Color::Reset,
+            fg: Color::Reset,
+            dim: Color::DarkGray,
+            charging: Color::Reset,
+        },
+    ),
+];
+
+impl Default for Palette {
+    fn default() -> Self {
+        THEMES[0].1
+    }
+}
+
+impl Palette {
+    /// Palette from config, with every problem logged.
+    pub fn from_config(colors: &ColorsConfig) -> Self {
+        let (palette, problems) = colors.palette();
+        for problem in problems {
+            warn!("[colors] {}", problem);
+        }
+        palette
+    }
+}
+
+impl ColorsConfig {
+    /// The resolved palette plus what was wrong with the table. An unknown
+    /// theme falls back to "default", and a color that doesn't parse keeps
+    /// the theme's color.
+    pub fn palette(&self) -> (Palette, Vec<String>) {
+        let mut problems = Vec::new();
+        let theme = self.theme.as_deref().unwrap_or(THEMES[0].0);
+        let base = match THEMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(theme))
+        {
+            Some((_, palette)) => *palette,
+            None => {
+                let names: Vec<&str> = THEMES.iter().map(|(name, _)| *name).collect();
+                problems.push(format!(
+                    "unknown theme {:?} (one of {}), using \"default\"",
+                    theme,
+                    names.join(", ")
+                ));
+                Palette::default()
+            }
+        };
+        let mut pick = |name: &str, value: &Option<String>, fallback: Color| match value {
+            None => fallback,
+            Some(v) => v.parse().unwrap_or_else(|_| {
+                problems.push(format!("invalid {} color {:?}, using the theme's", name, v));
+                fallback
+            }),
+        };
+        let palette = Palette {
+            accent: pick("accent", &self.accent, base.accent),
+            focus: pick("focus", &self.focus, base.focus),
+            header: pick("header", &self.header, base.header),
+            fg: pick("fg", &self.fg, base.fg),
+            dim: pick("dim", &self.dim, base.dim),
+            charging: pick("charging", &self.charging, base.charging),
+        };
+        (palette, problems)
+    }
+}
+
+/// Line format for stderr and file logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// env_logger's human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line: timestamp, level, module, mac, message
+    Json,
+}
+
+/// `[log]` table. `-d`, `--log-level` and `--log-file` take precedence,
+/// and `RUST_LOG` overrides the level entirely.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// "error", "warn", "info", "debug" or "trace" (default "warn").
+    pub level: Option<String>,
+    /// Line format for stderr and file output (the journal is always
+    /// structured).
+    pub format: LogFormat,
+    /// Log to a file instead of stderr.
+    pub file: bool,
+    /// Log to the systemd journal with structured fields (AIRPODS_MAC,
+    /// AIRPODS_SUBSYSTEM); wins over `file`.
+    pub journald: bool,
+    /// File to log to; defaults to `$XDG_STATE_HOME/airpods-tui/airpods-tui.log`.
+    pub path: Option<PathBuf>,
+    /// Rotate the file once it would grow past this size (0 never rotates).
+    pub max_size_kb: u64,
+    /// Rotated files kept next to it (`.1` is the newest).
+    pub keep: u32,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: None,
+            format: LogFormat::Text,
+            file: false,
+            journald: false,
+            path: None,
+            max_size_kb: 1024,
+            keep: 3,
+        }
+    }
 }
 
 impl Default for Config {
@@ -35,33 +701,191 @@ impl Default for Config {
                 "{}".into(),
             ],
             restart_audio_server: None,
+            audio_backend: AudioBackend::Auto,
             battery_alert_command: vec!["notify-send".into(), "AirPods".into(), "{}".into()],
+            dbus_signals: true,
+            battery_provider: true,
+            adapter: None,
+            nearby_scan: false,
+            auto_connect: AutoConnect::Never,
+            gatt_battery: false,
+            history: false,
+            tray_terminal_command: vec!["xdg-terminal-exec".into(), "{}".into()],
+            ipc_socket_mode: 0o600,
+            ipc_socket_group: None,
+            ipc_tcp_listen: None,
+            ipc_tcp_tls_cert: None,
+            ipc_tcp_tls_key: None,
+            ipc_tcp_token: None,
+            idle_inhibit: false,
+            pause_automation_when_locked: true,
+            mpris_ignore: Vec::new(),
+            mpris_priority: Vec::new(),
+            auto_pause_exceptions: Vec::new(),
+            ear_pause: EarPausePolicy::AnyOut,
+            play_on_wear: false,
+            release_profile: None,
+            a2dp_codecs: vec!["aac".into(), "sbc_xq".into(), "sbc".into()],
+            switch_default_sink: true,
+            restore_default_sink: true,
+            remember_volume: true,
+            auto_headset_profile: false,
+            stem: StemConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            hooks: HooksConfig::default(),
+            waybar: WaybarConfig::default(),
+            keys: KeysConfig::default(),
+            colors: ColorsConfig::default(),
+            log: LogConfig::default(),
+        }
+    }
+}
+
+/// How `Config::read` went, to be logged once the logger is up.
+pub enum LoadStatus {
+    Loaded(PathBuf),
+    Missing(PathBuf),
+    Invalid(PathBuf, String),
+}
+
+impl LoadStatus {
+    pub fn log(&self) {
+        match self {
+            LoadStatus::Loaded(path) => info!("Loaded config from {}", path.display()),
+            LoadStatus::Missing(path) => {
+                info!("No config file at {}, using defaults", path.display())
+            }
+            LoadStatus::Invalid(path, e) => {
+                log::warn!("Failed to parse {}: {}, using defaults", path.display(), e)
+            }
         }
     }
 }
 
 impl Config {
     pub fn load() -> Self {
+        let (cfg, status) = Self::read();
+        status.log();
+        cfg
+    }
+
+    /// Load without logging, for use before the logger exists.
+    pub fn read() -> (Self, LoadStatus) {
         let path = config_path();
         match std::fs::read_to_string(&path) {
             Ok(contents) => match toml::from_str::<Config>(&contents) {
-                Ok(cfg) => {
-                    info!("Loaded config from {}", path.display());
-                    cfg
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse {}: {}, using defaults", path.display(), e);
-                    Config::default()
-                }
+                Ok(cfg) => (cfg, LoadStatus::Loaded(path)),
+                Err(e) => (Config::default(), LoadStatus::Invalid(path, e.to_string())),
             },
-            Err(_) => {
-                info!("No config file at {}, using defaults", path.display());
-                Config::default()
+            Err(_) => (Config::default(), LoadStatus::Missing(path)),
+        }
+    }
+}
+
+#[derive(clap::Subcommand)]
+pub enum ConfigCommand {
+    /// Parse the config file and report invalid or clashing `[keys]` and
+    /// invalid `[colors]`
+    Check,
+    /// Write a starter config with the default `[keys]` table
+    Init {
+        #[arg(long, help = "Replace an existing config file")]
+        force: bool,
+    },
+}
+
+/// Entry point for the `config` subcommand.
+pub fn run(command: ConfigCommand) -> std::io::Result<()> {
+    let path = config_path();
+    match command {
+        ConfigCommand::Check => {
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    println!("No config file at {}, using defaults", path.display());
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let cfg = toml::from_str::<Config>(&contents).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            })?;
+            let problems: Vec<String> = [
+                ("keys", cfg.keys.bindings().1),
+                ("colors", cfg.colors.palette().1),
+            ]
+            .into_iter()
+            .flat_map(|(table, problems)| {
+                problems
+                    .into_iter()
+                    .map(move |p| format!("[{}] {}", table, p))
+            })
+            .collect();
+            if problems.is_empty() {
+                println!("{} is valid", path.display());
+                return Ok(());
+            }
+            for problem in &problems {
+                eprintln!("{}", problem);
             }
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}: {} problem(s)", path.display(), problems.len()),
+            ))
+        }
+        ConfigCommand::Init { force } => {
+            if path.exists() && !force {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} already exists (--force replaces it)", path.display()),
+                ));
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, starter_config())?;
+            println!("Wrote {}", path.display());
+            Ok(())
         }
     }
 }
 
+/// What `config init` writes: every setting is optional, so only the
+/// `[keys]` table is spelled out, with the built-in chords.
+fn starter_config() -> String {
+    let keys = KeysConfig::default();
+    format!(
+        "# airpods-tui config; see the README for every setting.\n\
+         \n\
+         # TUI shortcuts: a key (\"x\", \"F2\", \"space\", \"enter\", \"esc\") with optional\n\
+         # \"ctrl+\" / \"alt+\". Check edits with `airpods-tui config check`.\n\
+         [keys]\n\
+         quit = {:?}\n\
+         transparency = {:?}\n\
+         adaptive = {:?}\n\
+         noise_cancellation = {:?}\n\
+         conversation_awareness = {:?}\n\
+         rename = {:?}\n\
+         mic_mute = {:?}\n\
+         info = {:?}\n\
+         history = {:?}\n\
+         locate = {:?}\n",
+        keys.quit,
+        keys.transparency,
+        keys.adaptive,
+        keys.noise_cancellation,
+        keys.conversation_awareness,
+        keys.rename,
+        keys.mic_mute,
+        keys.info,
+        keys.history,
+        keys.locate,
+    )
+}
+
 fn config_path() -> PathBuf {
     dirs_path().join("config.toml")
 }
@@ -82,22 +906,132 @@ fn dirs_path() -> PathBuf {
 /// so there is no shell-injection risk. The first element of `template` is
 /// executed directly as a binary path.
 pub fn run_template_cmd(template: &[String], value: &str) {
+    let _ = try_template_cmd(template, value);
+}
+
+/// Like [`run_template_cmd`], but reports a command that can't start or
+/// exits non-zero.
+pub fn try_template_cmd(template: &[String], value: &str) -> std::io::Result<()> {
     if template.is_empty() {
-        return;
+        return Ok(());
     }
     let args: Vec<String> = template
         .iter()
         .map(|arg| arg.replace("{}", value))
         .collect();
-    let _ = std::process::Command::new(&args[0])
+    let output = std::process::Command::new(&args[0])
         .args(&args[1..])
-        .output();
+        .output()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", args[0], e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{} exited with {}",
+            args[0], output.status
+        )))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn starter_config_parses_to_default_keys() {
+        let cfg: Config = toml::from_str(&starter_config()).unwrap();
+        let defaults = KeysConfig::default();
+        assert_eq!(cfg.keys.quit, defaults.quit);
+        assert_eq!(cfg.keys.info, defaults.info);
+        assert!(cfg.keys.bindings().1.is_empty());
+    }
+
+    #[test]
+    fn chords_parse() {
+        assert_eq!(
+            parse_chord("x"),
+            Some((KeyCode::Char('x'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_chord("ctrl+t"),
+            Some((KeyCode::Char('t'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(parse_chord("F2"), Some((KeyCode::F(2), KeyModifiers::NONE)));
+        assert_eq!(
+            parse_chord("f"),
+            Some((KeyCode::Char('f'), KeyModifiers::NONE))
+        );
+        assert_eq!(parse_chord("hyper+x"), None);
+        assert_eq!(parse_chord("xy"), None);
+        assert_eq!(parse_chord(""), None);
+    }
+
+    #[test]
+    fn conflicting_chords_are_rejected() {
+        let keys = KeysConfig {
+            rename: "m".into(),
+            info: "space".into(),
+            quit: "ctrl+c".into(),
+            ..Default::default()
+        };
+        let (bindings, problems) = keys.bindings();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        let bound = |c: char| {
+            bindings
+                .iter()
+                .find(|b| b.code == KeyCode::Char(c) && b.modifiers == KeyModifiers::NONE)
+                .map(|b| b.action)
+        };
+        // Fixed keys win; the action keeps its default
+        assert_eq!(bound('q'), Some(KeyAction::Quit));
+        assert_eq!(bound('i'), Some(KeyAction::Info));
+        // The earlier action keeps "m", so MicMute falls back to its
+        // default, which Rename now holds, and ends up unbound
+        assert_eq!(bound('m'), Some(KeyAction::Rename));
+        assert_eq!(bound('r'), None);
+        assert!(bindings.iter().all(|b| b.action != KeyAction::MicMute));
+        assert!(problems.iter().any(|p| p.contains("MicMute")));
+        assert!(KeysConfig::default().bindings().1.is_empty());
+    }
+
+    #[test]
+    fn palette_overrides_and_falls_back() {
+        let colors = ColorsConfig {
+            accent: Some("#102030".into()),
+            dim: Some("not a color".into()),
+            ..Default::default()
+        };
+        let (p, problems) = colors.palette();
+        let default = Palette::default();
+        assert_eq!(p.accent, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(p.dim, default.dim);
+        assert_eq!(p.focus, default.focus);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+    }
+
+    #[test]
+    fn named_theme_under_overrides() {
+        let colors: ColorsConfig = toml::from_str(
+            r#"
+theme = "Light"
+header = "red"
+"#,
+        )
+        .unwrap();
+        let (p, problems) = colors.palette();
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(p.header, Color::Red);
+        assert_eq!(p.fg, Color::Black);
+
+        let colors = ColorsConfig {
+            theme: Some("neon".into()),
+            ..Default::default()
+        };
+        let (p, problems) = colors.palette();
+        assert_eq!(p, Palette::default());
+        assert!(problems[0].contains("neon"));
+    }
+
     #[test]
     fn default_config_has_expected_commands() {
         let cfg = Config::default();
@@ -134,6 +1068,16 @@ volume_set_command = ["echo", "{}"]
         assert!(cfg.battery_alert_command.is_empty());
     }
 
+    #[test]
+    fn config_parses_audio_backend() {
+        assert_eq!(Config::default().audio_backend, AudioBackend::Auto);
+        let cfg: Config = toml::from_str(r#"audio_backend = "pulseaudio""#).unwrap();
+        assert_eq!(cfg.audio_backend, AudioBackend::PulseAudio);
+        let cfg: Config = toml::from_str(r#"audio_backend = "pipewire""#).unwrap();
+        assert_eq!(cfg.audio_backend, AudioBackend::PipeWire);
+        assert!(toml::from_str::<Config>(r#"audio_backend = "alsa""#).is_err());
+    }
+
     #[test]
     fn config_can_set_restart_audio_server() {
         let cfg: Config = toml::from_str(
@@ -151,10 +1095,303 @@ volume_set_command = ["echo", "{}"]
         );
     }
 
+    /// Integrations are opt-in unless they only add to what the desktop
+    /// already shows; an omitted key must match `Config::default()`.
+    #[test]
+    fn defaults_for_omitted_keys() {
+        type Flag = (&'static str, fn(&Config) -> bool, bool);
+        let flags: &[Flag] = &[
+            ("battery_provider", |c| c.battery_provider, true),
+            ("dbus_signals", |c| c.dbus_signals, true),
+            ("idle_inhibit", |c| c.idle_inhibit, false),
+            (
+                "pause_automation_when_locked",
+                |c| c.pause_automation_when_locked,
+                true,
+            ),
+            ("auto_headset_profile", |c| c.auto_headset_profile, false),
+            ("remember_volume", |c| c.remember_volume, true),
+            ("switch_default_sink", |c| c.switch_default_sink, true),
+            ("restore_default_sink", |c| c.restore_default_sink, true),
+            ("play_on_wear", |c| c.play_on_wear, false),
+            ("nearby_scan", |c| c.nearby_scan, false),
+            ("gatt_battery", |c| c.gatt_battery, false),
+            ("history", |c| c.history, false),
+            (
+                "ipc_tcp_tls_cert set",
+                |c| c.ipc_tcp_tls_cert.is_some(),
+                false,
+            ),
+            (
+                "ipc_tcp_tls_key set",
+                |c| c.ipc_tcp_tls_key.is_some(),
+                false,
+            ),
+            ("ipc_tcp_listen set", |c| c.ipc_tcp_listen.is_some(), false),
+            ("ipc_tcp_token set", |c| c.ipc_tcp_token.is_some(), false),
+            ("adapter set", |c| c.adapter.is_some(), false),
+        ];
+        let parsed: Config = toml::from_str("").unwrap();
+        for (name, get, expected) in flags {
+            assert_eq!(get(&Config::default()), *expected, "{} default", name);
+            assert_eq!(get(&parsed), *expected, "{} when omitted", name);
+        }
+        assert_eq!(parsed.ipc_socket_mode, 0o600);
+    }
+
+    #[test]
+    fn config_idle_inhibit_is_opt_in() {
+        assert!(!Config::default().idle_inhibit);
+        let cfg: Config = toml::from_str("idle_inhibit = true").unwrap();
+        assert!(cfg.idle_inhibit);
+    }
+
+    #[test]
+    fn config_lock_suppression_default_on() {
+        assert!(Config::default().pause_automation_when_locked);
+        let cfg: Config = toml::from_str("pause_automation_when_locked = false").unwrap();
+        assert!(!cfg.pause_automation_when_locked);
+    }
+
+    #[test]
+    fn config_dbus_signals_default_on() {
+        assert!(Config::default().dbus_signals);
+        let cfg: Config = toml::from_str("dbus_signals = false").unwrap();
+        assert!(!cfg.dbus_signals);
+    }
+
+    #[test]
+    fn config_can_disable_battery_provider() {
+        assert!(Config::default().battery_provider);
+        let cfg: Config = toml::from_str("battery_provider = false").unwrap();
+        assert!(!cfg.battery_provider);
+    }
+
+    #[test]
+    fn config_socket_mode_accepts_octal() {
+        assert_eq!(Config::default().ipc_socket_mode, 0o600);
+        let cfg: Config = toml::from_str(
+            r#"
+ipc_socket_mode = 0o660
+ipc_socket_group = "audio"
+"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.ipc_socket_mode, 0o660);
+        assert_eq!(cfg.ipc_socket_group.as_deref(), Some("audio"));
+    }
+
+    #[test]
+    fn config_remote_ipc_is_off_by_default() {
+        let cfg = Config::default();
+        assert!(cfg.ipc_tcp_listen.is_none());
+        assert!(cfg.ipc_tcp_token.is_none());
+        let cfg: Config = toml::from_str(
+            r#"
+ipc_tcp_listen = "0.0.0.0:7625"
+ipc_tcp_token = "s3cret"
+ipc_tcp_tls_cert = "/etc/airpods-tui/cert.pem"
+ipc_tcp_tls_key = "/etc/airpods-tui/key.pem"
+"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.ipc_tcp_listen.as_deref(), Some("0.0.0.0:7625"));
+        assert_eq!(cfg.ipc_tcp_token.as_deref(), Some("s3cret"));
+        assert_eq!(
+            cfg.ipc_tcp_tls_cert,
+            Some(PathBuf::from("/etc/airpods-tui/cert.pem"))
+        );
+        assert!(cfg.ipc_tcp_tls_key.is_some());
+    }
+
+    #[test]
+    fn config_reads_hooks_table() {
+        let cfg: Config = toml::from_str(
+            r#"
+[hooks]
+on_connect = ["notify-send", "AirPods connected"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.hooks.on_connect,
+            vec!["notify-send", "AirPods connected"]
+        );
+        assert!(cfg.hooks.on_disconnect.is_empty());
+    }
+
     #[test]
     fn run_template_cmd_with_empty_template_does_not_spawn() {
         // No assertion needed beyond "doesn't panic"; an empty template must early-return
         // before std::process::Command would be invoked with index 0.
         run_template_cmd(&[], "anything");
     }
+
+    #[test]
+    fn try_template_cmd_reports_failures() {
+        assert!(try_template_cmd(&[], "x").is_ok());
+        assert!(try_template_cmd(&["true".into()], "x").is_ok());
+        let err = try_template_cmd(&["false".into()], "x").unwrap_err();
+        assert!(err.to_string().starts_with("false exited with"));
+        assert!(try_template_cmd(&["/nonexistent/airpods-tui-cmd".into()], "x").is_err());
+    }
+
+    #[test]
+    fn config_a2dp_codecs_prefer_aac() {
+        assert_eq!(Config::default().a2dp_codecs, ["aac", "sbc_xq", "sbc"]);
+        let cfg: Config = toml::from_str(r#"a2dp_codecs = ["sbc_xq"]"#).unwrap();
+        assert_eq!(cfg.a2dp_codecs, ["sbc_xq"]);
+    }
+
+    #[test]
+    fn reconnect_backoff_doubles_up_to_cap() {
+        let rc = ReconnectConfig::default();
+        let secs: Vec<u64> = (1..=6).map(|n| rc.delay(n).as_secs()).collect();
+        assert_eq!(secs, [2, 4, 8, 16, 30, 30]);
+        assert!(rc.allows(9));
+        assert!(!rc.allows(10));
+        let cfg: Config = toml::from_str("[reconnect]\nmax_attempts = 0").unwrap();
+        assert!(cfg.reconnect.allows(u32::MAX - 1));
+        assert_eq!(cfg.reconnect.delay(u32::MAX).as_secs(), 30);
+    }
+
+    #[test]
+    fn config_stem_can_be_disabled() {
+        assert!(Config::default().stem.enabled);
+        let cfg: Config = toml::from_str("[stem]\nenabled = false").unwrap();
+        assert!(!cfg.stem.enabled);
+        assert_eq!(cfg.stem.single, StemAction::PlayPause);
+    }
+
+    #[test]
+    fn config_stem_skip_actions() {
+        let cfg: Config = toml::from_str("[stem]\nlong = \"skip-forward\"").unwrap();
+        assert_eq!(cfg.stem.long, StemAction::SkipForward);
+    }
+
+    #[test]
+    fn config_auto_connect() {
+        assert_eq!(Config::default().auto_connect, AutoConnect::Never);
+        let cfg: Config = toml::from_str("auto_connect = \"out-of-case\"").unwrap();
+        assert_eq!(cfg.auto_connect, AutoConnect::OutOfCase);
+        let cfg: Config = toml::from_str("auto_connect = \"in-ear\"").unwrap();
+        assert_eq!(cfg.auto_connect, AutoConnect::InEar);
+    }
+
+    #[test]
+    fn config_ear_pause_policy() {
+        assert_eq!(Config::default().ear_pause, EarPausePolicy::AnyOut);
+        let cfg: Config = toml::from_str("ear_pause = \"both-out\"").unwrap();
+        assert_eq!(cfg.ear_pause, EarPausePolicy::BothOut);
+        let cfg: Config = toml::from_str("ear_pause = \"never\"").unwrap();
+        assert_eq!(cfg.ear_pause, EarPausePolicy::Never);
+    }
+
+    #[test]
+    fn config_log_defaults_to_stderr() {
+        let cfg = Config::default();
+        assert!(!cfg.log.file);
+        assert_eq!(cfg.log.level, None);
+        let cfg: Config = toml::from_str("[log]\nfile = true\nkeep = 5").unwrap();
+        assert!(cfg.log.file);
+        assert_eq!(cfg.log.keep, 5);
+        assert_eq!(cfg.log.max_size_kb, 1024);
+        assert_eq!(cfg.log.format, LogFormat::Text);
+        let cfg: Config = toml::from_str("[log]\nformat = \"json\"").unwrap();
+        assert_eq!(cfg.log.format, LogFormat::Json);
+    }
+
+    #[test]
+    fn config_keys_partial_table() {
+        let cfg: Config = toml::from_str("[keys]\nquit = \"x\"").unwrap();
+        assert_eq!(cfg.keys.quit, "x");
+        assert_eq!(cfg.keys.rename, "r");
+    }
+
+    #[test]
+    fn config_history_default_off() {
+        assert!(!Config::default().history);
+        let cfg: Config = toml::from_str("history = true").unwrap();
+        assert!(cfg.history);
+    }
+
+    #[test]
+    fn config_gatt_battery_default_off() {
+        assert!(!Config::default().gatt_battery);
+        let cfg: Config = toml::from_str("gatt_battery = true").unwrap();
+        assert!(cfg.gatt_battery);
+    }
+
+    #[test]
+    fn config_nearby_scan_default_off() {
+        assert!(!Config::default().nearby_scan);
+        let cfg: Config = toml::from_str("nearby_scan = true").unwrap();
+        assert!(cfg.nearby_scan);
+    }
+
+    #[test]
+    fn config_adapter() {
+        assert_eq!(Config::default().adapter, None);
+        let cfg: Config = toml::from_str("adapter = \"hci1\"").unwrap();
+        assert_eq!(cfg.adapter.as_deref(), Some("hci1"));
+    }
+
+    #[test]
+    fn config_play_on_wear_default_off() {
+        assert!(!Config::default().play_on_wear);
+        let cfg: Config = toml::from_str("play_on_wear = true").unwrap();
+        assert!(cfg.play_on_wear);
+    }
+
+    #[test]
+    fn config_auto_headset_profile_default_off() {
+        assert!(!Config::default().auto_headset_profile);
+        assert!(Config::default().remember_volume);
+        assert!(Config::default().switch_default_sink);
+        assert!(Config::default().restore_default_sink);
+        let cfg: Config = toml::from_str("auto_headset_profile = true").unwrap();
+        assert!(cfg.auto_headset_profile);
+    }
+
+    #[test]
+    fn config_reads_mpris_lists() {
+        let cfg: Config = toml::from_str(
+            r#"
+mpris_ignore = ["firefox", "chromium"]
+mpris_priority = ["spotify"]
+auto_pause_exceptions = ["Zoom"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.mpris_ignore, ["firefox", "chromium"]);
+        assert_eq!(cfg.mpris_priority, ["spotify"]);
+        assert_eq!(cfg.auto_pause_exceptions, ["Zoom"]);
+        assert!(Config::default().mpris_ignore.is_empty());
+    }
+
+    #[test]
+    fn config_reads_stem_actions() {
+        let cfg: Config = toml::from_str(
+            r#"
+[stem]
+long = "mic-mute"
+"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.stem.long, StemAction::MicMute);
+        assert_eq!(cfg.stem.single, StemAction::PlayPause);
+    }
+
+    #[test]
+    fn config_reads_waybar_icons() {
+        let cfg: Config = toml::from_str(
+            r#"
+[waybar]
+icon_left = "Left "
+"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.waybar.icon_left, "Left ");
+        assert_eq!(cfg.waybar.icon_right, WaybarConfig::default().icon_right);
+    }
 }
@@ -0,0 +1,19 @@
+//! Session-bus service exported by the daemon.
+//!
+//! Everything we publish on D-Bus lives under one well-known name so
+//! desktop integrations only have to watch a single owner.
+
+pub mod signals;
+
+/// Well-known name owned by the daemon on the session bus.
+pub const BUS_NAME: &str = "io.github.annoyedmilk.AirPodsTui";
+/// Root object path; child objects hang off this prefix.
+pub const ROOT_PATH: &str = "/io/github/annoyedmilk/AirPodsTui";
+
+/// Connect to the session bus and claim [`BUS_NAME`].
+pub async fn connect() -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .build()
+        .await
+}
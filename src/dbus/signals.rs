@@ -0,0 +1,190 @@
+//! Push-style state change signals.
+//!
+//! Bars and widgets subscribe to these instead of polling `--waybar`.
+//! Signals are emitted from [`ROOT_PATH`] on the
+//! `io.github.annoyedmilk.AirPodsTui1` interface.
+
+use super::ROOT_PATH;
+use crate::bluetooth::aacp::{
+    AACPEvent, BatteryComponent, BatteryInfo, BatteryStatus, ControlCommandIdentifiers,
+};
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::tui::app::AppEvent;
+use log::debug;
+use std::collections::HashMap;
+use zbus::object_server::SignalEmitter;
+
+/// Battery levels in signal argument order; -1 = unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Levels {
+    left: i32,
+    right: i32,
+    case: i32,
+    headphone: i32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            left: -1,
+            right: -1,
+            case: -1,
+            headphone: -1,
+        }
+    }
+}
+
+impl Levels {
+    fn merge(mut self, infos: &[BatteryInfo]) -> Self {
+        for b in infos {
+            // A closed case reports Disconnected; keep its last level
+            if b.status == BatteryStatus::Disconnected {
+                continue;
+            }
+            let level = b.level as i32;
+            match b.component {
+                BatteryComponent::Left => self.left = level,
+                BatteryComponent::Right => self.right = level,
+                BatteryComponent::Case => self.case = level,
+                BatteryComponent::Headphone => self.headphone = level,
+            }
+        }
+        self
+    }
+}
+
+struct StateSignals;
+
+#[zbus::interface(name = "io.github.annoyedmilk.AirPodsTui1")]
+impl StateSignals {
+    /// Levels are 0-100, or -1 when the component hasn't reported.
+    #[zbus(signal)]
+    async fn battery_changed(
+        emitter: &SignalEmitter<'_>,
+        mac: &str,
+        left: i32,
+        right: i32,
+        case: i32,
+        headphone: i32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn noise_mode_changed(
+        emitter: &SignalEmitter<'_>,
+        mac: &str,
+        mode: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn device_connected(
+        emitter: &SignalEmitter<'_>,
+        mac: &str,
+        name: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn device_disconnected(emitter: &SignalEmitter<'_>, mac: &str) -> zbus::Result<()>;
+}
+
+/// Turns AppEvents into D-Bus signals, only when the value actually changed.
+pub struct StateSignalEmitter {
+    emitter: SignalEmitter<'static>,
+    levels: HashMap<String, Levels>,
+    noise_modes: HashMap<String, u8>,
+}
+
+impl StateSignalEmitter {
+    pub async fn new(conn: &zbus::Connection) -> zbus::Result<Self> {
+        conn.object_server().at(ROOT_PATH, StateSignals).await?;
+        Ok(Self {
+            emitter: SignalEmitter::new(conn, ROOT_PATH)?.into_owned(),
+            levels: HashMap::new(),
+            noise_modes: HashMap::new(),
+        })
+    }
+
+    pub async fn handle(&mut self, event: &AppEvent) {
+        let result = match event {
+            AppEvent::DeviceConnected { mac, name, .. } => {
+                StateSignals::device_connected(&self.emitter, mac, name).await
+            }
+            AppEvent::DeviceDisconnected(mac) => {
+                self.levels.remove(mac);
+                self.noise_modes.remove(mac);
+                StateSignals::device_disconnected(&self.emitter, mac).await
+            }
+            AppEvent::AACPEvent(mac, aacp_event) => match &**aacp_event {
+                AACPEvent::BatteryInfo(infos) => {
+                    let prev = self.levels.get(mac).copied().unwrap_or_default();
+                    let next = prev.merge(infos);
+                    self.levels.insert(mac.clone(), next);
+                    if next == prev {
+                        return;
+                    }
+                    StateSignals::battery_changed(
+                        &self.emitter,
+                        mac,
+                        next.left,
+                        next.right,
+                        next.case,
+                        next.headphone,
+                    )
+                    .await
+                }
+                AACPEvent::ControlCommand(cmd)
+                    if cmd.identifier == ControlCommandIdentifiers::ListeningMode =>
+                {
+                    let Some(&byte) = cmd.value.first() else {
+                        return;
+                    };
+                    if self.noise_modes.insert(mac.clone(), byte) == Some(byte) {
+                        return;
+                    }
+                    let mode = AirPodsNoiseControlMode::from_byte(byte).to_string();
+                    StateSignals::noise_mode_changed(&self.emitter, mac, &mode).await
+                }
+                _ => return,
+            },
+            AppEvent::AudioUnavailable
+            | AppEvent::AudioProfile { .. }
+            | AppEvent::MicMuted { .. }
+            | AppEvent::NowPlaying { .. }
+            | AppEvent::Playback { .. }
+            | AppEvent::LinkLost(_)
+            | AppEvent::CommandFailed { .. }
+            | AppEvent::BatteryDevice { .. }
+            | AppEvent::LastSeen { .. }
+            | AppEvent::CachedBattery { .. }
+            | AppEvent::Nearby(_)
+            | AppEvent::LidOpened(_) => return,
+        };
+        if let Err(e) = result {
+            debug!("Failed to emit D-Bus signal: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(component: BatteryComponent, level: u8, status: BatteryStatus) -> BatteryInfo {
+        BatteryInfo {
+            component,
+            level,
+            status,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_last_level_of_closed_case() {
+        let levels = Levels::default().merge(&[
+            info(BatteryComponent::Left, 80, BatteryStatus::NotCharging),
+            info(BatteryComponent::Case, 60, BatteryStatus::NotCharging),
+        ]);
+        let levels = levels.merge(&[info(BatteryComponent::Case, 0, BatteryStatus::Disconnected)]);
+        assert_eq!(levels.left, 80);
+        assert_eq!(levels.right, -1);
+        assert_eq!(levels.case, 60);
+    }
+}
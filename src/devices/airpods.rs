@@ -1,10 +1,8 @@
 use crate::bluetooth::aacp::ControlCommandIdentifiers;
-use crate::bluetooth::aacp::{
-    AACPEvent, AACPManager, AirPodsLEKeys, ProximityKeyType, StemPressType, opcodes,
-};
+use crate::bluetooth::aacp::{AACPEvent, AACPManager, AirPodsLEKeys, ProximityKeyType, opcodes};
 use crate::config::Config;
 use crate::media_controller::MediaController;
-use crate::tui::app::AppEvent;
+use crate::tui::app::{AppEvent, DataSource, LAST_SEEN_STEP_SECS};
 use bluer::Address;
 use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
@@ -15,6 +13,7 @@ use tokio::time::Duration;
 
 pub struct AirPodsDevice {
     pub aacp_manager: AACPManager,
+    pub media_controller: Arc<Mutex<MediaController>>,
 }
 
 impl AirPodsDevice {
@@ -27,7 +26,21 @@ impl AirPodsDevice {
     ) -> Result<Self, bluer::Error> {
         info!("Creating new AirPodsDevice for {}", mac_address);
         let mut aacp_manager = AACPManager::new();
-        aacp_manager.connect(mac_address).await;
+        let session = bluer::Session::new().await?;
+        let pinned = {
+            let state = aacp_manager.state.lock().await;
+            crate::bluetooth::preferred_adapter(
+                state.devices.get(&mac_address.to_string()),
+                config.adapter.as_deref(),
+            )
+            .map(str::to_string)
+        };
+        let adapter = crate::bluetooth::open_adapter(&session, pinned.as_deref()).await?;
+        let local = match pinned {
+            Some(_) => Some(adapter.address().await?),
+            None => None,
+        };
+        aacp_manager.connect(mac_address, local).await;
 
         // connect() logs but doesn't return an error. If the L2CAP socket
         // didn't come up, sender stays None and every later send_*  call would
@@ -225,8 +238,6 @@ impl AirPodsDevice {
         let _ = Self::wait_for_opcode(&aacp_manager, Some(opcodes::PROXIMITY_KEYS_RSP), 500).await;
 
         // ── Media controller setup ──
-        let session = bluer::Session::new().await?;
-        let adapter = session.default_adapter().await?;
         let local_mac = adapter.address().await?.to_string();
 
         let media_controller = Arc::new(Mutex::new(MediaController::new(
@@ -258,6 +269,34 @@ impl AirPodsDevice {
             }
         });
 
+        // Report when AACP data last arrived, so clients can flag stale values
+        let aacp_seen = aacp_manager.clone();
+        let app_tx_seen = app_tx.clone();
+        let mac_seen = mac_address.to_string();
+        tokio::spawn(async move {
+            let step = Duration::from_secs(LAST_SEEN_STEP_SECS);
+            let mut sent = None;
+            loop {
+                tokio::time::sleep(step).await;
+                let Some(idle) = aacp_seen.idle().await else {
+                    break; // session gone
+                };
+                let at = crate::utils::unix_now().saturating_sub(idle.as_secs());
+                if sent.is_some_and(|prev| at < prev + LAST_SEEN_STEP_SECS) {
+                    continue;
+                }
+                sent = Some(at);
+                let event = AppEvent::LastSeen {
+                    mac: mac_seen.clone(),
+                    source: DataSource::Aacp,
+                    at,
+                };
+                if app_tx_seen.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
         // Main AACP event loop
         let aacp_manager_clone_events = aacp_manager.clone();
         let local_mac_events = local_mac.clone();
@@ -328,6 +367,7 @@ impl AirPodsDevice {
                     }
                     AACPEvent::ConnectionLost => {
                         info!("AACP L2CAP connection lost for {}", mac_address);
+                        let _ = app_tx_events.send(AppEvent::LinkLost(mac_address.to_string()));
                         // Request reconnect from bluetooth_main (if running in-process)
                         if let Some(ref rtx) = reconnect_tx_clone {
                             let _ = rtx.send((mac_address, product_id));
@@ -336,23 +376,7 @@ impl AirPodsDevice {
                     }
                     AACPEvent::StemPress(press_type, _bud) => {
                         let controller = mc_clone.lock().await;
-                        match press_type {
-                            StemPressType::Single => {
-                                info!("Stem single press - toggling play/pause");
-                                controller.toggle_play_pause().await;
-                            }
-                            StemPressType::Double => {
-                                info!("Stem double press - next track");
-                                controller.next_track().await;
-                            }
-                            StemPressType::Triple => {
-                                info!("Stem triple press - previous track");
-                                controller.previous_track().await;
-                            }
-                            StemPressType::Long => {
-                                debug!("Stem long press - ignored");
-                            }
-                        }
+                        controller.handle_stem_press(press_type).await;
                     }
                     _ => {
                         debug!("Forwarding AACP event to TUI: {:?}", event_clone);
@@ -365,10 +389,10 @@ impl AirPodsDevice {
             }
         });
 
-        // media_controller and mac_address are used by spawned tasks above
-        // but not needed in the struct after initialization
-        drop(media_controller);
-        Ok(AirPodsDevice { aacp_manager })
+        Ok(AirPodsDevice {
+            aacp_manager,
+            media_controller,
+        })
     }
 
     /// Abort a half-dead init: close the L2CAP session (so the retry's fresh
@@ -408,7 +432,7 @@ impl AirPodsDevice {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AirPodsInformation {
     pub name: String,
     pub model_number: String,
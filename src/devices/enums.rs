@@ -22,6 +22,24 @@ pub struct DeviceData {
     /// when the device reports a different state.
     #[serde(default)]
     pub volume_swipe: Option<bool>,
+    /// Sink volume (percent) last used with this device.
+    #[serde(default)]
+    pub volume: Option<u32>,
+    /// Adapter this device is pinned to, e.g. "hci1". Overrides `adapter`
+    /// from config.toml for this device only.
+    #[serde(default)]
+    pub adapter: Option<String>,
+    /// Last battery levels reported over AACP, shown (as stale) at the
+    /// next connect until fresh ones arrive.
+    #[serde(default)]
+    pub last_battery: Option<StoredBattery>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredBattery {
+    /// Unix time of the report.
+    pub at: u64,
+    pub components: Vec<crate::bluetooth::aacp::BatteryInfo>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -68,6 +86,27 @@ impl AirPodsNoiseControlMode {
 mod tests {
     use super::*;
 
+    #[test]
+    fn device_data_without_volume_still_loads() {
+        let d: DeviceData =
+            serde_json::from_str(r#"{"name":"Pods","type_":"AirPods","information":null}"#)
+                .unwrap();
+        assert_eq!(d.volume, None);
+        assert_eq!(d.adapter, None);
+    }
+
+    #[test]
+    fn pinned_adapter_overrides_config() {
+        let d: DeviceData = serde_json::from_str(
+            r#"{"name":"Pods","type_":"AirPods","information":null,"adapter":"hci1"}"#,
+        )
+        .unwrap();
+        let preferred = crate::bluetooth::preferred_adapter;
+        assert_eq!(preferred(Some(&d), Some("hci0")), Some("hci1"));
+        assert_eq!(preferred(None, Some("hci0")), Some("hci0"));
+        assert_eq!(preferred(None, None), None);
+    }
+
     #[test]
     fn noise_mode_byte_roundtrip() {
         for m in [
@@ -1,3 +1,4 @@
 pub mod airpods;
 pub mod apple_models;
 pub mod enums;
+pub mod store;
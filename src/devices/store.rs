@@ -0,0 +1,156 @@
+//! devices.json access shared by every AACP session and process: updates
+//! are read-modify-write under an exclusive lock on `devices.json.lock`,
+//! and land through a temp file + rename so readers never see a torn file.
+
+use crate::devices::enums::DeviceData;
+use crate::utils::get_devices_path;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub type Devices = HashMap<String, DeviceData>;
+
+/// Current contents of devices.json; empty if missing or unreadable.
+pub fn load() -> Devices {
+    read(&get_devices_path())
+}
+
+/// Insert or replace one device's entry, keeping everything else that is
+/// on disk (including entries written by other sessions).
+pub async fn save_device(mac: String, data: DeviceData) -> io::Result<()> {
+    let path = get_devices_path();
+    tokio::task::spawn_blocking(move || {
+        update(&path, |devices| {
+            devices.insert(mac, data);
+        })
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Apply `change` to the entries on disk under the same lock as
+/// `save_device`, for edits beyond a single insert.
+pub async fn modify(change: impl FnOnce(&mut Devices) + Send + 'static) -> io::Result<()> {
+    let path = get_devices_path();
+    tokio::task::spawn_blocking(move || update(&path, change))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn read(path: &Path) -> Devices {
+    read_strict(path).unwrap_or_default()
+}
+
+/// Like `read`, but only a missing file counts as empty: writing back after
+/// a failed parse would drop every entry that was on disk.
+fn read_strict(path: &Path) -> io::Result<Devices> {
+    match std::fs::read_to_string(path) {
+        Ok(s) => serde_json::from_str(&s).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Devices::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn update(path: &Path, change: impl FnOnce(&mut Devices)) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // The data file is replaced on every write, so lock a stable sidecar
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    lock.lock()?;
+
+    let mut devices = read_strict(path)?;
+    change(&mut devices);
+    let json = serde_json::to_string(&devices).map_err(io::Error::other)?;
+
+    let tmp = sibling(path, ".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+    // Dropping `lock` releases it
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::enums::DeviceType;
+
+    fn device(name: &str) -> DeviceData {
+        DeviceData {
+            name: name.into(),
+            type_: DeviceType::AirPods,
+            information: None,
+            volume_swipe: None,
+            volume: None,
+            adapter: None,
+            last_battery: None,
+        }
+    }
+
+    #[test]
+    fn updates_merge_with_disk() {
+        let dir = std::env::temp_dir().join(format!("airpods-tui-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("devices.json");
+
+        // Two sessions, each only knowing its own device
+        update(&path, |d| {
+            d.insert("AA".into(), device("first"));
+        })
+        .unwrap();
+        update(&path, |d| {
+            d.insert("BB".into(), device("second"));
+        })
+        .unwrap();
+        update(&path, |d| {
+            d.insert("AA".into(), device("renamed"));
+        })
+        .unwrap();
+
+        let devices = read(&path);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices["AA"].name, "renamed");
+        assert_eq!(devices["BB"].name, "second");
+        assert!(!sibling(&path, ".tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn update_refuses_to_overwrite_corrupt_file() {
+        let dir =
+            std::env::temp_dir().join(format!("airpods-tui-store-corrupt-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("devices.json");
+        std::fs::write(&path, "{\"AA\": {\"name\": 1}").unwrap();
+
+        let err = update(&path, |d| {
+            d.insert("BB".into(), device("second"));
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"AA\": {\"name\": 1}"
+        );
+        assert!(read(&path).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
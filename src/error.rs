@@ -0,0 +1,76 @@
+//! Crate-wide error for device commands, so a failed write reaches the UI
+//! (as `AppEvent::CommandFailed`) instead of only the log.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// No AACP session for this device.
+    NotConnected(String),
+    /// Writing to the AACP L2CAP socket failed.
+    Aacp(bluer::Error),
+    /// A BlueZ call (alias, properties) failed.
+    Bluez(bluer::Error),
+    DBus(zbus::Error),
+    /// The audio server couldn't do what was asked.
+    Audio(String),
+    /// A configured external command couldn't run or exited non-zero.
+    Command(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotConnected(mac) => write!(f, "{} is not connected", mac),
+            Error::Aacp(e) => write!(f, "AirPods write failed: {}", e),
+            Error::Bluez(e) => write!(f, "BlueZ: {}", e),
+            Error::DBus(e) => write!(f, "D-Bus: {}", e),
+            Error::Audio(msg) => write!(f, "Audio: {}", msg),
+            Error::Command(e) => write!(f, "Command failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Aacp(e) | Error::Bluez(e) => Some(e),
+            Error::DBus(e) => Some(e),
+            Error::Command(e) => Some(e),
+            Error::NotConnected(_) | Error::Audio(_) => None,
+        }
+    }
+}
+
+impl From<zbus::Error> for Error {
+    fn from(e: zbus::Error) -> Self {
+        Error::DBus(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Command(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_the_subsystem() {
+        assert_eq!(
+            Error::NotConnected("AA:BB:CC:DD:EE:FF".into()).to_string(),
+            "AA:BB:CC:DD:EE:FF is not connected"
+        );
+        let io = std::io::Error::other("exit status 1");
+        assert_eq!(Error::from(io).to_string(), "Command failed: exit status 1");
+        assert_eq!(
+            Error::Audio("no microphone".into()).to_string(),
+            "Audio: no microphone"
+        );
+    }
+}
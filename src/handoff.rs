@@ -43,6 +43,8 @@ pub enum Action {
     },
     /// Suspend/resume the bluez sink to force a fresh AVDTP_START.
     RestartAudioStream,
+    /// Play the players PauseTracked paused.
+    ResumeTracked,
     ActivateA2dp,
     DeactivateA2dp,
 }
@@ -51,6 +53,8 @@ pub enum Action {
 pub struct HandoffFsm {
     state: Ownership,
     generation: u64,
+    /// A peer phone call paused our media; the reclaim after it resumes.
+    resume_after_call: bool,
 }
 
 impl HandoffFsm {
@@ -92,6 +96,7 @@ impl HandoffFsm {
                 Vec::new()
             };
         }
+        self.resume_after_call = false;
         if is_local {
             self.state = Ownership::Linux;
             return Vec::new();
@@ -104,6 +109,18 @@ impl HandoffFsm {
         vec![Action::PauseTracked]
     }
 
+    /// AUDIO_SOURCE of type Call from a peer: a phone call on the iPhone.
+    /// Hands the session over for the call and, if Linux was playing,
+    /// takes it back and resumes once the peer goes quiet.
+    pub fn on_peer_call(&mut self, linux_has_audio: bool) -> Vec<Action> {
+        let armed = linux_has_audio || self.reclaim_armed();
+        self.state = Ownership::Peer {
+            reclaim_when_silent: armed,
+        };
+        self.resume_after_call = armed;
+        vec![Action::PauseTracked, Action::ReleaseOwnership]
+    }
+
     /// Local media started playing (the caller has already verified the buds
     /// are in ear). Claims the session unless we already hold it, which
     /// stops claim/activate storms while a peer contests ownership.
@@ -112,6 +129,7 @@ impl HandoffFsm {
             return Vec::new();
         }
         self.state = Ownership::Linux;
+        self.resume_after_call = false;
         vec![Action::ClaimOwnership, Action::ActivateA2dp]
     }
 
@@ -135,12 +153,17 @@ impl HandoffFsm {
             return Vec::new();
         }
         self.state = Ownership::Linux;
-        vec![Action::ClaimOwnership, Action::RestartAudioStream]
+        let mut actions = vec![Action::ClaimOwnership, Action::RestartAudioStream];
+        if std::mem::take(&mut self.resume_after_call) {
+            actions.push(Action::ResumeTracked);
+        }
+        actions
     }
 
     /// Smart-routing SetOwnershipToFalse request: the device asks us to
     /// hand the session over.
     pub fn on_ownership_to_false(&mut self) -> Vec<Action> {
+        self.resume_after_call = false;
         self.state = Ownership::Peer {
             reclaim_when_silent: false,
         };
@@ -243,6 +266,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn peer_call_releases_and_resumes_after() {
+        let mut fsm = HandoffFsm::default();
+        fsm.on_local_play();
+        assert_eq!(
+            fsm.on_peer_call(true),
+            vec![Action::PauseTracked, Action::ReleaseOwnership]
+        );
+        source_none(&mut fsm);
+        assert_eq!(
+            fsm.on_settle_expired(1),
+            vec![
+                Action::ClaimOwnership,
+                Action::RestartAudioStream,
+                Action::ResumeTracked
+            ]
+        );
+        assert_eq!(fsm.state(), Ownership::Linux);
+    }
+
+    #[test]
+    fn peer_media_after_call_cancels_resume() {
+        let mut fsm = HandoffFsm::default();
+        fsm.on_peer_call(true);
+        // The user starts music on the phone once the call ends.
+        peer_steal(&mut fsm, false);
+        source_none(&mut fsm);
+        assert_eq!(
+            fsm.on_settle_expired(1),
+            vec![Action::ClaimOwnership, Action::RestartAudioStream]
+        );
+    }
+
+    #[test]
+    fn peer_call_while_idle_does_not_reclaim() {
+        let mut fsm = HandoffFsm::default();
+        fsm.on_peer_call(false);
+        assert!(source_none(&mut fsm).is_empty());
+    }
+
     #[test]
     fn local_play_claims_once_then_stays_quiet() {
         let mut fsm = HandoffFsm::default();
@@ -0,0 +1,646 @@
+//! Device history recorded by the daemon (`history = true`): battery
+//! samples, connections and noise-mode changes in an SQLite database at
+//! `$XDG_STATE_HOME/airpods-tui/history.sqlite3`. Rows older than
+//! `RETENTION_DAYS` are pruned. The TUI history panel reads it for recent
+//! events and time-remaining estimates; `airpods-tui history export`
+//! dumps it.
+
+use crate::bluetooth::aacp::{
+    AACPEvent, BatteryComponent, BatteryStatus, ControlCommandIdentifiers,
+};
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::tui::app::AppEvent;
+use rusqlite::{Connection, OpenFlags, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// How long rows are kept.
+pub const RETENTION_DAYS: u64 = 90;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// A gap this long between battery samples ends a discharge run (the buds
+/// were in the case or disconnected).
+const MAX_SAMPLE_GAP_SECS: u64 = 30 * 60;
+
+/// A run must span this long before it predicts anything.
+const MIN_RUN_SECS: u64 = 10 * 60;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    at INTEGER NOT NULL,
+    mac TEXT NOT NULL,
+    event TEXT NOT NULL,
+    detail TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX IF NOT EXISTS events_mac_at ON events (mac, at);
+CREATE TABLE IF NOT EXISTS battery (
+    at INTEGER NOT NULL,
+    mac TEXT NOT NULL,
+    left_level INTEGER,
+    right_level INTEGER,
+    case_level INTEGER,
+    headphone_level INTEGER
+);
+CREATE INDEX IF NOT EXISTS battery_mac_at ON battery (mac, at);
+";
+
+pub fn history_path() -> PathBuf {
+    crate::utils::state_dir().join("history.sqlite3")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix time.
+    pub at: u64,
+    pub mac: String,
+    #[serde(flatten)]
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Kind {
+    Connected {
+        name: String,
+    },
+    Disconnected,
+    Battery {
+        left: Option<u8>,
+        right: Option<u8>,
+        case: Option<u8>,
+        headphone: Option<u8>,
+    },
+    NoiseMode {
+        mode: String,
+    },
+}
+
+/// Export format for `airpods-tui history export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Json,
+    /// `at,mac,event,detail` with a header row
+    Csv,
+}
+
+#[derive(clap::Subcommand)]
+pub enum HistoryCommand {
+    /// Print the recorded history, oldest first
+    Export {
+        #[arg(long, value_enum, default_value = "json")]
+        format: ExportFormat,
+        #[arg(long, value_name = "MAC", help = "Only this device")]
+        mac: Option<String>,
+    },
+}
+
+/// The history database.
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    /// Open for recording, creating the file and tables as needed.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            // open() reports the missing directory if this fails
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open an existing database without writing to it.
+    pub fn open_read_only(path: &Path) -> rusqlite::Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        Ok(Self {
+            conn: Connection::open_with_flags(path, flags)?,
+        })
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        // The TUI reads while the daemon writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn insert(&self, record: &Record) -> rusqlite::Result<()> {
+        let (event, detail) = match &record.kind {
+            Kind::Battery {
+                left,
+                right,
+                case,
+                headphone,
+            } => {
+                self.conn.execute(
+                    "INSERT INTO battery (at, mac, left_level, right_level, case_level, headphone_level)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![record.at, record.mac, left, right, case, headphone],
+                )?;
+                return Ok(());
+            }
+            Kind::Connected { name } => ("connected", name.as_str()),
+            Kind::Disconnected => ("disconnected", ""),
+            Kind::NoiseMode { mode } => ("noise_mode", mode.as_str()),
+        };
+        self.conn.execute(
+            "INSERT INTO events (at, mac, event, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![record.at, record.mac, event, detail],
+        )?;
+        Ok(())
+    }
+
+    /// Drop rows recorded before `before` (unix time).
+    pub fn prune(&self, before: u64) -> rusqlite::Result<usize> {
+        let events = self
+            .conn
+            .execute("DELETE FROM events WHERE at < ?1", params![before])?;
+        let battery = self
+            .conn
+            .execute("DELETE FROM battery WHERE at < ?1", params![before])?;
+        Ok(events + battery)
+    }
+
+    /// Records at or after `since`, oldest first; all devices when `mac`
+    /// is None.
+    pub fn records(&self, mac: Option<&str>, since: u64) -> rusqlite::Result<Vec<Record>> {
+        let mut records = Vec::new();
+        let mut stmt = self.conn.prepare(
+            "SELECT at, mac, event, detail FROM events
+             WHERE at >= ?1 AND (?2 IS NULL OR mac = ?2)",
+        )?;
+        let rows = stmt.query_map(params![since, mac], |row| {
+            let detail: String = row.get(3)?;
+            let kind = match row.get::<_, String>(2)?.as_str() {
+                "connected" => Kind::Connected { name: detail },
+                "noise_mode" => Kind::NoiseMode { mode: detail },
+                _ => Kind::Disconnected,
+            };
+            Ok(Record {
+                at: row.get(0)?,
+                mac: row.get(1)?,
+                kind,
+            })
+        })?;
+        for row in rows {
+            records.push(row?);
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT at, mac, left_level, right_level, case_level, headphone_level FROM battery
+             WHERE at >= ?1 AND (?2 IS NULL OR mac = ?2)",
+        )?;
+        let rows = stmt.query_map(params![since, mac], |row| {
+            Ok(Record {
+                at: row.get(0)?,
+                mac: row.get(1)?,
+                kind: Kind::Battery {
+                    left: row.get(2)?,
+                    right: row.get(3)?,
+                    case: row.get(4)?,
+                    headphone: row.get(5)?,
+                },
+            })
+        })?;
+        for row in rows {
+            records.push(row?);
+        }
+        // Stable: at equal times events stay ahead of battery samples
+        records.sort_by_key(|r| r.at);
+        Ok(records)
+    }
+}
+
+pub struct HistoryRecorder {
+    store: HistoryStore,
+    /// Last listening-mode byte per MAC; only changes are recorded.
+    noise_modes: HashMap<String, u8>,
+    /// Last battery levels per MAC; repeats aren't recorded.
+    batteries: HashMap<String, Kind>,
+    /// MACs seen in DeviceConnected; BlueZ reports every device's
+    /// disconnect, so only these are recorded.
+    connected: HashSet<String>,
+    /// When rows past `RETENTION_DAYS` were last dropped.
+    pruned_at: u64,
+}
+
+impl HistoryRecorder {
+    pub fn new(store: HistoryStore) -> Self {
+        Self {
+            store,
+            noise_modes: HashMap::new(),
+            batteries: HashMap::new(),
+            connected: HashSet::new(),
+            pruned_at: 0,
+        }
+    }
+
+    pub fn handle(&mut self, event: &AppEvent) {
+        let now = crate::utils::unix_now();
+        if now.saturating_sub(self.pruned_at) >= DAY_SECS {
+            self.pruned_at = now;
+            let cutoff = now.saturating_sub(RETENTION_DAYS * DAY_SECS);
+            if let Err(e) = self.store.prune(cutoff) {
+                log::warn!("Failed to prune history: {}", e);
+            }
+        }
+        let Some(record) = self.record_for(event, now) else {
+            return;
+        };
+        if let Err(e) = self.store.insert(&record) {
+            log::warn!("Failed to record history: {}", e);
+        }
+    }
+
+    fn record_for(&mut self, event: &AppEvent, at: u64) -> Option<Record> {
+        let (mac, kind) = match event {
+            AppEvent::DeviceConnected { mac, name, .. } => {
+                self.connected.insert(mac.clone());
+                (mac, Kind::Connected { name: name.clone() })
+            }
+            AppEvent::DeviceDisconnected(mac) => {
+                self.noise_modes.remove(mac);
+                self.batteries.remove(mac);
+                if !self.connected.remove(mac) {
+                    return None;
+                }
+                (mac, Kind::Disconnected)
+            }
+            AppEvent::AACPEvent(mac, aacp_event) => match &**aacp_event {
+                AACPEvent::BatteryInfo(infos) => {
+                    let level = |component| {
+                        infos
+                            .iter()
+                            .find(|b| b.component == component)
+                            .filter(|b| b.status != BatteryStatus::Disconnected)
+                            .map(|b| b.level)
+                    };
+                    let kind = Kind::Battery {
+                        left: level(BatteryComponent::Left),
+                        right: level(BatteryComponent::Right),
+                        case: level(BatteryComponent::Case),
+                        headphone: level(BatteryComponent::Headphone),
+                    };
+                    if self.batteries.get(mac) == Some(&kind) {
+                        return None;
+                    }
+                    self.batteries.insert(mac.clone(), kind.clone());
+                    (mac, kind)
+                }
+                AACPEvent::ControlCommand(cmd)
+                    if cmd.identifier == ControlCommandIdentifiers::ListeningMode =>
+                {
+                    let byte = *cmd.value.first()?;
+                    if self.noise_modes.insert(mac.clone(), byte) == Some(byte) {
+                        return None;
+                    }
+                    let mode = AirPodsNoiseControlMode::from_byte(byte).to_string();
+                    (mac, Kind::NoiseMode { mode })
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Record {
+            at,
+            mac: mac.clone(),
+            kind,
+        })
+    }
+}
+
+/// Seconds until a component runs flat at the rate it drained over its
+/// latest discharge run. `samples` are `(unix time, level)`, oldest first.
+/// The run ends, going back, at a rise in level (charging) or a long gap.
+pub fn time_remaining(samples: &[(u64, u8)]) -> Option<u64> {
+    let &(end_at, end_level) = samples.last()?;
+    let mut start = (end_at, end_level);
+    for &(at, level) in samples.iter().rev().skip(1) {
+        if level < start.1 || start.0 - at > MAX_SAMPLE_GAP_SECS {
+            break;
+        }
+        start = (at, level);
+    }
+    let (start_at, start_level) = start;
+    let span = end_at - start_at;
+    let drop = u64::from(start_level.checked_sub(end_level)?);
+    if span < MIN_RUN_SECS || drop == 0 {
+        return None;
+    }
+    Some(u64::from(end_level) * span / drop)
+}
+
+/// What the TUI history panel shows for one device.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryView {
+    /// (component label, seconds left) for components with an estimate.
+    pub remaining: Vec<(&'static str, u64)>,
+    /// Connections and noise-mode changes, newest first.
+    pub recent: Vec<Record>,
+    /// Why there's nothing to show, e.g. the daemon doesn't record.
+    pub error: Option<String>,
+}
+
+/// Events listed in the panel.
+const RECENT_EVENTS: usize = 8;
+
+/// Battery samples from the last day feed the estimates.
+const ESTIMATE_WINDOW_SECS: u64 = DAY_SECS;
+
+impl HistoryView {
+    /// Load the view for `mac` from the database at `path`.
+    pub fn load(path: &Path, mac: &str, now: u64) -> Self {
+        if !path.exists() {
+            return Self::failed(format!(
+                "No history at {} (set history = true for the daemon)",
+                path.display()
+            ));
+        }
+        let records = HistoryStore::open_read_only(path).and_then(|store| {
+            store.records(Some(mac), now.saturating_sub(RETENTION_DAYS * DAY_SECS))
+        });
+        match records {
+            Ok(records) => Self::from_records(&records, now),
+            Err(e) => Self::failed(format!("Can't read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    fn from_records(records: &[Record], now: u64) -> Self {
+        let since = now.saturating_sub(ESTIMATE_WINDOW_SECS);
+        let samples = |pick: fn(&Kind) -> Option<u8>| -> Vec<(u64, u8)> {
+            records
+                .iter()
+                .filter(|r| r.at >= since)
+                .filter_map(|r| pick(&r.kind).map(|level| (r.at, level)))
+                .collect()
+        };
+        let components: [(&'static str, fn(&Kind) -> Option<u8>); 3] = [
+            ("Left", |k| match k {
+                Kind::Battery { left, .. } => *left,
+                _ => None,
+            }),
+            ("Right", |k| match k {
+                Kind::Battery { right, .. } => *right,
+                _ => None,
+            }),
+            ("Headphones", |k| match k {
+                Kind::Battery { headphone, .. } => *headphone,
+                _ => None,
+            }),
+        ];
+        let remaining = components
+            .into_iter()
+            .filter_map(|(label, pick)| time_remaining(&samples(pick)).map(|secs| (label, secs)))
+            .collect();
+        let recent = records
+            .iter()
+            .rev()
+            .filter(|r| !matches!(r.kind, Kind::Battery { .. }))
+            .take(RECENT_EVENTS)
+            .cloned()
+            .collect();
+        Self {
+            remaining,
+            recent,
+            error: None,
+        }
+    }
+}
+
+fn csv_line(record: &Record) -> String {
+    let (event, detail) = match &record.kind {
+        Kind::Connected { name } => ("connected", name.replace([',', '"'], " ")),
+        Kind::Disconnected => ("disconnected", String::new()),
+        Kind::Battery {
+            left,
+            right,
+            case,
+            headphone,
+        } => {
+            let parts: Vec<String> = [
+                ("left", left),
+                ("right", right),
+                ("case", case),
+                ("headphone", headphone),
+            ]
+            .iter()
+            .filter_map(|(k, v)| v.map(|v| format!("{}={}", k, v)))
+            .collect();
+            ("battery", parts.join(" "))
+        }
+        Kind::NoiseMode { mode } => ("noise_mode", mode.clone()),
+    };
+    format!("{},{},{},{}", record.at, record.mac, event, detail)
+}
+
+/// Entry point for the `history` subcommand.
+pub fn run(command: HistoryCommand) -> io::Result<()> {
+    match command {
+        HistoryCommand::Export { format, mac } => export(format, mac.as_deref()),
+    }
+}
+
+fn export(format: ExportFormat, mac: Option<&str>) -> io::Result<()> {
+    let path = history_path();
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No history at {} (set history = true for the daemon)",
+                path.display()
+            ),
+        ));
+    }
+    let records = HistoryStore::open_read_only(&path)
+        .and_then(|store| store.records(mac, 0))
+        .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))?;
+    let mut out = io::stdout().lock();
+    if format == ExportFormat::Csv {
+        writeln!(out, "at,mac,event,detail")?;
+    }
+    for record in &records {
+        match format {
+            ExportFormat::Json => {
+                let line = serde_json::to_string(record).map_err(io::Error::other)?;
+                writeln!(out, "{}", line)?;
+            }
+            ExportFormat::Csv => writeln!(out, "{}", csv_line(record))?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::aacp::{BatteryInfo, ControlCommandStatus};
+
+    const MAC: &str = "AA:BB:CC:DD:EE:FF";
+
+    fn recorder() -> HistoryRecorder {
+        HistoryRecorder::new(HistoryStore::init(Connection::open_in_memory().unwrap()).unwrap())
+    }
+
+    fn noise(byte: u8) -> AppEvent {
+        AppEvent::AACPEvent(
+            MAC.into(),
+            Box::new(AACPEvent::ControlCommand(ControlCommandStatus {
+                identifier: ControlCommandIdentifiers::ListeningMode,
+                value: vec![byte],
+            })),
+        )
+    }
+
+    fn left(level: u8) -> AppEvent {
+        AppEvent::AACPEvent(
+            MAC.into(),
+            Box::new(AACPEvent::BatteryInfo(vec![
+                BatteryInfo {
+                    component: BatteryComponent::Left,
+                    level,
+                    status: BatteryStatus::NotCharging,
+                },
+                BatteryInfo {
+                    component: BatteryComponent::Case,
+                    level: 10,
+                    status: BatteryStatus::Disconnected,
+                },
+            ])),
+        )
+    }
+
+    #[test]
+    fn records_battery_and_noise_changes() {
+        let mut rec = recorder();
+        let record = rec.record_for(&left(80), 7).unwrap();
+        assert_eq!(
+            record.kind,
+            Kind::Battery {
+                left: Some(80),
+                right: None,
+                case: None,
+                headphone: None
+            }
+        );
+        assert_eq!(csv_line(&record), format!("7,{},battery,left=80", MAC));
+        // The same levels again aren't a new sample
+        assert!(rec.record_for(&left(80), 8).is_none());
+
+        assert!(rec.record_for(&noise(0x02), 8).is_some());
+        assert!(rec.record_for(&noise(0x02), 9).is_none());
+        let record = rec.record_for(&noise(0x03), 10).unwrap();
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            format!(
+                r#"{{"at":10,"mac":"{}","event":"noise_mode","mode":"Transparency"}}"#,
+                MAC
+            )
+        );
+    }
+
+    #[test]
+    fn records_disconnect_only_after_connect() {
+        let mut rec = recorder();
+        let disconnect = AppEvent::DeviceDisconnected(MAC.into());
+        assert!(rec.record_for(&disconnect, 1).is_none());
+        let connect = AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "Pods".into(),
+            product_id: 0,
+        };
+        assert!(rec.record_for(&connect, 2).is_some());
+        let record = rec.record_for(&disconnect, 3).unwrap();
+        assert_eq!(record.kind, Kind::Disconnected);
+        assert!(rec.record_for(&disconnect, 4).is_none());
+    }
+
+    #[test]
+    fn store_round_trips_and_prunes() {
+        let mut rec = recorder();
+        let connect = AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "Pods".into(),
+            product_id: 0,
+        };
+        for (event, at) in [(connect, 100), (left(90), 100), (noise(0x02), 200)] {
+            let record = rec.record_for(&event, at).unwrap();
+            rec.store.insert(&record).unwrap();
+        }
+        let records = rec.store.records(Some(MAC), 0).unwrap();
+        let kinds: Vec<&str> = records
+            .iter()
+            .map(|r| match r.kind {
+                Kind::Connected { .. } => "connected",
+                Kind::Battery { .. } => "battery",
+                Kind::NoiseMode { .. } => "noise_mode",
+                Kind::Disconnected => "disconnected",
+            })
+            .collect();
+        assert_eq!(kinds, ["connected", "battery", "noise_mode"]);
+        let other = rec.store.records(Some("11:22:33:44:55:66"), 0).unwrap();
+        assert!(other.is_empty());
+
+        assert_eq!(rec.store.prune(150).unwrap(), 2);
+        assert_eq!(rec.store.records(None, 0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn estimate_uses_the_latest_discharge_run() {
+        let min = 60;
+        // Charging up to 100, then 10 points in 20 minutes
+        let samples = [(0, 40), (30 * min, 100), (40 * min, 95), (50 * min, 90)];
+        assert_eq!(time_remaining(&samples), Some(90 * 20 * min / 10));
+        // Too short a run to tell
+        assert_eq!(time_remaining(&[(0, 95), (5 * min, 90)]), None);
+        // A long gap starts a new run
+        let gap = [(0, 100), (20 * min, 90), (120 * min, 90)];
+        assert_eq!(time_remaining(&gap), None);
+        assert_eq!(time_remaining(&[]), None);
+    }
+
+    #[test]
+    fn view_lists_recent_events_newest_first() {
+        let min = 60;
+        let record = |at, kind| Record {
+            at,
+            mac: MAC.into(),
+            kind,
+        };
+        let battery = |level| Kind::Battery {
+            left: Some(level),
+            right: Some(level),
+            case: None,
+            headphone: None,
+        };
+        let records = [
+            record(
+                0,
+                Kind::Connected {
+                    name: "Pods".into(),
+                },
+            ),
+            record(0, battery(100)),
+            record(30 * min, battery(90)),
+            record(
+                30 * min,
+                Kind::NoiseMode {
+                    mode: "Transparency".into(),
+                },
+            ),
+        ];
+        let view = HistoryView::from_records(&records, 30 * min);
+        assert_eq!(view.remaining, [("Left", 270 * min), ("Right", 270 * min)]);
+        assert_eq!(view.recent.len(), 2);
+        assert!(matches!(view.recent[0].kind, Kind::NoiseMode { .. }));
+    }
+}
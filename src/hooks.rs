@@ -0,0 +1,330 @@
+//! User hook scripts run by the daemon on device events.
+//!
+//! Each hook is an argv vector from the `[hooks]` config table. Event
+//! details are passed as `AIRPODS_*` environment variables rather than
+//! interpolated into arguments, so scripts never have to re-parse them.
+
+use crate::bluetooth::aacp::{
+    AACPEvent, BatteryComponent, ControlCommandIdentifiers, EarDetectionStatus,
+};
+use crate::config::HooksConfig;
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::tui::app::AppEvent;
+use log::{debug, warn};
+use std::collections::HashMap;
+
+type Env = Vec<(&'static str, String)>;
+
+pub struct HookRunner {
+    hooks: HooksConfig,
+    /// Display name per MAC, from DeviceConnected.
+    names: HashMap<String, String>,
+    /// Last reported listening-mode byte per MAC.
+    noise_modes: HashMap<String, u8>,
+}
+
+impl HookRunner {
+    pub fn new(hooks: HooksConfig) -> Self {
+        Self {
+            hooks,
+            names: HashMap::new(),
+            noise_modes: HashMap::new(),
+        }
+    }
+
+    /// Run whichever hooks `event` triggers.
+    pub fn handle(&mut self, event: &AppEvent) {
+        for (hook, env) in self.triggered(event) {
+            spawn_hook(hook, env);
+        }
+    }
+
+    /// Run `on_low_battery`; the daemon's alert thresholds decide when.
+    pub fn low_battery(&self, mac: &str, component: BatteryComponent, level: u8) {
+        let mut env = self.base_env("low_battery", mac);
+        env.push(("AIRPODS_COMPONENT", format!("{:?}", component)));
+        env.push(("AIRPODS_LEVEL", level.to_string()));
+        spawn_hook(&self.hooks.on_low_battery, env);
+    }
+
+    fn triggered(&mut self, event: &AppEvent) -> Vec<(&[String], Env)> {
+        let mut out = Vec::new();
+        match event {
+            AppEvent::DeviceConnected { mac, name, .. } => {
+                self.names.insert(mac.clone(), name.clone());
+                out.push((
+                    self.hooks.on_connect.as_slice(),
+                    self.base_env("connect", mac),
+                ));
+            }
+            AppEvent::DeviceDisconnected(mac) => {
+                // BlueZ reports every device's disconnect, and failed inits
+                // report one too; only AirPods we saw connect count.
+                if self.names.contains_key(mac) {
+                    out.push((
+                        self.hooks.on_disconnect.as_slice(),
+                        self.base_env("disconnect", mac),
+                    ));
+                }
+                self.names.remove(mac);
+                self.noise_modes.remove(mac);
+            }
+            AppEvent::AACPEvent(mac, aacp_event) => match &**aacp_event {
+                AACPEvent::EarDetection {
+                    old_left,
+                    old_right,
+                    new_left,
+                    new_right,
+                } => {
+                    let left_out = removed_from_ear(old_left, new_left);
+                    let right_out = removed_from_ear(old_right, new_right);
+                    let bud = match (left_out, right_out) {
+                        (true, true) => Some("both"),
+                        (true, false) => Some("left"),
+                        (false, true) => Some("right"),
+                        (false, false) => None,
+                    };
+                    if let Some(bud) = bud {
+                        let mut env = self.base_env("ear_out", mac);
+                        env.push(("AIRPODS_BUD", bud.to_string()));
+                        out.push((self.hooks.on_ear_out.as_slice(), env));
+                    }
+                }
+                AACPEvent::ControlCommand(cmd)
+                    if cmd.identifier == ControlCommandIdentifiers::ListeningMode =>
+                {
+                    if let Some(&byte) = cmd.value.first() {
+                        let prev = self.noise_modes.insert(mac.clone(), byte);
+                        // The first report after connect is state, not a change
+                        if let Some(prev) = prev
+                            && prev != byte
+                        {
+                            let mut env = self.base_env("noise_mode_change", mac);
+                            env.push((
+                                "AIRPODS_NOISE_MODE",
+                                AirPodsNoiseControlMode::from_byte(byte).to_string(),
+                            ));
+                            env.push((
+                                "AIRPODS_PREVIOUS_NOISE_MODE",
+                                AirPodsNoiseControlMode::from_byte(prev).to_string(),
+                            ));
+                            out.push((self.hooks.on_noise_mode_change.as_slice(), env));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            AppEvent::LidOpened(nearby) => {
+                // Not connected, so the name comes from devices.json
+                let mut env = vec![
+                    ("AIRPODS_EVENT", "lid_open".to_string()),
+                    ("AIRPODS_MAC", nearby.mac.clone().unwrap_or_default()),
+                    ("AIRPODS_NAME", nearby.name.clone().unwrap_or_default()),
+                ];
+                let advert = &nearby.advert;
+                for (key, level) in [
+                    ("AIRPODS_LEFT", advert.left),
+                    ("AIRPODS_RIGHT", advert.right),
+                    ("AIRPODS_CASE", advert.case),
+                ] {
+                    env.push((key, level.map(|l| l.to_string()).unwrap_or_default()));
+                }
+                out.push((self.hooks.on_lid_open.as_slice(), env));
+            }
+            AppEvent::AudioUnavailable
+            | AppEvent::AudioProfile { .. }
+            | AppEvent::MicMuted { .. }
+            | AppEvent::NowPlaying { .. }
+            | AppEvent::Playback { .. }
+            | AppEvent::LinkLost(_)
+            | AppEvent::CommandFailed { .. }
+            | AppEvent::BatteryDevice { .. }
+            | AppEvent::LastSeen { .. }
+            | AppEvent::CachedBattery { .. }
+            | AppEvent::Nearby(_) => {}
+        }
+        out.retain(|(hook, _)| !hook.is_empty());
+        out
+    }
+
+    fn base_env(&self, event: &str, mac: &str) -> Env {
+        vec![
+            ("AIRPODS_EVENT", event.to_string()),
+            ("AIRPODS_MAC", mac.to_string()),
+            (
+                "AIRPODS_NAME",
+                self.names.get(mac).cloned().unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+/// Whether a bud went from in-ear to out (taking it out, or straight into
+/// the case).
+fn removed_from_ear(old: &Option<EarDetectionStatus>, new: &Option<EarDetectionStatus>) -> bool {
+    matches!(old, Some(EarDetectionStatus::InEar))
+        && matches!(
+            new,
+            Some(EarDetectionStatus::OutOfEar | EarDetectionStatus::InCase)
+        )
+}
+
+/// Start the hook without waiting for it; a reaper task collects the exit
+/// status so slow scripts never stall the event loop.
+fn spawn_hook(hook: &[String], env: Env) {
+    let Some((program, args)) = hook.split_first() else {
+        return;
+    };
+    match tokio::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .spawn()
+    {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => debug!("Hook exited with {}", status),
+                    _ => {}
+                }
+            });
+        }
+        Err(e) => warn!("Failed to run hook {}: {}", program, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::aacp::ControlCommandStatus;
+    use crate::bluetooth::proximity::NearbyAirPods;
+
+    const MAC: &str = "AA:BB:CC:DD:EE:FF";
+
+    fn runner() -> HookRunner {
+        let hook = vec!["true".to_string()];
+        HookRunner::new(HooksConfig {
+            on_connect: hook.clone(),
+            on_disconnect: hook.clone(),
+            on_low_battery: hook.clone(),
+            on_ear_out: hook.clone(),
+            on_noise_mode_change: hook.clone(),
+            on_lid_open: hook,
+        })
+    }
+
+    fn env_of<'a>(env: &'a Env, key: &str) -> Option<&'a str> {
+        env.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn noise(byte: u8) -> AppEvent {
+        AppEvent::AACPEvent(
+            MAC.into(),
+            Box::new(AACPEvent::ControlCommand(ControlCommandStatus {
+                identifier: ControlCommandIdentifiers::ListeningMode,
+                value: vec![byte],
+            })),
+        )
+    }
+
+    #[test]
+    fn connect_passes_mac_and_name() {
+        let mut r = runner();
+        let fired = r.triggered(&AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "Pods".into(),
+            product_id: 0,
+        });
+        assert_eq!(fired.len(), 1);
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_EVENT"), Some("connect"));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_MAC"), Some(MAC));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_NAME"), Some("Pods"));
+    }
+
+    #[test]
+    fn noise_mode_change_skips_initial_report() {
+        let mut r = runner();
+        assert!(r.triggered(&noise(0x02)).is_empty());
+        assert!(r.triggered(&noise(0x02)).is_empty());
+        let fired = r.triggered(&noise(0x03));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            env_of(&fired[0].1, "AIRPODS_NOISE_MODE"),
+            Some("Transparency")
+        );
+    }
+
+    #[test]
+    fn ear_out_reports_which_bud() {
+        let mut r = runner();
+        let fired = r.triggered(&AppEvent::AACPEvent(
+            MAC.into(),
+            Box::new(AACPEvent::EarDetection {
+                old_left: Some(EarDetectionStatus::InEar),
+                old_right: Some(EarDetectionStatus::InEar),
+                new_left: Some(EarDetectionStatus::InEar),
+                new_right: Some(EarDetectionStatus::OutOfEar),
+            }),
+        ));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_BUD"), Some("right"));
+    }
+
+    #[test]
+    fn disconnect_fires_only_for_connected_devices() {
+        let mut r = runner();
+        assert!(
+            r.triggered(&AppEvent::DeviceDisconnected("11:22:33:44:55:66".into()))
+                .is_empty()
+        );
+        r.triggered(&AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "Pods".into(),
+            product_id: 0,
+        });
+        let fired = r.triggered(&AppEvent::DeviceDisconnected(MAC.into()));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_EVENT"), Some("disconnect"));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_NAME"), Some("Pods"));
+    }
+
+    #[test]
+    fn lid_open_passes_levels() {
+        let mut r = runner();
+        let mut data = [0u8; 10];
+        data[..9].copy_from_slice(&[0x07, 0x19, 0x01, 0x0e, 0x20, 0x2b, 0x87, 0x4f, 0x01]);
+        let fired = r.triggered(&AppEvent::LidOpened(NearbyAirPods {
+            address: "5A:11:22:33:44:55".into(),
+            mac: Some(MAC.into()),
+            name: Some("Pods".into()),
+            advert: crate::bluetooth::proximity::parse(&data).unwrap(),
+            distance: None,
+            rssi: None,
+        }));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_EVENT"), Some("lid_open"));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_MAC"), Some(MAC));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_NAME"), Some("Pods"));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_LEFT"), Some("70"));
+        assert_eq!(env_of(&fired[0].1, "AIRPODS_CASE"), Some(""));
+    }
+
+    #[test]
+    fn empty_hooks_never_fire() {
+        let mut r = HookRunner::new(HooksConfig::default());
+        // Each of these fires with hooks configured
+        assert!(
+            r.triggered(&AppEvent::DeviceConnected {
+                mac: MAC.into(),
+                name: "Pods".into(),
+                product_id: 0,
+            })
+            .is_empty()
+        );
+        assert!(r.triggered(&noise(0x02)).is_empty());
+        assert!(r.triggered(&noise(0x03)).is_empty());
+        assert!(
+            r.triggered(&AppEvent::DeviceDisconnected(MAC.into()))
+                .is_empty()
+        );
+    }
+}
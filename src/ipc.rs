@@ -1,15 +1,381 @@
+use crate::bluetooth::aacp::{AACPEvent, BatteryComponent, BatteryInfo, BatteryStatus};
 use crate::tui::app::{AppEvent, DeviceCommand};
-use log::{error, info};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{UnixListener, UnixStream};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
 use tokio::sync::{RwLock, broadcast, mpsc};
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{self, crypto::CryptoProvider};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+// ── Wire protocol ──
+//
+// Every frame is a length-prefixed JSON-RPC 2.0 message. The daemon opens
+// with a `hello` notification carrying PROTOCOL_VERSION and CAPABILITIES,
+// then replays the state snapshot and streams live state as `event`
+// notifications. Clients send requests with their own ids and get exactly
+// one response per id. TCP clients send `auth` first; the hello follows
+// its response. With a certificate configured, TCP runs inside TLS.
+
+/// Bumped on any incompatible change to the messages below.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Features this daemon offers within PROTOCOL_VERSION, so additions don't
+/// need a bump: clients check for what they use and ignore the rest.
+pub const CAPABILITIES: &[&str] = &[
+    "events",
+    "command",
+    "get_state",
+    "get_battery",
+    "list_devices",
+];
+const JSONRPC: &str = "2.0";
+
+/// Every `Request` method, to tell an unknown method from bad params.
+const METHODS: &[&str] = &[
+    "auth",
+    "command",
+    "get_state",
+    "get_battery",
+    "list_devices",
+];
+
+/// JSON-RPC error codes.
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+/// Server-defined: missing or wrong auth token on a TCP connection.
+pub const UNAUTHORIZED: i32 = -32001;
+/// Server-defined: the MAC in a request isn't a current device.
+pub const UNKNOWN_DEVICE: i32 = -32002;
+
+/// Largest frame read from a peer that is allowed to talk to the daemon.
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+/// Largest frame read from a TCP peer before it has authenticated; an
+/// `auth` request fits easily.
+const MAX_AUTH_FRAME: usize = 4 * 1024;
+
+/// How long a remote client may take to authenticate.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a one-shot request waits for its response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client → daemon calls, encoded as JSON-RPC `method` + `params`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Request {
+    /// Present the shared token. Required as the first request on TCP
+    /// connections; a no-op on the local socket.
+    Auth { token: String },
+    /// Forward a control command or rename to a connected device.
+    Command { mac: String, command: DeviceCommand },
+    /// The events that rebuild the current state (what a new client gets
+    /// replayed), without subscribing to the stream.
+    GetState,
+    /// Latest battery levels of one device.
+    GetBattery { mac: String },
+    /// Devices the daemon currently knows.
+    ListDevices,
+}
+
+/// Successful results, one variant per request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    /// The request was accepted and queued.
+    Ack,
+    State(Vec<AppEvent>),
+    /// Empty until the device has reported any levels.
+    Battery(Vec<BatteryInfo>),
+    Devices(Vec<DeviceSummary>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSummary {
+    pub mac: String,
+    pub name: String,
+    /// Apple product ID; None for Battery Service devices.
+    pub product_id: Option<u16>,
+    /// The AACP session dropped and is being re-established.
+    pub link_lost: bool,
+}
+
+/// Daemon → client notifications (no id, no reply).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Notification {
+    Hello {
+        version: u32,
+        /// Absent from daemons that predate capability flags.
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    Event(AppEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+impl RpcRequest {
+    pub fn new(id: u64, request: Request) -> Self {
+        Self {
+            jsonrpc: JSONRPC.to_string(),
+            id,
+            request,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    /// `None` only when the request was too malformed to read its id.
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Response>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    fn ok(id: u64, result: Response) -> Self {
+        Self {
+            jsonrpc: JSONRPC.to_string(),
+            id: Some(id),
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn err(id: Option<u64>, code: i32, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: JSONRPC.to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcNotification {
+    pub jsonrpc: String,
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+impl RpcNotification {
+    pub fn new(notification: Notification) -> Self {
+        Self {
+            jsonrpc: JSONRPC.to_string(),
+            notification,
+        }
+    }
+}
+
+/// Anything the daemon writes. Notifications carry a `method`, responses
+/// don't; the variant order matters for untagged decoding because a
+/// response's `id` may legitimately be absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ServerMessage {
+    Notification(RpcNotification),
+    Response(RpcResponse),
+}
+
+/// Answer one decoded client frame; queries are served from `snapshot`.
+fn handle_request(
+    data: &[u8],
+    cmd_tx: &mpsc::UnboundedSender<(String, DeviceCommand)>,
+    snapshot: &[AppEvent],
+) -> RpcResponse {
+    let req = match serde_json::from_slice::<RpcRequest>(data) {
+        Ok(req) => req,
+        Err(e) => {
+            // Salvage the id so the client can still correlate the failure
+            let value = serde_json::from_slice::<serde_json::Value>(data).ok();
+            let id = value
+                .as_ref()
+                .and_then(|v| v.get("id").and_then(|id| id.as_u64()));
+            let method = value
+                .as_ref()
+                .and_then(|v| v.get("method").and_then(|m| m.as_str()));
+            return match (id, method) {
+                (None, _) => RpcResponse::err(None, PARSE_ERROR, e.to_string()),
+                (Some(_), Some(method)) if !METHODS.contains(&method) => {
+                    RpcResponse::err(id, METHOD_NOT_FOUND, format!("unknown method {}", method))
+                }
+                (Some(_), _) => RpcResponse::err(id, INVALID_REQUEST, e.to_string()),
+            };
+        }
+    };
+    if req.jsonrpc != JSONRPC {
+        return RpcResponse::err(
+            Some(req.id),
+            INVALID_REQUEST,
+            format!("unsupported jsonrpc version {}", req.jsonrpc),
+        );
+    }
+    match req.request {
+        // Connections that get this far are already authenticated
+        Request::Auth { .. } => RpcResponse::ok(req.id, Response::Ack),
+        Request::Command { mac, command } => {
+            let _ = cmd_tx.send((mac, command));
+            RpcResponse::ok(req.id, Response::Ack)
+        }
+        Request::GetState => RpcResponse::ok(req.id, Response::State(snapshot.to_vec())),
+        Request::GetBattery { mac } => match battery_of(snapshot, &mac) {
+            Some(levels) => RpcResponse::ok(req.id, Response::Battery(levels)),
+            None => RpcResponse::err(
+                Some(req.id),
+                UNKNOWN_DEVICE,
+                format!("{} is not connected", mac),
+            ),
+        },
+        Request::ListDevices => RpcResponse::ok(req.id, Response::Devices(device_list(snapshot))),
+    }
+}
+
+/// Battery of `mac` in the snapshot: live AACP levels, else the stored
+/// ones, else a Battery Service level. None if the device isn't there.
+fn battery_of(snapshot: &[AppEvent], mac: &str) -> Option<Vec<BatteryInfo>> {
+    let mut known = false;
+    let mut cached = None;
+    for event in snapshot {
+        match event {
+            AppEvent::AACPEvent(m, aacp) if m == mac => {
+                if let AACPEvent::BatteryInfo(levels) = &**aacp {
+                    return Some(levels.clone());
+                }
+            }
+            AppEvent::CachedBattery {
+                mac: m, batteries, ..
+            } if m == mac => {
+                cached = Some(batteries.clone());
+            }
+            AppEvent::BatteryDevice { mac: m, level, .. } if m == mac => {
+                cached = Some(vec![BatteryInfo {
+                    component: BatteryComponent::Headphone,
+                    level: *level,
+                    status: BatteryStatus::NotCharging,
+                }]);
+            }
+            AppEvent::DeviceConnected { mac: m, .. } if m == mac => known = true,
+            _ => {}
+        }
+    }
+    cached.or(known.then(Vec::new))
+}
+
+fn device_list(snapshot: &[AppEvent]) -> Vec<DeviceSummary> {
+    let mut devices: Vec<DeviceSummary> = Vec::new();
+    for event in snapshot {
+        match event {
+            AppEvent::DeviceConnected {
+                mac,
+                name,
+                product_id,
+            } => devices.push(DeviceSummary {
+                mac: mac.clone(),
+                name: name.clone(),
+                product_id: Some(*product_id),
+                link_lost: false,
+            }),
+            AppEvent::BatteryDevice { mac, name, .. } if !devices.iter().any(|d| d.mac == *mac) => {
+                devices.push(DeviceSummary {
+                    mac: mac.clone(),
+                    name: name.clone(),
+                    product_id: None,
+                    link_lost: false,
+                })
+            }
+            _ => {}
+        }
+    }
+    for event in snapshot {
+        if let AppEvent::LinkLost(mac) = event
+            && let Some(d) = devices.iter_mut().find(|d| d.mac == *mac)
+        {
+            d.link_lost = true;
+        }
+    }
+    devices
+}
+
+fn encode_event(event: &AppEvent) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&RpcNotification::new(Notification::Event(event.clone())))
+}
 
 pub fn socket_path() -> std::io::Result<PathBuf> {
     Ok(crate::utils::runtime_dir()?.join("airpods-tui.sock"))
 }
 
+/// Held for its lifetime by the one process that talks to the AirPods
+/// (daemon, or a TUI/client running Bluetooth in-process), which also
+/// serves the socket. Everyone else connects to that instance.
+pub struct InstanceLock(#[allow(dead_code)] std::fs::File);
+
+impl InstanceLock {
+    /// The lock, or None while another instance holds it.
+    pub fn acquire() -> std::io::Result<Option<Self>> {
+        Self::acquire_at(&crate::utils::runtime_dir()?.join("airpods-tui.lock"))
+    }
+
+    fn acquire_at(path: &std::path::Path) -> std::io::Result<Option<Self>> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(InstanceLock(file))),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+            Err(std::fs::TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+/// Where the daemon leaves its last state on a clean shutdown.
+pub fn saved_snapshot_path() -> PathBuf {
+    crate::utils::state_dir().join("snapshot.json")
+}
+
+/// Write `events` as a JSON array, through a temporary file so a reader
+/// never sees half of it.
+pub fn save_snapshot(path: &std::path::Path, events: &[AppEvent]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(events).map_err(std::io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
+}
+
 async fn write_msg(stream: &mut (impl AsyncWriteExt + Unpin), data: &[u8]) -> std::io::Result<()> {
     let len = (data.len() as u32).to_be_bytes();
     stream.write_all(&len).await?;
@@ -18,10 +384,18 @@ async fn write_msg(stream: &mut (impl AsyncWriteExt + Unpin), data: &[u8]) -> st
 }
 
 async fn read_msg(stream: &mut (impl AsyncReadExt + Unpin)) -> std::io::Result<Vec<u8>> {
+    read_msg_limited(stream, MAX_FRAME).await
+}
+
+/// `read_msg` that refuses frames over `max` bytes before allocating them.
+async fn read_msg_limited(
+    stream: &mut (impl AsyncReadExt + Unpin),
+    max: usize,
+) -> std::io::Result<Vec<u8>> {
     let mut len_buf = [0u8; 4];
     stream.read_exact(&mut len_buf).await?;
     let len = u32::from_be_bytes(len_buf) as usize;
-    if len > 16 * 1024 * 1024 {
+    if len > max {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             "message too large",
@@ -44,6 +418,14 @@ pub fn update_snapshot(snapshot: &mut Vec<AppEvent>, event: &AppEvent) {
             snapshot.retain(|e| match e {
                 AppEvent::DeviceConnected { mac: m, .. } => m != mac,
                 AppEvent::AACPEvent(m, _) => m != mac,
+                AppEvent::AudioProfile { mac: m, .. } => m != mac,
+                AppEvent::MicMuted { mac: m, .. } => m != mac,
+                AppEvent::NowPlaying { mac: m, .. } => m != mac,
+                AppEvent::Playback { mac: m, .. } => m != mac,
+                AppEvent::LinkLost(m) => m != mac,
+                AppEvent::BatteryDevice { mac: m, .. } => m != mac,
+                AppEvent::LastSeen { mac: m, .. } => m != mac,
+                AppEvent::CachedBattery { mac: m, .. } => m != mac,
                 _ => true,
             });
             snapshot.push(event.clone());
@@ -52,6 +434,14 @@ pub fn update_snapshot(snapshot: &mut Vec<AppEvent>, event: &AppEvent) {
             snapshot.retain(|e| match e {
                 AppEvent::DeviceConnected { mac: m, .. } => m != mac,
                 AppEvent::AACPEvent(m, _) => m != mac,
+                AppEvent::AudioProfile { mac: m, .. } => m != mac,
+                AppEvent::MicMuted { mac: m, .. } => m != mac,
+                AppEvent::NowPlaying { mac: m, .. } => m != mac,
+                AppEvent::Playback { mac: m, .. } => m != mac,
+                AppEvent::LinkLost(m) => m != mac,
+                AppEvent::BatteryDevice { mac: m, .. } => m != mac,
+                AppEvent::LastSeen { mac: m, .. } => m != mac,
+                AppEvent::CachedBattery { mac: m, .. } => m != mac,
                 AppEvent::DeviceDisconnected(m) => m != mac,
                 _ => true,
             });
@@ -61,6 +451,10 @@ pub fn update_snapshot(snapshot: &mut Vec<AppEvent>, event: &AppEvent) {
             use crate::bluetooth::aacp::AACPEvent as AE;
             match &**aacp_event {
                 AE::BatteryInfo(new_infos) => {
+                    // Fresh levels supersede the stored ones
+                    snapshot.retain(
+                        |e| !matches!(e, AppEvent::CachedBattery { mac: m, .. } if m == mac),
+                    );
                     // Preserve last known "good" case battery when the new event
                     // reports Case as Disconnected (case was closed).  Without this,
                     // new IPC clients that replay the snapshot would lose the case
@@ -122,6 +516,46 @@ pub fn update_snapshot(snapshot: &mut Vec<AppEvent>, event: &AppEvent) {
             }
             snapshot.push(event.clone());
         }
+        AppEvent::AudioProfile { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::AudioProfile { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::MicMuted { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::MicMuted { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::NowPlaying { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::NowPlaying { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::Playback { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::Playback { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::LastSeen { mac, source, .. } => {
+            snapshot.retain(|e| {
+                !matches!(e, AppEvent::LastSeen { mac: m, source: s, .. } if m == mac && s == source)
+            });
+            snapshot.push(event.clone());
+        }
+        AppEvent::CachedBattery { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::CachedBattery { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::BatteryDevice { mac, .. } => {
+            snapshot.retain(|e| !matches!(e, AppEvent::BatteryDevice { mac: m, .. } if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::LinkLost(mac) => {
+            snapshot.retain(|e| !matches!(e, AppEvent::LinkLost(m) if m == mac));
+            snapshot.push(event.clone());
+        }
+        AppEvent::Nearby(_) => {
+            snapshot.retain(|e| !matches!(e, AppEvent::Nearby(_)));
+            snapshot.push(event.clone());
+        }
+        // Only meaningful to clients connected when it happened
+        AppEvent::CommandFailed { .. } | AppEvent::LidOpened(_) => {}
         AppEvent::AudioUnavailable => {
             if !snapshot
                 .iter()
@@ -133,6 +567,47 @@ pub fn update_snapshot(snapshot: &mut Vec<AppEvent>, event: &AppEvent) {
     }
 }
 
+/// Who may use the Unix socket, beyond its file mode.
+pub struct SocketAccess {
+    /// File mode applied after bind.
+    pub mode: u32,
+    /// Group whose members may connect too (needs a group-readable mode).
+    pub group: Option<crate::utils::GroupEntry>,
+    owner_uid: u32,
+}
+
+impl SocketAccess {
+    pub fn from_config(config: &crate::config::Config) -> std::io::Result<Self> {
+        let group = match config.ipc_socket_group.as_deref() {
+            Some(name) => Some(crate::utils::lookup_group(name).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("ipc_socket_group {:?} does not exist", name),
+                )
+            })?),
+            None => None,
+        };
+        Ok(Self {
+            mode: config.ipc_socket_mode,
+            group,
+            owner_uid: crate::utils::current_uid()?,
+        })
+    }
+
+    /// SO_PEERCRED check: the daemon's own user, or a member of `group`.
+    fn allows(&self, uid: u32, gid: u32) -> bool {
+        if uid == self.owner_uid {
+            return true;
+        }
+        let Some(group) = &self.group else {
+            return false;
+        };
+        gid == group.gid
+            || crate::utils::user_name(uid).is_some_and(|name| group.members.contains(&name))
+    }
+}
+
+#[derive(Clone)]
 pub struct IpcServer {
     snapshot: StateSnapshot,
     broadcast_tx: broadcast::Sender<AppEvent>,
@@ -158,7 +633,7 @@ impl IpcServer {
     }
 
     /// Run the IPC server, accepting connections on the Unix socket.
-    pub async fn run(&self) -> std::io::Result<()> {
+    pub async fn run(&self, access: SocketAccess) -> std::io::Result<()> {
         let path = socket_path()?;
         // Remove stale socket - ignore NotFound, log other errors
         if let Err(e) = std::fs::remove_file(&path)
@@ -169,95 +644,361 @@ impl IpcServer {
 
         let listener = UnixListener::bind(&path)?;
 
-        // Restrict socket to owner-only access
+        // Owner-only by default; ipc_socket_mode/group can widen it
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            if let Err(e) =
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(access.mode))
             {
                 log::warn!("Failed to set socket permissions: {}", e);
             }
+            if let Some(group) = &access.group
+                && let Err(e) = std::os::unix::fs::chown(&path, None, Some(group.gid))
+            {
+                log::warn!("Failed to set socket group: {}", e);
+            }
         }
 
         info!("IPC server listening on {}", path.display());
 
         loop {
             let (stream, _) = listener.accept().await?;
+            // The file mode is only the first gate: check who actually
+            // connected before they can send commands to the headphones.
+            match stream.peer_cred() {
+                Ok(cred) if access.allows(cred.uid(), cred.gid()) => {}
+                Ok(cred) => {
+                    warn!("Rejected IPC client uid={} gid={}", cred.uid(), cred.gid());
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Rejected IPC client without peer credentials: {}", e);
+                    continue;
+                }
+            }
             info!("IPC client connected");
-            let snapshot = self.snapshot.clone();
-            let mut event_rx = self.broadcast_tx.subscribe();
-            let cmd_tx = self.cmd_tx.clone();
+            self.spawn_client(stream, None);
+        }
+    }
 
+    /// Accept remote clients on TCP, inside TLS when `tls` is set. Every
+    /// client must send an `auth` request carrying `token` before the
+    /// daemon sends anything.
+    pub async fn run_tcp(
+        &self,
+        addr: &str,
+        token: String,
+        tls: Option<TlsAcceptor>,
+    ) -> std::io::Result<()> {
+        let listener = TcpListener::bind(&tcp_bind_addrs(addr, tls.is_some()).await?[..]).await?;
+        let scheme = if tls.is_some() { "tls" } else { "tcp" };
+        info!(
+            "IPC server listening on {}://{}",
+            scheme,
+            listener.local_addr()?
+        );
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let _ = stream.set_nodelay(true);
+            let Some(tls) = &tls else {
+                info!("Remote IPC client {} connected", peer);
+                self.spawn_client(stream, Some(token.clone()));
+                continue;
+            };
+            // Handshake off the accept loop so a stalled peer can't block it
+            let (server, tls, token) = (self.clone(), tls.clone(), token.clone());
             tokio::spawn(async move {
-                let (reader, writer) = stream.into_split();
-                let mut reader = tokio::io::BufReader::new(reader);
-                let mut writer = tokio::io::BufWriter::new(writer);
+                match tokio::time::timeout(AUTH_TIMEOUT, tls.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        info!("Remote IPC client {} connected over TLS", peer);
+                        server.spawn_client(stream, Some(token));
+                    }
+                    Ok(Err(e)) => warn!("TLS handshake with {} failed: {}", peer, e),
+                    Err(_) => warn!("TLS handshake with {} timed out", peer),
+                }
+            });
+        }
+    }
 
-                // Replay snapshot
-                {
-                    let snap = snapshot.read().await;
-                    for event in snap.iter() {
-                        let json = match serde_json::to_vec(event) {
-                            Ok(j) => j,
-                            Err(e) => {
-                                error!("Failed to serialize snapshot event: {}", e);
-                                continue;
-                            }
-                        };
-                        if write_msg(&mut writer, &json).await.is_err() {
-                            return;
-                        }
+    fn spawn_client<S>(&self, stream: S, token: Option<String>)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let snapshot = self.snapshot.clone();
+        let mut event_rx = self.broadcast_tx.subscribe();
+        let cmd_tx = self.cmd_tx.clone();
+
+        tokio::spawn(async move {
+            let (reader, writer) = tokio::io::split(stream);
+            let mut reader = tokio::io::BufReader::new(reader);
+            let mut writer = tokio::io::BufWriter::new(writer);
+
+            // TCP peers get nothing, not even the hello, before the token
+            if let Some(token) = token
+                && !authenticate(&mut reader, &mut writer, &token).await
+            {
+                warn!("Remote IPC client failed to authenticate");
+                return;
+            }
+
+            let hello = RpcNotification::new(hello());
+            match serde_json::to_vec(&hello) {
+                Ok(json) => {
+                    if write_msg(&mut writer, &json).await.is_err() {
+                        return;
                     }
                 }
+                Err(e) => {
+                    error!("Failed to serialize hello: {}", e);
+                    return;
+                }
+            }
 
-                // Spawn writer task: forward broadcast events to client
-                let (write_tx, mut write_rx) = mpsc::unbounded_channel::<Vec<u8>>();
-                let writer_handle = tokio::spawn(async move {
-                    while let Some(data) = write_rx.recv().await {
-                        if write_msg(&mut writer, &data).await.is_err() {
-                            break;
+            // Replay snapshot
+            {
+                let snap = snapshot.read().await;
+                for event in snap.iter() {
+                    let json = match encode_event(event) {
+                        Ok(j) => j,
+                        Err(e) => {
+                            error!("Failed to serialize snapshot event: {}", e);
+                            continue;
                         }
+                    };
+                    if write_msg(&mut writer, &json).await.is_err() {
+                        return;
                     }
-                });
-
-                // Forward broadcast events
-                let write_tx_clone = write_tx.clone();
-                let event_forward = tokio::spawn(async move {
-                    loop {
-                        match event_rx.recv().await {
-                            Ok(event) => {
-                                if let Ok(json) = serde_json::to_vec(&event)
-                                    && write_tx_clone.send(json).is_err()
-                                {
-                                    break;
-                                }
-                            }
-                            Err(broadcast::error::RecvError::Lagged(n)) => {
-                                info!("IPC client lagged by {} events", n);
+                }
+            }
+
+            // Spawn writer task: forward broadcast events to client
+            let (write_tx, mut write_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+            let writer_handle = tokio::spawn(async move {
+                while let Some(data) = write_rx.recv().await {
+                    if write_msg(&mut writer, &data).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            // Forward broadcast events
+            let write_tx_clone = write_tx.clone();
+            let event_forward = tokio::spawn(async move {
+                loop {
+                    match event_rx.recv().await {
+                        Ok(event) => {
+                            if let Ok(json) = encode_event(&event)
+                                && write_tx_clone.send(json).is_err()
+                            {
+                                break;
                             }
-                            Err(broadcast::error::RecvError::Closed) => break,
                         }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            info!("IPC client lagged by {} events", n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
-                });
+                }
+            });
 
-                // Read commands from client
-                while let Ok(data) = read_msg(&mut reader).await {
-                    match serde_json::from_slice::<(String, DeviceCommand)>(&data) {
-                        Ok(cmd) => {
-                            let _ = cmd_tx.send(cmd);
-                        }
-                        Err(e) => {
-                            error!("Invalid IPC command: {}", e);
+            // Read requests from client
+            while let Ok(data) = read_msg(&mut reader).await {
+                let response = handle_request(&data, &cmd_tx, &snapshot.read().await);
+                if let Some(err) = &response.error {
+                    warn!("Invalid IPC request: {}", err.message);
+                }
+                match serde_json::to_vec(&response) {
+                    Ok(json) => {
+                        if write_tx.send(json).is_err() {
+                            break;
                         }
                     }
+                    Err(e) => error!("Failed to serialize IPC response: {}", e),
                 }
+            }
 
-                info!("IPC client disconnected");
-                event_forward.abort();
-                writer_handle.abort();
-            });
+            info!("IPC client disconnected");
+            event_forward.abort();
+            writer_handle.abort();
+        });
+    }
+}
+
+/// Wait for the client's `auth` request and answer it. Returns whether the
+/// client presented `token`.
+async fn authenticate(
+    reader: &mut (impl AsyncReadExt + Unpin),
+    writer: &mut (impl AsyncWriteExt + Unpin),
+    token: &str,
+) -> bool {
+    let Ok(Ok(data)) =
+        tokio::time::timeout(AUTH_TIMEOUT, read_msg_limited(reader, MAX_AUTH_FRAME)).await
+    else {
+        return false;
+    };
+    let response = match serde_json::from_slice::<RpcRequest>(&data) {
+        Ok(RpcRequest {
+            id,
+            request: Request::Auth { token: presented },
+            ..
+        }) => {
+            if token_matches(&presented, token) {
+                RpcResponse::ok(id, Response::Ack)
+            } else {
+                RpcResponse::err(Some(id), UNAUTHORIZED, "invalid token")
+            }
         }
+        Ok(req) => RpcResponse::err(Some(req.id), UNAUTHORIZED, "authenticate first"),
+        Err(e) => RpcResponse::err(None, PARSE_ERROR, e.to_string()),
+    };
+    let ok = response.error.is_none();
+    if let Ok(json) = serde_json::to_vec(&response) {
+        let _ = write_msg(writer, &json).await;
     }
+    ok
+}
+
+/// Where `ipc_tcp_listen` binds: a bare port means loopback. Addresses off
+/// this host need `tls`, so the token and state never cross the network
+/// in the clear.
+async fn tcp_bind_addrs(listen: &str, tls: bool) -> std::io::Result<Vec<std::net::SocketAddr>> {
+    if let Ok(port) = listen.parse::<u16>() {
+        return Ok(vec![(std::net::Ipv4Addr::LOCALHOST, port).into()]);
+    }
+    let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host(listen).await?.collect();
+    if !tls && addrs.iter().any(|a| !a.ip().is_loopback()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} is not a loopback address; set ipc_tcp_tls_cert and ipc_tcp_tls_key to serve it over TLS",
+                listen
+            ),
+        ));
+    }
+    Ok(addrs)
+}
+
+// ── TLS ──
+//
+// The daemon serves the certificate and key from `ipc_tcp_tls_cert` and
+// `ipc_tcp_tls_key`. Clients pin that same certificate instead of
+// checking a CA chain, so a self-signed one is enough and the hostname
+// they dial doesn't matter.
+
+/// Name sent in the ClientHello; the pinned certificate is not checked
+/// against it.
+fn tls_server_name() -> ServerName<'static> {
+    ServerName::try_from("airpods-tui").expect("airpods-tui is a valid DNS name")
+}
+
+fn tls_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn pem_error(path: &std::path::Path, e: rustls::pki_types::pem::Error) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), e),
+    )
+}
+
+/// TLS server side of the TCP listener, from a PEM certificate chain and
+/// private key.
+pub fn tls_acceptor(cert: &std::path::Path, key: &std::path::Path) -> std::io::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| pem_error(cert, e))?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| pem_error(key, e))?;
+    let config = rustls::ServerConfig::builder_with_provider(tls_provider())
+        .with_safe_default_protocol_versions()
+        .and_then(|b| b.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(std::io::Error::other)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// TLS client side for `--remote`, trusting only the daemon certificate
+/// in `cert` (its first PEM block).
+pub fn tls_connector(cert: &std::path::Path) -> std::io::Result<TlsConnector> {
+    let pinned = CertificateDer::from_pem_file(cert).map_err(|e| pem_error(cert, e))?;
+    let provider = tls_provider();
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(std::io::Error::other)?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCert { pinned, provider }))
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Accepts exactly one server certificate; signatures are still checked,
+/// so the peer must hold its key.
+#[derive(Debug)]
+struct PinnedCert {
+    pinned: CertificateDer<'static>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if end_entity.as_ref() == self.pinned.as_ref() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Compare tokens without short-circuiting on the first differing byte.
+fn token_matches(presented: &str, expected: &str) -> bool {
+    let (a, b) = (presented.as_bytes(), expected.as_bytes());
+    if a.len() != b.len() || b.is_empty() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 /// Connect to a running daemon via Unix socket.
@@ -269,26 +1010,76 @@ pub async fn ipc_connect() -> std::io::Result<(
     let path = socket_path()?;
     let stream = UnixStream::connect(&path).await?;
     info!("Connected to IPC daemon at {}", path.display());
+    client_session(stream, None).await
+}
 
-    let (reader, writer) = stream.into_split();
+/// Connect to a remote daemon's TCP listener, over TLS when `tls` is set,
+/// and authenticate with `token`.
+pub async fn ipc_connect_tcp(
+    addr: &str,
+    token: &str,
+    tls: Option<&TlsConnector>,
+) -> std::io::Result<(
+    mpsc::UnboundedSender<(String, DeviceCommand)>,
+    mpsc::UnboundedReceiver<AppEvent>,
+)> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    info!("Connected to remote IPC daemon at {}", addr);
+    match tls {
+        Some(tls) => {
+            client_session(tls.connect(tls_server_name(), stream).await?, Some(token)).await
+        }
+        None => client_session(stream, Some(token)).await,
+    }
+}
+
+async fn client_session<S>(
+    stream: S,
+    token: Option<&str>,
+) -> std::io::Result<(
+    mpsc::UnboundedSender<(String, DeviceCommand)>,
+    mpsc::UnboundedReceiver<AppEvent>,
+)>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, writer) = tokio::io::split(stream);
     let mut reader = tokio::io::BufReader::new(reader);
     let mut writer = tokio::io::BufWriter::new(writer);
 
+    // Over TCP the daemon only says hello once the token is accepted
+    if let Some(token) = token {
+        client_auth(&mut reader, &mut writer, token).await?;
+    }
+    let capabilities = read_hello(&mut reader).await?;
+    info!("IPC daemon capabilities: {}", capabilities.join(", "));
+
     let (event_tx, event_rx) = mpsc::unbounded_channel::<AppEvent>();
     let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel::<(String, DeviceCommand)>();
 
-    // Read events from daemon → event_tx
+    // Read notifications and responses from daemon → event_tx
     tokio::spawn(async move {
         loop {
             match read_msg(&mut reader).await {
-                Ok(data) => match serde_json::from_slice::<AppEvent>(&data) {
-                    Ok(event) => {
-                        if event_tx.send(event).is_err() {
-                            break;
+                Ok(data) => match serde_json::from_slice::<ServerMessage>(&data) {
+                    Ok(ServerMessage::Notification(n)) => match n.notification {
+                        Notification::Event(event) => {
+                            if event_tx.send(event).is_err() {
+                                break;
+                            }
+                        }
+                        Notification::Hello { .. } => {}
+                    },
+                    Ok(ServerMessage::Response(r)) => {
+                        if let Some(err) = r.error {
+                            warn!("IPC request {:?} failed: {}", r.id, err.message);
                         }
                     }
+                    // A newer daemon (same version) may add events or
+                    // messages this client doesn't know; skip them.
                     Err(e) => {
-                        error!("Invalid IPC event: {}", e);
+                        debug!("Skipping IPC message this client can't read: {}", e);
                     }
                 },
                 Err(_) => {
@@ -301,18 +1092,186 @@ pub async fn ipc_connect() -> std::io::Result<(
 
     // Write commands from cmd_tx → daemon
     tokio::spawn(async move {
-        while let Some(cmd) = cmd_rx.recv().await {
-            if let Ok(json) = serde_json::to_vec(&cmd)
+        let mut next_id: u64 = 1;
+        while let Some((mac, command)) = cmd_rx.recv().await {
+            let req = RpcRequest::new(next_id, Request::Command { mac, command });
+            next_id += 1;
+            if let Ok(json) = serde_json::to_vec(&req)
                 && write_msg(&mut writer, &json).await.is_err()
             {
                 break;
             }
         }
+        // Half-close so the daemon hangs up once it has handled everything;
+        // one-shot clients wait for event_rx to close to know it's done.
+        let _ = writer.shutdown().await;
     });
 
     Ok((cmd_tx, event_rx))
 }
 
+/// Present `token` as the first request (id 0, reserved for auth; regular
+/// requests start at 1).
+async fn client_auth(
+    reader: &mut (impl AsyncReadExt + Unpin),
+    writer: &mut (impl AsyncWriteExt + Unpin),
+    token: &str,
+) -> std::io::Result<()> {
+    let req = RpcRequest::new(
+        0,
+        Request::Auth {
+            token: token.to_string(),
+        },
+    );
+    write_msg(writer, &serde_json::to_vec(&req)?).await?;
+    let reply = read_msg(reader).await?;
+    match serde_json::from_slice::<ServerMessage>(&reply) {
+        Ok(ServerMessage::Response(RpcResponse { error: None, .. })) => Ok(()),
+        Ok(ServerMessage::Response(RpcResponse {
+            error: Some(err), ..
+        })) => Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            err.message,
+        )),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "unexpected reply to auth",
+        )),
+    }
+}
+
+/// Send one request to the local daemon and return its response, for
+/// one-shot commands that shouldn't wait on the event stream.
+pub async fn ipc_request(request: Request) -> std::io::Result<Response> {
+    let stream = UnixStream::connect(socket_path()?).await?;
+    one_shot(stream, None, request).await
+}
+
+/// `ipc_request` against a remote daemon's TCP listener.
+pub async fn ipc_request_tcp(
+    addr: &str,
+    token: &str,
+    tls: Option<&TlsConnector>,
+    request: Request,
+) -> std::io::Result<Response> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    match tls {
+        Some(tls) => {
+            one_shot(
+                tls.connect(tls_server_name(), stream).await?,
+                Some(token),
+                request,
+            )
+            .await
+        }
+        None => one_shot(stream, Some(token), request).await,
+    }
+}
+
+async fn one_shot<S>(stream: S, token: Option<&str>, request: Request) -> std::io::Result<Response>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, writer) = tokio::io::split(stream);
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut writer = tokio::io::BufWriter::new(writer);
+
+    if let Some(token) = token {
+        client_auth(&mut reader, &mut writer, token).await?;
+    }
+    let capabilities = read_hello(&mut reader).await?;
+    let method = method_name(&request);
+    if !capabilities.contains(&method) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("daemon doesn't support {}; restart it", method),
+        ));
+    }
+
+    let req = RpcRequest::new(1, request);
+    write_msg(&mut writer, &serde_json::to_vec(&req)?).await?;
+    let response = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        // The snapshot replay comes first; skip to our response
+        loop {
+            let data = read_msg(&mut reader).await?;
+            if let Ok(ServerMessage::Response(r)) = serde_json::from_slice::<ServerMessage>(&data)
+                && r.id == Some(1)
+            {
+                return Ok::<_, std::io::Error>(r);
+            }
+        }
+    })
+    .await
+    .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "daemon did not answer"))??;
+    match response {
+        RpcResponse {
+            result: Some(result),
+            ..
+        } => Ok(result),
+        RpcResponse {
+            error: Some(err), ..
+        } => Err(std::io::Error::other(err.message)),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "empty response",
+        )),
+    }
+}
+
+/// The JSON-RPC method (and capability) name of `request`.
+fn method_name(request: &Request) -> String {
+    serde_json::to_value(request)
+        .ok()
+        .and_then(|v| v.get("method")?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn hello() -> Notification {
+    Notification::Hello {
+        version: PROTOCOL_VERSION,
+        capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+/// Read the daemon's opening hello, returning its capabilities if it
+/// speaks our protocol version.
+async fn read_hello(reader: &mut (impl AsyncReadExt + Unpin)) -> std::io::Result<Vec<String>> {
+    // The daemon always opens with hello; refuse to guess at anything else.
+    let hello = read_msg(reader).await?;
+    match serde_json::from_slice::<ServerMessage>(&hello) {
+        Ok(ServerMessage::Notification(RpcNotification {
+            notification:
+                Notification::Hello {
+                    version,
+                    capabilities,
+                },
+            ..
+        })) => {
+            check_version(version)?;
+            Ok(capabilities)
+        }
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "daemon did not send a protocol hello (version mismatch?)",
+        )),
+    }
+}
+
+fn check_version(version: u32) -> std::io::Result<()> {
+    if version == PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "daemon speaks IPC protocol v{}, this client speaks v{}; restart the daemon",
+                version, PROTOCOL_VERSION
+            ),
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,6 +1281,32 @@ mod tests {
         EarDetectionStatus, StemPressBudType, StemPressType,
     };
 
+    fn access(group: Option<crate::utils::GroupEntry>) -> SocketAccess {
+        SocketAccess {
+            mode: 0o600,
+            group,
+            owner_uid: 1000,
+        }
+    }
+
+    #[test]
+    fn socket_access_owner_only_by_default() {
+        let a = access(None);
+        assert!(a.allows(1000, 1000));
+        assert!(!a.allows(1001, 1000));
+        assert!(!a.allows(0, 0));
+    }
+
+    #[test]
+    fn socket_access_admits_group_by_primary_gid() {
+        let a = access(Some(crate::utils::GroupEntry {
+            gid: 995,
+            members: Vec::new(),
+        }));
+        assert!(a.allows(1001, 995));
+        assert!(!a.allows(1001, 1001));
+    }
+
     const MAC_A: &str = "AA:BB:CC:DD:EE:FF";
     const MAC_B: &str = "11:22:33:44:55:66";
 
@@ -682,6 +1667,27 @@ mod tests {
         assert_eq!(count_aacp(&snap, MAC_A), 1);
     }
 
+    #[test]
+    fn snapshot_keeps_latest_audio_profile() {
+        let mut snap = Vec::new();
+        let profile = |p: &str| AppEvent::AudioProfile {
+            mac: MAC_A.into(),
+            profile: Some(p.into()),
+        };
+        update_snapshot(&mut snap, &profile("a2dp-sink-sbc"));
+        update_snapshot(&mut snap, &profile("a2dp-sink-aac"));
+        assert_eq!(snap.len(), 1);
+        assert!(
+            matches!(&snap[0], AppEvent::AudioProfile { profile: Some(p), .. } if p == "a2dp-sink-aac")
+        );
+        update_snapshot(&mut snap, &AppEvent::DeviceDisconnected(MAC_A.into()));
+        assert!(
+            !snap
+                .iter()
+                .any(|e| matches!(e, AppEvent::AudioProfile { .. }))
+        );
+    }
+
     #[test]
     fn snapshot_audio_unavailable_dedupes() {
         let mut snap = Vec::new();
@@ -695,4 +1701,368 @@ mod tests {
             1
         );
     }
+
+    #[test]
+    fn request_uses_jsonrpc_envelope() {
+        let req = RpcRequest::new(
+            7,
+            Request::Command {
+                mac: MAC_A.into(),
+                command: DeviceCommand::Rename("Pods".into()),
+            },
+        );
+        let v = serde_json::to_value(&req).unwrap();
+        assert_eq!(v["jsonrpc"], "2.0");
+        assert_eq!(v["id"], 7);
+        assert_eq!(v["method"], "command");
+        assert_eq!(v["params"]["mac"], MAC_A);
+    }
+
+    #[test]
+    fn handle_request_acks_command_and_forwards_it() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let req = RpcRequest::new(
+            3,
+            Request::Command {
+                mac: MAC_A.into(),
+                command: DeviceCommand::Rename("Pods".into()),
+            },
+        );
+        let resp = handle_request(&serde_json::to_vec(&req).unwrap(), &tx, &[]);
+        assert_eq!(resp.id, Some(3));
+        assert!(matches!(resp.result, Some(Response::Ack)));
+        assert!(resp.error.is_none());
+        let (mac, _) = rx.try_recv().unwrap();
+        assert_eq!(mac, MAC_A);
+    }
+
+    #[test]
+    fn handle_request_reports_invalid_params_with_id() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let resp = handle_request(
+            br#"{"jsonrpc":"2.0","id":9,"method":"get_battery","params":{}}"#,
+            &tx,
+            &[],
+        );
+        assert_eq!(resp.id, Some(9));
+        assert_eq!(resp.error.unwrap().code, INVALID_REQUEST);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn handle_request_reports_unknown_method() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let resp = handle_request(br#"{"jsonrpc":"2.0","id":9,"method":"bogus"}"#, &tx, &[]);
+        assert_eq!(resp.id, Some(9));
+        assert_eq!(resp.error.unwrap().code, METHOD_NOT_FOUND);
+        assert!(rx.try_recv().is_err());
+
+        let known = [
+            Request::Auth { token: "t".into() },
+            Request::Command {
+                mac: MAC_A.into(),
+                command: DeviceCommand::Rename("Pods".into()),
+            },
+            Request::GetState,
+            Request::GetBattery { mac: MAC_A.into() },
+            Request::ListDevices,
+        ];
+        for request in &known {
+            assert!(METHODS.contains(&method_name(request).as_str()));
+        }
+        assert_eq!(known.len(), METHODS.len());
+    }
+
+    #[test]
+    fn handle_request_reports_parse_error_without_id() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let resp = handle_request(b"not json", &tx, &[]);
+        assert_eq!(resp.id, None);
+        assert_eq!(resp.error.unwrap().code, PARSE_ERROR);
+    }
+
+    #[test]
+    fn queries_answer_from_snapshot() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let left = BatteryInfo {
+            component: BatteryComponent::Left,
+            level: 70,
+            status: BatteryStatus::NotCharging,
+        };
+        let snap = vec![
+            AppEvent::DeviceConnected {
+                mac: MAC_A.into(),
+                name: "Pods".into(),
+                product_id: 0x2014,
+            },
+            battery_event(MAC_A, vec![left.clone()]),
+            AppEvent::BatteryDevice {
+                mac: MAC_B.into(),
+                name: "Buds".into(),
+                level: 40,
+            },
+            AppEvent::LinkLost(MAC_A.into()),
+        ];
+        let ask = |id, request| {
+            handle_request(
+                &serde_json::to_vec(&RpcRequest::new(id, request)).unwrap(),
+                &tx,
+                &snap,
+            )
+        };
+
+        match ask(1, Request::GetBattery { mac: MAC_A.into() }).result {
+            Some(Response::Battery(levels)) => assert_eq!(levels, vec![left]),
+            other => panic!("unexpected {:?}", other),
+        }
+        match ask(2, Request::GetBattery { mac: MAC_B.into() }).result {
+            Some(Response::Battery(levels)) => assert_eq!(levels[0].level, 40),
+            other => panic!("unexpected {:?}", other),
+        }
+        let resp = ask(
+            3,
+            Request::GetBattery {
+                mac: "00:00:00:00:00:00".into(),
+            },
+        );
+        assert_eq!(resp.id, Some(3));
+        assert_eq!(resp.error.unwrap().code, UNKNOWN_DEVICE);
+
+        match ask(4, Request::ListDevices).result {
+            Some(Response::Devices(devices)) => {
+                assert_eq!(devices.len(), 2);
+                assert_eq!(devices[0].product_id, Some(0x2014));
+                assert!(devices[0].link_lost);
+                assert_eq!(devices[1].name, "Buds");
+                assert_eq!(devices[1].product_id, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match ask(5, Request::GetState).result {
+            Some(Response::State(events)) => assert_eq!(events.len(), snap.len()),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(method_name(&Request::ListDevices), "list_devices");
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        let json = serde_json::to_vec(&RpcNotification::new(hello())).unwrap();
+        match serde_json::from_slice::<ServerMessage>(&json).unwrap() {
+            ServerMessage::Notification(RpcNotification {
+                notification:
+                    Notification::Hello {
+                        version,
+                        capabilities,
+                    },
+                ..
+            }) => {
+                assert_eq!(version, PROTOCOL_VERSION);
+                assert_eq!(capabilities, CAPABILITIES);
+            }
+            other => panic!("not a hello: {:?}", other),
+        }
+
+        let json = encode_event(&AppEvent::DeviceDisconnected(MAC_A.into())).unwrap();
+        assert!(matches!(
+            serde_json::from_slice::<ServerMessage>(&json).unwrap(),
+            ServerMessage::Notification(RpcNotification {
+                notification: Notification::Event(AppEvent::DeviceDisconnected(_)),
+                ..
+            })
+        ));
+
+        let json = serde_json::to_vec(&RpcResponse::ok(1, Response::Ack)).unwrap();
+        assert!(matches!(
+            serde_json::from_slice::<ServerMessage>(&json).unwrap(),
+            ServerMessage::Response(RpcResponse { id: Some(1), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn hello_without_capabilities_still_reads() {
+        // What a daemon from before capability flags sends
+        let old = br#"{"jsonrpc":"2.0","method":"hello","params":{"version":1}}"#;
+        let mut frame = (old.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(old);
+        let caps = read_hello(&mut frame.as_slice()).await.unwrap();
+        assert!(caps.is_empty());
+
+        let newer =
+            br#"{"jsonrpc":"2.0","method":"hello","params":{"version":2,"capabilities":[]}}"#;
+        let mut frame = (newer.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(newer);
+        let err = read_hello(&mut frame.as_slice()).await.unwrap_err();
+        assert!(err.to_string().contains("restart the daemon"));
+    }
+
+    #[test]
+    fn instance_lock_is_exclusive() {
+        let path =
+            std::env::temp_dir().join(format!("airpods-tui-instance-{}.lock", std::process::id()));
+        let first = InstanceLock::acquire_at(&path).unwrap();
+        assert!(first.is_some());
+        assert!(InstanceLock::acquire_at(&path).unwrap().is_none());
+        drop(first);
+        assert!(InstanceLock::acquire_at(&path).unwrap().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_disk() {
+        let dir = std::env::temp_dir().join(format!("airpods-tui-snapshot-{}", std::process::id()));
+        let path = dir.join("snapshot.json");
+        let events = vec![AppEvent::DeviceDisconnected("AA:BB:CC:DD:EE:FF".into())];
+        save_snapshot(&path, &events).unwrap();
+        let read: Vec<AppEvent> = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(read.len(), 1);
+        assert!(
+            matches!(&read[0], AppEvent::DeviceDisconnected(mac) if mac == "AA:BB:CC:DD:EE:FF")
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn version_mismatch_is_rejected() {
+        assert!(check_version(PROTOCOL_VERSION).is_ok());
+        assert!(check_version(PROTOCOL_VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn token_matches_requires_exact_nonempty_token() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cre", "s3cret"));
+        assert!(!token_matches("s3creT", "s3cret"));
+        assert!(!token_matches("", ""));
+    }
+
+    #[tokio::test]
+    async fn tcp_client_must_authenticate() {
+        let (cmd_tx, _cmd_rx) = mpsc::unbounded_channel();
+        let snapshot: StateSnapshot = Arc::new(RwLock::new(vec![AppEvent::DeviceConnected {
+            mac: MAC_A.into(),
+            name: "Pods".into(),
+            product_id: 0x2014,
+        }]));
+        let server = Arc::new(IpcServer::new(snapshot, cmd_tx));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let srv = server.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                srv.spawn_client(stream, Some("s3cret".into()));
+            }
+        });
+
+        let err = ipc_connect_tcp(&addr, "wrong", None).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+
+        let (_tx, mut rx) = ipc_connect_tcp(&addr, "s3cret", None).await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(event, AppEvent::DeviceConnected { mac, .. } if mac == MAC_A));
+
+        // One-shot requests skip the replay and get their own response
+        let resp = ipc_request_tcp(
+            &addr,
+            "s3cret",
+            None,
+            Request::GetBattery { mac: MAC_A.into() },
+        )
+        .await
+        .unwrap();
+        assert!(matches!(resp, Response::Battery(levels) if levels.is_empty()));
+        let err = ipc_request_tcp(&addr, "wrong", None, Request::ListDevices)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_tcp_peer_gets_nothing() {
+        let (cmd_tx, _cmd_rx) = mpsc::unbounded_channel();
+        let server = IpcServer::new(Arc::new(RwLock::new(Vec::new())), cmd_tx);
+
+        // No hello (or anything else) before the auth request
+        let (client, daemon) = tokio::io::duplex(64 * 1024);
+        server.spawn_client(daemon, Some("s3cret".into()));
+        let (mut reader, _writer) = tokio::io::split(client);
+        let early = tokio::time::timeout(Duration::from_millis(200), read_msg(&mut reader)).await;
+        assert!(early.is_err(), "daemon wrote before auth");
+
+        // An oversized first frame is refused without reading its body
+        let (client, daemon) = tokio::io::duplex(64 * 1024);
+        server.spawn_client(daemon, Some("s3cret".into()));
+        let (mut reader, mut writer) = tokio::io::split(client);
+        writer
+            .write_all(&((MAX_AUTH_FRAME + 1) as u32).to_be_bytes())
+            .await
+            .unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(2), read_msg(&mut reader))
+            .await
+            .unwrap();
+        assert!(closed.is_err());
+    }
+
+    #[tokio::test]
+    async fn tcp_listener_defaults_to_loopback() {
+        assert_eq!(
+            tcp_bind_addrs("7625", false).await.unwrap(),
+            vec!["127.0.0.1:7625".parse().unwrap()]
+        );
+        assert!(tcp_bind_addrs("127.0.0.1:7625", false).await.is_ok());
+        let err = tcp_bind_addrs("0.0.0.0:7625", false).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(tcp_bind_addrs("0.0.0.0:7625", true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn tls_client_pins_the_daemon_certificate() {
+        let dir = std::env::temp_dir().join(format!("airpods-tui-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let daemon = rcgen::generate_simple_self_signed(vec!["airpods-tui".into()]).unwrap();
+        let other = rcgen::generate_simple_self_signed(vec!["airpods-tui".into()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), daemon.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), daemon.signing_key.serialize_pem()).unwrap();
+        std::fs::write(dir.join("other.pem"), other.cert.pem()).unwrap();
+
+        let (cmd_tx, _cmd_rx) = mpsc::unbounded_channel();
+        let server = IpcServer::new(Arc::new(RwLock::new(Vec::new())), cmd_tx);
+        let tls = tls_acceptor(&dir.join("cert.pem"), &dir.join("key.pem")).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                if let Ok(stream) = tls.accept(stream).await {
+                    server.spawn_client(stream, Some("s3cret".into()));
+                }
+            }
+        });
+
+        let pinned = tls_connector(&dir.join("cert.pem")).unwrap();
+        let resp = ipc_request_tcp(&addr, "s3cret", Some(&pinned), Request::ListDevices)
+            .await
+            .unwrap();
+        assert!(matches!(resp, Response::Devices(devices) if devices.is_empty()));
+
+        // Another certificate, even for the same name, is refused
+        let wrong = tls_connector(&dir.join("other.pem")).unwrap();
+        assert!(
+            ipc_request_tcp(&addr, "s3cret", Some(&wrong), Request::ListDevices)
+                .await
+                .is_err()
+        );
+        // And a plain client never gets past the handshake
+        assert!(
+            ipc_request_tcp(&addr, "s3cret", None, Request::ListDevices)
+                .await
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
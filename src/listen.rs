@@ -0,0 +1,73 @@
+//! `listen` subcommand: every `AppEvent` as one JSON line on stdout,
+//! `{"version":1,"event":...}`, for tools that want raw events (ear
+//! detection, control commands) rather than `status` snapshots. The event
+//! is in the shape the IPC socket carries; `version` goes up when that
+//! shape changes incompatibly.
+
+use crate::tui::app::{AppEvent, DeviceCommand};
+use serde::Serialize;
+use std::io::{self, Write};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// Version of the line format.
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Line<'a> {
+    version: u32,
+    event: &'a AppEvent,
+}
+
+pub async fn run(
+    mut app_rx: UnboundedReceiver<AppEvent>,
+    // Held so an in-process backend keeps running
+    _cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    while let Some(event) = app_rx.recv().await {
+        match print(&mut stdout, &event).and_then(|_| stdout.flush()) {
+            Ok(()) => {}
+            // Reader went away (`| head`, script exited)
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn print(out: &mut impl Write, event: &AppEvent) -> io::Result<()> {
+    let line = Line {
+        version: VERSION,
+        event,
+    };
+    let line = serde_json::to_string(&line).map_err(io::Error::other)?;
+    writeln!(out, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::aacp::{AACPEvent, EarDetectionStatus};
+
+    #[test]
+    fn events_are_single_json_lines() {
+        let event = AppEvent::AACPEvent(
+            "AA:BB:CC:DD:EE:FF".into(),
+            Box::new(AACPEvent::EarDetection {
+                old_left: Some(EarDetectionStatus::InEar),
+                old_right: None,
+                new_left: Some(EarDetectionStatus::OutOfEar),
+                new_right: None,
+            }),
+        );
+        let mut out = Vec::new();
+        print(&mut out, &event).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let line = out.strip_suffix('\n').unwrap();
+        assert!(!line.contains('\n'));
+        let v: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(v["version"], 1);
+        assert_eq!(v["event"]["AACPEvent"][0], "AA:BB:CC:DD:EE:FF");
+        assert!(v["event"]["AACPEvent"][1]["EarDetection"].is_object());
+    }
+}
@@ -0,0 +1,308 @@
+//! Logger setup: stderr by default (the journal picks it up under systemd),
+//! a size-rotated file from `[log]` / `--log-file`, or the journal's native
+//! protocol with structured fields.
+
+use crate::config::{LogConfig, LogFormat};
+use log::{Level, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Where log records go.
+pub enum Destination {
+    Stderr,
+    File(PathBuf),
+    Journal,
+}
+
+/// Default log file: `$XDG_STATE_HOME/airpods-tui/airpods-tui.log`.
+pub fn default_log_path() -> PathBuf {
+    crate::utils::state_dir().join("airpods-tui.log")
+}
+
+/// Install the global logger. `RUST_LOG` overrides `level`.
+pub fn init(level: &str, destination: Destination, format: LogFormat, config: &LogConfig) {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level));
+    builder.target(env_logger::Target::Stderr);
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = json_line(&buf.timestamp().to_string(), record);
+            writeln!(buf, "{}", line)
+        });
+    }
+    if let Destination::Journal = destination {
+        match JournalLogger::connect(builder.build()) {
+            Ok(logger) => {
+                log::set_max_level(logger.filter.filter());
+                if log::set_boxed_logger(Box::new(logger)).is_ok() {
+                    return;
+                }
+            }
+            Err(e) => eprintln!("Can't reach the journal: {}, logging to stderr", e),
+        }
+    }
+    if let Destination::File(path) = &destination {
+        match RotatingFile::open(path, config.max_size_kb * 1024, config.keep) {
+            Ok(file) => {
+                builder
+                    .target(env_logger::Target::Pipe(Box::new(file)))
+                    .write_style(env_logger::WriteStyle::Never);
+            }
+            Err(e) => eprintln!(
+                "Can't open log file {}: {}, logging to stderr",
+                path.display(),
+                e
+            ),
+        }
+    }
+    builder.init();
+}
+
+/// Log file that moves itself to `<path>.1` (shifting older ones up to
+/// `<path>.<keep>`) once it would grow past `max_bytes`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: u32,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_bytes: u64, keep: u32) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    fn rotated(&self, n: u32) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep > 0 {
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Logger speaking journald's native protocol, so records carry
+/// PRIORITY, the module, and the device MAC when the message names one.
+struct JournalLogger {
+    /// env_logger, used only for its `RUST_LOG`-style filtering.
+    filter: env_logger::Logger,
+    socket: UnixDatagram,
+}
+
+impl JournalLogger {
+    fn connect(filter: env_logger::Logger) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+        Ok(Self { filter, socket })
+    }
+}
+
+impl Log for JournalLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.matches(record) {
+            return;
+        }
+        let message = record.args().to_string();
+        if let Err(e) = self.socket.send(&journal_entry(record, &message)) {
+            eprintln!("[{}] {} (journal: {})", record.level(), message, e);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// syslog(3) priority for a log level.
+fn priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+fn journal_entry(record: &Record, message: &str) -> Vec<u8> {
+    let mut entry = Vec::new();
+    let mut field = |name: &str, value: &str| {
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Binary-safe form: name, newline, little-endian length, value
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    };
+    field("MESSAGE", message);
+    field("PRIORITY", &priority(record.level()).to_string());
+    field("SYSLOG_IDENTIFIER", "airpods-tui");
+    field("AIRPODS_MODULE", record.target());
+    if let Some(subsystem) = record.target().rsplit("::").next() {
+        field("AIRPODS_SUBSYSTEM", subsystem);
+    }
+    if let Some(mac) = find_mac(message) {
+        field("AIRPODS_MAC", mac);
+    }
+    if let Some(file) = record.file() {
+        field("CODE_FILE", file);
+    }
+    if let Some(line) = record.line() {
+        field("CODE_LINE", &line.to_string());
+    }
+    entry
+}
+
+/// One `[log] format = "json"` line.
+fn json_line(timestamp: &str, record: &Record) -> String {
+    let message = record.args().to_string();
+    serde_json::json!({
+        "timestamp": timestamp,
+        "level": record.level().as_str(),
+        "module": record.target(),
+        "mac": find_mac(&message),
+        "message": message,
+    })
+    .to_string()
+}
+
+/// First `AA:BB:CC:DD:EE:FF`-shaped address in `text`.
+fn find_mac(text: &str) -> Option<&str> {
+    let bytes = text.as_bytes();
+    (0..bytes.len().saturating_sub(16)).find_map(|start| {
+        let candidate = &bytes[start..start + 17];
+        let is_mac = candidate.iter().enumerate().all(|(i, b)| {
+            if i % 3 == 2 {
+                *b == b':'
+            } else {
+                b.is_ascii_hexdigit()
+            }
+        });
+        is_mac.then(|| &text[start..start + 17])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_mac_in_message() {
+        assert_eq!(
+            find_mac("AACP connected to aa:bb:CC:DD:EE:FF on hci0"),
+            Some("aa:bb:CC:DD:EE:FF")
+        );
+        assert_eq!(find_mac("AA:BB:CC:DD:EE"), None);
+        assert_eq!(find_mac(""), None);
+    }
+
+    #[test]
+    fn journal_entry_fields() {
+        let message = "Battery for AA:BB:CC:DD:EE:FF\nsecond line";
+        let args = format_args!("{}", message);
+        let record = Record::builder()
+            .args(args)
+            .level(Level::Warn)
+            .target("airpods_tui::bluetooth::aacp")
+            .build();
+        let entry = journal_entry(&record, message);
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        expected.extend_from_slice(message.as_bytes());
+        expected.push(b'\n');
+        assert!(entry.starts_with(&expected));
+        let text = String::from_utf8_lossy(&entry);
+        assert!(text.contains("PRIORITY=4\n"));
+        assert!(text.contains("AIRPODS_SUBSYSTEM=aacp\n"));
+        assert!(text.contains("AIRPODS_MAC=AA:BB:CC:DD:EE:FF\n"));
+    }
+
+    #[test]
+    fn json_line_fields() {
+        let record = Record::builder()
+            .args(format_args!("AACP connected to AA:BB:CC:DD:EE:FF"))
+            .level(Level::Info)
+            .target("airpods_tui::bluetooth::aacp")
+            .build();
+        let line: serde_json::Value =
+            serde_json::from_str(&json_line("2026-01-01T00:00:00Z", &record)).unwrap();
+        assert_eq!(line["timestamp"], "2026-01-01T00:00:00Z");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["module"], "airpods_tui::bluetooth::aacp");
+        assert_eq!(line["mac"], "AA:BB:CC:DD:EE:FF");
+        assert_eq!(line["message"], "AACP connected to AA:BB:CC:DD:EE:FF");
+
+        let record = Record::builder().args(format_args!("idle")).build();
+        let line: serde_json::Value = serde_json::from_str(&json_line("t", &record)).unwrap();
+        assert!(line["mac"].is_null());
+    }
+
+    #[test]
+    fn rotates_and_keeps_n_files() {
+        let dir = std::env::temp_dir().join(format!("airpods-tui-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("test.log");
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["aaaaaaaa\n", "bbbbbbbb\n", "cccccccc\n", "dddddddd\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        let read = |p: PathBuf| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(path.clone()), "dddddddd\n");
+        assert_eq!(read(file.rotated(1)), "cccccccc\n");
+        assert_eq!(read(file.rotated(2)), "bbbbbbbb\n");
+        assert!(!file.rotated(3).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
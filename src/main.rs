@@ -1,17 +1,32 @@
+mod anc;
 mod bluetooth;
 mod config;
+mod dbus;
 mod devices;
+mod error;
 mod handoff;
+mod history;
+mod hooks;
 mod ipc;
+mod listen;
+mod logging;
 mod media_controller;
+mod registry;
+mod report;
+mod session_lock;
+mod status;
+mod swaybar;
+mod systemd;
+mod tray;
 mod tui;
 mod utils;
+mod volume;
+mod waybar;
 
 use crate::bluetooth::discovery::find_connected_airpods;
 use crate::bluetooth::managers::DeviceManagers;
 use crate::devices::enums::DeviceData;
 use crate::tui::app::{App, AppEvent};
-use crate::utils::get_devices_path;
 use bluer::Address;
 use clap::Parser;
 use crossterm::{
@@ -24,7 +39,7 @@ use futures::StreamExt;
 use log::{debug, info};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::sync::Arc;
 use std::time::Duration;
@@ -38,6 +53,24 @@ use crate::bluetooth::AIRPODS_AACP_UUID;
 struct Args {
     #[arg(long, short = 'd', help = "Enable debug logging")]
     debug: bool,
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Log level: error, warn, info, debug or trace"
+    )]
+    log_level: Option<String>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "",
+        help = "Log to a rotating file (default $XDG_STATE_HOME/airpods-tui/airpods-tui.log)"
+    )]
+    log_file: Option<std::path::PathBuf>,
+    #[arg(long, help = "Log to the systemd journal with structured fields")]
+    journald: bool,
+    #[arg(long, value_enum, value_name = "FORMAT", help = "Log line format")]
+    log_format: Option<config::LogFormat>,
     #[arg(long, short = 'v', help = "Show version and exit")]
     version: bool,
     #[arg(long, help = "Print JSON status for waybar and exit")]
@@ -52,17 +85,189 @@ struct Args {
         help = "Run as headless daemon (no TUI, just maintain connections)"
     )]
     daemon: bool,
+    #[arg(long, help = "Print i3bar/swaybar protocol status (with click events)")]
+    swaybar: bool,
+    #[arg(long, help = "Show a system tray icon (StatusNotifierItem)")]
+    tray: bool,
+    #[arg(
+        long,
+        value_name = "HOST:PORT",
+        help = "Attach to a remote daemon over TCP (token from AIRPODS_TUI_TOKEN or ipc_tcp_token; TLS when ipc_tcp_tls_cert is set)"
+    )]
+    remote: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        help = "Send a waybar click/scroll action to the daemon and exit"
+    )]
+    click: Option<waybar::ClickAction>,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-/// Read the BlueZ Modalias property for a device and return its Apple product ID (0 if unknown).
-async fn read_product_id(addr_str: &str) -> u16 {
-    use crate::devices::apple_models::{APPLE_VENDOR_ID, parse_modalias};
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Print device status for scripts and widgets (eww, genmon, tmux)
+    Status {
+        #[arg(long, help = "Print a new line on every change")]
+        follow: bool,
+        #[arg(long, value_enum, default_value = "json")]
+        format: status::StatusFormat,
+        #[arg(
+            long,
+            value_name = "FMT",
+            help = "Render a template instead, e.g. '{left}% {right}%'"
+        )]
+        template: Option<String>,
+    },
+    /// Print battery levels, e.g. `battery --format '{left} {right} {case}'`
+    Battery {
+        #[arg(
+            long,
+            value_name = "FMT",
+            default_value = "{left} {right} {case}",
+            help = "Template; placeholders as for `status --template`"
+        )]
+        format: String,
+    },
+    /// Stream every event as one JSON line, for scripts that react to them
+    Listen,
+    /// Print every Apple BLE advert with timestamps, for protocol reports
+    LeDump {
+        #[arg(
+            long,
+            help = "Also decrypt adverts from devices with LE keys in devices.json"
+        )]
+        decrypt: bool,
+    },
+    /// Read the daemon's device history (needs `history = true`)
+    History {
+        #[command(subcommand)]
+        command: history::HistoryCommand,
+    },
+    /// Dump everything known about each device, for bug reports
+    Report {
+        #[arg(long, conflicts_with = "markdown", help = "Print JSON")]
+        json: bool,
+        #[arg(long, help = "Print Markdown (the default)")]
+        markdown: bool,
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            help = "Write to FILE instead of stdout"
+        )]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Control noise cancellation, e.g. `anc set transparency` for hotkeys
+    Anc {
+        #[command(subcommand)]
+        command: anc::AncCommand,
+    },
+    /// Change the AirPods volume: `volume +5`, `volume -5`, `volume set 40`
+    Volume(volume::VolumeArgs),
+    /// Validate the config file, or write a starter one
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommand,
+    },
+    /// List, add or remove entries in devices.json
+    Devices {
+        #[command(subcommand)]
+        command: registry::DevicesCommand,
+    },
+    /// Privileged half of `devices import-irk`, run through pkexec
+    #[command(hide = true)]
+    ReadIrk { mac: bluer::Address },
+    /// Pair, trust and connect AirPods, then save their LE keys to devices.json
+    Pair {
+        #[arg(
+            value_name = "MAC",
+            help = "Bluetooth address, e.g. AA:BB:CC:DD:EE:FF (default: first AirPods in pairing mode)"
+        )]
+        address: Option<Address>,
+    },
+    /// Connect a known device through BlueZ
+    Connect {
+        #[arg(
+            value_name = "MAC|NAME",
+            help = "Bluetooth address or name from devices.json"
+        )]
+        device: String,
+    },
+    /// Disconnect a device through BlueZ
+    Disconnect {
+        #[arg(
+            value_name = "MAC|NAME",
+            help = "Bluetooth address or name from devices.json"
+        )]
+        device: String,
+    },
+}
+
+/// Attach to the local daemon's socket, or to `remote` over TCP.
+async fn connect_daemon(
+    remote: Option<&str>,
+    config: &config::Config,
+) -> io::Result<(
+    tokio::sync::mpsc::UnboundedSender<(String, crate::tui::app::DeviceCommand)>,
+    tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+)> {
+    match remote {
+        Some(addr) => {
+            ipc::ipc_connect_tcp(addr, &ipc_token(config), remote_tls(config)?.as_ref()).await
+        }
+        None => ipc::ipc_connect().await,
+    }
+}
+
+/// One request to the local daemon, or to `remote` over TCP.
+async fn daemon_request(
+    remote: Option<&str>,
+    config: &config::Config,
+    request: ipc::Request,
+) -> io::Result<ipc::Response> {
+    match remote {
+        Some(addr) => {
+            let tls = remote_tls(config)?;
+            ipc::ipc_request_tcp(addr, &ipc_token(config), tls.as_ref(), request).await
+        }
+        None => ipc::ipc_request(request).await,
+    }
+}
+
+/// TLS for `--remote` when the config pins the daemon's certificate.
+fn remote_tls(config: &config::Config) -> io::Result<Option<tokio_rustls::TlsConnector>> {
+    config
+        .ipc_tcp_tls_cert
+        .as_deref()
+        .map(ipc::tls_connector)
+        .transpose()
+}
+
+fn ipc_token(config: &config::Config) -> String {
+    std::env::var("AIRPODS_TUI_TOKEN")
+        .ok()
+        .or_else(|| config.ipc_tcp_token.clone())
+        .unwrap_or_default()
+}
+
+/// Read the BlueZ Modalias property of the device at `device_path` and return
+/// its Apple product ID (0 if unknown).
+async fn read_product_id(device_path: &str) -> u16 {
     let Ok(conn) = zbus::Connection::system().await else {
         return 0;
     };
-    let path = format!("/org/bluez/hci0/dev_{}", addr_str.replace(':', "_"));
-    zbus_get_property::<String>(&conn, &path, "org.bluez.Device1", "Modalias")
-        .await
+    apple_product_id(
+        zbus_get_property::<String>(&conn, device_path, "org.bluez.Device1", "Modalias").await,
+    )
+}
+
+/// Apple product ID from a Device1 Modalias, 0 for other vendors.
+fn apple_product_id(modalias: Option<String>) -> u16 {
+    use crate::devices::apple_models::{APPLE_VENDOR_ID, parse_modalias};
+    modalias
         .and_then(|m| parse_modalias(&m))
         .filter(|(v, _)| *v == APPLE_VENDOR_ID)
         .map(|(_, p)| p)
@@ -149,31 +354,183 @@ fn main() -> io::Result<()> {
         return Ok(());
     }
 
-    let log_level = if args.debug { "debug" } else { "warn" };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
-        .target(env_logger::Target::Stderr)
-        .init();
+    // Runs as root under pkexec: read the key and nothing else
+    if let Some(Command::ReadIrk { mac }) = args.command {
+        return registry::read_irk(mac);
+    }
+
+    let (config, load_status) = config::Config::read();
+    let log_level = if args.debug {
+        "debug"
+    } else {
+        args.log_level
+            .as_deref()
+            .or(config.log.level.as_deref())
+            .unwrap_or("warn")
+    };
+    let log_destination = match &args.log_file {
+        Some(path) if !path.as_os_str().is_empty() => logging::Destination::File(path.clone()),
+        Some(_) => logging::Destination::File(logging::default_log_path()),
+        None if args.journald || config.log.journald => logging::Destination::Journal,
+        None if config.log.file => logging::Destination::File(
+            config
+                .log
+                .path
+                .clone()
+                .unwrap_or_else(logging::default_log_path),
+        ),
+        None => logging::Destination::Stderr,
+    };
+    let log_format = args.log_format.unwrap_or(config.log.format);
+    logging::init(log_level, log_destination, log_format, &config.log);
+    load_status.log();
+    media_controller::set_audio_backend(config.audio_backend);
 
     check_bluetooth_config();
 
-    let config = config::Config::load();
+    // One multi-threaded runtime for every mode: IPC, in-process Bluetooth
+    // and the UI are all tasks on it.
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(run(args, config))
+}
 
+async fn run(args: Args, config: config::Config) -> io::Result<()> {
     if args.waybar || args.waybar_watch {
-        return run_waybar_mode(args.waybar_watch);
+        return run_waybar_mode(args.waybar_watch, args.remote.as_deref()).await;
     }
 
-    let (app_tx, app_rx) = unbounded_channel::<AppEvent>();
-    let (cmd_tx, cmd_rx) = unbounded_channel::<(String, crate::tui::app::DeviceCommand)>();
+    if let Some(Command::Status {
+        follow,
+        format,
+        template,
+    }) = args.command
+    {
+        // A one-shot answer straight from the daemon's state, no waiting
+        if !follow
+            && let Ok(ipc::Response::State(events)) =
+                daemon_request(args.remote.as_deref(), &config, ipc::Request::GetState).await
+        {
+            status::print_state(events, format, template.as_deref());
+            return Ok(());
+        }
+        let (app_rx, cmd_tx) = attach_or_start(&config, args.remote.as_deref(), "Status").await?;
+        return status::run(app_rx, cmd_tx, follow, format, template).await;
+    }
 
-    let device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>> =
-        Arc::new(RwLock::new(HashMap::new()));
-    let dm_clone = device_managers.clone();
-    let app_tx_bt = app_tx.clone();
-    let bt_config = config.clone();
+    if let Some(Command::Listen) = args.command {
+        let (app_rx, cmd_tx) = attach_or_start(&config, args.remote.as_deref(), "Listen").await?;
+        return listen::run(app_rx, cmd_tx).await;
+    }
+
+    if let Some(Command::Report { json, output, .. }) = args.command {
+        let format = if json {
+            report::ReportFormat::Json
+        } else {
+            report::ReportFormat::Markdown
+        };
+        let (app_rx, cmd_tx) = attach_or_start(&config, args.remote.as_deref(), "Report").await?;
+        return report::run(app_rx, cmd_tx, format, output).await;
+    }
+
+    if let Some(Command::Battery { format }) = args.command {
+        // A daemon has live levels; otherwise whatever instance is running
+        // (or ran last) left them in the battery env file.
+        return match daemon_request(args.remote.as_deref(), &config, ipc::Request::GetState).await {
+            Ok(ipc::Response::State(events)) => {
+                status::print_state(events, status::StatusFormat::Json, Some(&format));
+                Ok(())
+            }
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected reply to get_state",
+            )),
+            Err(e) if args.remote.is_some() => Err(e),
+            Err(_) => {
+                let levels = utils::read_battery_env().map_err(|e| {
+                    io::Error::new(
+                        e.kind(),
+                        format!("no daemon running and no battery file: {}", e),
+                    )
+                })?;
+                println!("{}", status::render_battery_env(&format, &levels));
+                Ok(())
+            }
+        };
+    }
+
+    if let Some(Command::Anc { command }) = args.command {
+        let (app_rx, cmd_tx) = attach_or_start(&config, args.remote.as_deref(), "Anc").await?;
+        return anc::run(app_rx, cmd_tx, command).await;
+    }
+
+    if let Some(Command::Volume(volume_args)) = args.command {
+        return volume::run(volume_args, &config).await;
+    }
+
+    if let Some(Command::Config { command }) = args.command {
+        return config::run(command);
+    }
+
+    if let Some(Command::Devices { command }) = args.command {
+        return registry::run(command).await;
+    }
+
+    if let Some(Command::LeDump { decrypt }) = args.command {
+        let session = bluer::Session::new().await.map_err(io::Error::other)?;
+        let adapter = bluetooth::open_adapter(&session, config.adapter.as_deref())
+            .await
+            .map_err(io::Error::other)?;
+        return bluetooth::proximity::dump(adapter, decrypt)
+            .await
+            .map_err(io::Error::other);
+    }
+
+    if let Some(Command::History { command }) = args.command {
+        return history::run(command);
+    }
+
+    if let Some(Command::Pair { address }) = args.command {
+        // A running daemon opens its own AACP session and stores the keys
+        let instance_running = ipc::ipc_connect().await.is_ok();
+        return bluetooth::pairing::run(config.adapter.as_deref(), address, instance_running).await;
+    }
+
+    if let Some(Command::Connect { device }) = &args.command {
+        return bluetooth::link::connect(config.adapter.as_deref(), device).await;
+    }
+
+    if let Some(Command::Disconnect { device }) = &args.command {
+        return bluetooth::link::disconnect(config.adapter.as_deref(), device).await;
+    }
+
+    if let Some(action) = args.click {
+        return run_click_mode(action, config, args.remote.as_deref()).await;
+    }
+
+    if args.swaybar {
+        return run_swaybar_mode(config, args.remote.as_deref()).await;
+    }
+
+    if args.tray {
+        return run_tray_mode(config, args.remote.as_deref()).await;
+    }
 
     if args.daemon {
-        let rt = tokio::runtime::Runtime::new()?;
-        let exit_code = rt.block_on(async move {
+        let Some(_instance_lock) = ipc::InstanceLock::acquire()? else {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "another airpods-tui instance already talks to the AirPods; quit it first",
+            ));
+        };
+        let (app_tx, app_rx) = unbounded_channel::<AppEvent>();
+        let (cmd_tx, cmd_rx) = unbounded_channel::<(String, crate::tui::app::DeviceCommand)>();
+        let device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+        let dm_clone = device_managers.clone();
+        let app_tx_bt = app_tx.clone();
+        let bt_config = config.clone();
+
+        let exit_code = async move {
             let snapshot: ipc::StateSnapshot = Arc::new(RwLock::new(Vec::new()));
             let ipc_server = Arc::new(ipc::IpcServer::new(snapshot.clone(), cmd_tx));
 
@@ -181,15 +538,75 @@ fn main() -> io::Result<()> {
             let ipc_server_clone = ipc_server.clone();
             let snapshot_clone = snapshot.clone();
             let alert_cmd = config.battery_alert_command.clone();
+            let dbus_signals_enabled = config.dbus_signals;
+            let battery_provider_enabled = config.battery_provider;
+            let adapter_config = config.adapter.clone();
+            let mut hooks = hooks::HookRunner::new(config.hooks.clone());
+            let mut history = if config.history {
+                let path = history::history_path();
+                match history::HistoryStore::open(&path) {
+                    Ok(store) => Some(history::HistoryRecorder::new(store)),
+                    Err(e) => {
+                        log::warn!("History disabled, can't open {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
             let mut app_rx = app_rx;
+            let heartbeat = systemd::Heartbeat::default();
+            let event_heartbeat = heartbeat.clone();
             tokio::spawn(async move {
                 let mut battery_alerted: HashMap<String, u8> = HashMap::new();
+                let mut dbus_signals = None;
+                if dbus_signals_enabled {
+                    match dbus::connect().await {
+                        Ok(conn) => match dbus::signals::StateSignalEmitter::new(&conn).await {
+                            Ok(emitter) => dbus_signals = Some(emitter),
+                            Err(e) => log::warn!("Failed to register D-Bus signals: {}", e),
+                        },
+                        Err(e) => log::warn!("Failed to claim session bus name: {}", e),
+                    }
+                }
+                let mut battery_provider = None;
+                if battery_provider_enabled {
+                    let adapter_name = match bluer::Session::new().await {
+                        Ok(session) => bluetooth::open_adapter(&session, adapter_config.as_deref())
+                            .await
+                            .map(|a| a.name().to_string()),
+                        Err(e) => Err(e),
+                    };
+                    match adapter_name {
+                        Ok(name) => {
+                            match bluetooth::battery_provider::BatteryProvider::register(&name)
+                                .await
+                            {
+                                Ok(provider) => battery_provider = Some(provider),
+                                // Older BlueZ only exposes the manager with --experimental
+                                Err(e) => info!("BlueZ battery provider unavailable: {}", e),
+                            }
+                        }
+                        Err(e) => log::warn!("No adapter for battery provider: {}", e),
+                    }
+                }
                 while let Some(event) = app_rx.recv().await {
+                    event_heartbeat.busy();
                     {
                         let mut snap = snapshot_clone.write().await;
                         ipc::update_snapshot(&mut snap, &event);
                     }
                     ipc_server_clone.broadcast(&event);
+                    hooks.handle(&event);
+                    if let Some(history) = history.as_mut() {
+                        history.handle(&event);
+                    }
+                    if let Some(emitter) = dbus_signals.as_mut() {
+                        emitter.handle(&event).await;
+                    }
+                    if let Some(provider) = battery_provider.as_mut() {
+                        provider.handle(&event).await;
+                    }
 
                     if let AppEvent::AACPEvent(ref mac, ref aacp_event) = event
                         && let crate::bluetooth::aacp::AACPEvent::BatteryInfo(ref infos) =
@@ -234,6 +651,7 @@ fn main() -> io::Result<()> {
                                     battery_alerted.insert(key, threshold);
                                     let msg = format!("{:?} battery: {}%", b.component, b.level);
                                     config::run_template_cmd(&alert_cmd, &msg);
+                                    hooks.low_battery(mac, b.component, b.level);
                                 } else if threshold == 0 && prev < 100 {
                                     battery_alerted.insert(key, 100);
                                 }
@@ -246,23 +664,89 @@ fn main() -> io::Result<()> {
                             bat_headphone,
                         );
                     }
+                    event_heartbeat.idle();
                 }
             });
 
+            // systemd (Type=notify): ready once Bluetooth is up, then
+            // watchdog pings while the event loop and AACP link checks keep
+            // moving
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            tokio::spawn(async move {
+                if ready_rx.await.is_ok() {
+                    systemd::notify("READY=1");
+                }
+            });
+            if let Some(interval) = systemd::watchdog_interval() {
+                let heartbeats = vec![
+                    ("Event loop", heartbeat),
+                    ("AACP link check", systemd::AACP_HEARTBEAT.clone()),
+                ];
+                tokio::spawn(systemd::watchdog(interval, heartbeats));
+            }
+
+            // Task: remote IPC over TCP (opt-in, token required)
+            let tcp_handle = match (config.ipc_tcp_listen.clone(), config.ipc_tcp_token.clone()) {
+                (Some(addr), Some(token)) if !token.is_empty() => {
+                    let server = ipc_server.clone();
+                    let tls = match (&config.ipc_tcp_tls_cert, &config.ipc_tcp_tls_key) {
+                        (Some(cert), Some(key)) => ipc::tls_acceptor(cert, key).map(Some),
+                        (None, None) => Ok(None),
+                        _ => Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "set both ipc_tcp_tls_cert and ipc_tcp_tls_key",
+                        )),
+                    };
+                    Some(tokio::spawn(async move {
+                        let result = match tls {
+                            Ok(tls) => server.run_tcp(&addr, token, tls).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            log::error!("Remote IPC server error on {}: {}", addr, e);
+                        }
+                    }))
+                }
+                (Some(addr), _) => {
+                    log::warn!(
+                        "ipc_tcp_listen = {} ignored: set ipc_tcp_token to enable remote IPC",
+                        addr
+                    );
+                    None
+                }
+                _ => None,
+            };
+
             // Task: IPC server
+            let access = match ipc::SocketAccess::from_config(&config) {
+                Ok(access) => access,
+                Err(e) => {
+                    log::error!("IPC socket access: {}", e);
+                    return 1;
+                }
+            };
             let ipc_handle = tokio::spawn(async move {
-                if let Err(e) = ipc_server.run().await {
+                if let Err(e) = ipc_server.run(access).await {
                     log::error!("IPC server error: {}", e);
                 }
             });
 
             // Run bluetooth_main with graceful shutdown on SIGTERM/SIGINT
-            let mut sigterm =
-                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-                    .expect("failed to register SIGTERM handler");
+            let sigterm = async {
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                    Ok(mut sigterm) => {
+                        sigterm.recv().await;
+                    }
+                    Err(e) => {
+                        // Still stoppable with SIGINT
+                        log::warn!("Can't watch SIGTERM: {}", e);
+                        std::future::pending::<()>().await;
+                    }
+                }
+            };
 
             let exit_code: i32 = tokio::select! {
-                result = bluetooth_main(app_tx_bt, dm_clone, cmd_rx, bt_config) => {
+                result = bluetooth_main(app_tx_bt, dm_clone, cmd_rx, bt_config, Some(ready_tx)) => {
                     match result {
                         Ok(()) => 0,
                         Err(e) => {
@@ -275,51 +759,37 @@ fn main() -> io::Result<()> {
                     log::info!("Received SIGINT, shutting down...");
                     0
                 }
-                _ = sigterm.recv() => {
+                () = sigterm => {
                     log::info!("Received SIGTERM, shutting down...");
                     0
                 }
             };
 
+            // Leave things as we found them for the next start and for
+            // other hosts: session handed back, audio restored, last state
+            // on disk
+            release_devices(&device_managers).await;
+            let events = snapshot.read().await.clone();
+            if let Err(e) = ipc::save_snapshot(&ipc::saved_snapshot_path(), &events) {
+                log::warn!("Failed to save the state snapshot: {}", e);
+            }
+
             ipc_handle.abort();
+            if let Some(handle) = tcp_handle {
+                handle.abort();
+            }
             let _ = ipc::socket_path().and_then(std::fs::remove_file);
             log::info!("Daemon shutdown complete");
             exit_code
-        });
+        }
+        .await;
         if exit_code != 0 {
             std::process::exit(exit_code);
         }
         return Ok(());
     }
 
-    // Try connecting to a running daemon via IPC first.
-    // The runtime must stay alive so the IPC reader/writer tasks keep running.
-    let ipc_rt = tokio::runtime::Runtime::new()?;
-    let ipc_result = ipc_rt.block_on(ipc::ipc_connect());
-
-    let (_ipc_rt_guard, app_rx, cmd_tx) = if let Ok((ipc_cmd_tx, ipc_event_rx)) = ipc_result {
-        info!("Connected to daemon via IPC");
-        drop(app_tx_bt);
-        drop(dm_clone);
-        drop(bt_config);
-        drop(app_rx);
-        drop(cmd_rx);
-        drop(cmd_tx);
-        // Keep ipc_rt alive - its spawned tasks handle the socket I/O
-        (Some(ipc_rt), ipc_event_rx, ipc_cmd_tx)
-    } else {
-        drop(ipc_rt);
-        info!("No daemon running, starting in-process Bluetooth");
-        std::thread::spawn(move || {
-            let Ok(rt) = tokio::runtime::Runtime::new() else {
-                log::error!("Failed to create Tokio runtime for Bluetooth");
-                return;
-            };
-            rt.block_on(bluetooth_main(app_tx_bt, dm_clone, cmd_rx, bt_config))
-                .unwrap_or_else(|e| log::error!("Bluetooth error: {}", e));
-        });
-        (None, app_rx, cmd_tx)
-    };
+    let (app_rx, cmd_tx) = attach_or_start(&config, args.remote.as_deref(), "TUI").await?;
 
     // Set up terminal
     enable_raw_mode()?;
@@ -329,22 +799,10 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new(app_rx, cmd_tx);
+    app.keys = tui::events::KeyBindings::from_config(&config.keys);
+    app.palette = config::Palette::from_config(&config.colors);
 
-    // Main TUI loop
-    loop {
-        app.process_events();
-
-        terminal.draw(|f| tui::ui::draw(f, &app))?;
-
-        if event::poll(Duration::from_millis(50))? {
-            let ev = event::read()?;
-            tui::events::handle_event(&mut app, ev);
-        }
-
-        if app.should_quit {
-            break;
-        }
-    }
+    let result = run_tui(&mut terminal, &mut app).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -355,120 +813,125 @@ fn main() -> io::Result<()> {
     )?;
     terminal.show_cursor()?;
 
-    Ok(())
+    result
 }
 
-fn run_waybar_mode(watch: bool) -> io::Result<()> {
-    use crate::tui::app::DeviceState;
+/// Next thing the TUI loop has to react to.
+enum TuiWake {
+    App(Option<AppEvent>),
+    Input(Option<io::Result<event::Event>>),
+    Tick,
+}
 
-    let config = config::Config::load();
+/// Redraws after every AppEvent burst or input, plus once per TICK so ages
+/// ("updated 2m ago") and timed messages move; otherwise it sleeps.
+async fn run_tui(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> io::Result<()> {
+    const TICK: Duration = Duration::from_secs(1);
+    let mut input = event::EventStream::new();
+    let mut tick = tokio::time::interval(TICK);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // False once the backend hung up; the UI stays up with what it has
+    let mut backend_open = true;
+    // Draw only after input, an AppEvent, or a tick that moved something
+    // time-driven (ages, the error message, track position).
+    let mut dirty = true;
 
-    // Try IPC first (like the TUI does) to avoid conflicting L2CAP connections
-    let ipc_rt = tokio::runtime::Runtime::new()?;
-    let ipc_result = ipc_rt.block_on(ipc::ipc_connect());
+    loop {
+        // Events that queued up while drawing or handling input
+        dirty |= app.process_events();
+        if std::mem::take(&mut dirty) {
+            terminal.draw(|f| tui::ui::draw(f, app))?;
+        }
 
-    let (_ipc_rt_guard, app_rx, cmd_tx) = if let Ok((ipc_cmd_tx, ipc_event_rx)) = ipc_result {
-        info!("Waybar: connected to daemon via IPC");
-        (Some(ipc_rt), ipc_event_rx, ipc_cmd_tx)
-    } else {
-        drop(ipc_rt);
-        info!("Waybar: no daemon, starting in-process Bluetooth");
+        let wake = tokio::select! {
+            event = app.rx.recv(), if backend_open => TuiWake::App(event),
+            event = input.next() => TuiWake::Input(event),
+            _ = tick.tick() => TuiWake::Tick,
+        };
+        match wake {
+            TuiWake::App(Some(event)) => {
+                app.handle_event(event);
+                dirty = true;
+            }
+            TuiWake::App(None) => backend_open = false,
+            TuiWake::Input(Some(event)) => {
+                tui::events::handle_event(app, event?);
+                dirty = true;
+            }
+            TuiWake::Input(None) => break,
+            TuiWake::Tick => dirty = app.tick(),
+        }
 
-        let (app_tx, app_rx) = unbounded_channel::<AppEvent>();
-        let (cmd_tx, cmd_rx) = unbounded_channel::<(String, crate::tui::app::DeviceCommand)>();
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(())
+}
 
-        let device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>> =
-            Arc::new(RwLock::new(HashMap::new()));
-        let dm_clone = device_managers.clone();
-        let app_tx_bt = app_tx.clone();
+async fn run_waybar_mode(watch: bool, remote: Option<&str>) -> io::Result<()> {
+    use crate::tui::app::DeviceState;
 
-        std::thread::spawn(move || {
-            let Ok(rt) = tokio::runtime::Runtime::new() else {
-                log::error!("Failed to create Tokio runtime for waybar Bluetooth");
-                return;
-            };
-            rt.block_on(bluetooth_main(app_tx_bt, dm_clone, cmd_rx, config))
-                .unwrap_or_else(|e| log::error!("Bluetooth error: {}", e));
-        });
+    let config = config::Config::load();
+    let waybar_config = config.waybar.clone();
 
-        (None, app_rx, cmd_tx)
-    };
+    let (app_rx, cmd_tx, mut via_daemon) = attach(&config, remote, "Waybar").await?;
 
-    fn render_waybar_json(app: &App) -> String {
-        match app.selected_device() {
-            Some(DeviceState::AirPods(s)) => {
-                let model_name = s.model.as_deref().unwrap_or(&s.name);
-                let min_bat = [s.battery_left, s.battery_right, s.battery_headphone]
-                    .iter()
-                    .filter_map(|b| b.as_ref().map(|(l, _)| *l))
-                    .min();
-                let percentage = min_bat.unwrap_or(0);
-                let mut tooltip_parts = vec![model_name.to_string()];
-                if let Some((l, _)) = s.battery_left {
-                    tooltip_parts.push(format!("L: {}%", l));
-                }
-                if let Some((r, _)) = s.battery_right {
-                    tooltip_parts.push(format!("R: {}%", r));
-                }
-                if let Some((c, _)) = s.battery_case {
-                    tooltip_parts.push(format!("C: {}%", c));
-                }
-                if let Some((h, _)) = s.battery_headphone {
-                    tooltip_parts.push(format!("{}%", h));
-                }
-                serde_json::json!({
-                    "text": format!("{}%", percentage),
-                    "tooltip": tooltip_parts.join("\n"),
-                    "class": "connected",
-                    "percentage": percentage,
-                })
-                .to_string()
-            }
-            _ => serde_json::json!({
-                "text": "",
-                "tooltip": "No AirPods",
-                "class": "disconnected",
-                "percentage": 0,
-            })
-            .to_string(),
-        }
-    }
+    let render_waybar_json = |app: &App| {
+        let state = match app.selected_device() {
+            Some(DeviceState::AirPods(s)) => Some(&**s),
+            _ => None,
+        };
+        waybar::render(state, &waybar_config)
+    };
 
     let mut app = App::new(app_rx, cmd_tx);
-    let deadline = if watch {
-        None
-    } else {
-        Some(std::time::Instant::now() + Duration::from_secs(5))
-    };
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
     let mut last_json = String::new();
 
     loop {
-        // Block until an event arrives or timeout expires (avoids busy-wait polling)
-        let remaining = match deadline {
-            Some(d) => {
-                let now = std::time::Instant::now();
-                if now >= d {
-                    break;
-                }
-                d - now
+        let event = if watch {
+            app.rx.recv().await
+        } else {
+            match tokio::time::timeout_at(deadline, app.rx.recv()).await {
+                Ok(event) => event,
+                Err(_) => break,
             }
-            None => Duration::from_secs(60),
         };
-        // tokio's unbounded receiver has no blocking recv-with-timeout usable
-        // from sync code, so poll try_recv with short sleeps
-        match app.rx.try_recv() {
-            Ok(event) => {
+        match event {
+            Some(event) => {
                 // Process this event plus any others that have queued up
                 app.handle_event(event);
-                while let Ok(event) = app.rx.try_recv() {
-                    app.handle_event(event);
-                }
+                app.process_events();
             }
-            Err(tokio::sync::mpsc::error::TryRecvError::Empty) => {
-                // No event available - sleep for a reasonable interval
-                std::thread::sleep(remaining.min(Duration::from_secs(1)));
+            None => {
+                // The daemon went away (restart, upgrade). Keep the module
+                // alive and re-attach rather than racing it with a second
+                // Bluetooth stack.
+                if !(watch && via_daemon) {
+                    break;
+                }
+                info!("Waybar: daemon connection closed, waiting to re-attach");
+                let json = waybar::render(None, &waybar_config);
+                if json != last_json {
+                    println!("{}", json);
+                    last_json = json;
+                }
+                // Re-attach to a restarted daemon, or take over Bluetooth
+                // if the instance that had it (e.g. a TUI) has quit
+                let (app_rx, cmd_tx, via) = loop {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    if let Ok(conn) = attach(&config, remote, "Waybar").await {
+                        break conn;
+                    }
+                };
+                via_daemon = via;
+                app = App::new(app_rx, cmd_tx);
+                continue;
             }
-            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => break,
         }
 
         if watch {
@@ -492,9 +955,204 @@ fn run_waybar_mode(watch: bool) -> io::Result<()> {
     Ok(())
 }
 
+async fn run_click_mode(
+    action: waybar::ClickAction,
+    config: config::Config,
+    remote: Option<&str>,
+) -> io::Result<()> {
+    use crate::tui::app::DeviceState;
+
+    // Only through the daemon: a one-shot L2CAP session would take
+    // seconds and fight the daemon's own connection.
+    let (cmd_tx, app_rx) = connect_daemon(remote, &config).await?;
+    let mut app = App::new(app_rx, cmd_tx);
+
+    // The snapshot replay arrives as one burst right after connect
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    loop {
+        let wait = if app.selected_device().is_some() {
+            Duration::from_millis(150)
+        } else {
+            deadline.saturating_duration_since(tokio::time::Instant::now())
+        };
+        match tokio::time::timeout(wait, app.rx.recv()).await {
+            Ok(Some(event)) => app.handle_event(event),
+            _ => break,
+        }
+    }
+
+    let Some(mac) = app.selected_mac().cloned() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no AirPods connected to the daemon",
+        ));
+    };
+    let command = match app.selected_device() {
+        Some(DeviceState::AirPods(s)) => waybar::click_command(action, s),
+        Some(DeviceState::Battery(_)) | None => None,
+    };
+    let Some(command) = command else {
+        info!("{:?} does not apply to {}", action, mac);
+        return Ok(());
+    };
+    if let Some(tx) = app.command_tx.take() {
+        let _ = tx.send((mac, command));
+    }
+    // Dropping the sender half-closes the socket; the daemon hangs up
+    // after handling the command, which closes rx.
+    let _ = tokio::time::timeout(Duration::from_secs(2), async {
+        while app.rx.recv().await.is_some() {}
+    })
+    .await;
+    Ok(())
+}
+
+async fn run_tray_mode(config: config::Config, remote: Option<&str>) -> io::Result<()> {
+    let (app_rx, cmd_tx) = attach_or_start(&config, remote, "Tray").await?;
+    tray::run(app_rx, cmd_tx, config)
+        .await
+        .map_err(|e| io::Error::other(format!("tray: {}", e)))
+}
+
+async fn run_swaybar_mode(config: config::Config, remote: Option<&str>) -> io::Result<()> {
+    let (app_rx, cmd_tx) = attach_or_start(&config, remote, "Swaybar").await?;
+    swaybar::run(app_rx, cmd_tx, config).await
+}
+
+/// How long to wait for a starting instance (lock held) to open its socket.
+const INSTANCE_SOCKET_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Share the daemon's connection when one is running; otherwise run
+/// Bluetooth in-process. A failed `--remote` never falls back.
+async fn attach_or_start(
+    config: &config::Config,
+    remote: Option<&str>,
+    label: &str,
+) -> io::Result<(
+    tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+    tokio::sync::mpsc::UnboundedSender<(String, crate::tui::app::DeviceCommand)>,
+)> {
+    attach(config, remote, label)
+        .await
+        .map(|(app_rx, cmd_tx, _)| (app_rx, cmd_tx))
+}
+
+/// `attach_or_start`, also saying whether the channels go over IPC (true)
+/// or to Bluetooth started in this process.
+async fn attach(
+    config: &config::Config,
+    remote: Option<&str>,
+    label: &str,
+) -> io::Result<(
+    tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+    tokio::sync::mpsc::UnboundedSender<(String, crate::tui::app::DeviceCommand)>,
+    bool,
+)> {
+    match connect_daemon(remote, config).await {
+        Ok((cmd_tx, app_rx)) => {
+            info!("{}: connected to daemon via IPC", label);
+            Ok((app_rx, cmd_tx, true))
+        }
+        // Never fall back to a local Bluetooth stack when a remote was asked for
+        Err(e) => match remote {
+            Some(addr) => Err(io::Error::new(e.kind(), format!("{}: {}", addr, e))),
+            None => match ipc::InstanceLock::acquire()? {
+                Some(lock) => {
+                    info!("{}: no daemon, starting in-process Bluetooth", label);
+                    let (app_rx, cmd_tx) = start_in_process(config, lock);
+                    Ok((app_rx, cmd_tx, false))
+                }
+                // Another instance owns the AirPods but its socket isn't up
+                // yet (it's starting); wait for it rather than open a
+                // second AACP connection.
+                None => {
+                    let deadline = tokio::time::Instant::now() + INSTANCE_SOCKET_WAIT;
+                    loop {
+                        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                        match connect_daemon(None, config).await {
+                            Ok((cmd_tx, app_rx)) => {
+                                info!("{}: connected to running instance via IPC", label);
+                                return Ok((app_rx, cmd_tx, true));
+                            }
+                            Err(e) if tokio::time::Instant::now() >= deadline => {
+                                return Err(io::Error::new(
+                                    e.kind(),
+                                    format!(
+                                        "another airpods-tui instance is running but not answering on its socket: {}",
+                                        e
+                                    ),
+                                ));
+                            }
+                            Err(_) => {}
+                        }
+                    }
+                }
+            },
+        },
+    }
+}
+
+/// Run the Bluetooth side as a task on the current runtime, holding `lock`
+/// for as long as it runs. It serves the IPC socket too, so instances
+/// started later attach here instead of opening their own AACP session.
+fn start_in_process(
+    config: &config::Config,
+    lock: ipc::InstanceLock,
+) -> (
+    tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+    tokio::sync::mpsc::UnboundedSender<(String, crate::tui::app::DeviceCommand)>,
+) {
+    let (app_tx, mut bt_rx) = unbounded_channel::<AppEvent>();
+    let (local_tx, app_rx) = unbounded_channel::<AppEvent>();
+    let (cmd_tx, cmd_rx) = unbounded_channel::<(String, crate::tui::app::DeviceCommand)>();
+    let device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>> =
+        Arc::new(RwLock::new(HashMap::new()));
+
+    let snapshot: ipc::StateSnapshot = Arc::new(RwLock::new(Vec::new()));
+    let ipc_server = Arc::new(ipc::IpcServer::new(snapshot.clone(), cmd_tx.clone()));
+    match ipc::SocketAccess::from_config(config) {
+        Ok(access) => {
+            let server = ipc_server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.run(access).await {
+                    log::warn!("IPC server error: {}", e);
+                }
+            });
+        }
+        Err(e) => log::warn!("IPC socket access: {}", e),
+    }
+    tokio::spawn(async move {
+        while let Some(event) = bt_rx.recv().await {
+            ipc::update_snapshot(&mut *snapshot.write().await, &event);
+            ipc_server.broadcast(&event);
+            if local_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let bt_config = config.clone();
+    tokio::spawn(async move {
+        let _lock = lock;
+        if let Err(e) = bluetooth_main(app_tx, device_managers, cmd_rx, bt_config, None).await {
+            log::error!("Bluetooth error: {}", e);
+        }
+    });
+    (app_rx, cmd_tx)
+}
+
+/// Register a match rule given as a string with the bus daemon.
+async fn add_match_rule(proxy: &zbus::fdo::DBusProxy<'_>, rule: &str) -> zbus::fdo::Result<()> {
+    proxy.add_match_rule(zbus::MatchRule::try_from(rule)?).await
+}
+
 /// Async task: monitor BlueZ MediaTransport1 volume changes via zbus,
 /// sync AirPods stem swipe to system volume using configured commands.
-async fn avrcp_volume_monitor(config: config::Config) {
+/// Also feeds transport State changes to the media controller.
+async fn avrcp_volume_monitor(
+    config: config::Config,
+    app_tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) {
     let Ok(conn) = zbus::Connection::system().await else {
         log::error!("Failed to connect to system D-Bus for AVRCP monitor");
         return;
@@ -506,10 +1164,7 @@ async fn avrcp_volume_monitor(config: config::Config) {
         debug!("Failed to create DBusProxy for AVRCP volume monitor");
         return;
     };
-    if let Err(e) = proxy
-        .add_match_rule(rule.try_into().expect("valid match rule"))
-        .await
-    {
+    if let Err(e) = add_match_rule(&proxy, rule).await {
         log::error!("Failed to add AVRCP match rule: {}", e);
         return;
     }
@@ -521,6 +1176,8 @@ async fn avrcp_volume_monitor(config: config::Config) {
     let mut pending_pct: Option<i64> = None;
     let set_cmd = config.volume_set_command.clone();
     let osd_cmd = config.volume_osd_command.clone();
+    // Last AppEvent::LastSeen time sent per device
+    let mut seen_sent: HashMap<String, u64> = HashMap::new();
 
     // Debounce: a single stem swipe floods ~15 AVRCP Volume events in quick succession
     // (one per ~9-unit step on the 0-127 scale).  Wait until the stream is quiet for
@@ -537,7 +1194,9 @@ async fn avrcp_volume_monitor(config: config::Config) {
         tokio::select! {
             // Debounce timer fired - set the absolute target volume.
             () = &mut debounce_deadline, if pending_pct.is_some() => {
-                let new_pct = pending_pct.take().unwrap();
+                let Some(new_pct) = pending_pct.take() else {
+                    continue;
+                };
                 if applied_pct >= 0 {
                     if new_pct != applied_pct {
                         // Pass a 0.0-1.0 fraction to volume_set_command (e.g. wpctl).
@@ -580,6 +1239,27 @@ async fn avrcp_volume_monitor(config: config::Config) {
                     continue;
                 }
 
+                if let Some(mac) = bluetooth::mac_from_bluez_path(path.as_str()) {
+                    let now = crate::utils::unix_now();
+                    let due = seen_sent
+                        .get(&mac)
+                        .is_none_or(|&prev| now >= prev + crate::tui::app::LAST_SEEN_STEP_SECS);
+                    if due {
+                        seen_sent.insert(mac.clone(), now);
+                        let _ = app_tx.send(AppEvent::LastSeen {
+                            mac,
+                            source: crate::tui::app::DataSource::Avrcp,
+                            at: now,
+                        });
+                    }
+                }
+
+                if let Some(state) = changed.get("State")
+                    && let Ok(state) = <&str>::try_from(state)
+                {
+                    media_controller::note_transport_state(path.as_str(), state);
+                }
+
                 if let Some(vol_val) = changed.get("Volume") {
                     let vol: Option<u64> = u16::try_from(vol_val).ok().map(|v| v as u64)
                         .or_else(|| u32::try_from(vol_val).ok().map(|v| v as u64))
@@ -598,30 +1278,77 @@ async fn avrcp_volume_monitor(config: config::Config) {
     }
 }
 
+/// Start a Battery Service monitor for the device at `path` if it is a
+/// candidate (has the service, isn't AirPods).
+fn spawn_gatt_battery(
+    session: &bluer::Session,
+    path: &str,
+    uuids: Vec<String>,
+    app_tx: &tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) {
+    let uuids: Vec<uuid::Uuid> = uuids.iter().filter_map(|u| u.parse().ok()).collect();
+    if !bluetooth::gatt_battery::is_candidate(&uuids) {
+        return;
+    }
+    let (Some(adapter), Some(addr)) = (
+        bluetooth::adapter_of_path(path),
+        bluetooth::mac_from_bluez_path(path).and_then(|m| m.parse::<Address>().ok()),
+    ) else {
+        return;
+    };
+    match session.adapter(adapter) {
+        Ok(adapter) => {
+            tokio::spawn(bluetooth::gatt_battery::monitor(
+                adapter,
+                addr,
+                app_tx.clone(),
+            ));
+        }
+        Err(e) => debug!("No adapter for Battery Service monitor: {}", e),
+    }
+}
+
+/// A Device1 property from the cache, asking BlueZ only on a miss.
+async fn device_property<T: TryFrom<zbus::zvariant::OwnedValue>>(
+    cache: &bluetooth::object_cache::ObjectCache,
+    conn: &zbus::Connection,
+    path: &str,
+    property: &str,
+) -> Option<T> {
+    match cache.get(path, "org.bluez.Device1", property) {
+        Some(value) => Some(value),
+        None => zbus_get_property(conn, path, "org.bluez.Device1", property).await,
+    }
+}
+
 /// Async task: listen for BlueZ device connection/disconnection via zbus PropertiesChanged signals.
 async fn bluez_connection_listener(
     conn: zbus::Connection,
+    session: bluer::Session,
     app_tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
     device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>>,
     devices_list: HashMap<String, DeviceData>,
     config: config::Config,
     reconnect_tx: tokio::sync::mpsc::UnboundedSender<(Address, u16)>,
 ) {
-    let rule =
-        "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged'";
+    let rules = [
+        "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged'",
+        "type='signal',sender='org.bluez',interface='org.freedesktop.DBus.ObjectManager'",
+    ];
     let Ok(proxy) = zbus::fdo::DBusProxy::new(&conn).await else {
         debug!("Failed to create DBusProxy for BlueZ connection listener");
         return;
     };
-    if let Err(e) = proxy
-        .add_match_rule(rule.try_into().expect("valid match rule"))
-        .await
-    {
-        log::error!("Failed to add BlueZ match rule: {}", e);
-        return;
+    for rule in rules {
+        if let Err(e) = add_match_rule(&proxy, rule).await {
+            log::error!("Failed to add BlueZ match rule: {}", e);
+            return;
+        }
     }
 
+    // Subscribe before seeding so no change falls between the two
     let mut stream = zbus::MessageStream::from(&conn);
+    let mut cache = bluetooth::object_cache::ObjectCache::seed(&conn).await;
 
     while let Some(msg) = stream.next().await {
         let Ok(msg) = msg else { continue };
@@ -631,11 +1358,42 @@ async fn bluez_connection_listener(
             continue;
         }
 
+        match header.member().map(|m| m.as_str()) {
+            Some("InterfacesAdded") => {
+                if let Ok((path, ifaces)) = msg.body().deserialize::<(
+                    zbus::zvariant::OwnedObjectPath,
+                    HashMap<String, HashMap<String, zbus::zvariant::OwnedValue>>,
+                )>() {
+                    cache.interfaces_added(path.as_str(), ifaces);
+                }
+                continue;
+            }
+            Some("InterfacesRemoved") => {
+                if let Ok((path, ifaces)) = msg
+                    .body()
+                    .deserialize::<(zbus::zvariant::OwnedObjectPath, Vec<String>)>()
+                {
+                    cache.interfaces_removed(path.as_str(), &ifaces);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
         let Some(path) = header.path() else { continue };
         let path_str = path.as_str().to_string();
         if !path_str.contains("/org/bluez/hci") || !path_str.contains("/dev_") {
             continue;
         }
+        let pinned =
+            bluetooth::mac_from_bluez_path(&path_str).and_then(|mac| devices_list.get(&mac));
+        if !bluetooth::serves_adapter(
+            bluetooth::adapter_of_path(&path_str),
+            pinned,
+            config.adapter.as_deref(),
+        ) {
+            continue;
+        }
 
         let Ok(body) = msg.body().deserialize::<(
             String,
@@ -645,11 +1403,23 @@ async fn bluez_connection_listener(
             continue;
         };
 
-        let (iface, changed, _) = body;
+        let (iface, changed, invalidated) = body;
+        cache.properties_changed(&path_str, &iface, &changed, &invalidated);
         if iface != "org.bluez.Device1" {
             continue;
         }
 
+        // Other earbuds: the Battery Service is known once services resolve
+        if config.gatt_battery
+            && changed
+                .get("ServicesResolved")
+                .and_then(|v| bool::try_from(v).ok())
+                == Some(true)
+        {
+            let uuids = device_property(&cache, &conn, &path_str, "UUIDs").await;
+            spawn_gatt_battery(&session, &path_str, uuids.unwrap_or_default(), &app_tx);
+        }
+
         let Some(connected_val) = changed.get("Connected") else {
             continue;
         };
@@ -657,8 +1427,7 @@ async fn bluez_connection_listener(
             continue;
         };
 
-        let Some(addr_str) =
-            zbus_get_property::<String>(&conn, &path_str, "org.bluez.Device1", "Address").await
+        let Some(addr_str) = device_property::<String>(&cache, &conn, &path_str, "Address").await
         else {
             continue;
         };
@@ -675,14 +1444,13 @@ async fn bluez_connection_listener(
         };
 
         // AirPods: check UUID
-        let uuids: Option<Vec<String>> =
-            zbus_get_property(&conn, &path_str, "org.bluez.Device1", "UUIDs").await;
+        let uuids: Option<Vec<String>> = device_property(&cache, &conn, &path_str, "UUIDs").await;
         let Some(uuids) = uuids else { continue };
         if !uuids.iter().any(|u| u.to_lowercase() == AIRPODS_AACP_UUID) {
             continue;
         }
 
-        let bt_name: String = zbus_get_property(&conn, &path_str, "org.bluez.Device1", "Name")
+        let bt_name: String = device_property(&cache, &conn, &path_str, "Name")
             .await
             .unwrap_or_else(|| "Unknown AirPods".to_string());
         let name = devices_list
@@ -690,10 +1458,13 @@ async fn bluez_connection_listener(
             .filter(|d| !d.name.is_empty())
             .map(|d| d.name.clone())
             .unwrap_or(bt_name);
-        let product_id = read_product_id(&addr_str).await;
+        let product_id =
+            apple_product_id(device_property(&cache, &conn, &path_str, "Modalias").await);
         info!(
-            "AirPods connected: {}, product_id=0x{:04x}, initializing",
-            name, product_id
+            "AirPods connected on {}: {}, product_id=0x{:04x}, initializing",
+            bluetooth::adapter_of_path(&path_str).unwrap_or("?"),
+            name,
+            product_id
         );
         spawn_airpods_init(
             addr,
@@ -763,8 +1534,8 @@ async fn try_airpods_init(
             let mut managers = ctx.device_managers.write().await;
             managers
                 .entry(addr_str.clone())
-                .and_modify(|dm| dm.set_aacp(airpods_device.aacp_manager.clone()))
-                .or_insert_with(|| DeviceManagers::with_aacp(airpods_device.aacp_manager));
+                .or_insert_with(DeviceManagers::placeholder)
+                .set_device(airpods_device);
             drop(managers);
             // Notify the TUI only once AACP is alive. The handle_aacp_event
             // path auto-creates a placeholder device entry if any AACP event
@@ -776,6 +1547,17 @@ async fn try_airpods_init(
             }) {
                 log::warn!("Failed to send DeviceConnected for {}: {}", addr_str, e);
             }
+            // Last session's levels until AACP reports fresh ones
+            if let Some(stored) = devices::store::load()
+                .remove(&addr_str)
+                .and_then(|d| d.last_battery)
+            {
+                let _ = ctx.app_tx.send(AppEvent::CachedBattery {
+                    mac: addr_str.clone(),
+                    batteries: stored.components,
+                    at: stored.at,
+                });
+            }
             InitOutcome::Ready
         }
         Err(e) => {
@@ -791,6 +1573,94 @@ async fn try_airpods_init(
     }
 }
 
+/// Per-device marker for a running reconnect task. Released on drop, so
+/// every exit path (success, give-up, panic) frees the device for the
+/// next ConnectionLost.
+struct RetrySlot {
+    set: Arc<std::sync::Mutex<HashSet<String>>>,
+    mac: String,
+}
+
+impl RetrySlot {
+    fn claim(set: &Arc<std::sync::Mutex<HashSet<String>>>, mac: &str) -> Option<Self> {
+        let mut held = set.lock().unwrap_or_else(|e| e.into_inner());
+        held.insert(mac.to_string()).then(|| Self {
+            set: set.clone(),
+            mac: mac.to_string(),
+        })
+    }
+}
+
+impl Drop for RetrySlot {
+    fn drop(&mut self) {
+        let mut held = self.set.lock().unwrap_or_else(|e| e.into_inner());
+        held.remove(&self.mac);
+    }
+}
+
+/// Retry AACP init for one device until it succeeds, another path claims
+/// it, BlueZ drops the link, or `[reconnect] max_attempts` runs out.
+async fn reconnect_device(
+    addr: Address,
+    name: String,
+    product_id: u16,
+    ctx: &AirPodsInitContext,
+    adapter: &bluer::Adapter,
+) {
+    let addr_str = addr.to_string();
+    // Drop the dead session, but never touch a healthy or still-initializing
+    // one (queued retries can be stale).
+    {
+        let mut managers = ctx.device_managers.write().await;
+        if let Some(existing) = managers.get(&addr_str) {
+            let Some(aacp) = existing.get_aacp() else {
+                return; // init in progress elsewhere
+            };
+            if aacp.state.lock().await.sender.is_some() {
+                return; // healthy session, stale retry
+            }
+            managers.remove(&addr_str);
+        }
+    }
+    let policy = ctx.config.reconnect;
+    let mut attempt: u32 = 0;
+    loop {
+        attempt += 1;
+        let delay = policy.delay(attempt);
+        info!(
+            "AACP reconnect: {} ({}) attempt {} in {:?}",
+            name, addr, attempt, delay
+        );
+        tokio::time::sleep(delay).await;
+        if ctx.device_managers.read().await.contains_key(&addr_str) {
+            return; // another path claimed the device
+        }
+        let bluez_connected = match adapter.device(addr) {
+            Ok(device) => device.is_connected().await.unwrap_or(false),
+            Err(_) => false,
+        };
+        if !bluez_connected {
+            info!(
+                "{} is gone at the BlueZ level; the connection listener resumes when it returns",
+                addr_str
+            );
+            return;
+        }
+        match try_airpods_init(addr, name.clone(), product_id, ctx).await {
+            InitOutcome::Ready | InitOutcome::AlreadyClaimed => return,
+            InitOutcome::Failed if !policy.allows(attempt) => {
+                log::warn!(
+                    "Giving up AACP reconnect for {} after {} attempts",
+                    addr_str,
+                    attempt
+                );
+                return;
+            }
+            InitOutcome::Failed => {}
+        }
+    }
+}
+
 fn spawn_airpods_init(addr: Address, name: String, product_id: u16, ctx: AirPodsInitContext) {
     tokio::spawn(async move {
         if matches!(
@@ -806,68 +1676,256 @@ fn spawn_airpods_init(addr: Address, name: String, product_id: u16, ctx: AirPods
     });
 }
 
+/// Carry out one command from a client. The dispatcher reports failures
+/// back to clients as `AppEvent::CommandFailed`.
+async fn run_device_command(
+    mac: &str,
+    cmd: tui::app::DeviceCommand,
+    aacp: &bluetooth::aacp::AACPManager,
+    adapter: &bluer::Adapter,
+    config: &config::Config,
+    app_tx: &tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) -> error::Result<()> {
+    match cmd {
+        tui::app::DeviceCommand::ControlCommand(id, value) => aacp
+            .send_control_command(id, &value)
+            .await
+            .map_err(error::Error::Aacp),
+        tui::app::DeviceCommand::AdjustVolume(step) => {
+            // wpctl-style relative value, e.g. "0.05+"
+            let value = format!(
+                "{:.2}{}",
+                step.unsigned_abs() as f64 / 100.0,
+                if step < 0 { '-' } else { '+' }
+            );
+            config::try_template_cmd(&config.volume_set_command, &value)?;
+            config::run_template_cmd(&config.volume_osd_command, "+0");
+            Ok(())
+        }
+        tui::app::DeviceCommand::ToggleMicMute => {
+            media_controller::toggle_mic_mute(mac, Some(app_tx)).await?;
+            Ok(())
+        }
+        tui::app::DeviceCommand::Rename(name) => {
+            aacp.send_rename_packet(&name)
+                .await
+                .map_err(error::Error::Aacp)?;
+            // Set BlueZ alias with retry (no disconnect - avoids iPhone reclaiming the name)
+            let Ok(addr) = mac.parse::<Address>() else {
+                return Ok(());
+            };
+            let device = adapter.device(addr).map_err(error::Error::Bluez)?;
+            let mut last = None;
+            for _ in 0..3 {
+                match device.set_alias(name.clone()).await {
+                    Ok(()) => {
+                        log::info!("BlueZ alias updated to '{}'", name);
+                        return Ok(());
+                    }
+                    Err(e) => last = Some(e),
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+            last.map_or(Ok(()), |e| Err(error::Error::Bluez(e)))
+        }
+    }
+}
+
+/// Pause before retrying setup after bluetoothd came back; its adapters
+/// show up a moment after it takes the bus name.
+const BLUEZ_RESTART_RETRY: Duration = Duration::from_secs(2);
+
+fn dbus_error(e: zbus::Error) -> bluer::Error {
+    bluer::Error {
+        kind: bluer::ErrorKind::Internal(bluer::InternalErrorKind::DBus(e.to_string())),
+        message: e.to_string(),
+    }
+}
+
 async fn bluetooth_main(
     app_tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
     device_managers: Arc<RwLock<HashMap<String, DeviceManagers>>>,
     mut cmd_rx: tokio::sync::mpsc::UnboundedReceiver<(String, crate::tui::app::DeviceCommand)>,
     config: config::Config,
+    ready: Option<tokio::sync::oneshot::Sender<()>>,
 ) -> bluer::Result<()> {
-    let devices_path = get_devices_path();
-    let devices_json = std::fs::read_to_string(&devices_path).unwrap_or_else(|_| "{}".to_string());
-    let devices_list: HashMap<String, DeviceData> =
-        serde_json::from_str(&devices_json).unwrap_or_default();
-
-    let session = bluer::Session::new().await?;
-    let adapter = session.default_adapter().await?;
-    adapter.set_powered(true).await?;
+    if config.pause_automation_when_locked {
+        tokio::spawn(session_lock::watch());
+    }
 
     // AVRCP volume monitor
     let vol_config = config.clone();
+    let vol_tx = app_tx.clone();
     tokio::spawn(async move {
-        avrcp_volume_monitor(vol_config).await;
+        avrcp_volume_monitor(vol_config, vol_tx).await;
     });
 
-    // Command dispatcher - receives (mac, DeviceCommand) from TUI
+    // Command dispatcher - receives (mac, DeviceCommand) from TUI. Lives
+    // across BlueZ restarts; each start hands it the current adapter.
+    let (adapter_tx, adapter_rx) = tokio::sync::watch::channel::<Option<bluer::Adapter>>(None);
     let dm_cmd = device_managers.clone();
-    let adapter_cmd = adapter.clone();
+    let cmd_config = config.clone();
+    let cmd_app_tx = app_tx.clone();
     tokio::spawn(async move {
         while let Some((mac, cmd)) = cmd_rx.recv().await {
-            let managers = dm_cmd.read().await;
-            if let Some(dm) = managers.get(&mac)
-                && let Some(aacp) = dm.get_aacp()
-            {
-                match cmd {
-                    tui::app::DeviceCommand::ControlCommand(id, value) => {
-                        if let Err(e) = aacp.send_control_command(id, &value).await {
-                            log::error!("Failed to send control command: {}", e);
-                        }
-                    }
-                    tui::app::DeviceCommand::Rename(name) => {
-                        if let Err(e) = aacp.send_rename_packet(&name).await {
-                            log::error!("Failed to send rename: {}", e);
-                        }
-                        // Set BlueZ alias with retry (no disconnect - avoids iPhone reclaiming the name)
-                        if let Ok(addr) = mac.parse::<Address>()
-                            && let Ok(device) = adapter_cmd.device(addr)
-                        {
-                            for _ in 0..3 {
-                                if device.set_alias(name.clone()).await.is_ok() {
-                                    log::info!("BlueZ alias updated to '{}'", name);
-                                    break;
-                                }
-                                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                            }
-                        }
-                    }
+            let aacp = dm_cmd.read().await.get(&mac).and_then(|dm| dm.get_aacp());
+            let adapter = adapter_rx.borrow().clone();
+            let result = match (aacp, adapter) {
+                (Some(aacp), Some(adapter)) => {
+                    run_device_command(&mac, cmd, &aacp, &adapter, &cmd_config, &cmd_app_tx).await
                 }
+                _ => Err(error::Error::NotConnected(mac.clone())),
+            };
+            if let Err(e) = result {
+                log::error!("Command for {} failed: {}", mac, e);
+                let _ = cmd_app_tx.send(AppEvent::CommandFailed {
+                    mac,
+                    error: e.to_string(),
+                });
             }
         }
     });
 
+    // Supervisor: bring BlueZ-side tasks up, and when bluetoothd restarts or
+    // the adapter is unplugged, tear them down with every device session
+    // and start over once it's back
+    let bus = zbus::Connection::system().await.map_err(dbus_error)?;
+    let mut ready = ready;
+    let mut first_start = true;
+    loop {
+        let mut tasks = tokio::task::JoinSet::new();
+        let started =
+            start_bluez(&mut tasks, &app_tx, &device_managers, &config, &adapter_tx).await;
+        let mut stack = match started {
+            Ok(stack) => stack,
+            Err(e) if first_start => return Err(e),
+            Err(e) => {
+                log::warn!("Bluetooth setup after losing BlueZ failed: {}; retrying", e);
+                tasks.shutdown().await;
+                tokio::time::sleep(BLUEZ_RESTART_RETRY).await;
+                continue;
+            }
+        };
+        first_start = false;
+        if let Some(ready) = ready.take() {
+            let _ = ready.send(());
+        }
+
+        // Block on the D-Bus listener, or until bluetoothd or the adapter
+        // goes away
+        tokio::select! {
+            _ = &mut stack.listener => return Ok(()),
+            gone = bluetooth::bluez_watch::wait_for_bluez(&bus, false) => {
+                if let Err(e) = gone {
+                    log::warn!("Can't watch for BlueZ restarts: {}", e);
+                    let _ = stack.listener.await;
+                    return Ok(());
+                }
+                log::warn!("bluetoothd went away; waiting for it to come back");
+            }
+            Ok(()) = bluetooth::bluez_watch::wait_for_adapter_removal(&stack.session, &stack.adapter) => {
+                log::warn!("Bluetooth adapter {} went away; waiting for one to come back", stack.adapter);
+            }
+        }
+
+        stack.listener.abort();
+        tasks.shutdown().await;
+        adapter_tx.send_replace(None);
+        drop_all_sessions(&device_managers, &app_tx).await;
+        bluetooth::bluez_watch::wait_for_bluez(&bus, true)
+            .await
+            .map_err(dbus_error)?;
+        if let Err(e) =
+            bluetooth::bluez_watch::wait_for_adapter(&stack.session, config.adapter.as_deref())
+                .await
+        {
+            debug!("Can't watch for Bluetooth adapters: {}", e);
+        }
+        info!("Bluetooth is back; restarting");
+    }
+}
+
+/// Per-device cap on shutdown cleanup, so a wedged session can't hold up
+/// the exit.
+const SHUTDOWN_RELEASE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Daemon shutdown: release every live session (see
+/// `MediaController::release_for_shutdown`).
+async fn release_devices(device_managers: &Arc<RwLock<HashMap<String, DeviceManagers>>>) {
+    let devices: Vec<_> = device_managers
+        .read()
+        .await
+        .iter()
+        .filter_map(|(mac, dm)| Some((mac.clone(), dm.get_aacp()?, dm.get_media()?)))
+        .collect();
+    for (mac, aacp, media) in devices {
+        let release = async { media.lock().await.release_for_shutdown(&aacp).await };
+        if tokio::time::timeout(SHUTDOWN_RELEASE_TIMEOUT, release)
+            .await
+            .is_err()
+        {
+            log::warn!("Timed out releasing {}", mac);
+        }
+    }
+}
+
+/// Forget every device session; after a BlueZ restart their sockets are dead.
+async fn drop_all_sessions(
+    device_managers: &Arc<RwLock<HashMap<String, DeviceManagers>>>,
+    app_tx: &tokio::sync::mpsc::UnboundedSender<AppEvent>,
+) {
+    let sessions: Vec<_> = device_managers.write().await.drain().collect();
+    for (mac, managers) in sessions {
+        if let Some(aacp) = managers.get_aacp() {
+            aacp.disconnect().await;
+        }
+        let _ = app_tx.send(AppEvent::DeviceDisconnected(mac));
+    }
+}
+
+/// What the supervisor watches once start_bluez is done.
+struct BluezStack {
+    listener: tokio::task::JoinHandle<()>,
+    session: bluer::Session,
+    /// Name of the adapter in use, e.g. "hci0"
+    adapter: String,
+}
+
+/// Everything tied to one bluetoothd instance and adapter: discovery, the
+/// reconnect loop and the connection listener, then devices that are
+/// already connected. Tasks land in `tasks` so a restart can abort them.
+async fn start_bluez(
+    tasks: &mut tokio::task::JoinSet<()>,
+    app_tx: &tokio::sync::mpsc::UnboundedSender<AppEvent>,
+    device_managers: &Arc<RwLock<HashMap<String, DeviceManagers>>>,
+    config: &config::Config,
+    adapter_tx: &tokio::sync::watch::Sender<Option<bluer::Adapter>>,
+) -> bluer::Result<BluezStack> {
+    let devices_list = devices::store::load();
+
+    let session = bluer::Session::new().await?;
+    let adapter = bluetooth::open_adapter(&session, config.adapter.as_deref()).await?;
+    adapter.set_powered(true).await?;
+    info!("Using Bluetooth adapter {}", adapter.name());
+    adapter_tx.send_replace(Some(adapter.clone()));
+
+    if config.nearby_scan {
+        tasks.spawn(bluetooth::proximity::scan(
+            adapter.clone(),
+            app_tx.clone(),
+            config.auto_connect,
+        ));
+    }
+
+    if config.gatt_battery {
+        bluetooth::gatt_battery::scan_connected(&adapter, app_tx).await;
+    }
+
     // Reconnect channel: fed by ConnectionLost (L2CAP died) and by failed
-    // inits. Retries with backoff for as long as BlueZ still reports the
-    // device connected; once the BT link itself is gone, the connection
-    // listener owns recovery via the next Connected=true event.
+    // inits. Each device gets one retry task at a time, backing off per
+    // `[reconnect]` for as long as BlueZ still reports it connected; once
+    // the BT link itself is gone, the connection listener owns recovery via
+    // the next Connected=true event.
     let (reconnect_tx, mut reconnect_rx) = unbounded_channel::<(Address, u16)>();
     {
         let app_tx = app_tx.clone();
@@ -876,23 +1934,15 @@ async fn bluetooth_main(
         let reconnect_tx = reconnect_tx.clone();
         let dl = devices_list.clone();
         let adapter = adapter.clone();
-        tokio::spawn(async move {
+        let session = session.clone();
+        let retrying: Arc<std::sync::Mutex<HashSet<String>>> = Arc::default();
+        tasks.spawn(async move {
             while let Some((addr, product_id)) = reconnect_rx.recv().await {
                 let addr_str = addr.to_string();
-                // Drop the dead session, but never touch a healthy or
-                // still-initializing one (queued retries can be stale).
-                {
-                    let mut managers = dm.write().await;
-                    if let Some(existing) = managers.get(&addr_str) {
-                        let Some(aacp) = existing.get_aacp() else {
-                            continue; // init in progress elsewhere
-                        };
-                        if aacp.state.lock().await.sender.is_some() {
-                            continue; // healthy session, stale retry
-                        }
-                        managers.remove(&addr_str);
-                    }
-                }
+                let Some(guard) = RetrySlot::claim(&retrying, &addr_str) else {
+                    debug!("AACP reconnect for {} already in progress", addr_str);
+                    continue;
+                };
                 // Re-read the name from our store (may have been renamed)
                 let name = dl
                     .get(&addr_str)
@@ -905,91 +1955,100 @@ async fn bluetooth_main(
                     config: cfg.clone(),
                     reconnect_tx: reconnect_tx.clone(),
                 };
-                let mut attempt: u32 = 0;
-                loop {
-                    attempt += 1;
-                    let delay = Duration::from_secs((1u64 << attempt.min(5)).min(30));
-                    info!(
-                        "AACP reconnect: {} ({}) attempt {} in {:?}",
-                        name, addr, attempt, delay
-                    );
-                    tokio::time::sleep(delay).await;
-                    if dm.read().await.contains_key(&addr_str) {
-                        break; // another path claimed the device
-                    }
-                    let bluez_connected = match adapter.device(addr) {
-                        Ok(device) => device.is_connected().await.unwrap_or(false),
-                        Err(_) => false,
-                    };
-                    if !bluez_connected {
-                        info!(
-                            "{} is gone at the BlueZ level; the connection listener resumes when it returns",
-                            addr_str
-                        );
-                        break;
-                    }
-                    match try_airpods_init(addr, name.clone(), product_id, &ctx).await {
-                        InitOutcome::Ready | InitOutcome::AlreadyClaimed => break,
-                        InitOutcome::Failed => continue,
-                    }
-                }
+                // A device pinned elsewhere is checked on its own adapter
+                let adapter = dl
+                    .get(&addr_str)
+                    .and_then(|d| d.adapter.as_deref())
+                    .and_then(|pinned| session.adapter(pinned).ok())
+                    .unwrap_or_else(|| adapter.clone());
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    reconnect_device(addr, name, product_id, &ctx, &adapter).await;
+                });
             }
         });
     }
 
-    // Start D-Bus listener FIRST to avoid missing connections during startup checks
+    // Start D-Bus listener FIRST to avoid missing connections during startup
+    // checks. It gets its own bus connection so its match rules go away
+    // with it.
     info!("Listening for Bluetooth connections via D-Bus...");
-    let conn = zbus::Connection::system().await.map_err(|e| bluer::Error {
-        kind: bluer::ErrorKind::Internal(bluer::InternalErrorKind::DBus(e.to_string())),
-        message: e.to_string(),
-    })?;
+    let conn = zbus::Connection::system().await.map_err(dbus_error)?;
     let listener_handle = {
         let app_tx = app_tx.clone();
         let dm = device_managers.clone();
         let dl = devices_list.clone();
         let cfg = config.clone();
         let rtx = reconnect_tx.clone();
+        let session = session.clone();
         tokio::spawn(async move {
-            bluez_connection_listener(conn, app_tx, dm, dl, cfg, rtx).await;
+            bluez_connection_listener(conn, session, app_tx, dm, dl, cfg, rtx).await;
         })
     };
 
-    // Now check for already-connected devices (listener is already active)
+    // Now check for already-connected devices (listener is already active),
+    // on our adapter and on any adapter a device is pinned to
     info!("Checking for connected devices...");
-    match find_connected_airpods(&adapter).await {
-        Ok(device) => {
-            let bt_name = device
-                .name()
-                .await?
-                .unwrap_or_else(|| "Unknown AirPods".to_string());
-            let addr_str = device.address().to_string();
-            let name = devices_list
-                .get(&addr_str)
-                .filter(|d| !d.name.is_empty())
-                .map(|d| d.name.clone())
-                .unwrap_or(bt_name);
-            info!("Found connected AirPods: {}, initializing.", name);
-            let product_id = read_product_id(&addr_str).await;
-            info!("Product ID for {}: 0x{:04x}", addr_str, product_id);
-            spawn_airpods_init(
-                device.address(),
-                name,
-                product_id,
-                AirPodsInitContext {
-                    app_tx: app_tx.clone(),
-                    device_managers: device_managers.clone(),
-                    config: config.clone(),
-                    reconnect_tx: reconnect_tx.clone(),
-                },
-            );
+    let mut scan_adapters = vec![adapter.clone()];
+    for name in devices_list.values().filter_map(|d| d.adapter.as_deref()) {
+        if !scan_adapters.iter().any(|a| a.name() == name)
+            && let Ok(pinned) = session.adapter(name)
+        {
+            scan_adapters.push(pinned);
         }
-        Err(_) => {
-            info!("No connected AirPods found.");
+    }
+    let mut found = false;
+    for scan_adapter in &scan_adapters {
+        let Ok(device) = find_connected_airpods(scan_adapter).await else {
+            continue;
+        };
+        let addr_str = device.address().to_string();
+        if !bluetooth::serves_adapter(
+            Some(scan_adapter.name()),
+            devices_list.get(&addr_str),
+            config.adapter.as_deref(),
+        ) {
+            continue;
         }
+        found = true;
+        let bt_name = device
+            .name()
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "Unknown AirPods".to_string());
+        let name = devices_list
+            .get(&addr_str)
+            .filter(|d| !d.name.is_empty())
+            .map(|d| d.name.clone())
+            .unwrap_or(bt_name);
+        info!("Found connected AirPods: {}, initializing.", name);
+        let product_id = match zbus::Connection::system().await {
+            Ok(conn) => match bluetooth::device_path(&conn, &addr_str).await {
+                Some(path) => read_product_id(&path).await,
+                None => 0,
+            },
+            Err(_) => 0,
+        };
+        info!("Product ID for {}: 0x{:04x}", addr_str, product_id);
+        spawn_airpods_init(
+            device.address(),
+            name,
+            product_id,
+            AirPodsInitContext {
+                app_tx: app_tx.clone(),
+                device_managers: device_managers.clone(),
+                config: config.clone(),
+                reconnect_tx: reconnect_tx.clone(),
+            },
+        );
     }
-
-    // Block on the D-Bus listener
-    let _ = listener_handle.await;
-
-    Ok(())
+    if !found {
+        info!("No connected AirPods found.");
+    }
+    Ok(BluezStack {
+        listener: listener_handle,
+        adapter: adapter.name().to_string(),
+        session,
+    })
 }
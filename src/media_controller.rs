@@ -3,23 +3,44 @@ use crate::bluetooth::aacp::AudioSource;
 use crate::bluetooth::aacp::AudioSourceType;
 use crate::bluetooth::aacp::ControlCommandIdentifiers;
 use crate::bluetooth::aacp::EarDetectionStatus;
-use crate::config::Config;
-use crate::handoff::{Action, HandoffFsm, RECLAIM_SETTLE_MS};
+use crate::bluetooth::aacp::StemPressType;
+use crate::bluetooth::mac_from_bluez_path;
+use crate::config::{AudioBackend, Config, EarPausePolicy, StemAction};
+use crate::handoff::{Action, HandoffFsm, Ownership, RECLAIM_SETTLE_MS};
+use crate::tui::app::{NowPlaying, PlaybackStatus};
+use futures::StreamExt;
 use libpulse_binding::callbacks::ListResult;
-use libpulse_binding::context::introspect::{SinkInfo, SinkInputInfo};
+use libpulse_binding::context::introspect::{
+    SinkInfo, SinkInputInfo, SourceInfo, SourceOutputInfo,
+};
+use libpulse_binding::context::subscribe::{
+    Facility, InterestMaskSet, Operation as SubscribeOperation,
+};
 use libpulse_binding::context::{Context, FlagSet as ContextFlagSet};
 use libpulse_binding::def::Retval;
+use libpulse_binding::mainloop::api::Mainloop as _;
+use libpulse_binding::mainloop::events::io::FlagSet as IoEventFlagSet;
 use libpulse_binding::mainloop::standard::Mainloop;
 use libpulse_binding::operation::State as OperationState;
 use libpulse_binding::proplist::Proplist;
 use libpulse_binding::volume::{ChannelVolumes, Volume};
 use log::{debug, error, info, warn};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::TryRecvError;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
+#[cfg(feature = "pipewire")]
+mod pipewire_audio;
+
 // ── PulseAudio thread: single long-lived Mainloop + Context ──
 
 #[derive(Clone)]
@@ -32,6 +53,16 @@ struct OwnedCardInfo {
     index: u32,
     proplist: Proplist,
     profiles: Vec<OwnedCardProfileInfo>,
+    active_profile: Option<String>,
+}
+
+#[derive(Clone)]
+struct OwnedSourceInfo {
+    index: u32,
+    name: String,
+    is_monitor: bool,
+    proplist: Proplist,
+    mute: bool,
 }
 
 #[derive(Clone)]
@@ -95,22 +126,196 @@ enum AudioCommand {
         sink_name: String,
         reply: tokio::sync::oneshot::Sender<bool>,
     },
+    GetActiveProfile {
+        card_index: u32,
+        reply: tokio::sync::oneshot::Sender<Option<String>>,
+    },
+    IsCaptureActive {
+        reply: tokio::sync::oneshot::Sender<bool>,
+    },
+    RouteInputToMac {
+        mac: String,
+        reply: tokio::sync::oneshot::Sender<bool>,
+    },
+    GetDefaultSink {
+        reply: tokio::sync::oneshot::Sender<Option<String>>,
+    },
+    ToggleSourceMute {
+        mac: String,
+        reply: tokio::sync::oneshot::Sender<Option<bool>>,
+    },
 }
 
-/// Spawn a single background thread that owns the PulseAudio Mainloop + Context.
-/// Returns a sender for issuing commands.
-fn spawn_audio_thread(
-    app_tx: Option<tokio::sync::mpsc::UnboundedSender<crate::tui::app::AppEvent>>,
-) -> std::sync::mpsc::Sender<AudioCommand> {
+/// Audio server changes, from the PulseAudio subscription or the PipeWire
+/// registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AudioEvent {
+    /// A card was added, removed, or changed profile.
+    Card(u32),
+    /// A sink was added or removed.
+    Sinks,
+    /// A sink's volume, mute or state changed.
+    SinkChanged,
+    /// A recording stream appeared or went away.
+    SourceOutputs,
+}
+
+/// Outlives individual audio threads, so subscribers survive a respawn.
+static AUDIO_EVENTS: LazyLock<tokio::sync::broadcast::Sender<AudioEvent>> =
+    LazyLock::new(|| tokio::sync::broadcast::channel(64).0);
+
+/// Latest BlueZ MediaTransport1 state per device MAC, fed by the AVRCP
+/// monitor's PropertiesChanged stream.
+static TRANSPORTS: LazyLock<tokio::sync::watch::Sender<HashMap<String, TransportState>>> =
+    LazyLock::new(|| tokio::sync::watch::channel(HashMap::new()).0);
+
+/// How long the A2DP transport may take to leave idle after a claim.
+const TRANSPORT_READY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Claims re-sent when the transport doesn't come up.
+const TRANSPORT_RETRIES: u32 = 2;
+
+/// How long to wait for the device's card after the Bluetooth connect.
+const CARD_APPEAR_TIMEOUT: Duration = Duration::from_secs(6);
+
+/// How long the card may take to come back with A2DP after an audio
+/// server restart.
+const AUDIO_RESTART_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the sink may take to appear after a profile switch.
+const SINK_APPEAR_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Capture has to stay gone this long before the call profile is dropped;
+/// apps reopen the microphone when switching devices or unmuting.
+const CALL_END_GRACE: Duration = Duration::from_secs(2);
+
+/// Headset profiles in order of preference (mSBC is wideband).
+const HEADSET_PROFILES: [&str; 4] = [
+    "headset-head-unit-msbc",
+    "headset-head-unit",
+    "headset-head-unit-cvsd",
+    "handsfree_head_unit",
+];
+
+/// How far the skip-forward/skip-back stem actions seek.
+const STEM_SKIP_US: i64 = 30_000_000;
+
+/// How often the playback listener checks for session loss, ear-driven
+/// idle-inhibit changes and a call that has ended; playback and track
+/// changes themselves are signal-driven.
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_secs(1);
+
+type MprisSignals =
+    std::pin::Pin<Box<dyn futures::Stream<Item = zbus::Result<zbus::Message>> + Send>>;
+
+/// The process-wide audio thread and whether it is still running. Every
+/// MediaController shares it, so reconnects and multiple devices reuse one
+/// audio server connection instead of each opening their own.
+static AUDIO_THREAD: std::sync::Mutex<Option<(AudioTx, Arc<AtomicBool>)>> =
+    std::sync::Mutex::new(None);
+
+/// Everyone who asked for the audio thread with an event channel; all of
+/// them hear when the audio server can't be reached.
+static AUDIO_LISTENERS: std::sync::Mutex<Vec<AppTx>> = std::sync::Mutex::new(Vec::new());
+
+type AppTx = tokio::sync::mpsc::UnboundedSender<crate::tui::app::AppEvent>;
+
+/// Which audio server API the audio thread talks to, from the config.
+static AUDIO_BACKEND: std::sync::OnceLock<AudioBackend> = std::sync::OnceLock::new();
+
+/// Choose the audio backend; call before the first MediaController.
+pub fn set_audio_backend(backend: AudioBackend) {
+    #[cfg(not(feature = "pipewire"))]
+    if backend == AudioBackend::PipeWire {
+        warn!("Built without PipeWire support, using PulseAudio");
+    }
+    let _ = AUDIO_BACKEND.set(backend);
+}
+
+/// Sender for the shared audio thread, starting it (again) if it isn't
+/// running, e.g. on first use or after the audio server connection dropped.
+fn shared_audio_thread(app_tx: Option<AppTx>) -> AudioTx {
+    if let Some(app_tx) = app_tx {
+        let mut listeners = AUDIO_LISTENERS.lock().unwrap_or_else(|e| e.into_inner());
+        listeners.retain(|tx| !tx.is_closed());
+        if !listeners.iter().any(|tx| tx.same_channel(&app_tx)) {
+            listeners.push(app_tx);
+        }
+    }
+    let mut slot = AUDIO_THREAD.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((tx, alive)) = slot.as_ref()
+        && alive.load(Ordering::Acquire)
+    {
+        return tx.clone();
+    }
+    let alive = Arc::new(AtomicBool::new(true));
+    let tx = spawn_audio_thread(alive.clone());
+    *slot = Some((tx.clone(), alive));
+    tx
+}
+
+/// Tell every registered event channel that the audio server is unreachable.
+fn report_audio_unavailable() {
+    let mut listeners = AUDIO_LISTENERS.lock().unwrap_or_else(|e| e.into_inner());
+    listeners.retain(|tx| tx.send(crate::tui::app::AppEvent::AudioUnavailable).is_ok());
+}
+
+/// Clears the liveness flag however the audio thread exits.
+struct AliveGuard(Arc<AtomicBool>);
+
+impl Drop for AliveGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Spawn a single background thread that owns the PipeWire connection, or
+/// the PulseAudio Mainloop + Context. Returns a sender for issuing commands.
+fn spawn_audio_thread(alive: Arc<AtomicBool>) -> AudioTx {
     let (tx, rx) = std::sync::mpsc::channel::<AudioCommand>();
+    let wake = UnixStream::pair().and_then(|(r, w)| {
+        r.set_nonblocking(true)?;
+        w.set_nonblocking(true)?;
+        Ok((r, w))
+    });
+    let (wake_rx, wake_tx) = match wake {
+        Ok(pair) => pair,
+        Err(e) => {
+            error!("Failed to create the audio thread wakeup socket: {}", e);
+            report_audio_unavailable();
+            // Requests on the dropped receiver return their defaults
+            alive.store(false, Ordering::Release);
+            return AudioTx {
+                commands: tx,
+                wake: None,
+            };
+        }
+    };
 
     std::thread::spawn(move || {
+        let _alive = AliveGuard(alive);
         let fail = |msg: &str| {
             error!("{}", msg);
-            if let Some(ref tx) = app_tx {
-                let _ = tx.send(crate::tui::app::AppEvent::AudioUnavailable);
-            }
+            report_audio_unavailable();
         };
+
+        #[cfg(feature = "pipewire")]
+        {
+            let backend = AUDIO_BACKEND.get().copied().unwrap_or_default();
+            if backend != AudioBackend::PulseAudio {
+                match pipewire_audio::Connection::connect(backend == AudioBackend::Auto) {
+                    Ok(connection) => return connection.run(rx, wake_rx),
+                    Err(e) if backend == AudioBackend::Auto => {
+                        debug!("Not using PipeWire ({}), trying PulseAudio", e);
+                    }
+                    Err(e) => {
+                        fail(&format!("Failed to connect to PipeWire: {}", e));
+                        return;
+                    }
+                }
+            }
+        }
+
         let mut mainloop = match Mainloop::new() {
             Some(m) => m,
             None => {
@@ -148,82 +353,76 @@ fn spawn_audio_thread(
         }
         info!("PulseAudio audio thread connected and ready");
 
-        // Process commands
-        while let Ok(cmd) = rx.recv() {
-            match cmd {
-                AudioCommand::IsA2dpAvailable { card_index, reply } => {
-                    let result = pa_is_a2dp_available(&mut mainloop, &context, card_index);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::GetDeviceIndex { mac, reply } => {
-                    let result = pa_get_device_index(&mut mainloop, &context, &mac);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::SetCardProfile {
-                    card_index,
-                    profile,
-                    reply,
-                } => {
-                    let result =
-                        pa_set_card_profile(&mut mainloop, &mut context, card_index, &profile);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::GetSinkVolume { sink_name, reply } => {
-                    let result = pa_get_sink_volume(&mut mainloop, &context, &sink_name);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::TransitionVolume {
-                    sink_name,
-                    target,
-                    reply,
-                } => {
-                    let result =
-                        pa_transition_volume(&mut mainloop, &mut context, &sink_name, target);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::GetSinkNameByMac { mac, reply } => {
-                    let result = pa_get_sink_name_by_mac(&mut mainloop, &context, &mac);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::IsProfileAvailable {
-                    card_index,
-                    profile,
-                    reply,
-                } => {
-                    let result =
-                        pa_is_profile_available(&mut mainloop, &context, card_index, &profile);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::SetDefaultSink { sink_name, reply } => {
-                    let result = pa_set_default_sink(&mut mainloop, &mut context, &sink_name);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::MoveAllSinkInputs { sink_name, reply } => {
-                    let result = pa_move_all_sink_inputs(&mut mainloop, &mut context, &sink_name);
-                    let _ = reply.send(result);
-                }
-                AudioCommand::SuspendSinkByName {
-                    sink_name,
-                    suspend,
-                    reply,
-                } => {
-                    let result =
-                        pa_suspend_sink_by_name(&mut mainloop, &mut context, &sink_name, suspend);
-                    let _ = reply.send(result);
+        let pending = Rc::new(RefCell::new(Vec::<AudioEvent>::new()));
+        context.set_subscribe_callback(Some(Box::new({
+            let pending = pending.clone();
+            move |facility, op, index| {
+                let event = match facility {
+                    Some(Facility::Card) => AudioEvent::Card(index),
+                    Some(Facility::SourceOutput) => AudioEvent::SourceOutputs,
+                    Some(Facility::Sink) if op == Some(SubscribeOperation::Changed) => {
+                        AudioEvent::SinkChanged
+                    }
+                    Some(Facility::Sink) => AudioEvent::Sinks,
+                    _ => return,
+                };
+                let mut pending = pending.borrow_mut();
+                if pending.last() != Some(&event) {
+                    pending.push(event);
                 }
-                AudioCommand::SetSinkMute {
-                    sink_name,
-                    mute,
-                    reply,
-                } => {
-                    let result =
-                        pa_set_sink_mute_by_name(&mut mainloop, &mut context, &sink_name, mute);
-                    let _ = reply.send(result);
+            }
+        })));
+        context.subscribe(
+            InterestMaskSet::CARD | InterestMaskSet::SINK | InterestMaskSet::SOURCE_OUTPUT,
+            |_| {},
+        );
+
+        // Resolved sink per MAC, dropped whenever sinks or cards come and
+        // go so node renames and re-creations are picked up.
+        let mut sink_names: HashMap<String, String> = HashMap::new();
+
+        // Senders write a byte after each command, so the mainloop can
+        // sleep in poll() until the server or a command needs attention
+        let wake_fd = wake_rx.as_raw_fd();
+        let _wake_event = mainloop.new_io_event(
+            wake_fd,
+            IoEventFlagSet::INPUT,
+            Box::new(move |_, _, _| {
+                let mut buf = [0u8; 64];
+                while matches!((&wake_rx).read(&mut buf), Ok(n) if n > 0) {}
+            }),
+        );
+
+        // Process commands, dispatching subscription events in between
+        'outer: loop {
+            if mainloop.iterate(true).is_error() {
+                warn!("PulseAudio mainloop failed, audio thread exiting");
+                break;
+            }
+            loop {
+                for event in pending.borrow_mut().drain(..) {
+                    if matches!(event, AudioEvent::Sinks | AudioEvent::Card(_)) {
+                        sink_names.clear();
+                    }
+                    let _ = AUDIO_EVENTS.send(event);
                 }
-                AudioCommand::HasActiveSinkInput { sink_name, reply } => {
-                    let result = pa_has_active_sink_input(&mut mainloop, &context, &sink_name);
-                    let _ = reply.send(result);
+                // The server went away (restart, crash). Exit so the next
+                // controller starts a fresh connection; requests still
+                // queued return their defaults once the channel drops.
+                if matches!(
+                    context.get_state(),
+                    libpulse_binding::context::State::Failed
+                        | libpulse_binding::context::State::Terminated
+                ) {
+                    warn!("PulseAudio connection lost, audio thread exiting");
+                    break 'outer;
                 }
+                let cmd = match rx.try_recv() {
+                    Ok(cmd) => cmd,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => break 'outer,
+                };
+                run_audio_command(&mut mainloop, &mut context, &mut sink_names, cmd);
             }
         }
 
@@ -231,7 +430,119 @@ fn spawn_audio_thread(
         info!("PulseAudio audio thread exiting");
     });
 
-    tx
+    AudioTx {
+        commands: tx,
+        wake: Some(Arc::new(wake_tx)),
+    }
+}
+
+/// Run one command on the audio thread and send its reply.
+fn run_audio_command(
+    mainloop: &mut Mainloop,
+    context: &mut Context,
+    sink_names: &mut HashMap<String, String>,
+    cmd: AudioCommand,
+) {
+    match cmd {
+        AudioCommand::IsA2dpAvailable { card_index, reply } => {
+            let result = pa_is_a2dp_available(mainloop, context, card_index);
+            let _ = reply.send(result);
+        }
+        AudioCommand::GetDeviceIndex { mac, reply } => {
+            let result = pa_get_device_index(mainloop, context, &mac);
+            let _ = reply.send(result);
+        }
+        AudioCommand::SetCardProfile {
+            card_index,
+            profile,
+            reply,
+        } => {
+            let result = pa_set_card_profile(mainloop, context, card_index, &profile);
+            let _ = reply.send(result);
+        }
+        AudioCommand::GetSinkVolume { sink_name, reply } => {
+            let result = pa_get_sink_volume(mainloop, context, &sink_name);
+            let _ = reply.send(result);
+        }
+        AudioCommand::TransitionVolume {
+            sink_name,
+            target,
+            reply,
+        } => {
+            let result = pa_transition_volume(mainloop, context, &sink_name, target);
+            let _ = reply.send(result);
+        }
+        AudioCommand::GetSinkNameByMac { mac, reply } => {
+            let key = mac.to_ascii_uppercase();
+            let result = match sink_names.get(&key) {
+                Some(name) => Some(name.clone()),
+                None => {
+                    let found = pa_get_sink_name_by_mac(mainloop, context, &mac);
+                    if let Some(name) = &found {
+                        sink_names.insert(key, name.clone());
+                    }
+                    found
+                }
+            };
+            let _ = reply.send(result);
+        }
+        AudioCommand::IsProfileAvailable {
+            card_index,
+            profile,
+            reply,
+        } => {
+            let result = pa_is_profile_available(mainloop, context, card_index, &profile);
+            let _ = reply.send(result);
+        }
+        AudioCommand::SetDefaultSink { sink_name, reply } => {
+            let result = pa_set_default_sink(mainloop, context, &sink_name);
+            let _ = reply.send(result);
+        }
+        AudioCommand::MoveAllSinkInputs { sink_name, reply } => {
+            let result = pa_move_all_sink_inputs(mainloop, context, &sink_name);
+            let _ = reply.send(result);
+        }
+        AudioCommand::SuspendSinkByName {
+            sink_name,
+            suspend,
+            reply,
+        } => {
+            let result = pa_suspend_sink_by_name(mainloop, context, &sink_name, suspend);
+            let _ = reply.send(result);
+        }
+        AudioCommand::SetSinkMute {
+            sink_name,
+            mute,
+            reply,
+        } => {
+            let result = pa_set_sink_mute_by_name(mainloop, context, &sink_name, mute);
+            let _ = reply.send(result);
+        }
+        AudioCommand::HasActiveSinkInput { sink_name, reply } => {
+            let result = pa_has_active_sink_input(mainloop, context, &sink_name);
+            let _ = reply.send(result);
+        }
+        AudioCommand::GetActiveProfile { card_index, reply } => {
+            let result = pa_get_active_profile(mainloop, context, card_index);
+            let _ = reply.send(result);
+        }
+        AudioCommand::IsCaptureActive { reply } => {
+            let result = pa_is_capture_active(mainloop, context);
+            let _ = reply.send(result);
+        }
+        AudioCommand::RouteInputToMac { mac, reply } => {
+            let result = pa_route_input_to_mac(mainloop, context, &mac);
+            let _ = reply.send(result);
+        }
+        AudioCommand::ToggleSourceMute { mac, reply } => {
+            let result = pa_toggle_source_mute(mainloop, context, &mac);
+            let _ = reply.send(result);
+        }
+        AudioCommand::GetDefaultSink { reply } => {
+            let result = pa_get_default_sink(mainloop, context);
+            let _ = reply.send(result);
+        }
+    }
 }
 
 // ── Synchronous PA helpers (run inside the audio thread) ──
@@ -255,6 +566,10 @@ fn pa_get_card_info_list(mainloop: &mut Mainloop, context: &Context) -> Vec<Owne
                     index: item.index,
                     proplist: item.proplist.clone(),
                     profiles,
+                    active_profile: item
+                        .active_profile
+                        .as_ref()
+                        .and_then(|p| p.name.as_ref().map(|n| n.to_string())),
                 });
             }
             ListResult::End => *card_info_list.borrow_mut() = Some(list.clone()),
@@ -281,15 +596,10 @@ fn pa_is_a2dp_available(mainloop: &mut Mainloop, context: &Context, card_index:
 }
 
 fn pa_get_device_index(mainloop: &mut Mainloop, context: &Context, mac: &str) -> Option<u32> {
-    let cards = pa_get_card_info_list(mainloop, context);
-    for card in &cards {
-        if let Some(device_string) = card.proplist.get_str("device.string")
-            && device_string.contains(mac)
-        {
-            return Some(card.index);
-        }
-    }
-    None
+    pa_get_card_info_list(mainloop, context)
+        .iter()
+        .find(|card| proplist_matches_mac(&card.proplist, mac))
+        .map(|card| card.index)
 }
 
 fn pa_set_card_profile(
@@ -516,11 +826,9 @@ fn pa_get_sink_name_by_mac(
                     proplist: item.proplist.clone(),
                     volume: item.volume,
                 };
-                sink_info_list
-                    .borrow_mut()
-                    .as_mut()
-                    .expect("sink_info_list initialized as Some")
-                    .push(owned_item);
+                if let Some(list) = sink_info_list.borrow_mut().as_mut() {
+                    list.push(owned_item);
+                }
             }
         }
     });
@@ -528,30 +836,228 @@ fn pa_get_sink_name_by_mac(
         mainloop.iterate(false);
     }
 
-    if let Some(list) = sink_info_list.borrow().as_ref() {
-        for sink in list {
-            if let Some(device_string) = sink.proplist.get_str("device.string")
-                && device_string.to_uppercase().contains(&mac.to_uppercase())
-                && let Some(name) = &sink.name
-            {
-                return Some(name.to_string());
-            }
-            if let Some(bluez_path) = sink.proplist.get_str("bluez.path") {
-                let mac_from_path = bluez_path
-                    .split('/')
-                    .next_back()
-                    .unwrap_or("")
-                    .replace("dev_", "")
-                    .replace('_', ":");
-                if mac_from_path.eq_ignore_ascii_case(mac)
-                    && let Some(name) = &sink.name
-                {
-                    return Some(name.to_string());
-                }
+    let list = sink_info_list.borrow();
+    let mut candidates: Vec<(u8, &str)> = list
+        .iter()
+        .flatten()
+        .filter_map(|sink| {
+            let rank = BluezProps::of(&sink.proplist).match_rank(mac)?;
+            Some((rank, sink.name.as_deref()?))
+        })
+        .collect();
+    if candidates.len() > 1 {
+        debug!("Several sinks match {}: {:?}", mac, candidates);
+    }
+    // Stable: among equals the server's order decides
+    candidates.sort_by_key(|(rank, _)| *rank);
+    candidates.first().map(|(_, name)| name.to_string())
+}
+
+/// The Bluetooth identity properties of a sink/source.
+#[derive(Debug, Default)]
+struct BluezProps {
+    /// `api.bluez5.address` (PipeWire).
+    address: Option<String>,
+    device_string: Option<String>,
+    bluez_path: Option<String>,
+    /// `device.api`, "bluez5" on PipeWire's own nodes.
+    api: Option<String>,
+}
+
+impl BluezProps {
+    fn of(proplist: &Proplist) -> Self {
+        Self::from_lookup(|key| proplist.get_str(key))
+    }
+
+    fn from_lookup(get: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            address: get("api.bluez5.address"),
+            device_string: get("device.string"),
+            bluez_path: get("bluez.path"),
+            api: get("device.api"),
+        }
+    }
+
+    /// How directly this node belongs to `mac`: 0 for the device's own
+    /// Bluetooth node, 1 for anything else carrying its address (combined
+    /// sinks, loopbacks, renamed nodes); None when it isn't the device's.
+    fn match_rank(&self, mac: &str) -> Option<u8> {
+        let by_address = self
+            .address
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case(mac));
+        let by_path = self
+            .bluez_path
+            .as_deref()
+            .and_then(mac_from_bluez_path)
+            .is_some_and(|m| m.eq_ignore_ascii_case(mac));
+        let by_string = self
+            .device_string
+            .as_deref()
+            .is_some_and(|s| s.to_uppercase().contains(&mac.to_uppercase()));
+        if !(by_address || by_path || by_string) {
+            return None;
+        }
+        let native = by_address || self.api.as_deref() == Some("bluez5");
+        Some(if native { 0 } else { 1 })
+    }
+}
+
+/// Whether a sink/source belongs to the Bluetooth device `mac`.
+fn proplist_matches_mac(proplist: &Proplist, mac: &str) -> bool {
+    BluezProps::of(proplist).match_rank(mac).is_some()
+}
+
+fn pa_get_default_sink(mainloop: &mut Mainloop, context: &Context) -> Option<String> {
+    let name = Rc::new(RefCell::new(None));
+    let op = context.introspect().get_server_info({
+        let name = name.clone();
+        move |info| {
+            *name.borrow_mut() = info.default_sink_name.as_ref().map(|n| n.to_string());
+        }
+    });
+    while op.get_state() == OperationState::Running {
+        mainloop.iterate(false);
+    }
+    name.take()
+}
+
+fn pa_get_active_profile(
+    mainloop: &mut Mainloop,
+    context: &Context,
+    card_index: u32,
+) -> Option<String> {
+    pa_get_card_info_list(mainloop, context)
+        .into_iter()
+        .find(|c| c.index == card_index)
+        .and_then(|c| c.active_profile)
+}
+
+fn pa_get_sources(mainloop: &mut Mainloop, context: &Context) -> Vec<OwnedSourceInfo> {
+    let sources = Rc::new(RefCell::new(Vec::new()));
+    let op = context.introspect().get_source_info_list({
+        let sources = sources.clone();
+        move |result: ListResult<&SourceInfo>| {
+            if let ListResult::Item(item) = result {
+                sources.borrow_mut().push(OwnedSourceInfo {
+                    index: item.index,
+                    name: item.name.as_deref().unwrap_or_default().to_string(),
+                    is_monitor: item.monitor_of_sink.is_some(),
+                    proplist: item.proplist.clone(),
+                    mute: item.mute,
+                });
+            }
+        }
+    });
+    while op.get_state() == OperationState::Running {
+        mainloop.iterate(false);
+    }
+    sources.take()
+}
+
+/// The device's microphone: its non-monitor source.
+fn find_device_source<'a>(
+    sources: &'a [OwnedSourceInfo],
+    mac: &str,
+) -> Option<&'a OwnedSourceInfo> {
+    sources
+        .iter()
+        .find(|s| !s.is_monitor && proplist_matches_mac(&s.proplist, mac))
+}
+
+/// Flip the mute of the device's microphone; the new state, or None when
+/// no headset profile is providing one.
+fn pa_toggle_source_mute(
+    mainloop: &mut Mainloop,
+    context: &mut Context,
+    mac: &str,
+) -> Option<bool> {
+    let sources = pa_get_sources(mainloop, context);
+    let source = find_device_source(&sources, mac)?;
+    let mute = !source.mute;
+    let success = Rc::new(RefCell::new(false));
+    let op = context.introspect().set_source_mute_by_name(
+        &source.name,
+        mute,
+        Some(Box::new({
+            let success = success.clone();
+            move |result: bool| {
+                *success.borrow_mut() = result;
+            }
+        })),
+    );
+    while op.get_state() == OperationState::Running {
+        mainloop.iterate(false);
+    }
+    let ok = *success.borrow();
+    ok.then_some(mute)
+}
+
+/// (index, source index, application.id) for every recording stream.
+fn pa_get_source_outputs(
+    mainloop: &mut Mainloop,
+    context: &Context,
+) -> Vec<(u32, u32, Option<String>)> {
+    let outputs = Rc::new(RefCell::new(Vec::new()));
+    let op = context.introspect().get_source_output_info_list({
+        let outputs = outputs.clone();
+        move |result: ListResult<&SourceOutputInfo>| {
+            if let ListResult::Item(item) = result {
+                outputs.borrow_mut().push((
+                    item.index,
+                    item.source,
+                    item.proplist.get_str("application.id"),
+                ));
             }
         }
+    });
+    while op.get_state() == OperationState::Running {
+        mainloop.iterate(false);
+    }
+    outputs.take()
+}
+
+/// Whether any application is recording from a microphone.
+fn pa_is_capture_active(mainloop: &mut Mainloop, context: &Context) -> bool {
+    let sources = pa_get_sources(mainloop, context);
+    pa_get_source_outputs(mainloop, context)
+        .iter()
+        .any(|(_, source, app_id)| {
+            let monitor = sources
+                .iter()
+                .find(|s| s.index == *source)
+                .is_some_and(|s| s.is_monitor);
+            is_capture_stream(app_id.as_deref(), monitor)
+        })
+}
+
+/// Make the device's microphone the default source and move every
+/// recording stream onto it.
+fn pa_route_input_to_mac(mainloop: &mut Mainloop, context: &mut Context, mac: &str) -> bool {
+    let sources = pa_get_sources(mainloop, context);
+    let Some(source) = find_device_source(&sources, mac) else {
+        return false;
+    };
+    let name = &source.name;
+    let op = context.set_default_source(name, |_| {});
+    while op.get_state() == OperationState::Running {
+        mainloop.iterate(false);
+    }
+    for (idx, ..) in pa_get_source_outputs(mainloop, context) {
+        let mut introspector = context.introspect();
+        let op = introspector.move_source_output_by_name(idx, name, None);
+        while op.get_state() == OperationState::Running {
+            mainloop.iterate(false);
+        }
     }
-    None
+    true
+}
+
+/// A recording stream that means "someone wants the mic": not a sink
+/// monitor (visualisers, screen recorders of output) and not pavucontrol's
+/// level meters.
+fn is_capture_stream(app_id: Option<&str>, source_is_monitor: bool) -> bool {
+    !source_is_monitor && app_id != Some("org.PulseAudio.pavucontrol")
 }
 
 fn pa_is_profile_available(
@@ -574,9 +1080,26 @@ fn pa_is_profile_available(
 
 // ── Async wrappers: send command + await oneshot reply ──
 
-type AudioTx = std::sync::mpsc::Sender<AudioCommand>;
+/// Handle to the audio thread. Commands go over the channel; a byte on
+/// the socket wakes its mainloop to pick them up.
+#[derive(Clone)]
+struct AudioTx {
+    commands: std::sync::mpsc::Sender<AudioCommand>,
+    wake: Option<Arc<UnixStream>>,
+}
+
+impl AudioTx {
+    fn send(&self, cmd: AudioCommand) -> Result<(), std::sync::mpsc::SendError<AudioCommand>> {
+        self.commands.send(cmd)?;
+        if let Some(wake) = &self.wake {
+            // A full socket already holds a wakeup
+            let _ = (&**wake).write(&[0]);
+        }
+        Ok(())
+    }
+}
 
-/// Send one command to the PulseAudio thread and await its oneshot reply,
+/// Send one command to the audio thread and await its oneshot reply,
 /// returning `default` if the thread is gone.
 async fn audio_request<T>(
     tx: &AudioTx,
@@ -700,6 +1223,40 @@ async fn audio_cmd_has_active_sink_input(tx: &AudioTx, sink_name: &str) -> bool
     .await
 }
 
+async fn audio_cmd_get_active_profile(tx: &AudioTx, card_index: u32) -> Option<String> {
+    audio_request(tx, None, |reply| AudioCommand::GetActiveProfile {
+        card_index,
+        reply,
+    })
+    .await
+}
+
+async fn audio_cmd_is_capture_active(tx: &AudioTx) -> bool {
+    audio_request(tx, false, |reply| AudioCommand::IsCaptureActive { reply }).await
+}
+
+async fn audio_cmd_toggle_source_mute(tx: &AudioTx, mac: &str) -> Option<bool> {
+    let mac = mac.to_string();
+    audio_request(tx, None, |reply| AudioCommand::ToggleSourceMute {
+        mac,
+        reply,
+    })
+    .await
+}
+
+async fn audio_cmd_get_default_sink(tx: &AudioTx) -> Option<String> {
+    audio_request(tx, None, |reply| AudioCommand::GetDefaultSink { reply }).await
+}
+
+async fn audio_cmd_route_input_to_mac(tx: &AudioTx, mac: &str) -> bool {
+    let mac = mac.to_string();
+    audio_request(tx, false, |reply| AudioCommand::RouteInputToMac {
+        mac,
+        reply,
+    })
+    .await
+}
+
 // ── MediaController ──
 
 struct MediaControllerState {
@@ -712,10 +1269,32 @@ struct MediaControllerState {
     conv_original_volume: Option<u32>,
     conv_conversation_started: bool,
     playback_listener_running: bool,
+    /// Cookie from org.freedesktop.ScreenSaver.Inhibit while we hold one.
+    idle_inhibit_cookie: Option<u32>,
+    /// A2DP profile to return to once the call that switched the card to
+    /// the headset profile ends.
+    call_restore_profile: Option<String>,
+    /// When capture stopped during a call plus `CALL_END_GRACE`; the
+    /// housekeeping tick drops the call profile after it if capture is
+    /// still gone.
+    call_end_at: Option<tokio::time::Instant>,
+    /// Non-A2DP profile the card was on before we took it over (e.g. a
+    /// headset profile), restored on release.
+    pre_takeover_profile: Option<String>,
+    /// Default sink before we first switched to the AirPods, put back on
+    /// release or disconnect.
+    previous_default_sink: Option<String>,
+    /// Whether the device's sink existed at the last sink add/remove.
+    device_sink_present: bool,
+    /// Last track sent as AppEvent::NowPlaying.
+    now_playing: Option<NowPlaying>,
+    /// Last status sent as AppEvent::Playback.
+    playback_status: PlaybackStatus,
     /// Who owns the audio session; see `handoff` for the transition rules.
     handoff: HandoffFsm,
     config: Config,
     audio_tx: std::sync::mpsc::Sender<AudioCommand>,
+    app_tx: Option<tokio::sync::mpsc::UnboundedSender<crate::tui::app::AppEvent>>,
     session_conn: Option<zbus::Connection>,
 }
 
@@ -724,7 +1303,7 @@ impl MediaControllerState {
         config: Config,
         app_tx: Option<tokio::sync::mpsc::UnboundedSender<crate::tui::app::AppEvent>>,
     ) -> Self {
-        let audio_tx = spawn_audio_thread(app_tx);
+        let audio_tx = shared_audio_thread(app_tx.clone());
         MediaControllerState {
             connected_device_mac: String::new(),
             local_mac: String::new(),
@@ -735,9 +1314,18 @@ impl MediaControllerState {
             conv_original_volume: None,
             conv_conversation_started: false,
             playback_listener_running: false,
+            idle_inhibit_cookie: None,
+            call_restore_profile: None,
+            call_end_at: None,
+            pre_takeover_profile: None,
+            previous_default_sink: None,
+            device_sink_present: false,
+            now_playing: None,
+            playback_status: PlaybackStatus::Idle,
             handoff: HandoffFsm::default(),
             config,
             audio_tx,
+            app_tx,
             session_conn: None,
         }
     }
@@ -798,50 +1386,363 @@ impl MediaController {
 
     async fn playback_listener_loop(&self, aacp_manager: AACPManager) {
         info!("Starting playback listener loop");
+        let mut signals = self.mpris_signals().await;
+        let mut audio_events = AUDIO_EVENTS.subscribe();
+        let mut housekeeping = tokio::time::interval(HOUSEKEEPING_INTERVAL);
+        // Evaluate once up front: a player may already be playing
+        let mut refresh = true;
+        let mut track_changed = true;
         loop {
-            tokio::time::sleep(Duration::from_millis(500)).await;
-
             // Exit when the L2CAP session is gone (recv_thread/disconnect
             // clear the sender). Otherwise this loop outlives the session and
-            // every reconnect leaks a poll task, a PulseAudio thread, and the
-            // dead manager state - and stale loops keep re-activating the
+            // every reconnect leaks a listener task, a PulseAudio thread, and
+            // the dead manager state - and stale loops keep re-activating the
             // A2DP profile against live PulseAudio.
             if aacp_manager.state.lock().await.sender.is_none() {
                 info!("AACP session closed, stopping playback listener");
                 break;
             }
 
-            let is_playing = self.check_if_playing_async().await;
-
-            let mut state = self.state.lock().await;
-            let was_playing = state.is_playing;
-            state.is_playing = is_playing;
-            drop(state);
-
-            if !was_playing && is_playing {
-                let ear_ok = {
-                    let aacp_state = aacp_manager.state.lock().await;
-                    aacp_state.ear_detection_left == Some(EarDetectionStatus::InEar)
-                        || aacp_state.ear_detection_right == Some(EarDetectionStatus::InEar)
-                }; // ← aacp_state dropped; safe to re-enter aacp_manager below
-
-                if !ear_ok {
-                    info!("Media playback started but buds not in ear, skipping takeover");
-                    continue;
-                }
+            if refresh {
+                self.refresh_playback(&aacp_manager).await;
+            } else {
+                // Ear state changes without an MPRIS signal; keep the
+                // inhibit in step from the cached playback state.
+                let is_playing = self.state.lock().await.is_playing;
+                self.update_idle_inhibit(is_playing, &aacp_manager).await;
+            }
+            // Players only signal position jumps (Seeked); between them
+            // clients count the position on from its timestamp.
+            if std::mem::take(&mut track_changed) {
+                self.report_now_playing().await;
+            }
+            self.report_playback_status().await;
 
-                let actions = self.state.lock().await.handoff.on_local_play();
-                if actions.is_empty() {
-                    debug!("Playback started but Linux already owns the session, no claim needed");
-                    continue;
+            refresh = tokio::select! {
+                _ = housekeeping.tick() => {
+                    self.finish_call_profile().await;
+                    false
                 }
-                info!("Media playback started, claiming ownership and activating A2DP");
-                self.run_actions(actions, &aacp_manager).await;
-            }
+                event = audio_events.recv() => {
+                    match event {
+                        Ok(AudioEvent::SourceOutputs)
+                        | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                            self.update_call_profile().await;
+                        }
+                        Ok(AudioEvent::Sinks) => self.restore_volume(&aacp_manager).await,
+                        Ok(AudioEvent::SinkChanged) => {
+                            self.remember_volume(&aacp_manager).await;
+                        }
+                        _ => {}
+                    }
+                    false
+                }
+                msg = async {
+                    match signals.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => match msg {
+                    Some(Ok(msg)) => {
+                        track_changed |= is_track_signal(&msg);
+                        is_playback_signal(&msg)
+                    }
+                    Some(Err(_)) => false,
+                    None => {
+                        warn!("MPRIS signal stream ended, playback changes will be missed");
+                        signals = None;
+                        false
+                    }
+                },
+            };
         }
+        self.set_idle_inhibit(false).await;
+        self.restore_default_sink().await;
         self.state.lock().await.playback_listener_running = false;
     }
 
+    /// Put streams back on the output that was default before the AirPods
+    /// took over.
+    async fn restore_default_sink(&self) {
+        let mut state = self.state.lock().await;
+        let Some(previous) = state.previous_default_sink.take() else {
+            return;
+        };
+        if !state.config.restore_default_sink {
+            return;
+        }
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        info!("Restoring default output {}", previous);
+        if audio_cmd_set_default_sink(&audio_tx, &previous).await {
+            audio_cmd_move_all_sink_inputs(&audio_tx, &previous).await;
+        }
+    }
+
+    /// Put back the volume last used with this device when its sink appears.
+    async fn restore_volume(&self, aacp_manager: &AACPManager) {
+        let state = self.state.lock().await;
+        if !state.config.remember_volume || state.connected_device_mac.is_empty() {
+            return;
+        }
+        let mac = state.connected_device_mac.clone();
+        let audio_tx = state.audio_tx.clone();
+        let was_present = state.device_sink_present;
+        drop(state);
+
+        let sink = audio_cmd_get_sink_name_by_mac(&audio_tx, &mac).await;
+        self.state.lock().await.device_sink_present = sink.is_some();
+        let (Some(sink), false) = (sink, was_present) else {
+            return;
+        };
+        if let Some(volume) = aacp_manager.remembered_volume().await {
+            info!("Restoring volume {}% on {}", volume, sink);
+            audio_cmd_transition_volume(&audio_tx, &sink, volume).await;
+        }
+    }
+
+    /// Save the device sink's volume whenever a sink changes.
+    async fn remember_volume(&self, aacp_manager: &AACPManager) {
+        let state = self.state.lock().await;
+        if !state.config.remember_volume || !state.device_sink_present {
+            return;
+        }
+        let mac = state.connected_device_mac.clone();
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        let Some(sink) = audio_cmd_get_sink_name_by_mac(&audio_tx, &mac).await else {
+            return;
+        };
+        if let Some(volume) = audio_cmd_get_sink_volume(&audio_tx, &sink).await {
+            aacp_manager.remember_volume(volume).await;
+        }
+    }
+
+    /// Switch to the headset profile while something records from the mic,
+    /// and back to the A2DP profile we came from once it stops (after
+    /// `CALL_END_GRACE`, see `finish_call_profile`).
+    async fn update_call_profile(&self) {
+        let state = self.state.lock().await;
+        if !state.config.auto_headset_profile || state.connected_device_mac.is_empty() {
+            return;
+        }
+        let in_call = state.call_restore_profile.is_some();
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        let capturing = audio_cmd_is_capture_active(&audio_tx).await;
+        if capturing {
+            self.state.lock().await.call_end_at = None;
+            if !in_call {
+                self.enter_call_profile().await;
+            }
+        } else if in_call {
+            let mut state = self.state.lock().await;
+            if state.call_end_at.is_none() {
+                state.call_end_at = Some(tokio::time::Instant::now() + CALL_END_GRACE);
+            }
+        }
+    }
+
+    /// Leave the call profile once capture has stayed gone past the grace
+    /// period. Run from the housekeeping tick.
+    async fn finish_call_profile(&self) {
+        let mut state = self.state.lock().await;
+        match state.call_end_at {
+            Some(at) if at <= tokio::time::Instant::now() => state.call_end_at = None,
+            _ => return,
+        }
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        if !audio_cmd_is_capture_active(&audio_tx).await {
+            self.exit_call_profile().await;
+        }
+    }
+
+    async fn enter_call_profile(&self) {
+        let state = self.state.lock().await;
+        let (Some(idx), mac) = (state.device_index, state.connected_device_mac.clone()) else {
+            return;
+        };
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        // Only take over a card we're actually playing through
+        let Some(active) = audio_cmd_get_active_profile(&audio_tx, idx).await else {
+            return;
+        };
+        if !active.starts_with("a2dp-sink") {
+            return;
+        }
+        let mut headset = None;
+        for profile in HEADSET_PROFILES {
+            if audio_cmd_is_profile_available(&audio_tx, idx, profile).await {
+                headset = Some(profile);
+                break;
+            }
+        }
+        let Some(headset) = headset else {
+            debug!("Microphone in use but no headset profile available");
+            return;
+        };
+
+        info!("Microphone in use, switching to {}", headset);
+        if !audio_cmd_set_card_profile(&audio_tx, idx, headset).await {
+            warn!("Failed to switch to {}", headset);
+            return;
+        }
+        self.state.lock().await.call_restore_profile = Some(active);
+        self.report_profile(Some(headset)).await;
+        self.reroute_output(&audio_tx, &mac).await;
+        if !audio_cmd_route_input_to_mac(&audio_tx, &mac).await {
+            warn!("Could not find AirPods microphone for {}", mac);
+        }
+    }
+
+    async fn exit_call_profile(&self) {
+        let mut state = self.state.lock().await;
+        let Some(profile) = state.call_restore_profile.take() else {
+            return;
+        };
+        let (Some(idx), mac) = (state.device_index, state.connected_device_mac.clone()) else {
+            return;
+        };
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        info!("Microphone released, returning to {}", profile);
+        if audio_cmd_set_card_profile(&audio_tx, idx, &profile).await {
+            self.report_profile(Some(&profile)).await;
+            self.reroute_output(&audio_tx, &mac).await;
+        } else {
+            warn!("Failed to restore {}", profile);
+        }
+    }
+
+    /// Player property changes, seeks, and MPRIS players appearing or
+    /// vanishing, as one stream. None without a session bus.
+    async fn mpris_signals(&self) -> Option<MprisSignals> {
+        let conn = self.session_conn().await?;
+        let rules = [
+            "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',path='/org/mpris/MediaPlayer2',arg0='org.mpris.MediaPlayer2.Player'",
+            "type='signal',interface='org.mpris.MediaPlayer2.Player',member='Seeked',path='/org/mpris/MediaPlayer2'",
+            "type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged',arg0namespace='org.mpris.MediaPlayer2'",
+        ];
+        let mut streams = Vec::new();
+        for rule in rules {
+            match zbus::MessageStream::for_match_rule(rule, &conn, None).await {
+                Ok(stream) => streams.push(stream),
+                Err(e) => {
+                    error!("Failed to subscribe to MPRIS signals: {}", e);
+                    return None;
+                }
+            }
+        }
+        Some(Box::pin(futures::stream::select_all(streams)))
+    }
+
+    /// Re-read playback state after an MPRIS signal and claim the session
+    /// when playback just started with the buds in.
+    async fn refresh_playback(&self, aacp_manager: &AACPManager) {
+        let is_playing = self.check_if_playing_async().await;
+
+        let mut state = self.state.lock().await;
+        let was_playing = state.is_playing;
+        state.is_playing = is_playing;
+        if is_playing {
+            // Playing again by other means; nothing is waiting on the buds
+            state.paused_by_app_services.clear();
+        }
+        drop(state);
+
+        self.update_idle_inhibit(is_playing, aacp_manager).await;
+
+        if was_playing || !is_playing {
+            return;
+        }
+        let ear_ok = {
+            let aacp_state = aacp_manager.state.lock().await;
+            aacp_state.ear_detection_left == Some(EarDetectionStatus::InEar)
+                || aacp_state.ear_detection_right == Some(EarDetectionStatus::InEar)
+        }; // ← aacp_state dropped; safe to re-enter aacp_manager below
+
+        if !ear_ok {
+            info!("Media playback started but buds not in ear, skipping takeover");
+            return;
+        }
+
+        let actions = self.state.lock().await.handoff.on_local_play();
+        if actions.is_empty() {
+            debug!("Playback started but Linux already owns the session, no claim needed");
+            return;
+        }
+        info!("Media playback started, claiming ownership and activating A2DP");
+        self.run_actions(actions, aacp_manager).await;
+    }
+
+    async fn update_idle_inhibit(&self, is_playing: bool, aacp_manager: &AACPManager) {
+        if !self.state.lock().await.config.idle_inhibit {
+            return;
+        }
+        let inhibit = {
+            let aacp_state = aacp_manager.state.lock().await;
+            wants_idle_inhibit(
+                is_playing,
+                aacp_state.ear_detection_left,
+                aacp_state.ear_detection_right,
+            )
+        };
+        self.set_idle_inhibit(inhibit).await;
+    }
+
+    /// Take or release the screensaver inhibit. The session bus drops it on
+    /// its own if we exit without releasing.
+    async fn set_idle_inhibit(&self, inhibit: bool) {
+        let cookie = self.state.lock().await.idle_inhibit_cookie;
+        if inhibit == cookie.is_some() {
+            return;
+        }
+        let Some(conn) = self.session_conn().await else {
+            return;
+        };
+        let proxy = match zbus::Proxy::new(
+            &conn,
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+        )
+        .await
+        {
+            Ok(p) => p,
+            Err(e) => {
+                warn!("Failed to reach org.freedesktop.ScreenSaver: {}", e);
+                return;
+            }
+        };
+        match cookie {
+            None => match proxy
+                .call::<_, _, u32>("Inhibit", &("airpods-tui", "Listening on AirPods"))
+                .await
+            {
+                Ok(cookie) => {
+                    info!("Inhibiting idle while AirPods are in use");
+                    self.state.lock().await.idle_inhibit_cookie = Some(cookie);
+                }
+                Err(e) => warn!("Failed to inhibit idle: {}", e),
+            },
+            Some(cookie) => {
+                if let Err(e) = proxy.call::<_, _, ()>("UnInhibit", &(cookie,)).await {
+                    warn!("Failed to release idle inhibit: {}", e);
+                }
+                info!("Released idle inhibit");
+                self.state.lock().await.idle_inhibit_cookie = None;
+            }
+        }
+    }
+
     /// Execute the side effects the handoff FSM asked for, in order.
     /// Boxed because the reclaim timer it spawns calls back into it.
     fn run_actions<'a>(
@@ -850,6 +1751,7 @@ impl MediaController {
         aacp: &'a AACPManager,
     ) -> futures::future::BoxFuture<'a, ()> {
         Box::pin(async move {
+            let mut claimed = false;
             for action in actions {
                 match action {
                     Action::PauseTracked => self.pause().await,
@@ -869,6 +1771,7 @@ impl MediaController {
                         {
                             error!("Failed to send OwnsConnection={:02x}: {}", byte, e);
                         }
+                        claimed = action == Action::ClaimOwnership;
                     }
                     Action::ScheduleReclaim { generation } => {
                         info!(
@@ -896,14 +1799,68 @@ impl MediaController {
                     // steal. We deliberately do NOT Play the previously paused
                     // MPRIS players: that would feed the listener loop a Playing
                     // transition that cascades against the peer device.
-                    Action::RestartAudioStream => self.force_audio_stream_restart().await,
-                    Action::ActivateA2dp => self.activate_a2dp_profile().await,
+                    Action::RestartAudioStream | Action::ActivateA2dp => {
+                        self.start_stream(action).await;
+                        if claimed {
+                            let mc = self.clone();
+                            let aacp = aacp.clone();
+                            tokio::spawn(async move { mc.confirm_transport(action, &aacp).await });
+                        }
+                    }
+                    Action::ResumeTracked => {
+                        info!("Peer call ended, resuming local media");
+                        self.resume_tracked().await;
+                    }
                     Action::DeactivateA2dp => self.deactivate_a2dp_profile().await,
                 }
             }
         })
     }
 
+    async fn start_stream(&self, action: Action) {
+        if action == Action::RestartAudioStream {
+            self.force_audio_stream_restart().await;
+        } else {
+            self.activate_a2dp_profile().await;
+        }
+    }
+
+    /// After a claim, wait for the A2DP transport to leave idle. If it
+    /// doesn't, re-send the claim and redo `action`, as long as we still
+    /// hold the session.
+    async fn confirm_transport(&self, action: Action, aacp: &AACPManager) {
+        let mac = self.state.lock().await.connected_device_mac.clone();
+        if mac.is_empty() {
+            return;
+        }
+        for attempt in 1..=TRANSPORT_RETRIES + 1 {
+            if wait_for_transport(&mac, TRANSPORT_READY_TIMEOUT).await {
+                debug!("Transport for {} is up after claim", mac);
+                return;
+            }
+            let state = self.state.lock().await;
+            if state.handoff.state() != Ownership::Linux || state.call_restore_profile.is_some() {
+                return;
+            }
+            drop(state);
+            if attempt > TRANSPORT_RETRIES {
+                break;
+            }
+            warn!(
+                "Transport for {} still idle after claim, retrying ({}/{})",
+                mac, attempt, TRANSPORT_RETRIES
+            );
+            if let Err(e) = aacp
+                .send_control_command(ControlCommandIdentifiers::OwnsConnection, &[0x01])
+                .await
+            {
+                error!("Failed to re-send OwnsConnection=01: {}", e);
+            }
+            self.start_stream(action).await;
+        }
+        warn!("Transport for {} never came up after claiming", mac);
+    }
+
     /// OwnsConnection report from the device (01 = we own the session).
     pub async fn handle_owns_report(&self, owns: bool, aacp: &AACPManager) {
         let (actions, state_after) = {
@@ -930,7 +1887,8 @@ impl MediaController {
         service.starts_with("org.mpris.MediaPlayer2.kdeconnect.mpris_")
     }
 
-    /// All MPRIS player proxies on the session bus (kdeconnect ones excluded).
+    /// All MPRIS player proxies on the session bus, minus kdeconnect and
+    /// `mpris_ignore`, ordered by `mpris_priority`.
     async fn mpris_players(&self) -> Vec<(String, zbus::Proxy<'static>)> {
         let Some(conn) = self.session_conn().await else {
             return Vec::new();
@@ -941,11 +1899,19 @@ impl MediaController {
         let Ok(names) = dbus.list_names().await else {
             return Vec::new();
         };
+        let (ignore, priority) = {
+            let state = self.state.lock().await;
+            (
+                state.config.mpris_ignore.clone(),
+                state.config.mpris_priority.clone(),
+            )
+        };
         let mut players = Vec::new();
         for name in names {
             let service = name.as_str().to_string();
-            if !service.starts_with("org.mpris.MediaPlayer2.")
+            if !service.starts_with(MPRIS_PREFIX)
                 || Self::is_kdeconnect_service(&service)
+                || ignore.iter().any(|i| player_matches(&service, i))
             {
                 continue;
             }
@@ -960,6 +1926,8 @@ impl MediaController {
                 players.push((service, p));
             }
         }
+        // Stable, so unlisted players keep bus order
+        players.sort_by_key(|(service, _)| priority_rank(service, &priority));
         players
     }
 
@@ -972,6 +1940,75 @@ impl MediaController {
         false
     }
 
+    /// The track on the first playing (else paused) player, in
+    /// `mpris_priority` order.
+    async fn now_playing(&self) -> Option<NowPlaying> {
+        let mut paused = None;
+        for (_, p) in self.mpris_players().await {
+            let playing = match p.get_property::<String>("PlaybackStatus").await.as_deref() {
+                Ok("Playing") => true,
+                Ok("Paused") if paused.is_none() => false,
+                _ => continue,
+            };
+            let Ok(metadata) = p
+                .get_property::<HashMap<String, zbus::zvariant::OwnedValue>>("Metadata")
+                .await
+            else {
+                continue;
+            };
+            let position = p.get_property::<i64>("Position").await.unwrap_or(0);
+            let read_at = crate::utils::unix_now_ms();
+            let Some(track) = track_from_metadata(&metadata, playing, position, read_at) else {
+                continue;
+            };
+            if playing {
+                return Some(track);
+            }
+            paused = Some(track);
+        }
+        paused
+    }
+
+    /// Send AppEvent::NowPlaying when the track, its status, or its
+    /// position (a seek) changed.
+    async fn report_now_playing(&self) {
+        if self.state.lock().await.app_tx.is_none() {
+            return;
+        }
+        let track = self.now_playing().await;
+        let mut state = self.state.lock().await;
+        if same_now_playing(state.now_playing.as_ref(), track.as_ref()) {
+            return;
+        }
+        state.now_playing = track.clone();
+        if let Some(tx) = &state.app_tx {
+            let _ = tx.send(crate::tui::app::AppEvent::NowPlaying {
+                mac: state.connected_device_mac.clone(),
+                track,
+            });
+        }
+    }
+
+    /// Send AppEvent::Playback when the status changed.
+    async fn report_playback_status(&self) {
+        let mut state = self.state.lock().await;
+        let status = playback_status(
+            state.handoff.state(),
+            state.is_playing,
+            !state.paused_by_app_services.is_empty(),
+        );
+        if state.playback_status == status {
+            return;
+        }
+        state.playback_status = status;
+        if let Some(tx) = &state.app_tx {
+            let _ = tx.send(crate::tui::app::AppEvent::Playback {
+                mac: state.connected_device_mac.clone(),
+                status,
+            });
+        }
+    }
+
     async fn is_playing(p: &zbus::Proxy<'_>) -> bool {
         matches!(
             p.get_property::<String>("PlaybackStatus").await.as_deref(),
@@ -980,12 +2017,17 @@ impl MediaController {
     }
 
     /// Pause every playing MPRIS player; returns the services actually paused.
-    async fn pause_playing_players(&self) -> Vec<String> {
+    /// `exceptions` are `auto_pause_exceptions` entries to leave playing.
+    async fn pause_playing_players(&self, exceptions: &[String]) -> Vec<String> {
         let mut paused = Vec::new();
         for (service, p) in self.mpris_players().await {
             if !Self::is_playing(&p).await {
                 continue;
             }
+            if self.is_pause_exception(&service, exceptions).await {
+                info!("Leaving {} playing (auto_pause_exceptions)", service);
+                continue;
+            }
             if p.call_noreply("Pause", &()).await.is_ok() {
                 info!("Paused playback for: {}", service);
                 paused.push(service);
@@ -996,6 +2038,34 @@ impl MediaController {
         paused
     }
 
+    /// Whether `service` matches an `auto_pause_exceptions` entry by bus
+    /// name or by its MPRIS Identity.
+    async fn is_pause_exception(&self, service: &str, exceptions: &[String]) -> bool {
+        if exceptions.is_empty() {
+            return false;
+        }
+        if exceptions.iter().any(|e| player_matches(service, e)) {
+            return true;
+        }
+        let Some(conn) = self.session_conn().await else {
+            return false;
+        };
+        let Ok(root) = zbus::Proxy::new(
+            &conn,
+            service.to_string(),
+            "/org/mpris/MediaPlayer2",
+            "org.mpris.MediaPlayer2",
+        )
+        .await
+        else {
+            return false;
+        };
+        match root.get_property::<String>("Identity").await {
+            Ok(identity) => exceptions.iter().any(|e| e.eq_ignore_ascii_case(&identity)),
+            Err(_) => false,
+        }
+    }
+
     pub async fn handle_ear_detection(
         &self,
         old_left: Option<EarDetectionStatus>,
@@ -1033,7 +2103,14 @@ impl MediaController {
             in_ear, old_all_out, new_has_at_least_one_in, new_all_out
         );
 
-        if new_has_at_least_one_in && old_all_out {
+        // Removal still pauses while locked; only the audio-starting paths
+        // below are suppressed.
+        let locked = crate::session_lock::is_locked();
+        let policy = self.state.lock().await.config.ear_pause;
+
+        if new_has_at_least_one_in && old_all_out && locked {
+            info!("Buds inserted while session is locked, not activating A2DP");
+        } else if new_has_at_least_one_in && old_all_out {
             debug!("Condition met: buds inserted, activating A2DP");
             self.activate_a2dp_profile().await;
         } else if new_all_out && !old_all_out {
@@ -1041,8 +2118,10 @@ impl MediaController {
             // both buds are already out (e.g. AirPods echo redundant ear state)
             // would re-deactivate A2DP repeatedly, forcing wireplumber to
             // renegotiate the bluez profile and producing audible glitches.
-            debug!("Condition met: ear-out transition, pausing media");
-            self.pause().await;
+            debug!("Condition met: ear-out transition, deactivating A2DP");
+            if should_pause_on_removal(policy, true) {
+                self.pause().await;
+            }
             self.deactivate_a2dp_profile().await;
         }
 
@@ -1057,15 +2136,19 @@ impl MediaController {
         new_sorted.sort();
         if new_sorted != old_sorted {
             debug!("Ear data changed, checking resume/pause logic");
-            if in_ear {
-                debug!("Resuming media as buds are in ear");
-                self.resume().await;
-            } else if !old_all_out {
-                debug!("Pausing media as buds are not fully in ear");
-                self.pause().await;
-            } else {
-                debug!("Playing media");
-                self.resume().await;
+            match ear_playback_change(locked, in_ear, old_all_out, new_all_out, policy) {
+                Some(true) => {
+                    debug!("Resuming media as buds are in ear");
+                    self.resume().await;
+                }
+                Some(false) => {
+                    debug!("Pausing media as buds are not fully in ear");
+                    self.pause().await;
+                }
+                None => debug!(
+                    "Leaving playback alone (locked={}, {:?} policy)",
+                    locked, policy
+                ),
             }
         }
     }
@@ -1079,62 +2162,62 @@ impl MediaController {
             return;
         }
 
+        // A call holds the headset profile; A2DP comes back when it ends
+        if state.call_restore_profile.is_some() {
+            debug!("Call in progress, keeping headset profile");
+            return;
+        }
+
         let device_index = state.device_index;
         let mac = state.connected_device_mac.clone();
         let audio_tx = state.audio_tx.clone();
         drop(state);
 
-        let mut current_device_index = device_index;
-
-        if current_device_index.is_none() {
-            debug!("Device index not found, polling for it.");
-            // The PulseAudio card registers a few seconds after the BT
-            // connect that triggered us; poll instead of giving up.
-            for attempt in 0..8 {
-                if attempt > 0 {
-                    tokio::time::sleep(Duration::from_millis(750)).await;
-                }
-                current_device_index = audio_cmd_get_device_index(&audio_tx, &mac).await;
-                if current_device_index.is_some() {
-                    break;
-                }
-            }
-            if let Some(idx) = current_device_index {
-                let mut state = self.state.lock().await;
-                state.device_index = Some(idx);
-            } else {
-                warn!(
-                    "No PulseAudio card appeared for {}. Cannot activate A2DP profile.",
-                    mac
-                );
-                return;
+        let idx = match device_index {
+            Some(idx) => idx,
+            None => {
+                debug!("Device index not found, waiting for the card.");
+                // The PulseAudio card registers a few seconds after the BT
+                // connect that triggered us.
+                let found = wait_for_audio(CARD_APPEAR_TIMEOUT, || {
+                    audio_cmd_get_device_index(&audio_tx, &mac)
+                })
+                .await;
+                let Some(idx) = found else {
+                    warn!(
+                        "No PulseAudio card appeared for {}. Cannot activate A2DP profile.",
+                        mac
+                    );
+                    return;
+                };
+                self.state.lock().await.device_index = Some(idx);
+                idx
             }
-        }
+        };
 
-        let idx = current_device_index.unwrap();
+        let before = audio_cmd_get_active_profile(&audio_tx, idx).await;
+        if !before
+            .as_deref()
+            .is_some_and(|p| p.starts_with("a2dp-sink"))
+        {
+            self.state.lock().await.pre_takeover_profile = before.filter(|p| p != "off");
+        }
 
         if !audio_cmd_is_a2dp(&audio_tx, idx).await {
             warn!("A2DP profile not available, attempting to restart audio server");
             if self.restart_wire_plumber().await {
+                // The card is re-created, usually under a new index; go on
+                // as soon as it shows up with an A2DP profile.
+                let new_idx = wait_for_audio(AUDIO_RESTART_TIMEOUT, || async {
+                    let idx = audio_cmd_get_device_index(&audio_tx, &mac).await?;
+                    audio_cmd_is_a2dp(&audio_tx, idx).await.then_some(idx)
+                })
+                .await;
                 let mut state = self.state.lock().await;
-                state.device_index =
-                    audio_cmd_get_device_index(&state.audio_tx, &state.connected_device_mac).await;
-                let new_idx = state.device_index;
-                let audio_tx = state.audio_tx.clone();
+                state.device_index = new_idx;
                 drop(state);
-                if let Some(new_idx) = new_idx {
-                    // Retry loop: wait for A2DP profile to appear after audio server restart
-                    let mut retries = 3;
-                    while retries > 0 && !audio_cmd_is_a2dp(&audio_tx, new_idx).await {
-                        tokio::time::sleep(Duration::from_millis(800)).await;
-                        retries -= 1;
-                    }
-                    if retries == 0 && !audio_cmd_is_a2dp(&audio_tx, new_idx).await {
-                        error!("A2DP profile still not available after audio server restart");
-                        return;
-                    }
-                } else {
-                    error!("Could not get device index after audio server restart");
+                if new_idx.is_none() {
+                    error!("A2DP profile still not available after audio server restart");
                     return;
                 }
             } else {
@@ -1159,29 +2242,8 @@ impl MediaController {
             let ok = audio_cmd_set_card_profile(&audio_tx, idx, &preferred_profile).await;
             if ok {
                 info!("Successfully activated A2DP profile: {}", preferred_profile);
-                // The sink appears shortly after the profile switch; poll
-                // briefly so rerouting doesn't miss it.
-                let mut sink_name = None;
-                for attempt in 0..5 {
-                    if attempt > 0 {
-                        tokio::time::sleep(Duration::from_millis(750)).await;
-                    }
-                    sink_name = audio_cmd_get_sink_name_by_mac(&audio_tx, &mac).await;
-                    if sink_name.is_some() {
-                        break;
-                    }
-                }
-                if let Some(sink_name) = sink_name {
-                    audio_cmd_set_default_sink(&audio_tx, &sink_name).await;
-                    audio_cmd_move_all_sink_inputs(&audio_tx, &sink_name).await;
-                    // PipeWire persists a sink's mute flag across sessions; a
-                    // sink muted weeks ago comes back muted and the AirPods
-                    // look broken. Routing audio here means we want it heard.
-                    audio_cmd_set_sink_mute(&audio_tx, &sink_name, false).await;
-                    info!("Rerouted audio output to {}", sink_name);
-                } else {
-                    warn!("Could not find sink for MAC {} to reroute audio", mac);
-                }
+                self.report_profile(Some(&preferred_profile)).await;
+                self.reroute_output(&audio_tx, &mac).await;
             } else {
                 warn!("Failed to activate A2DP profile: {}", preferred_profile);
             }
@@ -1190,9 +2252,41 @@ impl MediaController {
         }
     }
 
+    /// Make the device's sink the default output and move every stream onto it.
+    async fn reroute_output(&self, audio_tx: &AudioTx, mac: &str) {
+        if !self.state.lock().await.config.switch_default_sink {
+            debug!("switch_default_sink is off, leaving audio routing alone");
+            return;
+        }
+        // The sink appears shortly after a profile switch
+        let sink_name = wait_for_audio(SINK_APPEAR_TIMEOUT, || {
+            audio_cmd_get_sink_name_by_mac(audio_tx, mac)
+        })
+        .await;
+        if let Some(sink_name) = sink_name {
+            let previous = audio_cmd_get_default_sink(audio_tx).await;
+            {
+                let mut state = self.state.lock().await;
+                if state.previous_default_sink.is_none() {
+                    state.previous_default_sink = previous.filter(|p| *p != sink_name);
+                }
+            }
+            audio_cmd_set_default_sink(audio_tx, &sink_name).await;
+            audio_cmd_move_all_sink_inputs(audio_tx, &sink_name).await;
+            // PipeWire persists a sink's mute flag across sessions; a sink muted
+            // weeks ago comes back muted and the AirPods look broken. Routing
+            // audio here means we want it heard.
+            audio_cmd_set_sink_mute(audio_tx, &sink_name, false).await;
+            info!("Rerouted audio output to {}", sink_name);
+        } else {
+            warn!("Could not find sink for MAC {} to reroute audio", mac);
+        }
+    }
+
     async fn pause(&self) {
         debug!("Pausing playback");
-        let paused = self.pause_playing_players().await;
+        let exceptions = self.state.lock().await.config.auto_pause_exceptions.clone();
+        let paused = self.pause_playing_players(&exceptions).await;
         if paused.is_empty() {
             info!("No playing media players found to pause");
             return;
@@ -1212,6 +2306,42 @@ impl MediaController {
         }
     }
 
+    /// Run the `[stem]` action configured for this press.
+    pub async fn handle_stem_press(&self, press: StemPressType) {
+        let stem = self.state.lock().await.config.stem;
+        if !stem.enabled {
+            info!(
+                "Stem {:?} press - handling disabled, leaving it to AVRCP",
+                press
+            );
+            return;
+        }
+        let action = match press {
+            StemPressType::Single => stem.single,
+            StemPressType::Double => stem.double,
+            StemPressType::Triple => stem.triple,
+            StemPressType::Long => stem.long,
+        };
+        info!("Stem {:?} press - {:?}", press, action);
+        match action {
+            StemAction::None => {}
+            StemAction::PlayPause => self.toggle_play_pause().await,
+            StemAction::Next => self.next_track().await,
+            StemAction::Previous => self.previous_track().await,
+            StemAction::SkipForward => self.seek(STEM_SKIP_US).await,
+            StemAction::SkipBack => self.seek(-STEM_SKIP_US).await,
+            StemAction::MicMute => {
+                let (mac, app_tx) = {
+                    let state = self.state.lock().await;
+                    (state.connected_device_mac.clone(), state.app_tx.clone())
+                };
+                if let Err(e) = toggle_mic_mute(&mac, app_tx.as_ref()).await {
+                    info!("{}", e);
+                }
+            }
+        }
+    }
+
     pub async fn toggle_play_pause(&self) {
         debug!("Toggling play/pause via MPRIS");
         self.mpris_call_first("PlayPause").await;
@@ -1227,10 +2357,21 @@ impl MediaController {
         self.mpris_call_first("Previous").await;
     }
 
+    /// Move the first player's position by `offset_us` microseconds.
+    pub async fn seek(&self, offset_us: i64) {
+        debug!("Seek {}us via MPRIS", offset_us);
+        for (service, p) in self.mpris_players().await {
+            if p.call_noreply("Seek", &(offset_us,)).await.is_ok() {
+                info!("Seek {}s for: {}", offset_us / 1_000_000, service);
+                break;
+            }
+        }
+    }
+
     /// Pause everything without tracking the players for a later resume.
     pub async fn pause_all_media(&self) {
         debug!("Pausing all media (without tracking for resume)");
-        let paused = self.pause_playing_players().await;
+        let paused = self.pause_playing_players(&[]).await;
         if !paused.is_empty() {
             info!(
                 "Paused {} media player(s) due to ownership loss",
@@ -1269,9 +2410,17 @@ impl MediaController {
             let is_local = source.mac.eq_ignore_ascii_case(&state.local_mac);
             let is_none = source.r#type == AudioSourceType::None;
             let linux_has_audio = state.is_playing || pa_active;
-            let actions = state
-                .handoff
-                .on_audio_source(is_local, is_none, linux_has_audio);
+            let actions = if !is_local && source.r#type == AudioSourceType::Call {
+                info!(
+                    "Phone call on {}, handing over the audio session",
+                    source.mac
+                );
+                state.handoff.on_peer_call(linux_has_audio)
+            } else {
+                state
+                    .handoff
+                    .on_audio_source(is_local, is_none, linux_has_audio)
+            };
             (actions, state.handoff.state())
         }; // ← state lock released before any await
 
@@ -1306,7 +2455,7 @@ impl MediaController {
             sink_name
         );
         if !audio_cmd_suspend_sink(&audio_tx, &sink_name, true).await {
-            warn!("PulseAudio suspend failed, falling back to profile cycle");
+            warn!("Sink suspend failed, falling back to profile cycle");
             self.activate_a2dp_profile().await;
             return;
         }
@@ -1314,15 +2463,36 @@ impl MediaController {
         tokio::time::sleep(Duration::from_millis(200)).await;
 
         if !audio_cmd_suspend_sink(&audio_tx, &sink_name, false).await {
-            warn!("PulseAudio resume failed, falling back to profile cycle");
+            warn!("Sink resume failed, falling back to profile cycle");
             self.activate_a2dp_profile().await;
+            return;
         }
+
+        // Streams that started while the peer held the buds went to another
+        // output; bring them over now that we own the session again.
+        self.reroute_output(&audio_tx, &mac).await;
     }
 
     async fn resume(&self) {
         debug!("Resuming playback");
+        let state = self.state.lock().await;
+        let nothing_paused = state.paused_by_app_services.is_empty();
+        let play_on_wear = state.config.play_on_wear;
+        let exceptions = state.config.auto_pause_exceptions.clone();
+        drop(state);
+
+        if starts_player_on_wear(play_on_wear, nothing_paused) {
+            self.play_on_wear(&exceptions).await;
+        } else {
+            self.resume_tracked().await;
+        }
+    }
+
+    /// Play the players `pause` paused, minus `auto_pause_exceptions`.
+    async fn resume_tracked(&self) {
         let state = self.state.lock().await;
         let services = state.paused_by_app_services.clone();
+        let exceptions = state.config.auto_pause_exceptions.clone();
         drop(state);
 
         if services.is_empty() {
@@ -1335,7 +2505,9 @@ impl MediaController {
         };
         let mut resumed_count = 0;
         for service in &services {
-            if Self::is_kdeconnect_service(service) {
+            if Self::is_kdeconnect_service(service)
+                || self.is_pause_exception(service, &exceptions).await
+            {
                 continue;
             }
             if let Ok(p) = zbus::Proxy::new(
@@ -1364,6 +2536,23 @@ impl MediaController {
         }
     }
 
+    /// Start the first eligible player when the buds go in and we had
+    /// nothing paused (`play_on_wear`).
+    async fn play_on_wear(&self, exceptions: &[String]) {
+        for (service, p) in self.mpris_players().await {
+            if Self::is_kdeconnect_service(&service)
+                || self.is_pause_exception(&service, exceptions).await
+            {
+                continue;
+            }
+            if p.call_noreply("Play", &()).await.is_ok() {
+                info!("Started playback on wear for: {}", service);
+                return;
+            }
+        }
+        info!("No media player to start on wear");
+    }
+
     async fn get_preferred_a2dp_profile(&self) -> String {
         let state = self.state.lock().await;
         let device_index = state.device_index;
@@ -1382,18 +2571,29 @@ impl MediaController {
             return cached_profile;
         }
 
-        let profiles_to_check = ["a2dp-sink-sbc_xq", "a2dp-sink-sbc", "a2dp-sink"];
-        for profile in profiles_to_check {
-            if audio_cmd_is_profile_available(&audio_tx, index, profile).await {
+        let codecs = self.state.lock().await.config.a2dp_codecs.clone();
+        for profile in a2dp_profile_candidates(&codecs) {
+            if audio_cmd_is_profile_available(&audio_tx, index, &profile).await {
                 info!("Selected best available A2DP profile: {}", profile);
                 let mut state = self.state.lock().await;
-                state.cached_a2dp_profile = profile.to_string();
-                return profile.to_string();
+                state.cached_a2dp_profile = profile.clone();
+                return profile;
             }
         }
         String::new()
     }
 
+    /// Tell the UI which profile (and so codec) the card is on now.
+    async fn report_profile(&self, profile: Option<&str>) {
+        let state = self.state.lock().await;
+        if let Some(tx) = &state.app_tx {
+            let _ = tx.send(crate::tui::app::AppEvent::AudioProfile {
+                mac: state.connected_device_mac.clone(),
+                profile: profile.map(str::to_string),
+            });
+        }
+    }
+
     async fn restart_wire_plumber(&self) -> bool {
         debug!("Entering restart_wire_plumber");
         let state = self.state.lock().await;
@@ -1416,7 +2616,6 @@ impl MediaController {
         match result {
             Ok(output) if output.status.success() => {
                 info!("Audio server restarted successfully");
-                tokio::time::sleep(Duration::from_secs(2)).await;
                 true
             }
             _ => {
@@ -1436,23 +2635,68 @@ impl MediaController {
             state.device_index = audio_cmd_get_device_index(&audio_tx, &mac).await;
         }
 
-        if state.connected_device_mac.is_empty() || state.device_index.is_none() {
+        let Some(device_index) = state
+            .device_index
+            .filter(|_| !state.connected_device_mac.is_empty())
+        else {
             warn!("Connected device MAC or index is empty, cannot deactivate A2DP profile");
             return;
-        }
-        let device_index = state.device_index.unwrap();
+        };
         let audio_tx = state.audio_tx.clone();
+        state.call_restore_profile = None;
+        let before_takeover = state.pre_takeover_profile.take();
+        let target = release_profile(
+            state.config.release_profile.as_deref(),
+            before_takeover.as_deref(),
+        );
         drop(state);
 
-        info!("Deactivating A2DP profile for AirPods by setting to off");
-        let ok = audio_cmd_set_card_profile(&audio_tx, device_index, "off").await;
+        info!(
+            "Deactivating A2DP profile for AirPods by setting to {}",
+            target
+        );
+        let ok = audio_cmd_set_card_profile(&audio_tx, device_index, &target).await;
         if ok {
             info!("Successfully deactivated A2DP profile");
+            self.restore_default_sink().await;
+            self.report_profile(Some(target.as_str()).filter(|p| *p != "off"))
+                .await;
         } else {
             warn!("Failed to deactivate A2DP profile");
         }
     }
 
+    /// Daemon shutdown: hand the session back if we hold it, and undo what
+    /// we changed on the audio side (the profile the card was on before we
+    /// took it over, or before a call, and the default sink). A card we
+    /// never switched is left playing as it is.
+    pub async fn release_for_shutdown(&self, aacp: &AACPManager) {
+        let mut state = self.state.lock().await;
+        let owned = state.handoff.state() == Ownership::Linux;
+        let before_takeover = state.pre_takeover_profile.take();
+        let restore = before_takeover.or(state.call_restore_profile.take());
+        let device_index = state.device_index;
+        let audio_tx = state.audio_tx.clone();
+        drop(state);
+
+        if owned {
+            info!("Handing the AirPods session back");
+            if let Err(e) = aacp
+                .send_control_command(ControlCommandIdentifiers::OwnsConnection, &[0x00])
+                .await
+            {
+                warn!("Failed to send OwnsConnection=00: {}", e);
+            }
+        }
+        if let (Some(profile), Some(idx)) = (restore, device_index) {
+            info!("Restoring card profile {}", profile);
+            if !audio_cmd_set_card_profile(&audio_tx, idx, &profile).await {
+                warn!("Failed to restore {}", profile);
+            }
+        }
+        self.restore_default_sink().await;
+    }
+
     pub async fn handle_conversational_awareness(&self, status: u8) {
         debug!(
             "Entering handle_conversational_awareness with status: {}",
@@ -1569,10 +2813,667 @@ impl MediaController {
     }
 }
 
+/// Mute or unmute the AirPods microphone and report it to the UI. Only the
+/// headset profile has a microphone; otherwise this just logs.
+pub async fn toggle_mic_mute(
+    mac: &str,
+    app_tx: Option<&tokio::sync::mpsc::UnboundedSender<crate::tui::app::AppEvent>>,
+) -> crate::error::Result<bool> {
+    let audio_tx = shared_audio_thread(app_tx.cloned());
+    let muted = audio_cmd_toggle_source_mute(&audio_tx, mac)
+        .await
+        .ok_or_else(|| {
+            crate::error::Error::Audio(
+                "no AirPods microphone active (headset profile not in use)".into(),
+            )
+        })?;
+    info!(
+        "AirPods microphone {}",
+        if muted { "muted" } else { "unmuted" }
+    );
+    if let Some(tx) = app_tx {
+        let _ = tx.send(crate::tui::app::AppEvent::MicMuted {
+            mac: mac.to_string(),
+            muted,
+        });
+    }
+    Ok(muted)
+}
+
+/// Set the volume of `mac`'s own sink to `change(current)` percent,
+/// leaving the default sink alone. Returns the new level.
+pub async fn change_sink_volume(
+    mac: &str,
+    change: impl FnOnce(u32) -> u32,
+) -> crate::error::Result<u32> {
+    let audio_tx = shared_audio_thread(None);
+    let sink = audio_cmd_get_sink_name_by_mac(&audio_tx, mac)
+        .await
+        .ok_or_else(|| crate::error::Error::Audio(format!("no audio sink for {}", mac)))?;
+    let current = audio_cmd_get_sink_volume(&audio_tx, &sink)
+        .await
+        .ok_or_else(|| crate::error::Error::Audio(format!("can't read volume of {}", sink)))?;
+    let target = change(current);
+    if !audio_cmd_transition_volume(&audio_tx, &sink, target).await {
+        return Err(crate::error::Error::Audio(format!(
+            "can't set volume of {}",
+            sink
+        )));
+    }
+    info!("Volume of {} set to {}%", sink, target);
+    Ok(target)
+}
+
+/// Header status: a peer holding the session wins, then local playback,
+/// then players we paused and will resume.
+fn playback_status(ownership: Ownership, is_playing: bool, paused_by_us: bool) -> PlaybackStatus {
+    match ownership {
+        Ownership::Peer { .. } | Ownership::ReclaimPending { .. } => PlaybackStatus::PeerOwned,
+        _ if is_playing => PlaybackStatus::Playing,
+        _ if paused_by_us => PlaybackStatus::PausedByEar,
+        _ => PlaybackStatus::Idle,
+    }
+}
+
+/// Whether taking a bud out pauses under `policy`; `all_out` is true once
+/// neither bud is in.
+fn should_pause_on_removal(policy: EarPausePolicy, all_out: bool) -> bool {
+    match policy {
+        EarPausePolicy::AnyOut => true,
+        EarPausePolicy::BothOut => all_out,
+        EarPausePolicy::Never => false,
+    }
+}
+
+/// Whether putting the buds in starts a player (`play_on_wear`) instead of
+/// resuming the ones removal paused; those always take precedence.
+fn starts_player_on_wear(play_on_wear: bool, nothing_paused: bool) -> bool {
+    play_on_wear && nothing_paused
+}
+
+/// What an ear-state change does to playback: Some(true) resumes,
+/// Some(false) pauses. Removal still pauses while the session is locked,
+/// but nothing resumes behind the lock screen.
+fn ear_playback_change(
+    locked: bool,
+    in_ear: bool,
+    old_all_out: bool,
+    new_all_out: bool,
+    policy: EarPausePolicy,
+) -> Option<bool> {
+    if locked && (in_ear || old_all_out) {
+        None
+    } else if in_ear || old_all_out {
+        Some(true)
+    } else {
+        should_pause_on_removal(policy, new_all_out).then_some(false)
+    }
+}
+
+/// Profile to leave the card on when giving up A2DP: the configured
+/// `release_profile`, else whatever it was on before takeover, else "off".
+fn release_profile(configured: Option<&str>, before_takeover: Option<&str>) -> String {
+    configured.or(before_takeover).unwrap_or("off").to_string()
+}
+
+/// Card profiles to try for the `a2dp_codecs` preference, then the plain
+/// profile (server-chosen codec) as a last resort.
+fn a2dp_profile_candidates(codecs: &[String]) -> Vec<String> {
+    codecs
+        .iter()
+        .map(|c| format!("a2dp-sink-{}", c.to_ascii_lowercase()))
+        .chain(std::iter::once("a2dp-sink".to_string()))
+        .collect()
+}
+
+/// Human-readable codec for a card profile, e.g. "AAC" for
+/// "a2dp-sink-aac"; None for "off".
+pub fn codec_label(profile: &str) -> Option<String> {
+    if let Some(codec) = profile.strip_prefix("a2dp-sink-") {
+        return Some(codec.to_ascii_uppercase().replace('_', "-"));
+    }
+    match profile {
+        "a2dp-sink" => Some("A2DP".into()),
+        "headset-head-unit-msbc" => Some("mSBC (call)".into()),
+        p if p.starts_with("headset-head-unit") || p == "handsfree_head_unit" => {
+            Some("CVSD (call)".into())
+        }
+        _ => None,
+    }
+}
+
+/// Run `probe` now and again on every card or sink change until it returns
+/// Some, giving up after `timeout` with one last try.
+async fn wait_for_audio<T, F, Fut>(timeout: Duration, mut probe: F) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Option<T>>,
+{
+    // Subscribe before probing so a change in between isn't missed
+    let mut events = AUDIO_EVENTS.subscribe();
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Some(found) = probe().await {
+            return Some(found);
+        }
+        loop {
+            match tokio::time::timeout_at(deadline, events.recv()).await {
+                Err(_) => return probe().await,
+                Ok(Ok(AudioEvent::SourceOutputs | AudioEvent::SinkChanged)) => continue,
+                Ok(Ok(_)) | Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => {
+                    break;
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) => {
+                    tokio::time::sleep_until(deadline).await;
+                    return probe().await;
+                }
+            }
+        }
+    }
+}
+
+/// BlueZ `MediaTransport1.State`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportState {
+    Idle,
+    /// Streaming requested, waiting for the audio server to acquire it.
+    Pending,
+    Active,
+}
+
+impl TransportState {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "idle" => Some(Self::Idle),
+            "pending" | "broadcasting" => Some(Self::Pending),
+            "active" => Some(Self::Active),
+            _ => None,
+        }
+    }
+
+    fn is_ready(self) -> bool {
+        self != Self::Idle
+    }
+}
+
+/// Record a MediaTransport1 State change seen on the system bus.
+pub fn note_transport_state(path: &str, state: &str) {
+    let (Some(mac), Some(state)) = (mac_from_bluez_path(path), TransportState::parse(state)) else {
+        return;
+    };
+    debug!("Transport for {} is now {:?}", mac, state);
+    TRANSPORTS.send_modify(|m| {
+        m.insert(mac, state);
+    });
+}
+
+/// Current transport state for `mac`, read from BlueZ. Seeds the tracker
+/// for transports that came up before anyone watched them.
+async fn query_transport_state(mac: &str) -> Option<TransportState> {
+    let conn = zbus::Connection::system().await.ok()?;
+    let objects = crate::bluetooth::managed_objects(&conn).await?;
+    objects.iter().find_map(|(path, ifaces)| {
+        if mac_from_bluez_path(path.as_str()).as_deref() != Some(mac) {
+            return None;
+        }
+        let props = ifaces.get("org.bluez.MediaTransport1")?;
+        let state = <&str>::try_from(props.get("State")?).ok()?;
+        TransportState::parse(state)
+    })
+}
+
+/// Wait until `mac`'s A2DP transport is pending or active.
+async fn wait_for_transport(mac: &str, timeout: Duration) -> bool {
+    let mut rx = TRANSPORTS.subscribe();
+    if let Some(state) = query_transport_state(mac).await {
+        TRANSPORTS.send_modify(|m| {
+            m.insert(mac.to_string(), state);
+        });
+    }
+    let ready = rx.wait_for(|m| m.get(mac).is_some_and(|s| s.is_ready()));
+    matches!(tokio::time::timeout(timeout, ready).await, Ok(Ok(_)))
+}
+
+/// Now-playing entry from an MPRIS `Metadata` map; None without a title.
+fn track_from_metadata(
+    metadata: &HashMap<String, zbus::zvariant::OwnedValue>,
+    playing: bool,
+    position_us: i64,
+    position_at_ms: u64,
+) -> Option<NowPlaying> {
+    let title = <&str>::try_from(metadata.get("xesam:title")?).ok()?;
+    if title.is_empty() {
+        return None;
+    }
+    let artist = metadata.get("xesam:artist").and_then(|v| match &**v {
+        zbus::zvariant::Value::Array(a) => {
+            let names: Vec<&str> = a.iter().filter_map(|n| <&str>::try_from(n).ok()).collect();
+            (!names.is_empty()).then(|| names.join(", "))
+        }
+        other => <&str>::try_from(other).ok().map(str::to_string),
+    });
+    // Players disagree on the integer type for the length
+    let length_us = metadata.get("mpris:length").and_then(|v| {
+        i64::try_from(v)
+            .ok()
+            .or_else(|| u64::try_from(v).ok().and_then(|l| i64::try_from(l).ok()))
+    });
+    Some(NowPlaying {
+        title: title.to_string(),
+        artist,
+        playing,
+        position_us,
+        length_us,
+        position_at_ms,
+    })
+}
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Whether `service` is the player named by a config entry: the part after
+/// the MPRIS prefix equals it, or continues with `.` (per-instance names like
+/// "firefox.instance_1_42"). Case-insensitive.
+fn player_matches(service: &str, entry: &str) -> bool {
+    let Some(id) = service.strip_prefix(MPRIS_PREFIX) else {
+        return false;
+    };
+    let id = id.to_ascii_lowercase();
+    let entry = entry.to_ascii_lowercase();
+    id == entry
+        || id
+            .strip_prefix(entry.as_str())
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Position in `mpris_priority`; unlisted players sort after all listed ones.
+fn priority_rank(service: &str, priority: &[String]) -> usize {
+    priority
+        .iter()
+        .position(|entry| player_matches(service, entry))
+        .unwrap_or(priority.len())
+}
+
+/// Whether an MPRIS signal can change what's playing: a player appeared or
+/// vanished, or its PlaybackStatus changed (not Metadata/Volume chatter).
+fn is_playback_signal(msg: &zbus::Message) -> bool {
+    signal_touches(msg, &["PlaybackStatus"])
+}
+
+/// Whether an MPRIS signal can change the reported track: a seek, a player
+/// appearing or vanishing, or a new track or status.
+fn is_track_signal(msg: &zbus::Message) -> bool {
+    is_member(msg, "Seeked") || signal_touches(msg, &["PlaybackStatus", "Metadata"])
+}
+
+fn is_member(msg: &zbus::Message, name: &str) -> bool {
+    msg.header().member().is_some_and(|m| m.as_str() == name)
+}
+
+/// Whether `msg` adds or drops a player, or changes one of `properties`.
+fn signal_touches(msg: &zbus::Message, properties: &[&str]) -> bool {
+    if is_member(msg, "NameOwnerChanged") {
+        return true;
+    }
+    if is_member(msg, "Seeked") {
+        return false;
+    }
+    type Changed = (
+        String,
+        HashMap<String, zbus::zvariant::OwnedValue>,
+        Vec<String>,
+    );
+    match msg.body().deserialize::<Changed>() {
+        Ok((_, changed, invalidated)) => properties
+            .iter()
+            .any(|p| changed.contains_key(*p) || invalidated.iter().any(|i| i == p)),
+        Err(_) => true,
+    }
+}
+
+/// Whether `next` shows the same as `previous`: same track and status, and
+/// a position where `previous` would have counted on to (no seek).
+fn same_now_playing(previous: Option<&NowPlaying>, next: Option<&NowPlaying>) -> bool {
+    const SEEK_SLACK_US: i64 = 1_000_000;
+    match (previous, next) {
+        (None, None) => true,
+        (Some(p), Some(n)) => {
+            p.title == n.title
+                && p.artist == n.artist
+                && p.playing == n.playing
+                && p.length_us == n.length_us
+                && (p.position_at(n.position_at_ms) - n.position_us).abs() < SEEK_SLACK_US
+        }
+        _ => false,
+    }
+}
+
+/// Hold the idle inhibit only while media plays and both buds are worn.
+fn wants_idle_inhibit(
+    is_playing: bool,
+    left: Option<EarDetectionStatus>,
+    right: Option<EarDetectionStatus>,
+) -> bool {
+    is_playing
+        && left == Some(EarDetectionStatus::InEar)
+        && right == Some(EarDetectionStatus::InEar)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn properties_changed(key: &str) -> zbus::Message {
+        let mut changed = HashMap::new();
+        changed.insert(key, zbus::zvariant::Value::from("Playing"));
+        zbus::Message::signal(
+            "/org/mpris/MediaPlayer2",
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+        )
+        .unwrap()
+        .build(&(
+            "org.mpris.MediaPlayer2.Player",
+            changed,
+            Vec::<String>::new(),
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn wait_for_audio_reprobes_on_change() {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = AUDIO_EVENTS.send(AudioEvent::Sinks);
+        });
+        let found = wait_for_audio(Duration::from_secs(5), || async {
+            (calls.fetch_add(1, Ordering::SeqCst) > 0).then_some("sink")
+        })
+        .await;
+        assert_eq!(found, Some("sink"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn bluez_node_preferred_over_derived_sinks() {
+        const MAC: &str = "AA:BB:CC:DD:EE:FF";
+        let native = BluezProps {
+            address: Some("aa:bb:cc:dd:ee:ff".into()),
+            ..Default::default()
+        };
+        assert_eq!(native.match_rank(MAC), Some(0));
+        let pulse = BluezProps {
+            bluez_path: Some("/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF".into()),
+            api: Some("bluez5".into()),
+            ..Default::default()
+        };
+        assert_eq!(pulse.match_rank(MAC), Some(0));
+        let combined = BluezProps {
+            device_string: Some("combine AA:BB:CC:DD:EE:FF".into()),
+            ..Default::default()
+        };
+        assert_eq!(combined.match_rank(MAC), Some(1));
+        assert_eq!(combined.match_rank("11:22:33:44:55:66"), None);
+    }
+
+    #[test]
+    fn transport_path_and_state_parse() {
+        assert_eq!(
+            mac_from_bluez_path("/org/bluez/hci0/dev_aa_BB_CC_DD_EE_FF/sep1/fd0").as_deref(),
+            Some("AA:BB:CC:DD:EE:FF")
+        );
+        assert_eq!(mac_from_bluez_path("/org/bluez/hci0"), None);
+        assert_eq!(
+            TransportState::parse("pending"),
+            Some(TransportState::Pending)
+        );
+        assert!(!TransportState::Idle.is_ready());
+        assert_eq!(TransportState::parse("bogus"), None);
+    }
+
+    #[tokio::test]
+    async fn transport_wait_sees_state_change() {
+        let mac = "11:22:33:44:55:66";
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            note_transport_state("/org/bluez/hci0/dev_11_22_33_44_55_66/sep1/fd3", "active");
+        });
+        assert!(wait_for_transport(mac, Duration::from_secs(5)).await);
+    }
+
+    #[test]
+    fn track_reads_mpris_metadata() {
+        use zbus::zvariant::{OwnedValue, Value};
+        let value = |v: Value<'static>| OwnedValue::try_from(v).unwrap();
+        let mut meta = HashMap::new();
+        meta.insert("xesam:title".to_string(), value(Value::from("Song")));
+        meta.insert(
+            "xesam:artist".to_string(),
+            value(Value::from(vec!["A", "B"])),
+        );
+        meta.insert("mpris:length".to_string(), value(Value::U64(180_000_000)));
+        let track = track_from_metadata(&meta, true, 5_000_000, 1).unwrap();
+        assert_eq!(track.title, "Song");
+        assert_eq!(track.artist.as_deref(), Some("A, B"));
+        assert_eq!(track.length_us, Some(180_000_000));
+        assert_eq!(track.position_us, 5_000_000);
+
+        meta.insert("xesam:title".to_string(), value(Value::from("")));
+        assert!(track_from_metadata(&meta, true, 0, 1).is_none());
+    }
+
+    #[test]
+    fn playback_status_precedence() {
+        let peer = Ownership::Peer {
+            reclaim_when_silent: true,
+        };
+        assert_eq!(playback_status(peer, true, true), PlaybackStatus::PeerOwned);
+        assert_eq!(
+            playback_status(Ownership::Linux, true, true),
+            PlaybackStatus::Playing
+        );
+        assert_eq!(
+            playback_status(Ownership::Linux, false, true),
+            PlaybackStatus::PausedByEar
+        );
+        assert_eq!(
+            playback_status(Ownership::Unknown, false, false),
+            PlaybackStatus::Idle
+        );
+    }
+
+    #[test]
+    fn ear_pause_policy_decides_removal() {
+        assert!(should_pause_on_removal(EarPausePolicy::AnyOut, false));
+        assert!(!should_pause_on_removal(EarPausePolicy::BothOut, false));
+        assert!(should_pause_on_removal(EarPausePolicy::BothOut, true));
+        assert!(!should_pause_on_removal(EarPausePolicy::Never, true));
+    }
+
+    #[test]
+    fn play_on_wear_only_when_nothing_was_paused() {
+        assert!(starts_player_on_wear(true, true));
+        assert!(!starts_player_on_wear(true, false));
+        assert!(!starts_player_on_wear(false, true));
+    }
+
+    #[test]
+    fn lock_blocks_resume_but_not_pause() {
+        let policy = EarPausePolicy::AnyOut;
+        // Both buds in after being out
+        assert_eq!(
+            ear_playback_change(false, true, true, false, policy),
+            Some(true)
+        );
+        assert_eq!(ear_playback_change(true, true, true, false, policy), None);
+        // One bud out of two
+        assert_eq!(
+            ear_playback_change(false, false, false, false, policy),
+            Some(false)
+        );
+        assert_eq!(
+            ear_playback_change(true, false, false, false, policy),
+            Some(false)
+        );
+        assert_eq!(
+            ear_playback_change(true, false, false, false, EarPausePolicy::Never),
+            None
+        );
+    }
+
+    #[test]
+    fn release_restores_pre_takeover_profile() {
+        assert_eq!(release_profile(None, None), "off");
+        assert_eq!(
+            release_profile(None, Some("headset-head-unit")),
+            "headset-head-unit"
+        );
+        assert_eq!(
+            release_profile(Some("off"), Some("headset-head-unit")),
+            "off"
+        );
+    }
+
+    #[test]
+    fn codec_preference_maps_to_profiles() {
+        let codecs = vec!["AAC".to_string(), "sbc_xq".to_string()];
+        assert_eq!(
+            a2dp_profile_candidates(&codecs),
+            ["a2dp-sink-aac", "a2dp-sink-sbc_xq", "a2dp-sink"]
+        );
+    }
+
+    #[test]
+    fn codec_labels() {
+        assert_eq!(codec_label("a2dp-sink-aac").as_deref(), Some("AAC"));
+        assert_eq!(codec_label("a2dp-sink-sbc_xq").as_deref(), Some("SBC-XQ"));
+        assert_eq!(
+            codec_label("headset-head-unit-msbc").as_deref(),
+            Some("mSBC (call)")
+        );
+        assert_eq!(codec_label("off"), None);
+    }
+
+    #[test]
+    fn capture_ignores_monitors_and_level_meters() {
+        assert!(is_capture_stream(Some("us.zoom.Zoom"), false));
+        assert!(is_capture_stream(None, false));
+        assert!(!is_capture_stream(None, true));
+        assert!(!is_capture_stream(
+            Some("org.PulseAudio.pavucontrol"),
+            false
+        ));
+    }
+
+    #[test]
+    fn player_entries_match_instances_case_insensitively() {
+        assert!(player_matches("org.mpris.MediaPlayer2.spotify", "Spotify"));
+        assert!(player_matches(
+            "org.mpris.MediaPlayer2.firefox.instance_1_42",
+            "firefox"
+        ));
+        assert!(!player_matches(
+            "org.mpris.MediaPlayer2.firefoxnightly",
+            "firefox"
+        ));
+        assert!(!player_matches("org.example.spotify", "spotify"));
+    }
+
+    #[test]
+    fn priority_puts_listed_players_first() {
+        let priority = vec!["mpv".to_string(), "spotify".to_string()];
+        let mut services = vec![
+            "org.mpris.MediaPlayer2.firefox",
+            "org.mpris.MediaPlayer2.spotify",
+            "org.mpris.MediaPlayer2.vlc",
+            "org.mpris.MediaPlayer2.mpv",
+        ];
+        services.sort_by_key(|s| priority_rank(s, &priority));
+        assert_eq!(
+            services,
+            [
+                "org.mpris.MediaPlayer2.mpv",
+                "org.mpris.MediaPlayer2.spotify",
+                "org.mpris.MediaPlayer2.firefox",
+                "org.mpris.MediaPlayer2.vlc",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_playback_status_changes_trigger_refresh() {
+        assert!(is_playback_signal(&properties_changed("PlaybackStatus")));
+        assert!(!is_playback_signal(&properties_changed("Metadata")));
+        let owner = zbus::Message::signal(
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "NameOwnerChanged",
+        )
+        .unwrap()
+        .build(&("org.mpris.MediaPlayer2.spotify", "", ":1.42"))
+        .unwrap();
+        assert!(is_playback_signal(&owner));
+        assert!(is_track_signal(&owner));
+    }
+
+    #[test]
+    fn track_refresh_follows_metadata_and_seeks_only() {
+        assert!(is_track_signal(&properties_changed("Metadata")));
+        assert!(is_track_signal(&properties_changed("PlaybackStatus")));
+        assert!(!is_track_signal(&properties_changed("Volume")));
+        let seeked = zbus::Message::signal(
+            "/org/mpris/MediaPlayer2",
+            "org.mpris.MediaPlayer2.Player",
+            "Seeked",
+        )
+        .unwrap()
+        .build(&(42_000_000i64,))
+        .unwrap();
+        assert!(is_track_signal(&seeked));
+        assert!(!is_playback_signal(&seeked));
+    }
+
+    #[test]
+    fn now_playing_resent_only_on_change_or_seek() {
+        let track = NowPlaying {
+            title: "Song".into(),
+            artist: None,
+            playing: true,
+            position_us: 1_000_000,
+            length_us: Some(180_000_000),
+            position_at_ms: 10_000,
+        };
+        // Re-read 5s later, 5s further in: nothing to send
+        let later = NowPlaying {
+            position_us: 6_000_000,
+            position_at_ms: 15_000,
+            ..track.clone()
+        };
+        assert!(same_now_playing(Some(&track), Some(&later)));
+        let seeked = NowPlaying {
+            position_us: 36_000_000,
+            ..later.clone()
+        };
+        assert!(!same_now_playing(Some(&track), Some(&seeked)));
+        let paused = NowPlaying {
+            playing: false,
+            ..later.clone()
+        };
+        assert!(!same_now_playing(Some(&track), Some(&paused)));
+        assert!(!same_now_playing(Some(&track), None));
+        assert!(same_now_playing(None, None));
+    }
+
+    #[test]
+    fn idle_inhibit_needs_playback_and_both_buds() {
+        let in_ear = Some(EarDetectionStatus::InEar);
+        let out = Some(EarDetectionStatus::OutOfEar);
+        assert!(wants_idle_inhibit(true, in_ear, in_ear));
+        assert!(!wants_idle_inhibit(false, in_ear, in_ear));
+        assert!(!wants_idle_inhibit(true, in_ear, out));
+        assert!(!wants_idle_inhibit(true, None, in_ear));
+    }
+
     /// The listener must exit once the AACP session's sender is gone,
     /// otherwise every reconnect leaks a poll task and a PulseAudio thread.
     #[tokio::test]
@@ -1590,7 +3491,7 @@ mod tests {
         mc.start_playback_listener(manager).await;
         assert!(mc.state.lock().await.playback_listener_running);
 
-        // First loop tick is after 500ms; allow a generous window.
+        // The first housekeeping tick is immediate; allow a generous window.
         for _ in 0..30 {
             tokio::time::sleep(Duration::from_millis(100)).await;
             if !mc.state.lock().await.playback_listener_running {
@@ -0,0 +1,1079 @@
+//! Native PipeWire backend for the audio thread: the same AudioCommands as
+//! the PulseAudio path, answered from a live mirror of the PipeWire graph
+//! instead of through pipewire-pulse.
+//!
+//! Card indices are device global ids and sink names are `node.name`, as
+//! pipewire-pulse reports them, and volumes use the PulseAudio (cubic)
+//! scale, so remembered levels carry over between backends. Suspending a
+//! sink has no counterpart in the client API; SuspendSinkByName fails and
+//! callers fall back to cycling the profile.
+
+use super::{AUDIO_EVENTS, AudioCommand, AudioEvent, BluezProps, is_capture_stream};
+use log::{debug, error, info, warn};
+use pipewire as pw;
+use pw::spa::param::ParamType;
+use pw::spa::pod::deserialize::PodDeserializer;
+use pw::spa::pod::serialize::PodSerializer;
+use pw::spa::pod::{Object, Pod, Property, Value, ValueArray};
+use pw::spa::support::system::IoFlags;
+use pw::spa::sys as spa_sys;
+use pw::spa::utils::SpaTypes;
+use pw::spa::utils::dict::DictRef;
+use pw::types::ObjectType;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::Read;
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+const SINK: &str = "Audio/Sink";
+const SOURCE: &str = "Audio/Source";
+const PLAYBACK_STREAM: &str = "Stream/Output/Audio";
+const CAPTURE_STREAM: &str = "Stream/Input/Audio";
+
+/// A Bluetooth device (PulseAudio's card).
+#[derive(Debug, Default)]
+struct Device {
+    props: HashMap<String, String>,
+    /// (index, name) from EnumProfile.
+    profiles: Vec<(i32, String)>,
+    active_profile: Option<String>,
+    /// (route index, card.profile.device) of each active Route.
+    routes: Vec<(i32, i32)>,
+}
+
+/// An audio node: a sink, a source or an application stream.
+#[derive(Debug, Default)]
+struct Node {
+    props: HashMap<String, String>,
+    running: bool,
+    /// Linear channel volumes from the Props param.
+    volumes: Vec<f32>,
+    mute: bool,
+}
+
+impl Node {
+    fn class(&self) -> &str {
+        self.props.get("media.class").map_or("", String::as_str)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.props.get("node.name").map(String::as_str)
+    }
+}
+
+/// What the backend knows about the PipeWire graph.
+#[derive(Debug, Default)]
+struct Graph {
+    devices: HashMap<u32, Device>,
+    nodes: HashMap<u32, Node>,
+    /// Link id to (output node, input node).
+    links: HashMap<u32, (u32, u32)>,
+    default_sink: Option<String>,
+}
+
+fn bluez_props(props: &HashMap<String, String>) -> BluezProps {
+    BluezProps::from_lookup(|key| props.get(key).cloned())
+}
+
+impl Graph {
+    fn device_for_mac(&self, mac: &str) -> Option<u32> {
+        let mut ids: Vec<u32> = self
+            .devices
+            .iter()
+            .filter(|(_, d)| bluez_props(&d.props).match_rank(mac).is_some())
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids.first().copied()
+    }
+
+    /// The node of `class` belonging to `mac`, preferring the device's own
+    /// Bluetooth node as the PulseAudio path does.
+    fn node_for_mac(&self, class: &str, mac: &str) -> Option<u32> {
+        let mut candidates: Vec<(u8, u32)> = self
+            .nodes
+            .iter()
+            .filter(|(_, n)| n.class() == class)
+            .filter_map(|(id, n)| Some((bluez_props(&n.props).match_rank(mac)?, *id)))
+            .collect();
+        if candidates.len() > 1 {
+            debug!("Several {} nodes match {}: {:?}", class, mac, candidates);
+        }
+        candidates.sort_unstable();
+        candidates.first().map(|(_, id)| *id)
+    }
+
+    fn node_by_name(&self, class: &str, name: &str) -> Option<u32> {
+        self.nodes
+            .iter()
+            .find(|(_, n)| n.class() == class && n.name() == Some(name))
+            .map(|(id, _)| *id)
+    }
+
+    fn streams(&self, class: &str) -> Vec<u32> {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.class() == class)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Nodes feeding `input` through a link.
+    fn linked_outputs(&self, input: u32) -> impl Iterator<Item = &Node> {
+        self.links
+            .values()
+            .filter(move |(_, i)| *i == input)
+            .filter_map(|(o, _)| self.nodes.get(o))
+    }
+
+    /// Whether a playing stream is linked to `sink`.
+    fn has_active_input(&self, sink: u32) -> bool {
+        self.links.values().any(|(o, i)| {
+            *i == sink
+                && self
+                    .nodes
+                    .get(o)
+                    .is_some_and(|n| n.class() == PLAYBACK_STREAM && n.running)
+        })
+    }
+
+    /// Whether any application records from a microphone; streams that
+    /// only read sink monitors don't count.
+    fn capture_active(&self) -> bool {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.class() == CAPTURE_STREAM)
+            .any(|(id, n)| {
+                let mut outputs = self.linked_outputs(*id).peekable();
+                let monitor = outputs.peek().is_some() && outputs.all(|o| o.class() == SINK);
+                is_capture_stream(n.props.get("application.id").map(String::as_str), monitor)
+            })
+    }
+
+    /// (device, route index, card.profile.device) carrying `node`'s
+    /// volume, for nodes that belong to a device route.
+    fn route_of(&self, node: u32) -> Option<(u32, i32, i32)> {
+        let props = &self.nodes.get(&node)?.props;
+        let device: u32 = props.get("device.id")?.parse().ok()?;
+        let card_device: i32 = props.get("card.profile.device")?.parse().ok()?;
+        let (index, _) = self
+            .devices
+            .get(&device)?
+            .routes
+            .iter()
+            .find(|(_, d)| *d == card_device)?;
+        Some((device, *index, card_device))
+    }
+}
+
+/// PulseAudio's volume percentage for linear PipeWire channel volumes.
+fn percent_from_linear(volumes: &[f32]) -> Option<u32> {
+    if volumes.is_empty() {
+        return None;
+    }
+    let total: f64 = volumes.iter().map(|v| f64::from(*v).max(0.0).cbrt()).sum();
+    Some((total / volumes.len() as f64 * 100.0).round() as u32)
+}
+
+fn linear_from_percent(percent: u32) -> f32 {
+    (f64::from(percent) / 100.0).powi(3) as f32
+}
+
+/// The node name in a `default.audio.*` metadata value.
+fn default_name(value: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(value).ok()?;
+    json.get("name")?.as_str().map(str::to_string)
+}
+
+fn dict_map(dict: Option<&DictRef>) -> HashMap<String, String> {
+    dict.map(|d| {
+        d.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+// ── Pods ──
+
+fn parse_object(pod: &Pod) -> Option<Object> {
+    match PodDeserializer::deserialize_any_from(pod.as_bytes()) {
+        Ok((_, Value::Object(object))) => Some(object),
+        _ => None,
+    }
+}
+
+fn prop(object: &Object, key: u32) -> Option<&Value> {
+    object
+        .properties
+        .iter()
+        .find(|p| p.key == key)
+        .map(|p| &p.value)
+}
+
+fn int_prop(object: &Object, key: u32) -> Option<i32> {
+    match prop(object, key)? {
+        Value::Int(i) => Some(*i),
+        _ => None,
+    }
+}
+
+/// (index, name) of a Profile/EnumProfile param.
+fn parse_profile(object: &Object) -> Option<(i32, String)> {
+    let index = int_prop(object, spa_sys::SPA_PARAM_PROFILE_index)?;
+    match prop(object, spa_sys::SPA_PARAM_PROFILE_name)? {
+        Value::String(name) => Some((index, name.clone())),
+        _ => None,
+    }
+}
+
+/// (route index, card.profile.device) of a Route param.
+fn parse_route(object: &Object) -> Option<(i32, i32)> {
+    Some((
+        int_prop(object, spa_sys::SPA_PARAM_ROUTE_index)?,
+        int_prop(object, spa_sys::SPA_PARAM_ROUTE_device)?,
+    ))
+}
+
+/// Channel volumes and mute of a Props param, where present.
+fn parse_props(object: &Object) -> (Option<Vec<f32>>, Option<bool>) {
+    let volumes = match prop(object, spa_sys::SPA_PROP_channelVolumes) {
+        Some(Value::ValueArray(ValueArray::Float(v))) => Some(v.clone()),
+        _ => None,
+    };
+    let mute = match prop(object, spa_sys::SPA_PROP_mute) {
+        Some(Value::Bool(m)) => Some(*m),
+        _ => None,
+    };
+    (volumes, mute)
+}
+
+fn object(type_: SpaTypes, id: ParamType, properties: Vec<Property>) -> Value {
+    Value::Object(Object {
+        type_: type_.as_raw(),
+        id: id.as_raw(),
+        properties,
+    })
+}
+
+/// A Props object setting volumes and/or mute.
+fn props_value(id: ParamType, volumes: Option<Vec<f32>>, mute: Option<bool>) -> Value {
+    let mut properties = Vec::new();
+    if let Some(volumes) = volumes {
+        properties.push(Property::new(
+            spa_sys::SPA_PROP_channelVolumes,
+            Value::ValueArray(ValueArray::Float(volumes)),
+        ));
+    }
+    if let Some(mute) = mute {
+        properties.push(Property::new(spa_sys::SPA_PROP_mute, Value::Bool(mute)));
+    }
+    object(SpaTypes::ObjectParamProps, id, properties)
+}
+
+fn serialize(value: &Value) -> Option<Vec<u8>> {
+    match PodSerializer::serialize(std::io::Cursor::new(Vec::new()), value) {
+        Ok((cursor, _)) => Some(cursor.into_inner()),
+        Err(e) => {
+            error!("Failed to build PipeWire param: {:?}", e);
+            None
+        }
+    }
+}
+
+// ── Connection ──
+
+/// Proxies for the objects we watch or change, with their listeners.
+#[derive(Default)]
+struct Proxies {
+    devices: HashMap<u32, (pw::device::Device, pw::device::DeviceListener)>,
+    nodes: HashMap<u32, (pw::node::Node, pw::node::NodeListener)>,
+    metadata: Option<(pw::metadata::Metadata, pw::metadata::MetadataListener)>,
+}
+
+/// A connected PipeWire core with the graph mirrored.
+pub(super) struct Connection {
+    // Dropped first: proxies and listeners before the core they live on
+    proxies: Rc<RefCell<Proxies>>,
+    _registry_listener: pw::registry::Listener,
+    _core_listener: pw::core::Listener,
+    graph: Rc<RefCell<Graph>>,
+    failed: Rc<Cell<bool>>,
+    _registry: pw::registry::RegistryRc,
+    core: pw::core::CoreRc,
+    _context: pw::context::ContextRc,
+    main_loop: pw::main_loop::MainLoopRc,
+}
+
+impl Connection {
+    /// Connect and take in the current graph. With `need_audio`, a
+    /// PipeWire that doesn't manage audio (PulseAudio still running) is an
+    /// error so the caller can use PulseAudio instead.
+    pub(super) fn connect(need_audio: bool) -> Result<Self, String> {
+        pw::init();
+        let main_loop = pw::main_loop::MainLoopRc::new(None).map_err(|e| e.to_string())?;
+        let context = pw::context::ContextRc::new(&main_loop, None).map_err(|e| e.to_string())?;
+        let core = context.connect_rc(None).map_err(|e| e.to_string())?;
+        let registry = core.get_registry_rc().map_err(|e| e.to_string())?;
+
+        let failed = Rc::new(Cell::new(false));
+        let core_listener = core
+            .add_listener_local()
+            .error({
+                let failed = failed.clone();
+                let main_loop = main_loop.downgrade();
+                move |id, _seq, res, message| {
+                    if id == pw::core::PW_ID_CORE {
+                        warn!("PipeWire connection failed ({}): {}", res, message);
+                        failed.set(true);
+                        if let Some(main_loop) = main_loop.upgrade() {
+                            main_loop.quit();
+                        }
+                    } else {
+                        debug!("PipeWire error on object {}: {}", id, message);
+                    }
+                }
+            })
+            .register();
+
+        let graph = Rc::new(RefCell::new(Graph::default()));
+        let proxies = Rc::new(RefCell::new(Proxies::default()));
+        let registry_listener = registry
+            .add_listener_local()
+            .global({
+                let registry = registry.downgrade();
+                let graph = graph.clone();
+                let proxies = proxies.clone();
+                move |global| {
+                    if let Some(registry) = registry.upgrade() {
+                        add_global(&registry, &graph, &proxies, global);
+                    }
+                }
+            })
+            .global_remove({
+                let graph = graph.clone();
+                let proxies = proxies.clone();
+                move |id| remove_global(&graph, &proxies, id)
+            })
+            .register();
+
+        let connection = Connection {
+            proxies,
+            _registry_listener: registry_listener,
+            _core_listener: core_listener,
+            graph,
+            failed,
+            _registry: registry,
+            core,
+            _context: context,
+            main_loop,
+        };
+        // Globals first, then the info and params of what we bound
+        connection.roundtrip()?;
+        connection.roundtrip()?;
+        let graph = connection.graph.borrow();
+        if need_audio
+            && !graph
+                .nodes
+                .values()
+                .any(|n| n.class().starts_with("Audio/"))
+        {
+            return Err("PipeWire has no audio devices".into());
+        }
+        info!(
+            "PipeWire audio thread connected ({} devices, {} nodes)",
+            graph.devices.len(),
+            graph.nodes.len()
+        );
+        drop(graph);
+        Ok(connection)
+    }
+
+    /// Run the loop until the server has handled everything sent so far.
+    fn roundtrip(&self) -> Result<(), String> {
+        let done = Rc::new(Cell::new(false));
+        let pending = self.core.sync(0).map_err(|e| e.to_string())?;
+        let _listener = self
+            .core
+            .add_listener_local()
+            .done({
+                let done = done.clone();
+                let main_loop = self.main_loop.downgrade();
+                move |id, seq| {
+                    if id == pw::core::PW_ID_CORE && seq == pending {
+                        done.set(true);
+                        if let Some(main_loop) = main_loop.upgrade() {
+                            main_loop.quit();
+                        }
+                    }
+                }
+            })
+            .register();
+        while !done.get() {
+            if self.failed.get() {
+                return Err("connection lost".into());
+            }
+            self.main_loop.run();
+        }
+        Ok(())
+    }
+
+    /// Serve commands until the channel closes or PipeWire goes away.
+    pub(super) fn run(self, rx: Receiver<AudioCommand>, wake: UnixStream) {
+        let main_loop = self.main_loop.clone();
+        let this = Rc::new(self);
+        let _wake = main_loop.loop_().add_io(wake, IoFlags::IN, {
+            let this = this.clone();
+            move |wake: &mut UnixStream| {
+                let mut buf = [0u8; 64];
+                while matches!(wake.read(&mut buf), Ok(n) if n > 0) {}
+                loop {
+                    match rx.try_recv() {
+                        Ok(cmd) => this.run_command(cmd),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            this.main_loop.quit();
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        // Commands sent while connecting were only signalled on the socket,
+        // which the loop sees on its first iteration
+        main_loop.run();
+        if this.failed.get() {
+            warn!("PipeWire connection lost, audio thread exiting");
+        }
+        info!("PipeWire audio thread exiting");
+    }
+
+    fn run_command(&self, cmd: AudioCommand) {
+        let graph = self.graph.borrow();
+        match cmd {
+            AudioCommand::IsA2dpAvailable { card_index, reply } => {
+                let result = graph.devices.get(&card_index).is_some_and(|d| {
+                    d.profiles
+                        .iter()
+                        .any(|(_, name)| name.starts_with("a2dp-sink"))
+                });
+                let _ = reply.send(result);
+            }
+            AudioCommand::GetDeviceIndex { mac, reply } => {
+                let _ = reply.send(graph.device_for_mac(&mac));
+            }
+            AudioCommand::SetCardProfile {
+                card_index,
+                profile,
+                reply,
+            } => {
+                drop(graph);
+                let _ = reply.send(self.set_profile(card_index, &profile));
+            }
+            AudioCommand::GetSinkVolume { sink_name, reply } => {
+                let result = graph
+                    .node_by_name(SINK, &sink_name)
+                    .and_then(|id| percent_from_linear(&graph.nodes[&id].volumes));
+                let _ = reply.send(result);
+            }
+            AudioCommand::TransitionVolume {
+                sink_name,
+                target,
+                reply,
+            } => {
+                let Some(id) = graph.node_by_name(SINK, &sink_name) else {
+                    error!("Sink not found: {}", sink_name);
+                    let _ = reply.send(false);
+                    return;
+                };
+                let channels = graph.nodes[&id].volumes.len().max(1);
+                drop(graph);
+                let volumes = vec![linear_from_percent(target); channels];
+                let _ = reply.send(self.set_node_props(id, Some(volumes), None));
+            }
+            AudioCommand::GetSinkNameByMac { mac, reply } => {
+                let result = graph
+                    .node_for_mac(SINK, &mac)
+                    .and_then(|id| graph.nodes[&id].name().map(str::to_string));
+                let _ = reply.send(result);
+            }
+            AudioCommand::IsProfileAvailable {
+                card_index,
+                profile,
+                reply,
+            } => {
+                let result = graph
+                    .devices
+                    .get(&card_index)
+                    .is_some_and(|d| d.profiles.iter().any(|(_, name)| *name == profile));
+                let _ = reply.send(result);
+            }
+            AudioCommand::SetDefaultSink { sink_name, reply } => {
+                drop(graph);
+                let _ = reply.send(self.set_default("default.configured.audio.sink", &sink_name));
+            }
+            AudioCommand::MoveAllSinkInputs { sink_name, reply } => {
+                let result = graph.node_by_name(SINK, &sink_name).map(|sink| {
+                    let streams = graph.streams(PLAYBACK_STREAM);
+                    (
+                        graph.nodes[&sink].props.get("object.serial").cloned(),
+                        streams,
+                    )
+                });
+                drop(graph);
+                let _ = reply.send(match result {
+                    Some((Some(serial), streams)) => self.retarget(&streams, &serial),
+                    _ => false,
+                });
+            }
+            AudioCommand::SuspendSinkByName {
+                sink_name, reply, ..
+            } => {
+                debug!("PipeWire can't suspend {} from a client", sink_name);
+                let _ = reply.send(false);
+            }
+            AudioCommand::SetSinkMute {
+                sink_name,
+                mute,
+                reply,
+            } => {
+                let id = graph.node_by_name(SINK, &sink_name);
+                drop(graph);
+                let _ = reply.send(id.is_some_and(|id| self.set_node_props(id, None, Some(mute))));
+            }
+            AudioCommand::HasActiveSinkInput { sink_name, reply } => {
+                let result = graph
+                    .node_by_name(SINK, &sink_name)
+                    .is_some_and(|id| graph.has_active_input(id));
+                let _ = reply.send(result);
+            }
+            AudioCommand::GetActiveProfile { card_index, reply } => {
+                let result = graph
+                    .devices
+                    .get(&card_index)
+                    .and_then(|d| d.active_profile.clone());
+                let _ = reply.send(result);
+            }
+            AudioCommand::IsCaptureActive { reply } => {
+                let _ = reply.send(graph.capture_active());
+            }
+            AudioCommand::RouteInputToMac { mac, reply } => {
+                let source = graph.node_for_mac(SOURCE, &mac).and_then(|id| {
+                    let props = &graph.nodes[&id].props;
+                    Some((
+                        props.get("node.name")?.clone(),
+                        props.get("object.serial")?.clone(),
+                    ))
+                });
+                let streams = graph.streams(CAPTURE_STREAM);
+                drop(graph);
+                let result = source.is_some_and(|(name, serial)| {
+                    self.set_default("default.configured.audio.source", &name)
+                        && self.retarget(&streams, &serial)
+                });
+                let _ = reply.send(result);
+            }
+            AudioCommand::ToggleSourceMute { mac, reply } => {
+                let source = graph
+                    .node_for_mac(SOURCE, &mac)
+                    .map(|id| (id, !graph.nodes[&id].mute));
+                drop(graph);
+                let result = source.and_then(|(id, mute)| {
+                    self.set_node_props(id, None, Some(mute)).then_some(mute)
+                });
+                let _ = reply.send(result);
+            }
+            AudioCommand::GetDefaultSink { reply } => {
+                let _ = reply.send(graph.default_sink.clone());
+            }
+        }
+    }
+
+    fn set_profile(&self, device: u32, profile: &str) -> bool {
+        let index = self.graph.borrow().devices.get(&device).and_then(|d| {
+            d.profiles
+                .iter()
+                .find(|(_, name)| name == profile)
+                .map(|(index, _)| *index)
+        });
+        let Some(index) = index else {
+            warn!("Device {} has no profile {}", device, profile);
+            return false;
+        };
+        let value = object(
+            SpaTypes::ObjectParamProfile,
+            ParamType::Profile,
+            vec![
+                Property::new(spa_sys::SPA_PARAM_PROFILE_index, Value::Int(index)),
+                Property::new(spa_sys::SPA_PARAM_PROFILE_save, Value::Bool(true)),
+            ],
+        );
+        let proxies = self.proxies.borrow();
+        let (Some((proxy, _)), Some(bytes)) = (proxies.devices.get(&device), serialize(&value))
+        else {
+            return false;
+        };
+        let Some(pod) = Pod::from_bytes(&bytes) else {
+            return false;
+        };
+        proxy.set_param(ParamType::Profile, 0, pod);
+        true
+    }
+
+    /// Set volume and/or mute on the device route carrying `node` (so
+    /// hardware volume follows, as with pipewire-pulse), or on the node.
+    fn set_node_props(&self, node: u32, volumes: Option<Vec<f32>>, mute: Option<bool>) -> bool {
+        let route = self.graph.borrow().route_of(node);
+        let proxies = self.proxies.borrow();
+        let sent = match route {
+            Some((device, index, card_device)) => {
+                let value = object(
+                    SpaTypes::ObjectParamRoute,
+                    ParamType::Route,
+                    vec![
+                        Property::new(spa_sys::SPA_PARAM_ROUTE_index, Value::Int(index)),
+                        Property::new(spa_sys::SPA_PARAM_ROUTE_device, Value::Int(card_device)),
+                        Property::new(
+                            spa_sys::SPA_PARAM_ROUTE_props,
+                            props_value(ParamType::Route, volumes.clone(), mute),
+                        ),
+                        Property::new(spa_sys::SPA_PARAM_ROUTE_save, Value::Bool(true)),
+                    ],
+                );
+                let bytes = serialize(&value);
+                match (
+                    proxies.devices.get(&device),
+                    bytes.as_deref().and_then(Pod::from_bytes),
+                ) {
+                    (Some((proxy, _)), Some(pod)) => {
+                        proxy.set_param(ParamType::Route, 0, pod);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            None => {
+                let bytes = serialize(&props_value(ParamType::Props, volumes.clone(), mute));
+                match (
+                    proxies.nodes.get(&node),
+                    bytes.as_deref().and_then(Pod::from_bytes),
+                ) {
+                    (Some((proxy, _)), Some(pod)) => {
+                        proxy.set_param(ParamType::Props, 0, pod);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        };
+        // Reads right after a change see it before the param event arrives
+        if sent && let Some(n) = self.graph.borrow_mut().nodes.get_mut(&node) {
+            if let Some(volumes) = volumes {
+                n.volumes = volumes;
+            }
+            if let Some(mute) = mute {
+                n.mute = mute;
+            }
+        }
+        sent
+    }
+
+    /// Set a `default.configured.*` key in the default metadata.
+    fn set_default(&self, key: &str, name: &str) -> bool {
+        let proxies = self.proxies.borrow();
+        let Some((metadata, _)) = &proxies.metadata else {
+            warn!("PipeWire has no default metadata");
+            return false;
+        };
+        let value = serde_json::json!({ "name": name }).to_string();
+        metadata.set_property(0, key, Some("Spa:String:JSON"), Some(&value));
+        true
+    }
+
+    /// Point each of `streams` at the node with `serial`; the session
+    /// manager moves them.
+    fn retarget(&self, streams: &[u32], serial: &str) -> bool {
+        let proxies = self.proxies.borrow();
+        let Some((metadata, _)) = &proxies.metadata else {
+            warn!("PipeWire has no default metadata");
+            return false;
+        };
+        for stream in streams {
+            metadata.set_property(*stream, "target.object", Some("Spa:Id"), Some(serial));
+        }
+        true
+    }
+}
+
+// ── Registry ──
+
+fn send_event(event: AudioEvent) {
+    let _ = AUDIO_EVENTS.send(event);
+}
+
+/// Events for a node of `class` coming or going.
+fn node_event(class: &str) -> Option<AudioEvent> {
+    match class {
+        SINK => Some(AudioEvent::Sinks),
+        CAPTURE_STREAM => Some(AudioEvent::SourceOutputs),
+        _ => None,
+    }
+}
+
+fn add_global(
+    registry: &pw::registry::RegistryRc,
+    graph: &Rc<RefCell<Graph>>,
+    proxies: &Rc<RefCell<Proxies>>,
+    global: &pw::registry::GlobalObject<&DictRef>,
+) {
+    let get = |key: &str| global.props.and_then(|p| p.get(key));
+    let id = global.id;
+    match global.type_ {
+        ObjectType::Device if get("device.api") == Some("bluez5") => {
+            let device: pw::device::Device = match registry.bind(global) {
+                Ok(device) => device,
+                Err(e) => {
+                    debug!("Can't bind PipeWire device {}: {}", id, e);
+                    return;
+                }
+            };
+            let listener = device
+                .add_listener_local()
+                .info({
+                    let graph = graph.clone();
+                    move |info| {
+                        if info
+                            .change_mask()
+                            .contains(pw::device::DeviceChangeMask::PROPS)
+                            && let Some(d) = graph.borrow_mut().devices.get_mut(&id)
+                        {
+                            d.props = dict_map(info.props());
+                        }
+                    }
+                })
+                .param({
+                    let graph = graph.clone();
+                    move |_seq, param_id, index, _next, param| {
+                        device_param(&graph, id, param_id, index, param);
+                    }
+                })
+                .register();
+            device.subscribe_params(&[
+                ParamType::EnumProfile,
+                ParamType::Profile,
+                ParamType::Route,
+            ]);
+            graph.borrow_mut().devices.insert(id, Device::default());
+            proxies.borrow_mut().devices.insert(id, (device, listener));
+            send_event(AudioEvent::Card(id));
+        }
+        ObjectType::Node => {
+            let class = get("media.class").unwrap_or_default().to_string();
+            if ![SINK, SOURCE, PLAYBACK_STREAM, CAPTURE_STREAM].contains(&class.as_str()) {
+                return;
+            }
+            let node: pw::node::Node = match registry.bind(global) {
+                Ok(node) => node,
+                Err(e) => {
+                    debug!("Can't bind PipeWire node {}: {}", id, e);
+                    return;
+                }
+            };
+            let listener = node
+                .add_listener_local()
+                .info({
+                    let graph = graph.clone();
+                    move |info| node_info(&graph, id, info)
+                })
+                .param({
+                    let graph = graph.clone();
+                    move |_seq, param_id, _index, _next, param| {
+                        node_param(&graph, id, param_id, param);
+                    }
+                })
+                .register();
+            if class == SINK || class == SOURCE {
+                node.subscribe_params(&[ParamType::Props]);
+            }
+            let props = dict_map(global.props);
+            graph.borrow_mut().nodes.insert(
+                id,
+                Node {
+                    props,
+                    ..Node::default()
+                },
+            );
+            proxies.borrow_mut().nodes.insert(id, (node, listener));
+            if let Some(event) = node_event(&class) {
+                send_event(event);
+            }
+        }
+        ObjectType::Link => {
+            let node = |key: &str| get(key).and_then(|v| v.parse::<u32>().ok());
+            let (Some(output), Some(input)) = (node("link.output.node"), node("link.input.node"))
+            else {
+                return;
+            };
+            let mut graph = graph.borrow_mut();
+            graph.links.insert(id, (output, input));
+            link_event(&graph, input);
+        }
+        ObjectType::Metadata if get("metadata.name") == Some("default") => {
+            let metadata: pw::metadata::Metadata = match registry.bind(global) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    debug!("Can't bind PipeWire metadata: {}", e);
+                    return;
+                }
+            };
+            let listener = metadata
+                .add_listener_local()
+                .property({
+                    let graph = graph.clone();
+                    move |subject, key, _type, value| {
+                        if subject == 0 && matches!(key, Some("default.audio.sink") | None) {
+                            graph.borrow_mut().default_sink = value.and_then(default_name);
+                        }
+                        0
+                    }
+                })
+                .register();
+            proxies.borrow_mut().metadata = Some((metadata, listener));
+        }
+        _ => {}
+    }
+}
+
+/// A link into a sink or a recording stream changes what plays or records.
+fn link_event(graph: &Graph, input: u32) {
+    match graph.nodes.get(&input).map(Node::class) {
+        Some(SINK) => send_event(AudioEvent::SinkChanged),
+        Some(CAPTURE_STREAM) => send_event(AudioEvent::SourceOutputs),
+        _ => {}
+    }
+}
+
+fn remove_global(graph: &Rc<RefCell<Graph>>, proxies: &Rc<RefCell<Proxies>>, id: u32) {
+    {
+        let mut proxies = proxies.borrow_mut();
+        proxies.devices.remove(&id);
+        proxies.nodes.remove(&id);
+    }
+    let mut graph = graph.borrow_mut();
+    if graph.devices.remove(&id).is_some() {
+        send_event(AudioEvent::Card(id));
+    } else if let Some(node) = graph.nodes.remove(&id) {
+        if let Some(event) = node_event(node.class()) {
+            send_event(event);
+        }
+    } else if let Some((_, input)) = graph.links.remove(&id) {
+        link_event(&graph, input);
+    }
+}
+
+fn device_param(
+    graph: &Rc<RefCell<Graph>>,
+    id: u32,
+    param_id: ParamType,
+    index: u32,
+    param: Option<&Pod>,
+) {
+    let mut graph = graph.borrow_mut();
+    let Some(device) = graph.devices.get_mut(&id) else {
+        return;
+    };
+    let object = param.and_then(parse_object);
+    // Each change re-sends the whole list, starting at index 0
+    match param_id {
+        ParamType::EnumProfile => {
+            if index == 0 {
+                device.profiles.clear();
+            }
+            device
+                .profiles
+                .extend(object.as_ref().and_then(parse_profile));
+        }
+        ParamType::Profile => {
+            let active = object
+                .as_ref()
+                .and_then(parse_profile)
+                .map(|(_, name)| name);
+            if device.active_profile != active {
+                device.active_profile = active;
+                send_event(AudioEvent::Card(id));
+            }
+        }
+        ParamType::Route => {
+            if index == 0 {
+                device.routes.clear();
+            }
+            device.routes.extend(object.as_ref().and_then(parse_route));
+        }
+        _ => {}
+    }
+}
+
+fn node_info(graph: &Rc<RefCell<Graph>>, id: u32, info: &pw::node::NodeInfoRef) {
+    let mut graph = graph.borrow_mut();
+    let Some(node) = graph.nodes.get_mut(&id) else {
+        return;
+    };
+    let mask = info.change_mask();
+    if mask.contains(pw::node::NodeChangeMask::PROPS) {
+        node.props = dict_map(info.props());
+    }
+    if mask.contains(pw::node::NodeChangeMask::STATE) {
+        node.running = matches!(info.state(), pw::node::NodeState::Running);
+    }
+    let class = node.class().to_string();
+    if class == SINK {
+        send_event(AudioEvent::SinkChanged);
+    } else if class == PLAYBACK_STREAM && mask.contains(pw::node::NodeChangeMask::STATE) {
+        // A stream starting or pausing changes HasActiveSinkInput
+        let sinks: Vec<u32> = graph
+            .links
+            .values()
+            .filter(|(o, _)| *o == id)
+            .map(|(_, i)| *i)
+            .collect();
+        for sink in sinks {
+            link_event(&graph, sink);
+        }
+    }
+}
+
+fn node_param(graph: &Rc<RefCell<Graph>>, id: u32, param_id: ParamType, param: Option<&Pod>) {
+    if param_id != ParamType::Props {
+        return;
+    }
+    let Some(object) = param.and_then(parse_object) else {
+        return;
+    };
+    let mut graph = graph.borrow_mut();
+    let Some(node) = graph.nodes.get_mut(&id) else {
+        return;
+    };
+    let (volumes, mute) = parse_props(&object);
+    if let Some(volumes) = volumes {
+        node.volumes = volumes;
+    }
+    if let Some(mute) = mute {
+        node.mute = mute;
+    }
+    if node.class() == SINK {
+        send_event(AudioEvent::SinkChanged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(class: &str, props: &[(&str, &str)]) -> Node {
+        let mut props: HashMap<String, String> = props
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        props.insert("media.class".into(), class.into());
+        Node {
+            props,
+            ..Node::default()
+        }
+    }
+
+    const MAC: &str = "AA:BB:CC:DD:EE:FF";
+
+    fn airpods_graph() -> Graph {
+        let mut graph = Graph::default();
+        graph.devices.insert(
+            40,
+            Device {
+                props: [("api.bluez5.address".to_string(), MAC.to_string())].into(),
+                profiles: vec![(1, "a2dp-sink".into()), (2, "headset-head-unit".into())],
+                active_profile: Some("a2dp-sink".into()),
+                routes: vec![(3, 1)],
+            },
+        );
+        graph.nodes.insert(
+            50,
+            node(
+                SINK,
+                &[
+                    ("node.name", "bluez_output.AA_BB_CC_DD_EE_FF.1"),
+                    ("api.bluez5.address", MAC),
+                    ("device.id", "40"),
+                    ("card.profile.device", "1"),
+                ],
+            ),
+        );
+        // A combined sink that also carries the address
+        graph.nodes.insert(
+            51,
+            node(SINK, &[("node.name", "combined"), ("device.string", MAC)]),
+        );
+        graph.nodes.insert(60, node(PLAYBACK_STREAM, &[]));
+        graph.links.insert(70, (60, 50));
+        graph
+    }
+
+    #[test]
+    fn finds_the_devices_own_nodes() {
+        let graph = airpods_graph();
+        assert_eq!(graph.device_for_mac(&MAC.to_lowercase()), Some(40));
+        assert_eq!(graph.node_for_mac(SINK, MAC), Some(50));
+        assert_eq!(graph.node_for_mac(SOURCE, MAC), None);
+        assert_eq!(graph.node_by_name(SINK, "combined"), Some(51));
+        // Volume goes through the device route
+        assert_eq!(graph.route_of(50), Some((40, 3, 1)));
+        assert_eq!(graph.route_of(51), None);
+    }
+
+    #[test]
+    fn active_input_needs_a_running_stream() {
+        let mut graph = airpods_graph();
+        assert!(!graph.has_active_input(50));
+        graph.nodes.get_mut(&60).unwrap().running = true;
+        assert!(graph.has_active_input(50));
+        assert!(!graph.has_active_input(51));
+    }
+
+    #[test]
+    fn monitor_and_meter_streams_are_not_capture() {
+        let mut graph = airpods_graph();
+        graph
+            .nodes
+            .insert(80, node(SOURCE, &[("node.name", "mic")]));
+        // A visualiser on the sink's monitor
+        graph.nodes.insert(90, node(CAPTURE_STREAM, &[]));
+        graph.links.insert(91, (50, 90));
+        assert!(!graph.capture_active());
+        // pavucontrol's level meter on the microphone
+        graph.nodes.insert(
+            92,
+            node(
+                CAPTURE_STREAM,
+                &[("application.id", "org.PulseAudio.pavucontrol")],
+            ),
+        );
+        graph.links.insert(93, (80, 92));
+        assert!(!graph.capture_active());
+        graph.nodes.insert(94, node(CAPTURE_STREAM, &[]));
+        graph.links.insert(95, (80, 94));
+        assert!(graph.capture_active());
+    }
+
+    #[test]
+    fn volume_uses_the_pulseaudio_scale() {
+        assert_eq!(percent_from_linear(&[]), None);
+        assert_eq!(percent_from_linear(&[1.0, 1.0]), Some(100));
+        assert_eq!(percent_from_linear(&[0.125, 0.125]), Some(50));
+        assert_eq!(percent_from_linear(&[linear_from_percent(40); 2]), Some(40));
+        assert_eq!(
+            default_name(r#"{"name":"bluez_output.AA_BB_CC_DD_EE_FF.1"}"#).as_deref(),
+            Some("bluez_output.AA_BB_CC_DD_EE_FF.1")
+        );
+        assert_eq!(default_name("not json"), None);
+    }
+}
@@ -0,0 +1,333 @@
+//! `devices` subcommand: view and edit devices.json from the command line,
+//! e.g. to paste LE keys read from another machine, or to copy the IRK out
+//! of BlueZ's root-only storage through pkexec.
+
+use crate::devices::airpods::AirPodsInformation;
+use crate::devices::enums::{DeviceData, DeviceInformation, DeviceType};
+use crate::devices::store::{self, Devices};
+use bluer::Address;
+use std::io;
+use std::process::Stdio;
+
+/// IRK and EncKey are both 128-bit.
+const KEY_BYTES: usize = 16;
+
+/// BlueZ's per-adapter device storage, readable by root only.
+const BLUEZ_STORAGE: &str = "/var/lib/bluetooth";
+
+#[derive(clap::Subcommand)]
+pub enum DevicesCommand {
+    /// Show the stored devices
+    List {
+        #[arg(long, help = "Print devices.json as JSON")]
+        json: bool,
+    },
+    /// Add a device, or update the given fields of an existing one
+    Add {
+        #[arg(value_name = "MAC")]
+        mac: Address,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(
+            long,
+            value_name = "HEX",
+            value_parser = parse_key,
+            help = "Identity Resolving Key (16 bytes)"
+        )]
+        irk: Option<String>,
+        #[arg(
+            long,
+            value_name = "HEX",
+            value_parser = parse_key,
+            help = "Encryption key (16 bytes)"
+        )]
+        enc_key: Option<String>,
+        #[arg(long, value_name = "HCI", help = "Pin to an adapter, e.g. hci1")]
+        adapter: Option<String>,
+    },
+    /// Copy a device's IRK from BlueZ's storage, asking for admin rights
+    /// through pkexec. The EncKey isn't kept there; it comes over AACP.
+    ImportIrk {
+        #[arg(value_name = "MAC")]
+        mac: Address,
+    },
+    /// Remove a device by MAC or name
+    Remove {
+        #[arg(value_name = "MAC|NAME")]
+        device: String,
+    },
+}
+
+/// A pasted key as lowercase hex; accepts `0x`, spaces, `:` and `-`.
+fn parse_key(s: &str) -> Result<String, String> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    let hex: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':' && *c != '-')
+        .collect();
+    let bytes = hex::decode(&hex).map_err(|e| format!("not hex: {}", e))?;
+    if bytes.len() != KEY_BYTES {
+        return Err(format!("expected {} bytes, got {}", KEY_BYTES, bytes.len()));
+    }
+    Ok(hex::encode(bytes))
+}
+
+struct AddFields {
+    name: Option<String>,
+    irk: Option<String>,
+    enc_key: Option<String>,
+    adapter: Option<String>,
+}
+
+fn apply_add(devices: &mut Devices, mac: &str, fields: AddFields) {
+    let data = devices
+        .entry(mac.to_string())
+        .or_insert_with(|| DeviceData {
+            name: mac.to_string(),
+            type_: DeviceType::AirPods,
+            information: None,
+            volume_swipe: None,
+            volume: None,
+            adapter: None,
+            last_battery: None,
+        });
+    if let Some(name) = fields.name {
+        data.name = name;
+    }
+    if fields.adapter.is_some() {
+        data.adapter = fields.adapter;
+    }
+    if fields.irk.is_none() && fields.enc_key.is_none() {
+        return;
+    }
+    if !matches!(data.information, Some(DeviceInformation::AirPods(_))) {
+        data.information = Some(DeviceInformation::AirPods(AirPodsInformation::default()));
+    }
+    if let Some(DeviceInformation::AirPods(info)) = &mut data.information {
+        if let Some(irk) = fields.irk {
+            info.le_keys.irk = irk;
+        }
+        if let Some(enc_key) = fields.enc_key {
+            info.le_keys.enc_key = enc_key;
+        }
+    }
+}
+
+/// The `[IdentityResolvingKey]` key of a BlueZ device info file. BlueZ
+/// writes it in the same byte order AACP uses, so it is stored as is.
+fn irk_from_info(info: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == "[IdentityResolvingKey]";
+        } else if in_section && let Some(key) = line.strip_prefix("Key=") {
+            return parse_key(key).ok();
+        }
+    }
+    None
+}
+
+/// Privileged half of `devices import-irk`, run as root by pkexec: print
+/// `mac`'s IRK from BlueZ's storage on stdout. It reads nothing but that
+/// device's info file under each adapter.
+pub fn read_irk(mac: Address) -> io::Result<()> {
+    for adapter in std::fs::read_dir(BLUEZ_STORAGE)? {
+        let info = adapter?.path().join(mac.to_string()).join("info");
+        let Ok(text) = std::fs::read_to_string(&info) else {
+            continue;
+        };
+        if let Some(irk) = irk_from_info(&text) {
+            println!("{}", irk);
+            return Ok(());
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "BlueZ has no IRK for {} (it only keeps one after LE pairing)",
+            mac
+        ),
+    ))
+}
+
+/// Run `read_irk` through pkexec and read the key back over its stdout.
+async fn fetch_irk(mac: Address) -> io::Result<String> {
+    let exe = std::env::current_exe()?;
+    let output = tokio::process::Command::new("pkexec")
+        .arg(exe)
+        .arg("read-irk")
+        .arg(mac.to_string())
+        .stderr(Stdio::inherit())
+        .output()
+        .await
+        .map_err(|e| io::Error::new(e.kind(), format!("pkexec: {}", e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "reading the IRK failed ({})",
+            output.status
+        )));
+    }
+    parse_key(String::from_utf8_lossy(&output.stdout).trim())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn list(devices: &Devices) -> String {
+    let mut macs: Vec<&String> = devices.keys().collect();
+    macs.sort();
+    let mut out = String::new();
+    for mac in macs {
+        let data = &devices[mac];
+        let keys = match &data.information {
+            Some(DeviceInformation::AirPods(info)) => {
+                match (info.le_keys.irk.is_empty(), info.le_keys.enc_key.is_empty()) {
+                    (false, false) => "keys",
+                    (true, true) => "no keys",
+                    _ => "partial keys",
+                }
+            }
+            None => "no keys",
+        };
+        out.push_str(&format!(
+            "{}  {}  ({:?}, {}",
+            mac, data.name, data.type_, keys
+        ));
+        if let Some(adapter) = &data.adapter {
+            out.push_str(&format!(", {}", adapter));
+        }
+        out.push_str(")\n");
+    }
+    out
+}
+
+pub async fn run(command: DevicesCommand) -> io::Result<()> {
+    match command {
+        DevicesCommand::List { json } => {
+            let devices = store::load();
+            if json {
+                let out = serde_json::to_string_pretty(&devices).map_err(io::Error::other)?;
+                println!("{}", out);
+            } else if devices.is_empty() {
+                println!(
+                    "No devices in {}",
+                    crate::utils::get_devices_path().display()
+                );
+            } else {
+                print!("{}", list(&devices));
+            }
+        }
+        DevicesCommand::Add {
+            mac,
+            name,
+            irk,
+            enc_key,
+            adapter,
+        } => {
+            let mac = mac.to_string();
+            let fields = AddFields {
+                name,
+                irk,
+                enc_key,
+                adapter,
+            };
+            let key = mac.clone();
+            store::modify(move |devices| apply_add(devices, &key, fields)).await?;
+            println!("Saved {}", mac);
+        }
+        DevicesCommand::ImportIrk { mac } => {
+            let irk = fetch_irk(mac).await?;
+            let fields = AddFields {
+                name: None,
+                irk: Some(irk),
+                enc_key: None,
+                adapter: None,
+            };
+            let key = mac.to_string();
+            store::modify(move |devices| apply_add(devices, &key, fields)).await?;
+            println!("Saved the IRK of {}", mac);
+        }
+        DevicesCommand::Remove { device } => {
+            let devices = store::load();
+            let (addr, data) = crate::bluetooth::link::resolve(&devices, &device)?;
+            let mac = addr.to_string();
+            if data.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in devices.json", mac),
+                ));
+            }
+            let key = mac.clone();
+            store::modify(move |devices| {
+                devices.remove(&key);
+            })
+            .await?;
+            println!("Removed {}", mac);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_keys_are_normalized() {
+        let key = "0x00112233 44556677:8899AABB-CCDDEEFF";
+        assert_eq!(parse_key(key).unwrap(), "00112233445566778899aabbccddeeff");
+        assert!(parse_key("0011").unwrap_err().contains("expected 16 bytes"));
+        assert!(parse_key("zz").is_err());
+    }
+
+    #[test]
+    fn irk_comes_from_its_own_section() {
+        let info = "[General]\nName=AirPods Pro\n\n[LinkKey]\nKey=00112233445566778899AABBCCDDEEFF\n\n\
+                    [IdentityResolvingKey]\nKey=0F0E0D0C0B0A09080706050403020100\n";
+        assert_eq!(
+            irk_from_info(info).as_deref(),
+            Some("0f0e0d0c0b0a09080706050403020100")
+        );
+        assert_eq!(
+            irk_from_info("[LinkKey]\nKey=00112233445566778899AABBCCDDEEFF\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn add_fills_in_without_clobbering() {
+        let mut devices = Devices::new();
+        let mac = "AA:BB:CC:DD:EE:FF";
+        apply_add(
+            &mut devices,
+            mac,
+            AddFields {
+                name: Some("Pods".into()),
+                irk: Some("aa".repeat(16)),
+                enc_key: None,
+                adapter: None,
+            },
+        );
+        apply_add(
+            &mut devices,
+            mac,
+            AddFields {
+                name: None,
+                irk: None,
+                enc_key: Some("bb".repeat(16)),
+                adapter: Some("hci1".into()),
+            },
+        );
+        let data = &devices[mac];
+        assert_eq!(data.name, "Pods");
+        assert_eq!(data.adapter.as_deref(), Some("hci1"));
+        let Some(DeviceInformation::AirPods(info)) = &data.information else {
+            panic!("no info");
+        };
+        assert_eq!(info.le_keys.irk, "aa".repeat(16));
+        assert_eq!(info.le_keys.enc_key, "bb".repeat(16));
+        assert!(list(&devices).contains("Pods  (AirPods, keys, hci1)"));
+    }
+}
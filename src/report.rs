@@ -0,0 +1,430 @@
+//! `report` subcommand: everything known about each device (live state from
+//! the daemon plus what devices.json remembers), for bug reports and for
+//! keeping track of several sets. Key material is reported as present or
+//! missing, never printed.
+
+use crate::bluetooth::aacp::{BatteryComponent, BatteryInfo, BatteryStatus};
+use crate::devices::apple_models::model_info;
+use crate::devices::enums::{DeviceData, DeviceInformation};
+use crate::devices::store;
+use crate::tui::app::{
+    AirPodsDeviceState, App, AppEvent, DeviceCommand, DeviceState, SettingsItem,
+};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// How long to wait for a connected device's battery report.
+const BATTERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Quiet period after which the settings burst is assumed complete.
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Version of the `--json` layout; goes up when keys change incompatibly.
+const JSON_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+}
+
+/// Top level of the `--json` output.
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    version: u32,
+    devices: &'a [DeviceReport],
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DeviceReport {
+    mac: String,
+    name: String,
+    connected: bool,
+    model: Option<String>,
+    model_number: Option<String>,
+    product_id: Option<String>,
+    firmware: Option<String>,
+    hardware_revision: Option<String>,
+    serial_number: Option<String>,
+    left_serial: Option<String>,
+    right_serial: Option<String>,
+    capabilities: Vec<&'static str>,
+    settings: Vec<Setting>,
+    audio_profile: Option<String>,
+    irk: bool,
+    enc_key: bool,
+    battery: Vec<Battery>,
+    /// Unix time of the battery report when it comes from a past session.
+    battery_from: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Setting {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Battery {
+    component: &'static str,
+    level: u8,
+    status: &'static str,
+}
+
+pub async fn run(
+    app_rx: UnboundedReceiver<AppEvent>,
+    cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+    format: ReportFormat,
+    output: Option<PathBuf>,
+) -> io::Result<()> {
+    let mut app = App::new(app_rx, cmd_tx);
+    collect(&mut app).await;
+    let reports = build(&mut app, store::load());
+    let text = match format {
+        ReportFormat::Json => json(&reports)?,
+        ReportFormat::Markdown => markdown(&reports),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text + "\n")?;
+            eprintln!("Report written to {}", path.display());
+            Ok(())
+        }
+        None => writeln!(io::stdout().lock(), "{}", text),
+    }
+}
+
+/// Feed the app until every connected device has a fresh battery report
+/// and the connect-time burst has died down, or the timeout passes.
+async fn collect(app: &mut App) {
+    let deadline = tokio::time::Instant::now() + BATTERY_TIMEOUT;
+    loop {
+        let settled = !app.device_order.is_empty() && all_have_battery(app);
+        let wait_until = if settled {
+            (tokio::time::Instant::now() + SETTLE).min(deadline)
+        } else {
+            deadline
+        };
+        match tokio::time::timeout_at(wait_until, app.rx.recv()).await {
+            Ok(Some(event)) => app.handle_event(event),
+            Ok(None) | Err(_) => break,
+        }
+    }
+}
+
+fn all_have_battery(app: &App) -> bool {
+    app.devices.values().all(|d| match d {
+        DeviceState::AirPods(s) => {
+            !s.battery_cached
+                && (s.battery_left.is_some()
+                    || s.battery_right.is_some()
+                    || s.battery_headphone.is_some())
+        }
+        _ => true,
+    })
+}
+
+/// Connected devices first (in UI order), then ones only devices.json knows.
+fn build(app: &mut App, mut stored: store::Devices) -> Vec<DeviceReport> {
+    let mut reports = Vec::new();
+    for idx in 0..app.device_order.len() {
+        app.selected_device_idx = idx;
+        let mac = app.device_order[idx].clone();
+        let Some(DeviceState::AirPods(s)) = app.devices.get(&mac) else {
+            continue;
+        };
+        let mut report = DeviceReport::default();
+        apply_stored(&mut report, stored.remove(&mac));
+        apply_live(&mut report, s);
+        report.mac = mac;
+        report.settings = app.settings_items().iter().map(setting).collect();
+        reports.push(report);
+    }
+    let mut offline: Vec<_> = stored.into_iter().collect();
+    offline.sort_by(|a, b| a.0.cmp(&b.0));
+    for (mac, data) in offline {
+        let mut report = DeviceReport {
+            mac,
+            ..Default::default()
+        };
+        apply_stored(&mut report, Some(data));
+        reports.push(report);
+    }
+    reports
+}
+
+fn apply_stored(report: &mut DeviceReport, data: Option<DeviceData>) {
+    let Some(data) = data else {
+        return;
+    };
+    report.name = data.name;
+    if let Some(DeviceInformation::AirPods(info)) = data.information {
+        let known = |s: String| (!s.is_empty()).then_some(s);
+        report.model_number = known(info.model_number);
+        report.firmware = known(info.version1);
+        report.hardware_revision = known(info.hardware_revision);
+        report.serial_number = known(info.serial_number);
+        report.left_serial = known(info.left_serial_number);
+        report.right_serial = known(info.right_serial_number);
+        report.irk = !info.le_keys.irk.is_empty();
+        report.enc_key = !info.le_keys.enc_key.is_empty();
+    }
+    if let Some(last) = data.last_battery {
+        report.battery = batteries_from_infos(&last.components);
+        report.battery_from = Some(last.at);
+    }
+}
+
+/// Live values win over stored ones; stored ones fill whatever the device
+/// hasn't reported yet this session.
+fn apply_live(report: &mut DeviceReport, s: &AirPodsDeviceState) {
+    let info = model_info(s.product_id);
+    report.name = s.name.clone();
+    report.connected = !s.link_lost;
+    report.model = s.model.clone().or_else(|| Some(info.name.to_string()));
+    report.product_id = Some(format!("0x{:04x}", s.product_id));
+    let prefer = |live: &Option<String>, stored: &mut Option<String>| {
+        if live.is_some() {
+            *stored = live.clone();
+        }
+    };
+    prefer(&s.firmware, &mut report.firmware);
+    prefer(&s.hardware_revision, &mut report.hardware_revision);
+    prefer(&s.serial_number, &mut report.serial_number);
+    prefer(&s.left_serial, &mut report.left_serial);
+    prefer(&s.right_serial, &mut report.right_serial);
+    report.capabilities = [
+        ("anc", s.has_anc),
+        ("adaptive", s.has_adaptive),
+        ("stem_controls", info.has_stem_controls),
+        ("conversation_awareness", info.has_conversation_awareness),
+    ]
+    .into_iter()
+    .filter_map(|(name, has)| has.then_some(name))
+    .collect();
+    report.audio_profile = s.audio_profile.clone();
+
+    let live: Vec<Battery> = [
+        (BatteryComponent::Left, s.battery_left),
+        (BatteryComponent::Right, s.battery_right),
+        (BatteryComponent::Case, s.battery_case),
+        (BatteryComponent::Headphone, s.battery_headphone),
+    ]
+    .into_iter()
+    .filter_map(|(component, b)| b.and_then(|(level, status)| battery(component, level, status)))
+    .collect();
+    if !live.is_empty() && !s.battery_cached {
+        report.battery = live;
+        report.battery_from = None;
+    }
+}
+
+fn batteries_from_infos(infos: &[BatteryInfo]) -> Vec<Battery> {
+    infos
+        .iter()
+        .filter_map(|b| battery(b.component, b.level, b.status))
+        .collect()
+}
+
+fn battery(component: BatteryComponent, level: u8, status: BatteryStatus) -> Option<Battery> {
+    let status = match status {
+        BatteryStatus::Charging => "charging",
+        BatteryStatus::NotCharging | BatteryStatus::InUse => "discharging",
+        BatteryStatus::Disconnected => return None,
+    };
+    let component = match component {
+        BatteryComponent::Left => "left",
+        BatteryComponent::Right => "right",
+        BatteryComponent::Case => "case",
+        BatteryComponent::Headphone => "headphone",
+    };
+    Some(Battery {
+        component,
+        level,
+        status,
+    })
+}
+
+fn setting(item: &SettingsItem) -> Setting {
+    let on_off = |v: bool| if v { "on" } else { "off" }.to_string();
+    let (name, value) = match item {
+        SettingsItem::Toggle { label, value, .. } => (*label, on_off(*value)),
+        SettingsItem::Enum {
+            label,
+            value,
+            options,
+            ..
+        } => (
+            *label,
+            options
+                .get(*value as usize)
+                .map_or_else(|| value.to_string(), |o| o.to_string()),
+        ),
+        SettingsItem::Slider { label, value, .. } => (*label, value.to_string()),
+        SettingsItem::CycleBit { label, value, .. } => (*label, on_off(*value)),
+        SettingsItem::HoldMode { label, value, .. } => (
+            *label,
+            if *value == 1 { "Siri" } else { "Noise Control" }.to_string(),
+        ),
+    };
+    Setting { name, value }
+}
+
+fn json(reports: &[DeviceReport]) -> io::Result<String> {
+    let report = JsonReport {
+        version: JSON_VERSION,
+        devices: reports,
+    };
+    serde_json::to_string_pretty(&report).map_err(io::Error::other)
+}
+
+fn markdown(reports: &[DeviceReport]) -> String {
+    if reports.is_empty() {
+        return "No devices connected or remembered.".to_string();
+    }
+    let mut out = String::new();
+    for r in reports {
+        let state = if r.connected {
+            "connected"
+        } else {
+            "not connected"
+        };
+        out.push_str(&format!("## {} ({}, {})\n\n", r.name, r.mac, state));
+        let rows = [
+            ("Model", &r.model),
+            ("Model number", &r.model_number),
+            ("Product ID", &r.product_id),
+            ("Firmware", &r.firmware),
+            ("Hardware revision", &r.hardware_revision),
+            ("Serial", &r.serial_number),
+            ("Left serial", &r.left_serial),
+            ("Right serial", &r.right_serial),
+            ("Audio profile", &r.audio_profile),
+        ];
+        for (label, value) in rows {
+            if let Some(value) = value {
+                out.push_str(&format!("- {}: {}\n", label, value));
+            }
+        }
+        if !r.capabilities.is_empty() {
+            out.push_str(&format!("- Capabilities: {}\n", r.capabilities.join(", ")));
+        }
+        let present = |has: bool| if has { "present" } else { "missing" };
+        out.push_str(&format!(
+            "- LE keys: IRK {}, ENC_KEY {}\n",
+            present(r.irk),
+            present(r.enc_key)
+        ));
+
+        if !r.battery.is_empty() {
+            out.push_str("\n### Battery");
+            if let Some(at) = r.battery_from {
+                out.push_str(&format!(" (last session, unix time {})", at));
+            }
+            out.push_str("\n\n");
+            for b in &r.battery {
+                out.push_str(&format!("- {}: {}% ({})\n", b.component, b.level, b.status));
+            }
+        }
+        if !r.settings.is_empty() {
+            out.push_str("\n### Settings\n\n");
+            for s in &r.settings {
+                out.push_str(&format!("- {}: {}\n", s.name, s.value));
+            }
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::aacp::AirPodsLEKeys;
+    use crate::devices::airpods::AirPodsInformation;
+    use crate::devices::enums::{DeviceType, StoredBattery};
+
+    const MAC: &str = "AA:BB:CC:DD:EE:FF";
+
+    fn stored() -> DeviceData {
+        DeviceData {
+            name: "Old Name".into(),
+            type_: DeviceType::AirPods,
+            information: Some(DeviceInformation::AirPods(AirPodsInformation {
+                name: "Old Name".into(),
+                model_number: "A2931".into(),
+                manufacturer: "Apple Inc.".into(),
+                serial_number: "SERIAL".into(),
+                version1: "6A300".into(),
+                version2: String::new(),
+                hardware_revision: "1.0.0".into(),
+                updater_identifier: String::new(),
+                left_serial_number: "LEFT".into(),
+                right_serial_number: String::new(),
+                version3: String::new(),
+                le_keys: AirPodsLEKeys {
+                    irk: "00112233".into(),
+                    enc_key: String::new(),
+                },
+            })),
+            volume_swipe: None,
+            volume: None,
+            adapter: None,
+            last_battery: Some(StoredBattery {
+                at: 100,
+                components: vec![BatteryInfo {
+                    component: BatteryComponent::Left,
+                    level: 40,
+                    status: BatteryStatus::NotCharging,
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn live_state_overrides_stored_info() {
+        let mut report = DeviceReport::default();
+        apply_stored(&mut report, Some(stored()));
+        assert_eq!(report.battery_from, Some(100));
+
+        let mut s = AirPodsDeviceState::new("My Pods".into());
+        s.product_id = 0x2014;
+        s.firmware = Some("7A100".into());
+        s.battery_right = Some((90, BatteryStatus::Charging));
+        apply_live(&mut report, &s);
+
+        assert_eq!(report.name, "My Pods");
+        assert_eq!(report.firmware.as_deref(), Some("7A100"));
+        assert_eq!(report.serial_number.as_deref(), Some("SERIAL"));
+        assert!(report.irk && !report.enc_key);
+        assert_eq!(report.battery.len(), 1);
+        assert_eq!(report.battery[0].component, "right");
+        assert_eq!(report.battery_from, None);
+    }
+
+    #[test]
+    fn markdown_lists_offline_devices_without_key_material() {
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        let reports = build(
+            &mut app,
+            store::Devices::from([(MAC.to_string(), stored())]),
+        );
+        let text = markdown(&reports);
+
+        assert!(text.starts_with("## Old Name (AA:BB:CC:DD:EE:FF, not connected)"));
+        assert!(text.contains("- Model number: A2931\n"));
+        assert!(text.contains("- LE keys: IRK present, ENC_KEY missing\n"));
+        assert!(text.contains("### Battery (last session, unix time 100)"));
+        assert!(text.contains("- left: 40% (discharging)"));
+        assert!(!text.contains("00112233"));
+
+        let v: serde_json::Value = serde_json::from_str(&json(&reports).unwrap()).unwrap();
+        assert_eq!(v["version"], 1);
+        assert_eq!(v["devices"][0]["irk"], true);
+        assert_eq!(v["devices"][0]["connected"], false);
+    }
+}
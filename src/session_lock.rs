@@ -0,0 +1,101 @@
+//! Session lock state from logind.
+//!
+//! Automation that starts audio (ear-detection resume, A2DP activation on
+//! insertion, advert-driven auto-connect) checks [`is_locked`] so putting
+//! the buds in at the lock screen doesn't start playback behind it.
+
+use futures::StreamExt;
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Wait before following the lock state again after logind's connection
+/// dropped or the session couldn't be looked up.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Whether the graphical session is currently locked. Always false unless
+/// [`watch`] is running.
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Follow `LockedHint` on the user's display session, starting over
+/// whenever the bus connection goes away.
+pub async fn watch() {
+    let mut failing = false;
+    loop {
+        match watch_inner().await {
+            Ok(()) => {
+                info!("Lost the session lock state, following it again");
+                failing = false;
+            }
+            // Once per outage; without a graphical session it never ends
+            Err(e) if !failing => {
+                warn!("Session lock tracking unavailable: {}", e);
+                failing = true;
+            }
+            Err(e) => debug!("Session lock tracking still unavailable: {}", e),
+        }
+        // Not tracking: nothing is held back
+        LOCKED.store(false, Ordering::Relaxed);
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+async fn watch_inner() -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let manager = zbus::Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )
+    .await?;
+    // Resolve through the user's display session: the daemon usually runs
+    // from the systemd user manager, which isn't itself part of a session.
+    let uid = crate::utils::current_uid().map_err(|e| zbus::Error::Failure(e.to_string()))?;
+    let user_path: zbus::zvariant::OwnedObjectPath = manager.call("GetUser", &(uid,)).await?;
+    let user = zbus::Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        user_path,
+        "org.freedesktop.login1.User",
+    )
+    .await?;
+    let (session_id, session_path): (String, zbus::zvariant::OwnedObjectPath) =
+        user.get_property("Display").await?;
+    if session_id.is_empty() {
+        return Err(zbus::Error::Failure("no graphical session".into()));
+    }
+    let session = zbus::Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        session_path,
+        "org.freedesktop.login1.Session",
+    )
+    .await?;
+
+    let locked: bool = session.get_property("LockedHint").await?;
+    LOCKED.store(locked, Ordering::Relaxed);
+    debug!(
+        "Tracking lock state of session {} (locked={})",
+        session_id, locked
+    );
+
+    let mut changes = session.receive_property_changed::<bool>("LockedHint").await;
+    while let Some(change) = changes.next().await {
+        let Ok(locked) = change.get().await else {
+            continue;
+        };
+        if LOCKED.swap(locked, Ordering::Relaxed) != locked {
+            if locked {
+                info!("Session locked, inserting the buds won't start audio");
+            } else {
+                info!("Session unlocked, inserting the buds starts audio again");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,277 @@
+//! `status` subcommand: flat device state for scripts and widgets.
+//!
+//! One line per change with `--follow`, which is what eww's `deflisten`
+//! expects; otherwise a single line once battery levels have arrived.
+//! `--template` renders the same fields into a user string for genmon,
+//! tmux and shell prompts.
+
+use crate::bluetooth::aacp::{BatteryStatus, EarDetectionStatus};
+use crate::tui::app::{AirPodsDeviceState, App, AppEvent, DeviceCommand, DeviceState};
+use crate::waybar;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// Version of the `status` and `--waybar` JSON. Bumped only when a field is
+/// removed or changes meaning; new fields can appear without a bump.
+pub const OUTPUT_VERSION: u32 = 1;
+
+/// How long a one-shot `status` waits for the first battery report.
+const ONE_SHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatusFormat {
+    /// One JSON object per line
+    #[value(alias = "eww")]
+    Json,
+    /// Space-separated key=value pairs
+    Kv,
+}
+
+pub async fn run(
+    app_rx: UnboundedReceiver<AppEvent>,
+    cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+    follow: bool,
+    format: StatusFormat,
+    template: Option<String>,
+) -> std::io::Result<()> {
+    let output = Output {
+        format,
+        template: template.as_deref(),
+    };
+    let mut app = App::new(app_rx, cmd_tx);
+    let deadline = tokio::time::Instant::now() + ONE_SHOT_TIMEOUT;
+    let mut last_line = String::new();
+
+    loop {
+        let line = render(&app, output);
+        if follow && line != last_line {
+            println!("{}", line);
+            last_line = line;
+        }
+        if !follow && has_battery(&app) {
+            break;
+        }
+
+        let event = if follow {
+            app.rx.recv().await
+        } else {
+            match tokio::time::timeout_at(deadline, app.rx.recv()).await {
+                Ok(event) => event,
+                Err(_) => break,
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+        app.handle_event(event);
+        while let Ok(event) = app.rx.try_recv() {
+            app.handle_event(event);
+        }
+    }
+
+    if !follow {
+        println!("{}", render(&app, output));
+    }
+    Ok(())
+}
+
+/// Print the state a daemon returned for `get_state` once, the same way
+/// a one-shot `run` would.
+pub fn print_state(events: Vec<AppEvent>, format: StatusFormat, template: Option<&str>) {
+    let (_, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, _) = tokio::sync::mpsc::unbounded_channel();
+    let mut app = App::new(rx, tx);
+    for event in events {
+        app.handle_event(event);
+    }
+    println!("{}", render(&app, Output { format, template }));
+}
+
+fn has_battery(app: &App) -> bool {
+    matches!(app.selected_device(), Some(DeviceState::AirPods(s))
+        if s.battery_left.is_some() || s.battery_right.is_some() || s.battery_headphone.is_some())
+}
+
+#[derive(Clone, Copy)]
+struct Output<'a> {
+    format: StatusFormat,
+    template: Option<&'a str>,
+}
+
+fn render(app: &App, output: Output) -> String {
+    let state = match (app.selected_mac(), app.selected_device()) {
+        (Some(mac), Some(DeviceState::AirPods(s))) => Some((mac.as_str(), &**s)),
+        _ => None,
+    };
+    let fields = fields(state);
+    if let Some(template) = output.template {
+        return render_template(template, &fields);
+    }
+    match output.format {
+        StatusFormat::Json => Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+        .to_string(),
+        StatusFormat::Kv => fields
+            .into_iter()
+            .map(|(k, v)| match v {
+                Value::Null => format!("{}=", k),
+                Value::String(s) => format!("{}={}", k, s.replace(' ', "_")),
+                other => format!("{}={}", k, other),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// `template` filled from `airpods-battery.env` levels, for `battery` when
+/// no daemon is running. Only the battery placeholders have values.
+pub fn render_battery_env(template: &str, levels: &[(String, u8)]) -> String {
+    let mut fields = fields(None);
+    for (key, value) in &mut fields {
+        if let Some((_, level)) = levels.iter().find(|(k, _)| k == key) {
+            *value = (*level).into();
+        }
+    }
+    render_template(template, &fields)
+}
+
+/// Replace `{key}` with the field's value; nulls render empty and unknown
+/// placeholders are left as written.
+fn render_template(template: &str, fields: &[(&'static str, Value)]) -> String {
+    let mut out = template.to_string();
+    for (key, value) in fields {
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        out = out.replace(&format!("{{{}}}", key), &text);
+    }
+    out
+}
+
+/// Every field is always present so widgets never hit a missing key;
+/// unknown values are null.
+fn fields(state: Option<(&str, &AirPodsDeviceState)>) -> Vec<(&'static str, Value)> {
+    let Some((mac, s)) = state else {
+        return vec![
+            ("version", OUTPUT_VERSION.into()),
+            ("connected", false.into()),
+            ("mac", Value::Null),
+            ("name", Value::Null),
+            ("left", Value::Null),
+            ("right", Value::Null),
+            ("case", Value::Null),
+            ("headphone", Value::Null),
+            ("charging_left", false.into()),
+            ("charging_right", false.into()),
+            ("charging_case", false.into()),
+            ("in_ear_left", false.into()),
+            ("in_ear_right", false.into()),
+            ("noise_mode", Value::Null),
+        ];
+    };
+    let level = |b: Option<(u8, BatteryStatus)>| b.map_or(Value::Null, |(l, _)| l.into());
+    let charging = |b: Option<(u8, BatteryStatus)>| {
+        Value::Bool(matches!(b, Some((_, BatteryStatus::Charging))))
+    };
+    let in_ear = |e: Option<EarDetectionStatus>| Value::Bool(e == Some(EarDetectionStatus::InEar));
+    let case = s
+        .battery_case
+        .filter(|(_, st)| *st != BatteryStatus::Disconnected);
+    vec![
+        ("version", OUTPUT_VERSION.into()),
+        ("connected", true.into()),
+        ("mac", mac.into()),
+        ("name", s.name.clone().into()),
+        ("left", level(s.battery_left)),
+        ("right", level(s.battery_right)),
+        ("case", level(case)),
+        ("headphone", level(s.battery_headphone)),
+        ("charging_left", charging(s.battery_left)),
+        ("charging_right", charging(s.battery_right)),
+        ("charging_case", charging(case)),
+        ("in_ear_left", in_ear(s.ear_left)),
+        ("in_ear_right", in_ear(s.ear_right)),
+        (
+            "noise_mode",
+            if s.has_anc {
+                waybar::noise_mode_slug(&s.listening_mode).into()
+            } else {
+                Value::Null
+            },
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pods() -> AirPodsDeviceState {
+        let mut s = AirPodsDeviceState::new("My Pods".into());
+        s.battery_left = Some((80, BatteryStatus::Charging));
+        s.ear_right = Some(EarDetectionStatus::InEar);
+        s.has_anc = true;
+        s
+    }
+
+    #[test]
+    fn json_has_flat_fields() {
+        let f = fields(Some(("AA:BB:CC:DD:EE:FF", &pods())));
+        let v = Value::Object(f.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+        assert_eq!(v["left"], 80);
+        assert_eq!(v["charging_left"], true);
+        assert!(v["right"].is_null());
+        assert_eq!(v["in_ear_right"], true);
+        assert_eq!(v["noise_mode"], "anc");
+    }
+
+    #[test]
+    fn template_fills_placeholders() {
+        let f = fields(Some(("AA:BB:CC:DD:EE:FF", &pods())));
+        assert_eq!(
+            render_template("{name}: {left}%/{right}% {bogus}", &f),
+            "My Pods: 80%/% {bogus}"
+        );
+    }
+
+    #[test]
+    fn battery_env_fills_battery_placeholders() {
+        let levels = [("left".to_string(), 70), ("case".to_string(), 5)];
+        assert_eq!(
+            render_battery_env("{left} {right} {case} {name}", &levels),
+            "70  5 "
+        );
+    }
+
+    #[test]
+    fn disconnected_has_same_keys() {
+        let on: Vec<_> = fields(Some(("m", &pods())))
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        let off: Vec<_> = fields(None).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(on, off);
+    }
+
+    #[test]
+    fn kv_escapes_spaces_and_blanks_nulls() {
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let app = App::new(rx, cmd_tx);
+        let line = render(
+            &app,
+            Output {
+                format: StatusFormat::Kv,
+                template: None,
+            },
+        );
+        assert!(line.starts_with("version=1 connected=false mac= "));
+    }
+}
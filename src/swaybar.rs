@@ -0,0 +1,144 @@
+//! i3bar/swaybar status protocol (`--swaybar`).
+//!
+//! Writes the protocol header, then an endless JSON array with one status
+//! line (a single `airpods` block) per change. Click events arrive on stdin
+//! and map onto the same actions as waybar's `--click`.
+
+use crate::config::Config;
+use crate::tui::app::{App, AppEvent, DeviceCommand, DeviceState};
+use crate::waybar::{self, ClickAction, Level};
+use log::{debug, info};
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+const BLOCK_NAME: &str = "airpods";
+
+const HEADER: &str = r#"{"version":1,"click_events":true}"#;
+
+pub async fn run(
+    app_rx: UnboundedReceiver<AppEvent>,
+    cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+    config: Config,
+) -> std::io::Result<()> {
+    let mut app = App::new(app_rx, cmd_tx);
+    let mut stdin = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    let mut stdin_open = true;
+
+    println!("{}", HEADER);
+    println!("[");
+    let mut last_line = String::new();
+    loop {
+        let line = status_line(&app, &config);
+        if line != last_line {
+            println!("{},", line);
+            last_line = line;
+        }
+
+        tokio::select! {
+            event = app.rx.recv() => {
+                let Some(event) = event else {
+                    info!("Swaybar: event source closed");
+                    return Ok(());
+                };
+                app.handle_event(event);
+                while let Ok(event) = app.rx.try_recv() {
+                    app.handle_event(event);
+                }
+            }
+            input = stdin.next_line(), if stdin_open => match input {
+                Ok(Some(input)) => {
+                    if let Some(action) = parse_click(&input) {
+                        click(&mut app, action);
+                    }
+                }
+                // swaybar closed our stdin: clicks are gone, keep reporting
+                _ => stdin_open = false,
+            },
+        }
+    }
+}
+
+fn click(app: &mut App, action: ClickAction) {
+    let Some(mac) = app.selected_mac().cloned() else {
+        return;
+    };
+    let command = match app.selected_device() {
+        Some(DeviceState::AirPods(s)) => waybar::click_command(action, s),
+        Some(DeviceState::Battery(_)) | None => None,
+    };
+    if let (Some(command), Some(tx)) = (command, app.command_tx.as_ref()) {
+        debug!("Swaybar click: {:?}", action);
+        let _ = tx.send((mac, command));
+    }
+}
+
+/// One status line: a JSON array holding the AirPods block.
+fn status_line(app: &App, config: &Config) -> String {
+    let block = match app.selected_device() {
+        Some(DeviceState::AirPods(s)) => {
+            let st = waybar::status(s, &config.waybar);
+            serde_json::json!({
+                "name": BLOCK_NAME,
+                "full_text": st.text,
+                "short_text": format!("{}%", st.percentage),
+                "urgent": st.level == Level::Critical,
+            })
+        }
+        Some(DeviceState::Battery(s)) => serde_json::json!({
+            "name": BLOCK_NAME,
+            "full_text": format!("{}%", s.level),
+        }),
+        None => serde_json::json!({
+            "name": BLOCK_NAME,
+            "full_text": "",
+        }),
+    };
+    serde_json::json!([block]).to_string()
+}
+
+/// Decode one line of the click-event stream. The stream is itself an
+/// endless array, so lines carry a leading `[` or `,`.
+fn parse_click(line: &str) -> Option<ClickAction> {
+    let json = line.trim().trim_start_matches(['[', ',']).trim();
+    let event: serde_json::Value = serde_json::from_str(json).ok()?;
+    if event.get("name")?.as_str()? != BLOCK_NAME {
+        return None;
+    }
+    match event.get("button")?.as_u64()? {
+        1 => Some(ClickAction::ToggleAnc),
+        4 => Some(ClickAction::VolumeUp),
+        5 => Some(ClickAction::VolumeDown),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_click_lines_with_array_punctuation() {
+        assert_eq!(
+            parse_click(r#"[{"name":"airpods","button":1}"#),
+            Some(ClickAction::ToggleAnc)
+        );
+        assert_eq!(
+            parse_click(r#",{"name":"airpods","button":5,"x":3}"#),
+            Some(ClickAction::VolumeDown)
+        );
+        assert_eq!(parse_click("["), None);
+        assert_eq!(parse_click(r#"{"name":"clock","button":1}"#), None);
+        assert_eq!(parse_click(r#"{"name":"airpods","button":3}"#), None);
+    }
+
+    #[test]
+    fn status_line_is_single_block_array() {
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let app = App::new(rx, cmd_tx);
+        let v: serde_json::Value =
+            serde_json::from_str(&status_line(&app, &Config::default())).unwrap();
+        assert_eq!(v[0]["name"], "airpods");
+        assert_eq!(v.as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+//! sd_notify for `--daemon` under a `Type=notify` unit: READY=1 once the
+//! Bluetooth side is up, and WATCHDOG=1 pings while the event loop and the
+//! AACP link checks keep moving. Everything is a no-op outside systemd.
+
+use log::{debug, warn};
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Send `state` (e.g. "READY=1") to the service manager, if there is one.
+pub fn notify(state: &str) {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    match send(&path, state) {
+        Ok(()) => debug!("sd_notify: {}", state),
+        Err(e) => warn!("sd_notify {} failed: {}", state, e),
+    }
+}
+
+fn send(path: &OsStr, state: &str) -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+    // "@name" is a socket in the abstract namespace
+    if let Some(name) = path.as_bytes().strip_prefix(b"@") {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+    } else {
+        socket.send_to(state.as_bytes(), path)?;
+    }
+    Ok(())
+}
+
+/// The unit's `WatchdogSec`, if the watchdog is on and meant for us.
+pub fn watchdog_interval() -> Option<Duration> {
+    parse_watchdog(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+fn parse_watchdog(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if let Some(pid) = pid
+        && pid.parse::<u32>().ok() != Some(own_pid)
+    {
+        return None;
+    }
+    let usec = usec?.parse::<u64>().ok().filter(|&u| u > 0)?;
+    Some(Duration::from_micros(usec))
+}
+
+/// When the daemon's event loop picked up the event it is still handling;
+/// None while it waits for the next one.
+#[derive(Clone, Default)]
+pub struct Heartbeat(Arc<Mutex<Option<Instant>>>);
+
+impl Heartbeat {
+    pub fn busy(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+
+    pub fn idle(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Busy until the guard drops, however the work ends.
+    pub fn beat(&self) -> BeatGuard<'_> {
+        self.busy();
+        BeatGuard(self)
+    }
+
+    fn stalled(&self, limit: Duration) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|since| since.elapsed() > limit)
+    }
+}
+
+pub struct BeatGuard<'a>(&'a Heartbeat);
+
+impl Drop for BeatGuard<'_> {
+    fn drop(&mut self) {
+        self.0.idle();
+    }
+}
+
+/// Busy while an AACP link check runs, so a session stuck on its state
+/// lock or a blocked send stops the pings too.
+pub static AACP_HEARTBEAT: LazyLock<Heartbeat> = LazyLock::new(Heartbeat::default);
+
+/// Ping the watchdog at half its interval. Any heartbeat busy for a whole
+/// interval stops the pings, so systemd restarts the daemon.
+pub async fn watchdog(interval: Duration, heartbeats: Vec<(&'static str, Heartbeat)>) {
+    let mut tick = tokio::time::interval(interval / 2);
+    loop {
+        tick.tick().await;
+        if let Some((name, _)) = heartbeats.iter().find(|(_, hb)| hb.stalled(interval)) {
+            warn!(
+                "{} stuck for over {:?}; skipping watchdog ping",
+                name, interval
+            );
+            continue;
+        }
+        notify("WATCHDOG=1");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_only_for_our_pid() {
+        let s = Some(Duration::from_secs(30));
+        assert_eq!(parse_watchdog(Some("30000000"), None, 42), s);
+        assert_eq!(parse_watchdog(Some("30000000"), Some("42"), 42), s);
+        assert_eq!(parse_watchdog(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(parse_watchdog(Some("0"), None, 42), None);
+        assert_eq!(parse_watchdog(None, None, 42), None);
+    }
+
+    #[test]
+    fn notify_reaches_socket() {
+        let path = std::env::temp_dir().join(format!("airpods-tui-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        send(path.as_os_str(), "READY=1").unwrap();
+        let mut buf = [0u8; 16];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn heartbeat_stalls_only_while_busy() {
+        let hb = Heartbeat::default();
+        assert!(!hb.stalled(Duration::ZERO));
+        hb.busy();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(hb.stalled(Duration::from_millis(1)));
+        hb.idle();
+        assert!(!hb.stalled(Duration::ZERO));
+
+        let guard = hb.beat();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(hb.stalled(Duration::from_millis(1)));
+        drop(guard);
+        assert!(!hb.stalled(Duration::ZERO));
+    }
+}
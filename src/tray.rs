@@ -0,0 +1,495 @@
+//! System tray icon (StatusNotifierItem) with a dbusmenu context menu.
+//!
+//! Speaks the SNI and `com.canonical.dbusmenu` protocols directly over
+//! zbus, the same D-Bus stack the rest of the app uses. State comes from
+//! the same AppEvent stream the TUI consumes, so the tray is just another
+//! view on top of [`App`].
+
+use crate::config::{self, Config};
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::tui::app::{App, AppEvent, DeviceCommand, DeviceState};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, StructureBuilder, Value};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+
+// Menu item ids. Noise modes use MENU_NOISE_BASE + wire byte.
+const MENU_ROOT: i32 = 0;
+const MENU_NOISE_BASE: i32 = 10;
+const MENU_CA: i32 = 20;
+const MENU_SEPARATOR: i32 = 30;
+const MENU_OPEN_TUI: i32 = 31;
+const MENU_QUIT: i32 = 32;
+
+type SharedApp = Arc<Mutex<App>>;
+/// SNI `ToolTip`: (icon name, icon pixmaps, title, body).
+type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+struct StatusNotifierItem {
+    app: SharedApp,
+    config: Config,
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItem {
+    fn activate(&self, _x: i32, _y: i32) {
+        open_tui(&self.config);
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: &str) {}
+
+    #[zbus(signal)]
+    async fn new_icon(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_tool_tip(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "Hardware"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "airpods-tui"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "AirPods"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        if lock(&self.app).selected_device().is_some() {
+            "Active"
+        } else {
+            "Passive"
+        }
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> String {
+        match min_level(&lock(&self.app)) {
+            Some((level, charging)) => icon_name(level, charging),
+            None => "audio-headphones".to_string(),
+        }
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> ToolTip {
+        let (title, body) = tooltip(&lock(&self.app));
+        ("audio-headphones".to_string(), Vec::new(), title, body)
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).expect("valid menu path")
+    }
+}
+
+struct DbusMenu {
+    app: SharedApp,
+    config: Config,
+    revision: u32,
+    quit_tx: UnboundedSender<()>,
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl DbusMenu {
+    fn get_layout(
+        &self,
+        _parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, Layout)> {
+        menu_layout(&lock(&self.app), self.revision)
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        menu_items(&lock(&self.app))
+            .into_iter()
+            .filter(|(id, _)| ids.is_empty() || ids.contains(id))
+            .map(|(id, props)| (id, owned_props(props)))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: &str) -> zbus::fdo::Result<OwnedValue> {
+        menu_items(&lock(&self.app))
+            .into_iter()
+            .find(|(item, _)| *item == id)
+            .and_then(|(_, props)| props.into_iter().find(|(k, _)| *k == name))
+            .and_then(|(_, v)| OwnedValue::try_from(v).ok())
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("no property {name}")))
+    }
+
+    fn event(&self, id: i32, event_id: &str, _data: Value<'_>, _timestamp: u32) {
+        if event_id == "clicked" {
+            self.on_clicked(id);
+        }
+    }
+
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        for (id, event_id, _, _) in events {
+            if event_id == "clicked" {
+                self.on_clicked(id);
+            }
+        }
+        Vec::new()
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl DbusMenu {
+    fn on_clicked(&self, id: i32) {
+        match id {
+            MENU_OPEN_TUI => open_tui(&self.config),
+            MENU_QUIT => {
+                let _ = self.quit_tx.send(());
+            }
+            MENU_CA => crate::tui::events::toggle_conversation_awareness(&mut lock(&self.app)),
+            id if (MENU_NOISE_BASE..MENU_NOISE_BASE + 5).contains(&id) => {
+                let mode = AirPodsNoiseControlMode::from_byte((id - MENU_NOISE_BASE) as u8);
+                crate::tui::events::set_noise_mode(&mut lock(&self.app), mode);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Run the tray until the session bus goes away or the user picks Quit.
+pub async fn run(
+    mut app_rx: UnboundedReceiver<AppEvent>,
+    cmd_tx: UnboundedSender<(String, DeviceCommand)>,
+    config: Config,
+) -> zbus::Result<()> {
+    // App normally drains its own receiver; here the loop below owns the
+    // real stream so the D-Bus handlers can share the state behind a lock.
+    let (_unused_tx, unused_rx) = unbounded_channel();
+    let app: SharedApp = Arc::new(Mutex::new(App::new(unused_rx, cmd_tx)));
+    let (quit_tx, mut quit_rx) = unbounded_channel();
+
+    let conn = zbus::connection::Builder::session()?
+        .serve_at(
+            ITEM_PATH,
+            StatusNotifierItem {
+                app: app.clone(),
+                config: config.clone(),
+            },
+        )?
+        .serve_at(
+            MENU_PATH,
+            DbusMenu {
+                app: app.clone(),
+                config,
+                revision: 0,
+                quit_tx,
+            },
+        )?
+        .build()
+        .await?;
+
+    // Register under our unique name; hosts then look up ITEM_PATH on it.
+    let service = conn
+        .unique_name()
+        .map(|n| n.to_string())
+        .unwrap_or_default();
+    conn.call_method(
+        Some("org.kde.StatusNotifierWatcher"),
+        "/StatusNotifierWatcher",
+        Some("org.kde.StatusNotifierWatcher"),
+        "RegisterStatusNotifierItem",
+        &(service.as_str(),),
+    )
+    .await?;
+    info!("Tray icon registered as {}", service);
+
+    let item = conn
+        .object_server()
+        .interface::<_, StatusNotifierItem>(ITEM_PATH)
+        .await?;
+    let menu = conn
+        .object_server()
+        .interface::<_, DbusMenu>(MENU_PATH)
+        .await?;
+
+    loop {
+        tokio::select! {
+            event = app_rx.recv() => {
+                let Some(event) = event else {
+                    warn!("Tray: event source closed");
+                    break;
+                };
+                lock(&app).handle_event(event);
+                // Coalesce bursts (snapshot replay, battery + settings) into
+                // a single refresh.
+                while let Ok(event) = app_rx.try_recv() {
+                    lock(&app).handle_event(event);
+                }
+                let status = item.get().await.status().to_string();
+                let emitter = item.signal_emitter();
+                let _ = StatusNotifierItem::new_icon(emitter).await;
+                let _ = StatusNotifierItem::new_tool_tip(emitter).await;
+                let _ = StatusNotifierItem::new_status(emitter, &status).await;
+                let revision = {
+                    let mut m = menu.get_mut().await;
+                    m.revision = m.revision.wrapping_add(1);
+                    m.revision
+                };
+                let _ = DbusMenu::layout_updated(menu.signal_emitter(), revision, MENU_ROOT).await;
+            }
+            _ = quit_rx.recv() => break,
+        }
+    }
+    Ok(())
+}
+
+fn lock(app: &SharedApp) -> std::sync::MutexGuard<'_, App> {
+    app.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn open_tui(config: &Config) {
+    let exe = std::env::current_exe()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "airpods-tui".to_string());
+    debug!("Tray: opening TUI via {:?}", config.tray_terminal_command);
+    let template = config.tray_terminal_command.clone();
+    // run_template_cmd waits for the child; don't block the D-Bus handler.
+    std::thread::spawn(move || config::run_template_cmd(&template, &exe));
+}
+
+/// Lowest bud (or headphone) level and whether it's charging.
+fn min_level(app: &App) -> Option<(u8, bool)> {
+    let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+        return None;
+    };
+    [s.battery_left, s.battery_right, s.battery_headphone]
+        .into_iter()
+        .flatten()
+        .min_by_key(|(level, _)| *level)
+        .map(|(level, status)| {
+            (
+                level,
+                status == crate::bluetooth::aacp::BatteryStatus::Charging,
+            )
+        })
+}
+
+/// Freedesktop icon name in the `battery-level-N[-charging]-symbolic` family.
+fn icon_name(level: u8, charging: bool) -> String {
+    let bucket = (level.min(100) as u32 + 5) / 10 * 10;
+    if charging {
+        format!("battery-level-{}-charging-symbolic", bucket)
+    } else {
+        format!("battery-level-{}-symbolic", bucket)
+    }
+}
+
+fn tooltip(app: &App) -> (String, String) {
+    let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+        return ("AirPods".to_string(), "Not connected".to_string());
+    };
+    let mut lines = Vec::new();
+    for (label, bat) in [
+        ("Left", s.battery_left),
+        ("Right", s.battery_right),
+        ("Case", s.battery_case),
+        ("Battery", s.battery_headphone),
+    ] {
+        if let Some((level, _)) = bat {
+            lines.push(format!("{}: {}%", label, level));
+        }
+    }
+    if s.has_anc {
+        lines.push(s.listening_mode.to_string());
+    }
+    (s.name.clone(), lines.join("\n"))
+}
+
+/// Flat menu: noise modes (radio), Conversation Awareness, Open TUI, Quit.
+fn menu_items(app: &App) -> Vec<(i32, Vec<(&'static str, Value<'static>)>)> {
+    let mut items = Vec::new();
+    if let Some(DeviceState::AirPods(s)) = app.selected_device()
+        && s.has_anc
+    {
+        for mode in crate::tui::ui::noise_mode_list(s.has_adaptive, s.allow_off_mode) {
+            items.push((
+                MENU_NOISE_BASE + mode.to_byte() as i32,
+                vec![
+                    ("label", Value::from(mode.to_string())),
+                    ("toggle-type", Value::from("radio")),
+                    (
+                        "toggle-state",
+                        Value::from((mode == s.listening_mode) as i32),
+                    ),
+                ],
+            ));
+        }
+        if crate::devices::apple_models::model_info(s.product_id).has_conversation_awareness {
+            items.push((
+                MENU_CA,
+                vec![
+                    ("label", Value::from("Conversation Awareness")),
+                    ("toggle-type", Value::from("checkmark")),
+                    ("toggle-state", Value::from(s.conversation_awareness as i32)),
+                ],
+            ));
+        }
+        items.push((MENU_SEPARATOR, vec![("type", Value::from("separator"))]));
+    }
+    items.push((MENU_OPEN_TUI, vec![("label", Value::from("Open TUI"))]));
+    items.push((MENU_QUIT, vec![("label", Value::from("Quit Tray"))]));
+    items
+}
+
+fn owned_props(props: Vec<(&'static str, Value<'static>)>) -> HashMap<String, OwnedValue> {
+    props
+        .into_iter()
+        .filter_map(|(k, v)| OwnedValue::try_from(v).ok().map(|v| (k.to_string(), v)))
+        .collect()
+}
+
+/// The root `(ia{sv}av)` node returned by `GetLayout`.
+type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+/// Build the `GetLayout` reply: the revision plus the root node with one
+/// child per menu item.
+fn menu_layout(app: &App, revision: u32) -> zbus::zvariant::Result<(u32, Layout)> {
+    let children = menu_items(app)
+        .into_iter()
+        .map(|(id, props)| layout_node(id, props, Vec::new()).and_then(OwnedValue::try_from))
+        .collect::<zbus::zvariant::Result<Vec<_>>>()?;
+    Ok((revision, (MENU_ROOT, HashMap::new(), children)))
+}
+
+/// One `(ia{sv}av)` node of a dbusmenu layout.
+fn layout_node(
+    id: i32,
+    props: impl IntoIterator<Item = (&'static str, Value<'static>)>,
+    children: Vec<Value<'static>>,
+) -> zbus::zvariant::Result<Value<'static>> {
+    let props: HashMap<String, Value<'static>> =
+        props.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    StructureBuilder::new()
+        .add_field(id)
+        .add_field(props)
+        .add_field(children)
+        .build()
+        .map(Value::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with(product_id: u16) -> App {
+        let (_tx, rx) = unbounded_channel();
+        let (cmd_tx, _cmd_rx) = unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        app.handle_event(AppEvent::DeviceConnected {
+            mac: "AA:BB:CC:DD:EE:FF".into(),
+            name: "Pods".into(),
+            product_id,
+        });
+        app
+    }
+
+    #[test]
+    fn menu_without_device_only_has_actions() {
+        let (_tx, rx) = unbounded_channel();
+        let (cmd_tx, _cmd_rx) = unbounded_channel();
+        let app = App::new(rx, cmd_tx);
+        let ids: Vec<i32> = menu_items(&app).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![MENU_OPEN_TUI, MENU_QUIT]);
+    }
+
+    #[test]
+    fn menu_lists_noise_modes_and_ca_for_pro2() {
+        let app = app_with(0x2014);
+        let ids: Vec<i32> = menu_items(&app).into_iter().map(|(id, _)| id).collect();
+        assert!(ids.contains(&(MENU_NOISE_BASE + 0x03))); // Transparency
+        assert!(ids.contains(&(MENU_NOISE_BASE + 0x04))); // Adaptive
+        assert!(ids.contains(&MENU_CA));
+    }
+
+    #[test]
+    fn icon_name_rounds_to_nearest_ten() {
+        assert_eq!(icon_name(84, false), "battery-level-80-symbolic");
+        assert_eq!(icon_name(85, true), "battery-level-90-charging-symbolic");
+        assert_eq!(icon_name(0, false), "battery-level-0-symbolic");
+        assert_eq!(icon_name(100, false), "battery-level-100-symbolic");
+    }
+
+    #[test]
+    fn layout_node_has_dbusmenu_signature() {
+        let node = layout_node(0, Vec::new(), Vec::new()).unwrap();
+        assert_eq!(node.value_signature().to_string(), "(ia{sv}av)");
+    }
+
+    #[test]
+    fn get_layout_reply_has_dbusmenu_signature() {
+        let reply = menu_layout(&app_with(0x2014), 7).unwrap();
+        let msg = zbus::Message::method_call(MENU_PATH, "GetLayout")
+            .unwrap()
+            .build(&reply)
+            .unwrap();
+        assert_eq!(msg.body().signature().to_string_no_parens(), "u(ia{sv}av)");
+        assert_eq!(reply.0, 7);
+        assert_eq!(reply.1.0, MENU_ROOT);
+        assert_eq!(reply.1.2.len(), menu_items(&app_with(0x2014)).len());
+    }
+}
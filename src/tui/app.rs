@@ -1,7 +1,8 @@
 use crate::bluetooth::aacp::{
-    AACPEvent, BatteryComponent, BatteryStatus, ConnectedDevice, ControlCommandIdentifiers,
-    EarDetectionStatus,
+    AACPEvent, BatteryComponent, BatteryInfo, BatteryStatus, ConnectedDevice,
+    ControlCommandIdentifiers, EarDetectionStatus,
 };
+use crate::bluetooth::proximity::NearbyAirPods;
 use crate::devices::enums::AirPodsNoiseControlMode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -11,6 +12,10 @@ use tokio::sync::mpsc::UnboundedReceiver;
 pub enum DeviceCommand {
     ControlCommand(ControlCommandIdentifiers, Vec<u8>),
     Rename(String),
+    /// Relative system volume step in percent (waybar scroll).
+    AdjustVolume(i8),
+    /// Mute or unmute the AirPods microphone.
+    ToggleMicMute,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +28,125 @@ pub enum AppEvent {
     DeviceDisconnected(String),
     AACPEvent(String, Box<crate::bluetooth::aacp::AACPEvent>),
     AudioUnavailable,
+    /// The device's audio card switched profile; None when it's off.
+    AudioProfile {
+        mac: String,
+        profile: Option<String>,
+    },
+    MicMuted {
+        mac: String,
+        muted: bool,
+    },
+    /// The MPRIS track the stems control; None when no player is active.
+    NowPlaying {
+        mac: String,
+        track: Option<NowPlaying>,
+    },
+    Playback {
+        mac: String,
+        status: PlaybackStatus,
+    },
+    /// When a source last produced data for the device (unix seconds).
+    /// Sent at most every `LAST_SEEN_STEP_SECS` per source.
+    LastSeen {
+        mac: String,
+        source: DataSource,
+        at: u64,
+    },
+    /// Battery levels stored at the device's last session (`at` is when
+    /// they were reported). Fills in only while no fresh report exists.
+    CachedBattery {
+        mac: String,
+        batteries: Vec<BatteryInfo>,
+        at: u64,
+    },
+    /// Battery of a non-AirPods device from the standard Battery Service
+    /// (`gatt_battery`); creates the device on first report.
+    BatteryDevice {
+        mac: String,
+        name: String,
+        level: u8,
+    },
+    /// The AACP session died while BlueZ still has the device; cleared by
+    /// the next DeviceConnected once the reconnect succeeds.
+    LinkLost(String),
+    /// AirPods advertising nearby that aren't connected here (`nearby_scan`).
+    Nearby(Vec<NearbyAirPods>),
+    /// The case of one of our AirPods just opened nearby; the advert
+    /// carries its battery levels.
+    LidOpened(NearbyAirPods),
+    /// A command sent for the device couldn't be carried out.
+    CommandFailed {
+        mac: String,
+        error: String,
+    },
+}
+
+/// Where a device's data comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DataSource {
+    /// Battery, ear and settings packets on the L2CAP session.
+    Aacp,
+    /// MediaTransport1 updates (volume, transport state) from BlueZ.
+    Avrcp,
+}
+
+/// Granularity of `AppEvent::LastSeen`.
+pub const LAST_SEEN_STEP_SECS: u64 = 30;
+
+/// Data not refreshed for this long is shown as stale.
+pub const STALE_AFTER_SECS: u64 = 180;
+
+/// How long a failed command stays in the footer.
+pub const ERROR_SHOW_FOR: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long the lid-open popup stays up (Esc closes it sooner).
+pub const LID_OPEN_SHOW_FOR: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The time-driven parts of the screen: whether the last error and the
+/// lid-open popup are still shown, and per device the stale minutes and
+/// now-playing second.
+type Clock = (bool, bool, Vec<(Option<u64>, Option<i64>)>);
+
+/// What the automatic media handling is doing, for the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PlaybackStatus {
+    #[default]
+    Idle,
+    Playing,
+    /// Paused by taking the buds out; putting them back resumes.
+    PausedByEar,
+    /// Another Apple device holds the audio session.
+    PeerOwned,
+}
+
+/// Track shown in the now-playing line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub title: String,
+    pub artist: Option<String>,
+    pub playing: bool,
+    /// Position and length in microseconds, as MPRIS reports them.
+    pub position_us: i64,
+    pub length_us: Option<i64>,
+    /// When `position_us` was read (unix milliseconds); 0 if unknown.
+    #[serde(default)]
+    pub position_at_ms: u64,
+}
+
+impl NowPlaying {
+    /// Position at `now_ms`: while playing, `position_us` plus the time
+    /// since it was read, capped at the track length.
+    pub fn position_at(&self, now_ms: u64) -> i64 {
+        if !self.playing || self.position_at_ms == 0 {
+            return self.position_us;
+        }
+        let elapsed_ms = now_ms.saturating_sub(self.position_at_ms);
+        let elapsed_us = i64::try_from(elapsed_ms.saturating_mul(1000)).unwrap_or(i64::MAX);
+        let position = self.position_us.saturating_add(elapsed_us);
+        self.length_us
+            .map_or(position, |length| position.min(length))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -94,9 +218,50 @@ pub struct AirPodsDeviceState {
     pub crown_reversed: Option<bool>,
     // Peer devices
     pub peer_devices: Vec<ConnectedDevice>,
+    /// Active card profile, e.g. "a2dp-sink-aac"; None while off.
+    pub audio_profile: Option<String>,
+    /// AirPods microphone muted (only meaningful on a headset profile).
+    pub mic_muted: bool,
+    pub now_playing: Option<NowPlaying>,
+    pub playback: PlaybackStatus,
+    /// AACP link down; values shown are the last ones received.
+    pub link_lost: bool,
+    /// Unix time each source last produced data.
+    pub last_seen: HashMap<DataSource, u64>,
+    /// Battery values come from the last session, not this one.
+    pub battery_cached: bool,
+}
+
+fn apply_battery(state: &mut AirPodsDeviceState, infos: &[BatteryInfo]) {
+    for b in infos {
+        match b.component {
+            BatteryComponent::Left => {
+                state.battery_left = Some((b.level, b.status));
+            }
+            BatteryComponent::Right => {
+                state.battery_right = Some((b.level, b.status));
+            }
+            BatteryComponent::Case => {
+                // Only update if not disconnected - preserve last known good value
+                if b.status != BatteryStatus::Disconnected {
+                    state.battery_case = Some((b.level, b.status));
+                }
+            }
+            BatteryComponent::Headphone => {
+                state.battery_headphone = Some((b.level, b.status));
+            }
+        }
+    }
 }
 
 impl AirPodsDeviceState {
+    /// Seconds since `source` last produced data, if it has and that was
+    /// at least `STALE_AFTER_SECS` ago.
+    pub fn stale_for(&self, source: DataSource, now: u64) -> Option<u64> {
+        let age = now.saturating_sub(*self.last_seen.get(&source)?);
+        (age >= STALE_AFTER_SECS).then_some(age)
+    }
+
     pub fn new(name: String) -> Self {
         Self {
             name,
@@ -108,15 +273,24 @@ impl AirPodsDeviceState {
     }
 }
 
+/// Earbuds without AACP: just a name and a battery level.
+#[derive(Debug, Clone, Default)]
+pub struct BatteryDeviceState {
+    pub name: String,
+    pub level: u8,
+}
+
 #[derive(Debug, Clone)]
 pub enum DeviceState {
-    AirPods(AirPodsDeviceState),
+    AirPods(Box<AirPodsDeviceState>),
+    Battery(BatteryDeviceState),
 }
 
 impl DeviceState {
     pub fn name(&self) -> &str {
         match self {
             DeviceState::AirPods(s) => &s.name,
+            DeviceState::Battery(s) => &s.name,
         }
     }
 }
@@ -132,7 +306,20 @@ pub struct App {
     pub command_tx: Option<tokio::sync::mpsc::UnboundedSender<(String, DeviceCommand)>>,
     pub rename_mode: Option<String>,
     pub show_info: bool,
+    /// The locate popup: a hot/cold meter per nearby advert.
+    pub locate: bool,
+    /// The history popup, loaded from the daemon's database when opened.
+    pub history: Option<crate::history::HistoryView>,
     pub audio_unavailable: bool,
+    /// Last failed command and when it arrived; the footer shows it briefly.
+    pub last_error: Option<(String, std::time::Instant)>,
+    pub nearby: Vec<NearbyAirPods>,
+    /// The last case opened nearby and when; shown as a popup briefly.
+    pub lid_open: Option<(NearbyAirPods, std::time::Instant)>,
+    pub keys: crate::tui::events::KeyBindings,
+    pub palette: crate::config::Palette,
+    /// `clock()` as of the last `tick`.
+    clock: Clock,
 }
 
 impl App {
@@ -151,7 +338,15 @@ impl App {
             command_tx: Some(command_tx),
             rename_mode: None,
             show_info: false,
+            locate: false,
+            history: None,
             audio_unavailable: false,
+            last_error: None,
+            nearby: Vec::new(),
+            lid_open: None,
+            keys: Default::default(),
+            palette: Default::default(),
+            clock: Default::default(),
         }
     }
 
@@ -381,6 +576,7 @@ impl App {
                 if self.devices.contains_key(&mac) {
                     if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
                         s.name = name;
+                        s.link_lost = false;
                         // AACP events may arrive before DeviceConnected and
                         // auto-create the entry without model info; fill it in.
                         if product_id != 0 && s.product_id == 0 {
@@ -400,7 +596,8 @@ impl App {
                     if product_id != 0 {
                         s.model = Some(info.name.to_string());
                     }
-                    self.devices.insert(mac.clone(), DeviceState::AirPods(s));
+                    self.devices
+                        .insert(mac.clone(), DeviceState::AirPods(Box::new(s)));
                     self.device_order.push(mac);
                 }
             }
@@ -419,14 +616,136 @@ impl App {
             AppEvent::AudioUnavailable => {
                 self.audio_unavailable = true;
             }
+            AppEvent::AudioProfile { mac, profile } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    // The microphone goes away with the headset profile
+                    if !profile.as_deref().is_some_and(|p| p.starts_with("headset")) {
+                        s.mic_muted = false;
+                    }
+                    s.audio_profile = profile;
+                }
+            }
+            AppEvent::MicMuted { mac, muted } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    s.mic_muted = muted;
+                }
+            }
+            AppEvent::NowPlaying { mac, track } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    s.now_playing = track;
+                }
+            }
+            AppEvent::Playback { mac, status } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    s.playback = status;
+                }
+            }
+            AppEvent::LastSeen { mac, source, at } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    s.last_seen.insert(source, at);
+                }
+            }
+            AppEvent::CachedBattery { mac, batteries, at } => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac)
+                    && s.battery_left.is_none()
+                    && s.battery_right.is_none()
+                    && s.battery_headphone.is_none()
+                {
+                    apply_battery(s, &batteries);
+                    s.battery_cached = true;
+                    s.last_seen.entry(DataSource::Aacp).or_insert(at);
+                }
+            }
+            AppEvent::BatteryDevice { mac, name, level } => {
+                if !self.devices.contains_key(&mac) {
+                    self.device_order.push(mac.clone());
+                }
+                self.devices.insert(
+                    mac,
+                    DeviceState::Battery(BatteryDeviceState { name, level }),
+                );
+            }
+            AppEvent::LinkLost(mac) => {
+                if let Some(DeviceState::AirPods(s)) = self.devices.get_mut(&mac) {
+                    s.link_lost = true;
+                }
+            }
+            AppEvent::Nearby(nearby) => {
+                self.nearby = nearby;
+            }
+            AppEvent::LidOpened(nearby) => {
+                self.lid_open = Some((nearby, std::time::Instant::now()));
+            }
+            AppEvent::CommandFailed { mac, error } => {
+                let name = match self.devices.get(&mac) {
+                    Some(DeviceState::AirPods(s)) => s.name.clone(),
+                    _ => mac,
+                };
+                self.last_error = Some((format!("{}: {}", name, error), std::time::Instant::now()));
+            }
         }
     }
 
-    /// Drain all pending AppEvents and update state.
-    pub fn process_events(&mut self) {
+    /// Drain all pending AppEvents and update state. Returns whether any
+    /// arrived, i.e. whether the screen needs redrawing.
+    pub fn process_events(&mut self) -> bool {
+        let mut changed = false;
         while let Ok(event) = self.rx.try_recv() {
             self.handle_event(event);
+            changed = true;
         }
+        changed
+    }
+
+    /// Whether anything on screen moved with time since the last call:
+    /// the error message expired, data went (more) stale, or a playing
+    /// track's position passed a second.
+    pub fn tick(&mut self) -> bool {
+        let clock = self.clock();
+        if clock == self.clock {
+            return false;
+        }
+        self.clock = clock;
+        true
+    }
+
+    fn clock(&self) -> Clock {
+        let now = crate::utils::unix_now();
+        let now_ms = crate::utils::unix_now_ms();
+        let devices = self
+            .device_order
+            .iter()
+            .filter_map(|mac| match self.devices.get(mac) {
+                Some(DeviceState::AirPods(s)) => Some((
+                    s.stale_for(DataSource::Aacp, now).map(|age| age / 60),
+                    s.now_playing
+                        .as_ref()
+                        .map(|t| t.position_at(now_ms) / 1_000_000),
+                )),
+                _ => None,
+            })
+            .collect();
+        (
+            self.shown_error().is_some(),
+            self.shown_lid_open().is_some(),
+            devices,
+        )
+    }
+
+    /// The last failed command, while it's recent enough to show.
+    pub fn shown_error(&self) -> Option<&str> {
+        self.last_error
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < ERROR_SHOW_FOR)
+            .map(|(msg, _)| msg.as_str())
+    }
+
+    /// The case opened nearby, while the popup is still due.
+    pub fn shown_lid_open(&self) -> Option<&NearbyAirPods> {
+        self.lid_open
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < LID_OPEN_SHOW_FOR)
+            .map(|(nearby, _)| nearby)
     }
 
     fn handle_aacp_event(&mut self, mac: &str, event: AACPEvent) {
@@ -434,7 +753,7 @@ impl App {
             let mac_owned = mac.to_string();
             self.devices.insert(
                 mac_owned.clone(),
-                DeviceState::AirPods(AirPodsDeviceState::new("AirPods".to_string())),
+                DeviceState::AirPods(Box::new(AirPodsDeviceState::new("AirPods".to_string()))),
             );
             self.device_order.push(mac_owned);
         }
@@ -448,24 +767,13 @@ impl App {
         if let Some(DeviceState::AirPods(state)) = self.devices.get_mut(mac) {
             match event {
                 AACPEvent::BatteryInfo(infos) => {
-                    for b in infos {
-                        match b.component {
-                            BatteryComponent::Left => {
-                                state.battery_left = Some((b.level, b.status));
-                            }
-                            BatteryComponent::Right => {
-                                state.battery_right = Some((b.level, b.status));
-                            }
-                            BatteryComponent::Case => {
-                                // Only update if not disconnected - preserve last known good value
-                                if b.status != BatteryStatus::Disconnected {
-                                    state.battery_case = Some((b.level, b.status));
-                                }
-                            }
-                            BatteryComponent::Headphone => {
-                                state.battery_headphone = Some((b.level, b.status));
-                            }
-                        }
+                    apply_battery(state, &infos);
+                    if state.battery_cached {
+                        // Fresh data: drop the cached report's age
+                        state.battery_cached = false;
+                        state
+                            .last_seen
+                            .insert(DataSource::Aacp, crate::utils::unix_now());
                     }
                     let bat_left = state.battery_left.map(|(l, _)| l);
                     let bat_right = state.battery_right.map(|(r, _)| r);
@@ -606,6 +914,14 @@ impl App {
         }
     }
 
+    pub fn send_mic_mute_toggle(&self, mac: &str) {
+        if let Some(tx) = &self.command_tx
+            && let Err(e) = tx.send((mac.to_string(), DeviceCommand::ToggleMicMute))
+        {
+            log::warn!("Failed to send mic mute toggle: {}", e);
+        }
+    }
+
     pub fn send_rename(&self, mac: &str, name: String) {
         if let Some(tx) = &self.command_tx
             && let Err(e) = tx.send((mac.to_string(), DeviceCommand::Rename(name.clone())))
@@ -1020,6 +1336,232 @@ mod tests {
         assert!(matches!(received.1, DeviceCommand::Rename(ref n) if n == "NewName"));
     }
 
+    #[test]
+    fn audio_profile_event_updates_device() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        app.handle_event(AppEvent::AudioProfile {
+            mac: MAC.into(),
+            profile: Some("a2dp-sink-aac".into()),
+        });
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert_eq!(s.audio_profile.as_deref(), Some("a2dp-sink-aac"));
+    }
+
+    #[test]
+    fn leaving_headset_profile_clears_mic_mute() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        app.handle_event(AppEvent::MicMuted {
+            mac: MAC.into(),
+            muted: true,
+        });
+        app.handle_event(AppEvent::AudioProfile {
+            mac: MAC.into(),
+            profile: Some("a2dp-sink-aac".into()),
+        });
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert!(!s.mic_muted);
+    }
+
+    #[test]
+    fn last_seen_marks_stale_data() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        app.handle_event(AppEvent::LastSeen {
+            mac: MAC.into(),
+            source: DataSource::Aacp,
+            at: 1_000,
+        });
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert_eq!(s.stale_for(DataSource::Aacp, 1_060), None);
+        assert_eq!(s.stale_for(DataSource::Aacp, 1_200), Some(200));
+        assert_eq!(s.stale_for(DataSource::Avrcp, 9_999), None);
+    }
+
+    #[test]
+    fn cached_battery_shows_stale_until_fresh() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        let left = |level| BatteryInfo {
+            component: BatteryComponent::Left,
+            level,
+            status: BatteryStatus::NotCharging,
+        };
+        app.handle_event(AppEvent::CachedBattery {
+            mac: MAC.into(),
+            batteries: vec![left(40)],
+            at: 1_000,
+        });
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert_eq!(s.battery_left.map(|(l, _)| l), Some(40));
+        assert!(s.battery_cached);
+        assert!(s.stale_for(DataSource::Aacp, 5_000).is_some());
+
+        app.handle_event(aacp(MAC, AE::BatteryInfo(vec![left(90)])));
+        // A late cached report must not overwrite fresh data
+        app.handle_event(AppEvent::CachedBattery {
+            mac: MAC.into(),
+            batteries: vec![left(40)],
+            at: 1_000,
+        });
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert_eq!(s.battery_left.map(|(l, _)| l), Some(90));
+        assert!(!s.battery_cached);
+        assert_eq!(
+            s.stale_for(DataSource::Aacp, crate::utils::unix_now()),
+            None
+        );
+    }
+
+    #[test]
+    fn battery_device_is_added_and_updated() {
+        let (mut app, _) = mk_app();
+        for level in [80, 75] {
+            app.handle_event(AppEvent::BatteryDevice {
+                mac: MAC.into(),
+                name: "Buds".into(),
+                level,
+            });
+        }
+        assert_eq!(app.device_order, [MAC]);
+        let Some(DeviceState::Battery(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert_eq!((s.name.as_str(), s.level), ("Buds", 75));
+        app.handle_event(AppEvent::DeviceDisconnected(MAC.into()));
+        assert!(app.devices.is_empty());
+    }
+
+    #[test]
+    fn link_lost_until_reconnected() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        app.handle_event(AppEvent::LinkLost(MAC.into()));
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert!(s.link_lost);
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        let Some(DeviceState::AirPods(s)) = app.selected_device() else {
+            panic!("device missing");
+        };
+        assert!(!s.link_lost);
+    }
+
+    #[test]
+    fn process_events_reports_changes() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = mpsc::unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        assert!(!app.process_events());
+        tx.send(connected(MAC, "My Pods", PRO2)).unwrap();
+        assert!(app.process_events());
+        assert!(!app.process_events());
+    }
+
+    #[test]
+    fn now_playing_position_runs_on_while_playing() {
+        let mut track = NowPlaying {
+            title: "Song".into(),
+            artist: None,
+            playing: true,
+            position_us: 1_000_000,
+            length_us: Some(2_500_000),
+            position_at_ms: 10_000,
+        };
+        assert_eq!(track.position_at(10_000), 1_000_000);
+        assert_eq!(track.position_at(11_000), 2_000_000);
+        assert_eq!(track.position_at(20_000), 2_500_000);
+        track.playing = false;
+        assert_eq!(track.position_at(20_000), 1_000_000);
+    }
+
+    #[test]
+    fn lid_open_popup_expires() {
+        let (mut app, _) = mk_app();
+        let advert = [0x07, 0x19, 0x01, 0x0e, 0x20, 0x2b, 0x87, 0x45, 0x01];
+        let nearby = NearbyAirPods {
+            address: "5A:11:22:33:44:55".into(),
+            mac: Some(MAC.into()),
+            name: Some("Pods".into()),
+            advert: crate::bluetooth::proximity::parse(&advert).unwrap(),
+            distance: None,
+            rssi: None,
+        };
+        app.handle_event(AppEvent::LidOpened(nearby));
+        assert_eq!(app.shown_lid_open().unwrap().name.as_deref(), Some("Pods"));
+        assert!(app.tick());
+        app.lid_open.as_mut().unwrap().1 -= LID_OPEN_SHOW_FOR;
+        assert!(app.shown_lid_open().is_none());
+        assert!(app.tick());
+    }
+
+    #[test]
+    fn tick_reports_only_time_driven_changes() {
+        let (mut app, _) = mk_app();
+        app.handle_event(connected(MAC, "Pods", PRO2));
+        assert!(app.tick());
+        assert!(!app.tick());
+
+        app.last_error = Some(("failed".into(), std::time::Instant::now()));
+        assert!(app.tick());
+        assert!(!app.tick());
+        app.last_error = Some(("failed".into(), std::time::Instant::now() - ERROR_SHOW_FOR));
+        assert!(app.tick());
+
+        let now_ms = crate::utils::unix_now_ms();
+        let track = NowPlaying {
+            title: "Song".into(),
+            artist: None,
+            playing: false,
+            position_us: 1_000_000,
+            length_us: Some(180_000_000),
+            position_at_ms: now_ms - 60_000,
+        };
+        app.handle_event(AppEvent::NowPlaying {
+            mac: MAC.into(),
+            track: Some(track.clone()),
+        });
+        assert!(app.tick());
+        // Paused: the position stays put
+        assert!(!app.tick());
+        app.handle_event(AppEvent::NowPlaying {
+            mac: MAC.into(),
+            track: Some(NowPlaying {
+                playing: true,
+                ..track
+            }),
+        });
+        assert!(app.tick());
+    }
+
+    #[test]
+    fn command_failed_names_device() {
+        let (mut app, _) = mk_app();
+        app.handle_event(AppEvent::DeviceConnected {
+            mac: MAC.into(),
+            name: "My Pods".into(),
+            product_id: PRO2,
+        });
+        app.handle_event(AppEvent::CommandFailed {
+            mac: MAC.into(),
+            error: "AirPods write failed: not connected".into(),
+        });
+        let (msg, _) = app.last_error.as_ref().unwrap();
+        assert_eq!(msg, "My Pods: AirPods write failed: not connected");
+    }
+
     #[test]
     fn audio_unavailable_event_sets_flag() {
         let (mut app, _) = mk_app();
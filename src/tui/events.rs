@@ -1,7 +1,47 @@
 use crate::bluetooth::aacp::ControlCommandIdentifiers;
+use crate::config::{KeyAction, KeyBinding};
 use crate::devices::enums::AirPodsNoiseControlMode;
 use crate::tui::app::{App, DeviceState, FocusedSection, SettingsItem};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use log::warn;
+
+/// Key chord to action lookup built from `[keys]`.
+#[derive(Debug, Clone)]
+pub struct KeyBindings(Vec<KeyBinding>);
+
+impl KeyBindings {
+    /// Bindings from config, with every problem logged.
+    pub fn from_config(keys: &crate::config::KeysConfig) -> Self {
+        let (bindings, problems) = keys.bindings();
+        for problem in problems {
+            warn!("[keys] {}", problem);
+        }
+        Self(bindings)
+    }
+
+    /// Action bound to `key`. Shift is ignored so `"R"` and Shift+r agree.
+    fn action(&self, key: &KeyEvent) -> Option<KeyAction> {
+        let mods = key.modifiers - KeyModifiers::SHIFT;
+        self.0
+            .iter()
+            .find(|b| b.code == key.code && b.modifiers == mods)
+            .map(|b| b.action)
+    }
+
+    /// The chord bound to `action`, as written in the config.
+    pub fn chord(&self, action: KeyAction) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|b| b.action == action)
+            .map(|b| b.chord.as_str())
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::from_config(&crate::config::KeysConfig::default())
+    }
+}
 
 pub fn handle_key(app: &mut App, key: KeyEvent) {
     // Rename mode intercepts all keys
@@ -11,12 +51,12 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
     }
 
     match key.code {
-        // Quit
-        KeyCode::Char('q') => app.should_quit = true,
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.should_quit = true;
         }
 
+        KeyCode::Esc if app.shown_lid_open().is_some() => app.lid_open = None,
+
         // Tab / Shift+Tab: cycle focused section
         KeyCode::Tab if has_settings(app) => {
             app.focused_section = app.focused_section.next();
@@ -51,42 +91,71 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
             }
         }
 
+        // Space/Enter - activate the focused row
+        KeyCode::Char(' ') | KeyCode::Enter => activate_row(app),
+
+        _ => {
+            if let Some(action) = app.keys.action(&key) {
+                run_action(app, action);
+            }
+        }
+    }
+}
+
+fn run_action(app: &mut App, action: KeyAction) {
+    let has_adaptive = matches!(
+        app.selected_device(),
+        Some(DeviceState::AirPods(s)) if s.has_adaptive
+    );
+    match action {
+        KeyAction::Quit => app.should_quit = true,
+
         // Direct noise mode shortcuts
-        KeyCode::Char('1') => set_noise_mode(app, AirPodsNoiseControlMode::Transparency),
-        KeyCode::Char('2') => {
-            let has_adaptive = matches!(
-                app.selected_device(),
-                Some(DeviceState::AirPods(s)) if s.has_adaptive
-            );
+        KeyAction::Transparency => set_noise_mode(app, AirPodsNoiseControlMode::Transparency),
+        KeyAction::Adaptive => {
             if has_adaptive {
                 set_noise_mode(app, AirPodsNoiseControlMode::Adaptive);
             } else {
                 set_noise_mode(app, AirPodsNoiseControlMode::NoiseCancellation);
             }
         }
-        KeyCode::Char('3') => {
-            if matches!(app.selected_device(), Some(DeviceState::AirPods(s)) if s.has_adaptive) {
+        KeyAction::NoiseCancellation => {
+            if has_adaptive {
                 set_noise_mode(app, AirPodsNoiseControlMode::NoiseCancellation);
             }
         }
 
-        // Toggle conversation awareness directly
-        KeyCode::Char('c') => toggle_conversation_awareness(app),
-
-        // Space/Enter - activate the focused row
-        KeyCode::Char(' ') | KeyCode::Enter => activate_row(app),
+        KeyAction::ConversationAwareness => toggle_conversation_awareness(app),
 
         // Device info popup
-        KeyCode::Char('i') => app.show_info = !app.show_info,
+        KeyAction::Info => app.show_info = !app.show_info,
+
+        // Hot/cold meter for finding AirPods by their adverts
+        KeyAction::Locate => app.locate = !app.locate,
+
+        // Battery estimates and recent events from the daemon's history
+        KeyAction::History => {
+            if app.history.take().is_none()
+                && let Some(mac) = app.selected_mac()
+            {
+                let path = crate::history::history_path();
+                let view = crate::history::HistoryView::load(&path, mac, crate::utils::unix_now());
+                app.history = Some(view);
+            }
+        }
 
-        // Enter rename mode
-        KeyCode::Char('r') => {
+        // Microphone mute (takes effect on the headset profile)
+        KeyAction::MicMute => {
+            if let Some(mac) = app.selected_mac() {
+                app.send_mic_mute_toggle(mac);
+            }
+        }
+
+        KeyAction::Rename => {
             if let Some(DeviceState::AirPods(s)) = app.selected_device() {
                 app.rename_mode = Some(s.name.clone());
             }
         }
-
-        _ => {}
     }
 }
 
@@ -279,7 +348,7 @@ fn send_setting(app: &mut App, cmd: ControlCommandIdentifiers, value: u8) {
     app.send_command(&mac, cmd, vec![wire_value]);
 }
 
-fn set_noise_mode(app: &mut App, mode: AirPodsNoiseControlMode) {
+pub(crate) fn set_noise_mode(app: &mut App, mode: AirPodsNoiseControlMode) {
     let Some(mac) = app.selected_mac().cloned() else {
         return;
     };
@@ -296,7 +365,7 @@ fn set_noise_mode(app: &mut App, mode: AirPodsNoiseControlMode) {
     );
 }
 
-fn toggle_conversation_awareness(app: &mut App) {
+pub(crate) fn toggle_conversation_awareness(app: &mut App) {
     let Some(mac) = app.selected_mac().cloned() else {
         return;
     };
@@ -452,6 +521,25 @@ mod tests {
         assert!(app.should_quit);
     }
 
+    #[test]
+    fn rebound_key_replaces_default() {
+        let (mut app, _) = mk_app(PRO2);
+        let keys = crate::config::KeysConfig {
+            quit: "x".into(),
+            info: "not a key".into(),
+            ..Default::default()
+        };
+        app.keys = KeyBindings::from_config(&keys);
+
+        handle_key(&mut app, key(KeyCode::Char('q')));
+        assert!(!app.should_quit);
+        // Invalid chord falls back to the default
+        handle_key(&mut app, key(KeyCode::Char('i')));
+        assert!(app.show_info);
+        handle_key(&mut app, key(KeyCode::Char('x')));
+        assert!(app.should_quit);
+    }
+
     #[test]
     fn ctrl_c_quits() {
         let (mut app, _) = mk_app(PRO2);
@@ -914,6 +1002,25 @@ mod tests {
         assert!(!app.show_info);
     }
 
+    #[test]
+    fn h_toggles_history_overlay() {
+        let (mut app, _) = mk_app(PRO2);
+        assert!(app.history.is_none());
+        handle_key(&mut app, key(KeyCode::Char('h')));
+        assert!(app.history.is_some());
+        handle_key(&mut app, key(KeyCode::Char('h')));
+        assert!(app.history.is_none());
+    }
+
+    #[test]
+    fn l_toggles_locate() {
+        let (mut app, _) = mk_app(PRO2);
+        handle_key(&mut app, key(KeyCode::Char('l')));
+        assert!(app.locate);
+        handle_key(&mut app, key(KeyCode::Char('l')));
+        assert!(!app.locate);
+    }
+
     #[test]
     fn unknown_keys_are_ignored() {
         let (mut app, _) = mk_app(PRO2);
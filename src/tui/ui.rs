@@ -1,6 +1,12 @@
 use crate::bluetooth::aacp::{BatteryStatus, EarDetectionStatus};
+use crate::bluetooth::proximity::{Distance, NearbyAirPods};
+use crate::config::{KeyAction, Palette};
 use crate::devices::enums::AirPodsNoiseControlMode;
-use crate::tui::app::{AirPodsDeviceState, App, DeviceState, FocusedSection, SettingsItem};
+use crate::history::{HistoryView, Kind};
+use crate::tui::app::{
+    AirPodsDeviceState, App, BatteryDeviceState, DataSource, DeviceState, FocusedSection,
+    NowPlaying, PlaybackStatus, SettingsItem,
+};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -9,21 +15,30 @@ use ratatui::{
     widgets::{Block, BorderType, Borders, Paragraph, Row, Table, TableState},
 };
 
-const ACCENT: Color = Color::Cyan;
-const FOCUS_COLOR: Color = Color::Green;
-const HEADER: Color = Color::Yellow;
-const FG: Color = Color::White;
-const DIM: Color = Color::DarkGray;
-
 pub fn draw(f: &mut Frame, app: &App) {
+    let p = &app.palette;
     let area = f.area();
 
     if app.device_order.is_empty() {
-        let msg = Paragraph::new("No device connected.\n\nWaiting…")
-            .style(Style::default().fg(DIM))
+        let mut lines = vec![
+            Line::from("No device connected."),
+            Line::from(""),
+            Line::from("Waiting…"),
+        ];
+        if !app.nearby.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "Nearby AirPods",
+                Style::default().fg(p.header).add_modifier(Modifier::BOLD),
+            ));
+            lines.extend(app.nearby.iter().map(|n| nearby_line(n, p)));
+        }
+        let msg = Paragraph::new(lines)
+            .style(Style::default().fg(p.dim))
             .alignment(Alignment::Center);
-        f.render_widget(msg, centered_rect(area, 50, 30));
+        f.render_widget(msg, centered_rect(area, 50, 50));
         draw_footer(f, footer_row(area), app);
+        draw_nearby_popups(f, area, app);
         return;
     }
 
@@ -46,18 +61,35 @@ pub fn draw(f: &mut Frame, app: &App) {
 
     // Rename popup overlay
     if let Some(ref buf) = app.rename_mode {
-        draw_rename_popup(f, area, buf);
+        draw_rename_popup(f, area, buf, p);
     }
 
     // Device info popup
     if app.show_info
         && let Some(DeviceState::AirPods(state)) = app.selected_device()
     {
-        draw_info_popup(f, area, state);
+        draw_info_popup(f, area, state, p);
+    }
+
+    if let Some(view) = &app.history {
+        draw_history_popup(f, area, view, p);
+    }
+
+    draw_nearby_popups(f, area, app);
+}
+
+/// Popups fed by the nearby scan, shown with or without a device connected.
+fn draw_nearby_popups(f: &mut Frame, area: Rect, app: &App) {
+    if app.locate {
+        draw_locate_popup(f, area, &app.nearby, &app.palette);
+    }
+    if let Some(nearby) = app.shown_lid_open() {
+        draw_lid_popup(f, area, nearby, &app.palette);
     }
 }
 
 fn draw_tabs(f: &mut Frame, area: Rect, app: &App) {
+    let p = &app.palette;
     let spans: Vec<Span> = app
         .device_order
         .iter()
@@ -70,16 +102,16 @@ fn draw_tabs(f: &mut Frame, area: Rect, app: &App) {
                 .unwrap_or_else(|| mac.clone());
             let style = if i == app.selected_device_idx {
                 Style::default()
-                    .fg(ACCENT)
+                    .fg(p.accent)
                     .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
             } else {
-                Style::default().fg(DIM)
+                Style::default().fg(p.dim)
             };
             if i == 0 {
                 vec![Span::styled(format!(" {} ", name), style)]
             } else {
                 vec![
-                    Span::styled("  ", Style::default().fg(DIM)),
+                    Span::styled("  ", Style::default().fg(p.dim)),
                     Span::styled(format!(" {} ", name), style),
                 ]
             }
@@ -92,6 +124,7 @@ fn draw_tabs(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn draw_content(f: &mut Frame, area: Rect, app: &App) {
+    let p = &app.palette;
     let Some(mac) = app.selected_mac() else {
         return;
     };
@@ -100,10 +133,38 @@ fn draw_content(f: &mut Frame, area: Rect, app: &App) {
     };
     match device {
         DeviceState::AirPods(state) => draw_airpods(f, area, state, app),
+        DeviceState::Battery(state) => draw_battery_device(f, area, state, p),
     }
 }
 
+/// Non-AirPods earbuds: name and the Battery Service level only.
+fn draw_battery_device(f: &mut Frame, area: Rect, state: &BatteryDeviceState, p: &Palette) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // name line
+            Constraint::Length(3), // battery box
+            Constraint::Fill(1),
+        ])
+        .split(area);
+    let name = Line::from(vec![
+        Span::styled(
+            format!("  {} ", state.name),
+            Style::default().fg(p.fg).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("● connected", Style::default().fg(Color::Green)),
+    ]);
+    f.render_widget(Paragraph::new(name).alignment(Alignment::Center), chunks[0]);
+    draw_battery_box(
+        f,
+        chunks[1],
+        &[("      ", state.level, BatteryStatus::NotCharging)],
+        p,
+    );
+}
+
 fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App) {
+    let p = &app.palette;
     // Collect battery entries
     let bat_entries: Vec<(&str, u8, BatteryStatus)> = [
         ("Left  ", &state.battery_left),
@@ -118,6 +179,7 @@ fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App
 
     let bat_count = bat_entries.len().max(1) as u16;
     let display_name = state.model.as_deref().unwrap_or(&state.name);
+    let np_rows = u16::from(state.now_playing.is_some());
 
     // No noise control box for non-ANC devices; settings still apply.
     if !state.has_anc {
@@ -126,6 +188,7 @@ fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(1),             // name line
+                Constraint::Length(np_rows),       // now playing
                 Constraint::Length(bat_count + 2), // battery box
                 // Settings box sized to content; spare space stays empty
                 Constraint::Max(settings_items.len() as u16 + 2),
@@ -134,17 +197,17 @@ fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App
             .split(area);
 
         f.render_widget(
-            Paragraph::new(name_line(display_name, state.ear_left, state.ear_right))
-                .alignment(Alignment::Center),
+            Paragraph::new(name_line(display_name, state, p)).alignment(Alignment::Center),
             chunks[0],
         );
-        draw_battery_box(f, chunks[1], &bat_entries);
+        draw_now_playing(f, chunks[1], state.now_playing.as_ref(), p);
+        draw_battery_box(f, chunks[2], &bat_entries, p);
 
         let st_focused = app.effective_section() == FocusedSection::Settings;
-        let st_block = section_block("Settings", st_focused);
-        let st_inner = st_block.inner(chunks[2]);
-        f.render_widget(st_block, chunks[2]);
-        draw_settings_table(f, st_inner, &settings_items, app.section_row, st_focused);
+        let st_block = section_block("Settings", st_focused, p);
+        let st_inner = st_block.inner(chunks[3]);
+        f.render_widget(st_block, chunks[3]);
+        draw_settings_table(f, st_inner, &settings_items, app.section_row, st_focused, p);
         return;
     }
 
@@ -156,6 +219,7 @@ fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),               // name line
+            Constraint::Length(np_rows),         // now playing
             Constraint::Length(bat_count + 2),   // Battery box
             Constraint::Length(noise_count + 2), // Noise Control box
             // Settings box sized to content; spare space stays empty
@@ -166,44 +230,45 @@ fn draw_airpods(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, app: &App
 
     // Name line
     f.render_widget(
-        Paragraph::new(name_line(display_name, state.ear_left, state.ear_right))
-            .alignment(Alignment::Center),
+        Paragraph::new(name_line(display_name, state, p)).alignment(Alignment::Center),
         chunks[0],
     );
 
+    draw_now_playing(f, chunks[1], state.now_playing.as_ref(), p);
+
     // Battery box (informational, never focused)
-    draw_battery_box(f, chunks[1], &bat_entries);
+    draw_battery_box(f, chunks[2], &bat_entries, p);
 
     // Noise Control box
     let nc_focused = app.focused_section == FocusedSection::NoiseControl;
-    let nc_block = section_block("Noise Control", nc_focused);
-    let nc_inner = nc_block.inner(chunks[2]);
-    f.render_widget(nc_block, chunks[2]);
-    draw_noise_options(f, nc_inner, state, app.section_row, nc_focused);
+    let nc_block = section_block("Noise Control", nc_focused, p);
+    let nc_inner = nc_block.inner(chunks[3]);
+    f.render_widget(nc_block, chunks[3]);
+    draw_noise_options(f, nc_inner, state, app.section_row, nc_focused, p);
 
     // Settings box
     let st_focused = app.focused_section == FocusedSection::Settings;
-    let st_block = section_block("Settings", st_focused);
-    let st_inner = st_block.inner(chunks[3]);
-    f.render_widget(st_block, chunks[3]);
-    draw_settings_table(f, st_inner, &settings_items, app.section_row, st_focused);
+    let st_block = section_block("Settings", st_focused, p);
+    let st_inner = st_block.inner(chunks[4]);
+    f.render_widget(st_block, chunks[4]);
+    draw_settings_table(f, st_inner, &settings_items, app.section_row, st_focused, p);
 }
 
-fn draw_battery_box(f: &mut Frame, area: Rect, entries: &[(&str, u8, BatteryStatus)]) {
+fn draw_battery_box(f: &mut Frame, area: Rect, entries: &[(&str, u8, BatteryStatus)], p: &Palette) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(DIM))
+        .border_style(Style::default().fg(p.dim))
         .title(Span::styled(
             " Battery ",
-            Style::default().fg(HEADER).add_modifier(Modifier::BOLD),
+            Style::default().fg(p.header).add_modifier(Modifier::BOLD),
         ));
     let inner = block.inner(area);
     f.render_widget(block, area);
 
     if entries.is_empty() {
         f.render_widget(
-            Paragraph::new("  Waiting for data…").style(Style::default().fg(DIM)),
+            Paragraph::new("  Waiting for data…").style(Style::default().fg(p.dim)),
             inner,
         );
         return;
@@ -216,7 +281,7 @@ fn draw_battery_box(f: &mut Frame, area: Rect, entries: &[(&str, u8, BatteryStat
         .split(inner);
 
     for (i, (label, level, status)) in entries.iter().enumerate() {
-        f.render_widget(bat_row(label, *level, status), rows[i]);
+        f.render_widget(bat_row(label, *level, status, p), rows[i]);
     }
 }
 
@@ -226,6 +291,7 @@ fn draw_noise_options(
     state: &AirPodsDeviceState,
     section_row: usize,
     focused: bool,
+    p: &Palette,
 ) {
     let noise_modes = noise_mode_list(state.has_adaptive, state.allow_off_mode);
 
@@ -239,7 +305,7 @@ fn draw_noise_options(
         let is_focused = focused && section_row == i;
         let active = std::mem::discriminant(mode) == std::mem::discriminant(&state.listening_mode);
         f.render_widget(
-            Paragraph::new(noise_row(&mode.to_string(), is_focused, active)),
+            Paragraph::new(noise_row(&mode.to_string(), is_focused, active, p)),
             rows[i],
         );
     }
@@ -251,10 +317,11 @@ fn draw_settings_table(
     items: &[SettingsItem],
     section_row: usize,
     focused: bool,
+    p: &Palette,
 ) {
     if items.is_empty() {
         f.render_widget(
-            Paragraph::new("  No settings available").style(Style::default().fg(DIM)),
+            Paragraph::new("  No settings available").style(Style::default().fg(p.dim)),
             area,
         );
         return;
@@ -266,19 +333,19 @@ fn draw_settings_table(
         .map(|(i, item)| {
             let is_selected = focused && section_row == i;
             let cursor = if is_selected {
-                Span::styled("▸ ", Style::default().fg(ACCENT))
+                Span::styled("▸ ", Style::default().fg(p.accent))
             } else {
                 Span::raw("  ")
             };
             let label_style = if is_selected {
-                Style::default().fg(FG)
+                Style::default().fg(p.fg)
             } else {
-                Style::default().fg(DIM)
+                Style::default().fg(p.dim)
             };
 
             let toggle_row = |label: &'static str, value: bool| {
                 let val_str = if value { "On" } else { "Off" };
-                let val_color = if value { ACCENT } else { DIM };
+                let val_color = if value { p.accent } else { p.dim };
                 Row::new(vec![
                     Line::from(vec![cursor.clone(), Span::styled(label, label_style)]),
                     Line::from(Span::styled(
@@ -298,7 +365,7 @@ fn draw_settings_table(
                         Line::from(vec![cursor.clone(), Span::styled(*label, label_style)]),
                         Line::from(Span::styled(
                             val_str,
-                            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+                            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
                         ))
                         .alignment(Alignment::Right),
                     ])
@@ -314,7 +381,7 @@ fn draw_settings_table(
                         Line::from(vec![cursor.clone(), Span::styled(*label, label_style)]),
                         Line::from(Span::styled(
                             *val_str,
-                            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+                            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
                         ))
                         .alignment(Alignment::Right),
                     ])
@@ -341,7 +408,7 @@ fn draw_settings_table(
                         Line::from(vec![cursor.clone(), Span::styled(*label, label_style)]),
                         Line::from(Span::styled(
                             bar,
-                            Style::default().fg(if is_selected { ACCENT } else { Color::Gray }),
+                            Style::default().fg(if is_selected { p.accent } else { Color::Gray }),
                         ))
                         .alignment(Alignment::Right),
                     ])
@@ -359,26 +426,24 @@ fn draw_settings_table(
     f.render_stateful_widget(table, area, &mut table_state);
 }
 
-fn section_block(title: &str, focused: bool) -> Block<'_> {
+fn section_block<'a>(title: &'a str, focused: bool, p: &Palette) -> Block<'a> {
     if focused {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Thick)
-            .border_style(Style::default().fg(FOCUS_COLOR))
+            .border_style(Style::default().fg(p.focus))
             .title(Span::styled(
                 format!(" {} ", title),
-                Style::default()
-                    .fg(FOCUS_COLOR)
-                    .add_modifier(Modifier::BOLD),
+                Style::default().fg(p.focus).add_modifier(Modifier::BOLD),
             ))
     } else {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(DIM))
+            .border_style(Style::default().fg(p.dim))
             .title(Span::styled(
                 format!(" {} ", title),
-                Style::default().fg(HEADER).add_modifier(Modifier::BOLD),
+                Style::default().fg(p.header).add_modifier(Modifier::BOLD),
             ))
     }
 }
@@ -392,51 +457,147 @@ fn ear_label(s: EarDetectionStatus) -> &'static str {
     }
 }
 
-fn name_line(
-    display_name: &str,
-    ear_left: Option<EarDetectionStatus>,
-    ear_right: Option<EarDetectionStatus>,
-) -> Line<'_> {
+fn name_line<'a>(display_name: &'a str, state: &AirPodsDeviceState, p: &Palette) -> Line<'a> {
+    let (ear_left, ear_right) = (state.ear_left, state.ear_right);
     let mut spans = vec![
         Span::styled(
             format!("  {} ", display_name),
-            Style::default().fg(FG).add_modifier(Modifier::BOLD),
+            Style::default().fg(p.fg).add_modifier(Modifier::BOLD),
         ),
-        Span::styled("● connected", Style::default().fg(Color::Green)),
+        if state.link_lost {
+            Span::styled(
+                "● link lost, reconnecting",
+                Style::default().fg(Color::Yellow),
+            )
+        } else {
+            Span::styled("● connected", Style::default().fg(Color::Green))
+        },
     ];
     if let (Some(l), Some(r)) = (ear_left, ear_right) {
         spans.push(Span::styled(
             format!("  L:{}  R:{}", ear_label(l), ear_label(r)),
-            Style::default().fg(DIM),
+            Style::default().fg(p.dim),
+        ));
+    }
+    if state.mic_muted {
+        spans.push(Span::styled("  mic muted", Style::default().fg(Color::Red)));
+    }
+    if let Some(age) = state.stale_for(DataSource::Aacp, crate::utils::unix_now()) {
+        spans.push(Span::styled(
+            format!("  no update for {} min", age / 60),
+            Style::default().fg(Color::Yellow),
         ));
     }
+    let playback = match state.playback {
+        PlaybackStatus::Idle => None,
+        PlaybackStatus::Playing => Some(("  playing ▸", Color::Green)),
+        PlaybackStatus::PausedByEar => Some(("  paused by ear detection", Color::Yellow)),
+        PlaybackStatus::PeerOwned => Some(("  on another device", p.accent)),
+    };
+    if let Some((label, color)) = playback {
+        spans.push(Span::styled(label, Style::default().fg(color)));
+    }
     Line::from(spans)
 }
 
-fn noise_row(label: &str, focused: bool, active: bool) -> Line<'static> {
+/// "AirPods Pro (White)  L 70% ●  R 80%  Case 50%" for a nearby advert,
+/// with the stored name instead for our own; ● marks a bud that is in an
+/// ear.
+fn nearby_line(nearby: &NearbyAirPods, p: &Palette) -> Line<'static> {
+    let advert = &nearby.advert;
+    let mut text = nearby.name.clone().unwrap_or_else(|| advert.display_name());
+    for (label, level, in_ear) in [
+        ("L", advert.left, advert.left_in_ear),
+        ("R", advert.right, advert.right_in_ear),
+        ("Case", advert.case, false),
+    ] {
+        if let Some(level) = level {
+            text.push_str(&format!("  {} {}%", label, level));
+        }
+        if in_ear {
+            text.push_str(" ●");
+        }
+    }
+    if let Some(distance) = nearby.distance {
+        text.push_str(&format!("  ({})", distance.label()));
+    }
+    Line::styled(text, Style::default().fg(p.fg))
+}
+
+/// Width of the now-playing progress bar in cells.
+const PROGRESS_WIDTH: usize = 20;
+
+fn draw_now_playing(f: &mut Frame, area: Rect, track: Option<&NowPlaying>, p: &Palette) {
+    let Some(track) = track else {
+        return;
+    };
+    let icon = if track.playing { "▶" } else { "⏸" };
+    let mut title = track.title.clone();
+    if let Some(artist) = &track.artist {
+        title = format!("{} — {}", title, artist);
+    }
+    let mut spans = vec![
+        Span::styled(format!("{} ", icon), Style::default().fg(p.accent)),
+        Span::styled(title, Style::default().fg(p.fg)),
+    ];
+    if let Some(length) = track.length_us.filter(|l| *l > 0) {
+        let position = track.position_at(crate::utils::unix_now_ms());
+        spans.push(Span::styled(
+            format!("  {}", progress_bar(position, length, PROGRESS_WIDTH)),
+            Style::default().fg(p.accent),
+        ));
+        spans.push(Span::styled(
+            format!(" {} / {}", fmt_track_time(position), fmt_track_time(length)),
+            Style::default().fg(p.dim),
+        ));
+    }
+    f.render_widget(
+        Paragraph::new(Line::from(spans)).alignment(Alignment::Center),
+        area,
+    );
+}
+
+fn progress_bar(position_us: i64, length_us: i64, width: usize) -> String {
+    let filled =
+        (position_us.clamp(0, length_us) as f64 / length_us as f64 * width as f64).round() as usize;
+    format!("{}{}", "━".repeat(filled), "─".repeat(width - filled))
+}
+
+/// `m:ss`, or `h:mm:ss` past an hour.
+fn fmt_track_time(us: i64) -> String {
+    let secs = us.max(0) / 1_000_000;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+fn noise_row(label: &str, focused: bool, active: bool, p: &Palette) -> Line<'static> {
     let prefix = if focused {
-        Span::styled("  ▸ ", Style::default().fg(ACCENT))
+        Span::styled("  ▸ ", Style::default().fg(p.accent))
     } else {
         Span::raw("    ")
     };
     let text_style = if active {
-        Style::default().fg(FG).add_modifier(Modifier::BOLD)
+        Style::default().fg(p.fg).add_modifier(Modifier::BOLD)
     } else if focused {
-        Style::default().fg(FG)
+        Style::default().fg(p.fg)
     } else {
-        Style::default().fg(DIM)
+        Style::default().fg(p.dim)
     };
     let mut spans = vec![prefix, Span::styled(label.to_string(), text_style)];
     if active {
-        spans.push(Span::styled("  (Active)", Style::default().fg(ACCENT)));
+        spans.push(Span::styled("  (Active)", Style::default().fg(p.accent)));
     }
     Line::from(spans)
 }
 
-fn bat_row<'a>(label: &'a str, level: u8, status: &BatteryStatus) -> Paragraph<'a> {
+fn bat_row<'a>(label: &'a str, level: u8, status: &BatteryStatus, p: &Palette) -> Paragraph<'a> {
     let charging = matches!(status, BatteryStatus::Charging | BatteryStatus::InUse);
     let color = if charging {
-        Color::Cyan
+        p.charging
     } else if level > 50 {
         Color::Green
     } else if level >= 20 {
@@ -447,7 +608,7 @@ fn bat_row<'a>(label: &'a str, level: u8, status: &BatteryStatus) -> Paragraph<'
     let filled = (level as usize * 10 / 100).min(10);
     let bar = format!("{}{}", "█".repeat(filled), "░".repeat(10 - filled));
     let mut spans = vec![
-        Span::styled(format!("  {}", label), Style::default().fg(DIM)),
+        Span::styled(format!("  {}", label), Style::default().fg(p.dim)),
         Span::styled(format!("{}  ", bar), Style::default().fg(color)),
         Span::styled(
             format!("{:>3}%", level),
@@ -457,22 +618,32 @@ fn bat_row<'a>(label: &'a str, level: u8, status: &BatteryStatus) -> Paragraph<'
     if charging {
         spans.push(Span::styled(
             "  [charging]",
-            Style::default().fg(Color::Cyan),
+            Style::default().fg(p.charging),
         ));
     }
     Paragraph::new(Line::from(spans))
 }
 
 fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
+    let p = &app.palette;
     let has_anc = matches!(
         app.selected_device(),
         Some(DeviceState::AirPods(s)) if s.has_anc
     );
-    let hint = |key: &'static str, action: &'static str| {
+    let has_mic = matches!(
+        app.selected_device(),
+        Some(DeviceState::AirPods(s))
+            if s.audio_profile.as_deref().is_some_and(|p| p.starts_with("headset"))
+    );
+    let has_adaptive = matches!(
+        app.selected_device(),
+        Some(DeviceState::AirPods(s)) if s.has_adaptive
+    );
+    let hint = |key: &str, action: &'static str| {
         [
-            Span::styled(key, Style::default().fg(ACCENT)),
+            Span::styled(key.to_string(), Style::default().fg(p.accent)),
             Span::styled(" ", Style::default()),
-            Span::styled(action, Style::default().fg(DIM)),
+            Span::styled(action, Style::default().fg(p.dim)),
             Span::styled("  ", Style::default()),
         ]
     };
@@ -484,17 +655,45 @@ fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
     hints.extend(hint("↑↓", "navigate"));
     hints.extend(hint("space", "select"));
     if has_anc {
-        hints.extend(hint("1-3", "noise"));
+        let mut noise = vec![KeyAction::Transparency, KeyAction::Adaptive];
+        if has_adaptive {
+            noise.push(KeyAction::NoiseCancellation);
+        }
+        let chords: Vec<&str> = noise
+            .into_iter()
+            .filter_map(|a| app.keys.chord(a))
+            .collect();
+        if !chords.is_empty() {
+            hints.extend(hint(&chords.join("/"), "noise"));
+        }
+    }
+    let mut actions = Vec::new();
+    if has_mic {
+        actions.push((KeyAction::MicMute, "mic"));
+    }
+    if !app.nearby.is_empty() {
+        actions.push((KeyAction::Locate, "locate"));
+    }
+    actions.extend([
+        (KeyAction::Rename, "rename"),
+        (KeyAction::Info, "info"),
+        (KeyAction::Quit, "quit"),
+    ]);
+    for (action, label) in actions {
+        if let Some(chord) = app.keys.chord(action) {
+            hints.extend(hint(chord, label));
+        }
     }
-    hints.extend(hint("r", "rename"));
-    hints.extend(hint("i", "info"));
-    hints.extend(hint("q", "quit"));
     if app.audio_unavailable {
         hints.push(Span::styled(
-            "PulseAudio unavailable",
+            "Audio server unavailable",
             Style::default().fg(Color::Red),
         ));
     }
+    if let Some(msg) = app.shown_error() {
+        // Replaces the hints so long messages aren't cut off
+        hints = vec![Span::styled(msg, Style::default().fg(Color::Red))];
+    }
 
     f.render_widget(
         Paragraph::new(Line::from(hints)).alignment(Alignment::Center),
@@ -502,7 +701,7 @@ fn draw_footer(f: &mut Frame, area: Rect, app: &App) {
     );
 }
 
-fn draw_rename_popup(f: &mut Frame, area: Rect, buf: &str) {
+fn draw_rename_popup(f: &mut Frame, area: Rect, buf: &str, p: &Palette) {
     let popup = centered_rect(area, 60, 30);
     // Clear the area behind the popup
     f.render_widget(ratatui::widgets::Clear, popup);
@@ -510,10 +709,10 @@ fn draw_rename_popup(f: &mut Frame, area: Rect, buf: &str) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(ACCENT))
+        .border_style(Style::default().fg(p.accent))
         .title(Span::styled(
             " Rename Device ",
-            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
         ));
     let inner = block.inner(popup);
     f.render_widget(block, popup);
@@ -531,26 +730,31 @@ fn draw_rename_popup(f: &mut Frame, area: Rect, buf: &str) {
     // Input line with cursor
     let input_text = format!(" {}▏", buf);
     f.render_widget(
-        Paragraph::new(input_text).style(Style::default().fg(FG)),
+        Paragraph::new(input_text).style(Style::default().fg(p.fg)),
         chunks[1],
     );
 
     // Help text
     f.render_widget(
         Paragraph::new(Line::from(vec![
-            Span::styled("Enter", Style::default().fg(ACCENT)),
-            Span::styled(" confirm  ", Style::default().fg(DIM)),
-            Span::styled("Esc", Style::default().fg(ACCENT)),
-            Span::styled(" cancel", Style::default().fg(DIM)),
+            Span::styled("Enter", Style::default().fg(p.accent)),
+            Span::styled(" confirm  ", Style::default().fg(p.dim)),
+            Span::styled("Esc", Style::default().fg(p.accent)),
+            Span::styled(" cancel", Style::default().fg(p.dim)),
         ]))
         .alignment(Alignment::Center),
         chunks[3],
     );
 }
 
-fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState) {
+fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState, p: &Palette) {
+    let codec = state
+        .audio_profile
+        .as_deref()
+        .and_then(crate::media_controller::codec_label);
     let fields: Vec<(&str, Option<&str>)> = vec![
         ("Model", state.model.as_deref()),
+        ("Codec", codec.as_deref()),
         ("Firmware", state.firmware.as_deref()),
         ("Hardware", state.hardware_revision.as_deref()),
         ("Serial", state.serial_number.as_deref()),
@@ -571,10 +775,10 @@ fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(ACCENT))
+        .border_style(Style::default().fg(p.accent))
         .title(Span::styled(
             " Device Info ",
-            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
         ));
     let inner = block.inner(popup);
     f.render_widget(block, popup);
@@ -582,7 +786,7 @@ fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState) {
     if row_count == 0 {
         // The device sends its info packet a few seconds after connecting.
         f.render_widget(
-            Paragraph::new("Waiting for device information…").style(Style::default().fg(DIM)),
+            Paragraph::new("Waiting for device information…").style(Style::default().fg(p.dim)),
             inner,
         );
         return;
@@ -593,8 +797,8 @@ fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState) {
         .filter_map(|(label, val)| {
             val.map(|v| {
                 Row::new(vec![
-                    Line::from(Span::styled(*label, Style::default().fg(DIM))),
-                    Line::from(Span::styled(v.to_owned(), Style::default().fg(FG)))
+                    Line::from(Span::styled(*label, Style::default().fg(p.dim))),
+                    Line::from(Span::styled(v.to_owned(), Style::default().fg(p.fg)))
                         .alignment(Alignment::Right),
                 ])
             })
@@ -607,6 +811,218 @@ fn draw_info_popup(f: &mut Frame, area: Rect, state: &AirPodsDeviceState) {
     );
 }
 
+fn draw_history_popup(f: &mut Frame, area: Rect, view: &HistoryView, p: &Palette) {
+    let now = crate::utils::unix_now();
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(error) = &view.error {
+        lines.push(Line::styled(error.as_str(), Style::default().fg(p.dim)));
+    } else {
+        if view.remaining.is_empty() {
+            // Needs ten minutes of discharge since the last charge
+            lines.push(Line::styled(
+                "Not enough battery history for an estimate",
+                Style::default().fg(p.dim),
+            ));
+        }
+        for (label, secs) in &view.remaining {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<12}", label), Style::default().fg(p.dim)),
+                Span::styled(
+                    format!("~{} left", hours_minutes(*secs)),
+                    Style::default().fg(p.fg),
+                ),
+            ]));
+        }
+        lines.push(Line::from(""));
+        if view.recent.is_empty() {
+            lines.push(Line::styled("No events yet", Style::default().fg(p.dim)));
+        }
+        for record in &view.recent {
+            let what = match &record.kind {
+                Kind::Connected { name } => format!("Connected ({})", name),
+                Kind::Disconnected => "Disconnected".to_string(),
+                Kind::NoiseMode { mode } => mode.clone(),
+                Kind::Battery { .. } => continue,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<12}", ago(now.saturating_sub(record.at))),
+                    Style::default().fg(p.dim),
+                ),
+                Span::styled(what, Style::default().fg(p.fg)),
+            ]));
+        }
+    }
+
+    let popup_h = (lines.len() as u16 + 2).min(area.height); // +2 for border
+    let popup_w = 56u16.min(area.width);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(popup_w)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_h)) / 2,
+        width: popup_w,
+        height: popup_h,
+    };
+    f.render_widget(ratatui::widgets::Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(p.accent))
+        .title(Span::styled(
+            " History ",
+            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Width of the locate meter in cells.
+const LOCATE_WIDTH: usize = 20;
+
+/// Hot/cold meter per nearby advert from its smoothed RSSI, our own
+/// AirPods first; walk around until it fills up.
+fn draw_locate_popup(f: &mut Frame, area: Rect, nearby: &[NearbyAirPods], p: &Palette) {
+    let mut shown: Vec<&NearbyAirPods> = nearby.iter().filter(|n| n.rssi.is_some()).collect();
+    shown.sort_by_key(|n| n.mac.is_none());
+    let mut lines: Vec<Line> = Vec::new();
+    for n in shown {
+        let (Some(rssi), Some(distance)) = (n.rssi, n.distance) else {
+            continue;
+        };
+        let name = n.name.clone().unwrap_or_else(|| n.advert.display_name());
+        let filled = usize::from(crate::bluetooth::proximity::warmth(rssi)) * LOCATE_WIDTH / 100;
+        let color = match distance {
+            Distance::Close => Color::Red,
+            Distance::Near => Color::Yellow,
+            Distance::Far => Color::Blue,
+        };
+        lines.push(Line::styled(name, Style::default().fg(p.fg)));
+        lines.push(Line::from(vec![
+            Span::styled("█".repeat(filled), Style::default().fg(color)),
+            Span::styled(
+                "░".repeat(LOCATE_WIDTH - filled),
+                Style::default().fg(p.dim),
+            ),
+            Span::styled(
+                format!("  {} ({} dBm)", distance.temperature(), rssi),
+                Style::default().fg(color),
+            ),
+        ]));
+    }
+    if lines.is_empty() {
+        // Adverts only go out with the case open or a bud out
+        lines.push(Line::styled(
+            "No AirPods advertising nearby",
+            Style::default().fg(p.dim),
+        ));
+        lines.push(Line::styled(
+            "Open the case or take a bud out (needs nearby_scan)",
+            Style::default().fg(p.dim),
+        ));
+    }
+
+    let popup_h = (lines.len() as u16 + 2).min(area.height); // +2 for border
+    let popup_w = 56u16.min(area.width);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(popup_w)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_h)) / 2,
+        width: popup_w,
+        height: popup_h,
+    };
+    f.render_widget(ratatui::widgets::Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(p.accent))
+        .title(Span::styled(
+            " Locate ",
+            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Battery snapshot of a case that was just opened nearby.
+fn draw_lid_popup(f: &mut Frame, area: Rect, nearby: &NearbyAirPods, p: &Palette) {
+    let advert = &nearby.advert;
+    let mut lines: Vec<Line> = [
+        ("Left", advert.left, advert.left_charging),
+        ("Right", advert.right, advert.right_charging),
+        ("Case", advert.case, advert.case_charging),
+    ]
+    .into_iter()
+    .filter_map(|(label, level, charging)| {
+        let level = level?;
+        let mut spans = vec![
+            Span::styled(format!("{:<8}", label), Style::default().fg(p.dim)),
+            Span::styled(format!("{:>3}%", level), Style::default().fg(p.fg)),
+        ];
+        if charging {
+            spans.push(Span::styled(
+                "  [charging]",
+                Style::default().fg(p.charging),
+            ));
+        }
+        Some(Line::from(spans))
+    })
+    .collect();
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            "No battery levels in the advert",
+            Style::default().fg(p.dim),
+        ));
+    } else if !advert.exact {
+        // No EncKey stored, so only the clear 10% steps
+        lines.push(Line::styled("approximate", Style::default().fg(p.dim)));
+    }
+
+    let popup_h = (lines.len() as u16 + 2).min(area.height); // +2 for border
+    let popup_w = 36u16.min(area.width);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(popup_w)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_h)) / 2,
+        width: popup_w,
+        height: popup_h,
+    };
+    f.render_widget(ratatui::widgets::Clear, popup);
+
+    let name = nearby.name.clone().unwrap_or_else(|| advert.display_name());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(p.accent))
+        .title(Span::styled(
+            format!(" {} ", name),
+            Style::default().fg(p.accent).add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// "3h 05m" or "42m".
+fn hours_minutes(secs: u64) -> String {
+    let minutes = secs / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// "just now", "12 min ago", "3 h ago", "2 d ago".
+fn ago(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} min ago", secs / 60),
+        3600..86400 => format!("{} h ago", secs / 3600),
+        _ => format!("{} d ago", secs / 86400),
+    }
+}
+
 /// Ordered list of noise control modes shown in the TUI.
 /// Order: Transparency → Adaptive (if available) → Noise Cancellation → Off (if allowed).
 /// Must match the row→mode mapping in `events::activate_noise_row`.
@@ -665,6 +1081,85 @@ fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
 mod tests {
     use super::*;
 
+    #[test]
+    fn footer_hints_follow_rebound_keys() {
+        use crate::tui::app::AppEvent;
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let (cmd_tx, _cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = App::new(rx, cmd_tx);
+        app.handle_event(AppEvent::DeviceConnected {
+            mac: "AA:BB:CC:DD:EE:FF".into(),
+            name: "Pods".into(),
+            product_id: 0x2014,
+        });
+        app.keys = crate::tui::events::KeyBindings::from_config(&crate::config::KeysConfig {
+            quit: "x".into(),
+            transparency: "F5".into(),
+            ..Default::default()
+        });
+
+        let backend = ratatui::backend::TestBackend::new(120, 1);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|f| draw_footer(f, f.area(), &app)).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(text.contains("F5/2/3 noise"), "{}", text);
+        assert!(text.contains("x quit"), "{}", text);
+        assert!(!text.contains("q quit"), "{}", text);
+    }
+
+    #[test]
+    fn locate_meter_fills_with_signal() {
+        let advert = [0x07, 0x19, 0x01, 0x0e, 0x20, 0x2b, 0x87, 0x45, 0x01];
+        let nearby = |mac: Option<&str>, rssi: i16, distance| NearbyAirPods {
+            address: "5A:11:22:33:44:55".into(),
+            mac: mac.map(Into::into),
+            name: mac.map(|_| "Pods".into()),
+            advert: crate::bluetooth::proximity::parse(&advert).unwrap(),
+            distance: Some(distance),
+            rssi: Some(rssi),
+        };
+        let list = [
+            nearby(None, -85, Distance::Far),
+            nearby(Some("AA:BB:CC:DD:EE:FF"), -55, Distance::Close),
+        ];
+        let backend = ratatui::backend::TestBackend::new(60, 8);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal
+            .draw(|f| draw_locate_popup(f, f.area(), &list, &Palette::default()))
+            .unwrap();
+        let rows: Vec<String> = terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(60)
+            .map(|row| row.iter().map(|c| c.symbol()).collect())
+            .collect();
+        // Ours first: -55 dBm is 75%, 15 of 20 cells
+        assert!(rows[2].contains("Pods"), "{:?}", rows);
+        assert!(rows[3].contains(&format!(
+            "{}{}  hot (-55 dBm)",
+            "█".repeat(15),
+            "░".repeat(5)
+        )));
+        assert!(rows[4].contains("AirPods Pro"), "{:?}", rows);
+        assert!(rows[5].contains("cold (-85 dBm)"), "{:?}", rows);
+    }
+
+    #[test]
+    fn history_durations_read_naturally() {
+        assert_eq!(hours_minutes(42 * 60 + 59), "42m");
+        assert_eq!(hours_minutes(3 * 3600 + 5 * 60), "3h 05m");
+        assert_eq!(ago(30), "just now");
+        assert_eq!(ago(12 * 60), "12 min ago");
+        assert_eq!(ago(2 * 86400 + 5), "2 d ago");
+    }
+
     #[test]
     fn noise_mode_list_minimal() {
         let m = noise_mode_list(false, false);
@@ -713,6 +1208,15 @@ mod tests {
         assert_eq!(m[3], AirPodsNoiseControlMode::Off);
     }
 
+    #[test]
+    fn track_progress_formatting() {
+        assert_eq!(fmt_track_time(83_000_000), "1:23");
+        assert_eq!(fmt_track_time(3_725_000_000), "1:02:05");
+        assert_eq!(progress_bar(50, 100, 4), "━━──");
+        // Position past the end (stale length) fills the bar, no panic
+        assert_eq!(progress_bar(200, 100, 4), "━━━━");
+    }
+
     #[test]
     fn noise_mode_list_order_is_stable() {
         // Activate-noise-row in events.rs maps section_row index to this list.
@@ -1,5 +1,10 @@
 use std::io;
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Last content written to airpods-battery.env, to skip identical rewrites.
+static BATTERY_ENV: Mutex<Option<String>> = Mutex::new(None);
 
 pub fn runtime_dir() -> io::Result<PathBuf> {
     std::env::var_os("XDG_RUNTIME_DIR")
@@ -13,13 +18,19 @@ pub fn runtime_dir() -> io::Result<PathBuf> {
 }
 
 /// Write battery levels to `airpods-battery.env` in the runtime directory
-/// for external consumers (waybar, scripts).
+/// for external consumers (waybar, scripts). Unchanged levels are not
+/// rewritten.
 pub fn write_battery_env(
     left: Option<u8>,
     right: Option<u8>,
     case: Option<u8>,
     headphone: Option<u8>,
 ) {
+    let content = battery_env(left, right, case, headphone);
+    let mut last = BATTERY_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    if last.as_deref() == Some(content.as_str()) {
+        return;
+    }
     let dir = match runtime_dir() {
         Ok(d) => d,
         Err(e) => {
@@ -27,6 +38,18 @@ pub fn write_battery_env(
             return;
         }
     };
+    match std::fs::write(dir.join("airpods-battery.env"), &content) {
+        Ok(()) => *last = Some(content),
+        Err(e) => log::warn!("Failed to write airpods-battery.env: {}", e),
+    }
+}
+
+fn battery_env(
+    left: Option<u8>,
+    right: Option<u8>,
+    case: Option<u8>,
+    headphone: Option<u8>,
+) -> String {
     let mut content = String::new();
     for (key, val) in [
         ("LEFT", left),
@@ -38,9 +61,31 @@ pub fn write_battery_env(
             content.push_str(&format!("{key}={v}\n"));
         }
     }
-    if let Err(e) = std::fs::write(dir.join("airpods-battery.env"), content) {
-        log::warn!("Failed to write airpods-battery.env: {}", e);
-    }
+    content
+}
+
+/// Levels from `airpods-battery.env`, keyed by lowercase component name
+/// ("left", "right", "case", "headphone").
+pub fn read_battery_env() -> io::Result<Vec<(String, u8)>> {
+    let path = runtime_dir()?.join("airpods-battery.env");
+    Ok(parse_battery_env(&std::fs::read_to_string(path)?))
+}
+
+fn parse_battery_env(content: &str) -> Vec<(String, u8)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_lowercase(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `$XDG_STATE_HOME/airpods-tui`, for logs and history.
+pub fn state_dir() -> PathBuf {
+    let state_dir = std::env::var("XDG_STATE_HOME")
+        .unwrap_or_else(|_| format!("{}/.local/state", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(state_dir).join("airpods-tui")
 }
 
 pub fn get_devices_path() -> PathBuf {
@@ -50,3 +95,100 @@ pub fn get_devices_path() -> PathBuf {
         .join("airpods-tui")
         .join("devices.json")
 }
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn unix_now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Real uid of this process.
+pub fn current_uid() -> io::Result<u32> {
+    std::fs::metadata("/proc/self").map(|m| m.uid())
+}
+
+/// A group from /etc/group: gid and supplementary member names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupEntry {
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+/// Look up a group by name or numeric gid in /etc/group.
+pub fn lookup_group(name_or_gid: &str) -> Option<GroupEntry> {
+    let content = std::fs::read_to_string("/etc/group").ok()?;
+    find_group(&content, name_or_gid)
+}
+
+/// Login name for `uid` from /etc/passwd.
+pub fn user_name(uid: u32) -> Option<String> {
+    let content = std::fs::read_to_string("/etc/passwd").ok()?;
+    content.lines().find_map(|line| {
+        let mut f = line.split(':');
+        let name = f.next()?;
+        let uid_field = f.nth(1)?;
+        (uid_field.parse() == Ok(uid)).then(|| name.to_string())
+    })
+}
+
+fn find_group(content: &str, name_or_gid: &str) -> Option<GroupEntry> {
+    content.lines().find_map(|line| {
+        let mut f = line.split(':');
+        let name = f.next()?;
+        let gid: u32 = f.nth(1)?.parse().ok()?;
+        if name != name_or_gid && gid.to_string() != name_or_gid {
+            return None;
+        }
+        let members = f
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .collect();
+        Some(GroupEntry { gid, members })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: &str = "root:x:0:\naudio:x:995:alice,bob\nwheel:x:998:alice\n";
+
+    #[test]
+    fn find_group_by_name_or_gid() {
+        let audio = find_group(GROUP, "audio").unwrap();
+        assert_eq!(audio.gid, 995);
+        assert_eq!(audio.members, vec!["alice", "bob"]);
+        assert_eq!(find_group(GROUP, "998").unwrap().members, vec!["alice"]);
+        assert!(find_group(GROUP, "root").unwrap().members.is_empty());
+        assert!(find_group(GROUP, "video").is_none());
+    }
+
+    #[test]
+    fn battery_env_skips_unknown_levels() {
+        assert_eq!(
+            battery_env(Some(70), Some(80), None, None),
+            "LEFT=70\nRIGHT=80\n"
+        );
+        assert_eq!(battery_env(None, None, None, Some(55)), "HEADPHONE=55\n");
+    }
+
+    #[test]
+    fn battery_env_round_trips() {
+        let content = battery_env(Some(70), None, Some(5), None);
+        assert_eq!(
+            parse_battery_env(&(content + "BOGUS\nRIGHT=x\n")),
+            vec![("left".to_string(), 70), ("case".to_string(), 5)]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! `volume` subcommand: change the AirPods sink's volume (`+5`, `-5`,
+//! `set 40`) rather than whichever sink is the default.
+
+use crate::config::{self, Config};
+use crate::devices::store;
+use std::io;
+
+/// Upper bound for relative steps and `set`; PulseAudio allows boosting
+/// past 100% but that clips on the buds.
+const MAX_VOLUME: u32 = 100;
+
+#[derive(clap::Args)]
+pub struct VolumeArgs {
+    #[arg(
+        value_name = "+N|-N|set",
+        allow_hyphen_values = true,
+        help = "Relative step in percent, or `set` followed by a level"
+    )]
+    change: String,
+    #[arg(value_name = "LEVEL", help = "Level in percent for `set`")]
+    level: Option<u32>,
+    #[arg(
+        long,
+        value_name = "MAC|NAME",
+        help = "Device from devices.json (default: the first one with a sink)"
+    )]
+    device: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VolumeChange {
+    Step(i32),
+    Set(u32),
+}
+
+impl VolumeChange {
+    fn parse(change: &str, level: Option<u32>) -> Result<Self, String> {
+        match (change, level) {
+            ("set", Some(level)) => Ok(VolumeChange::Set(level.min(MAX_VOLUME))),
+            ("set", None) => Err("`volume set` needs a level, e.g. `volume set 40`".into()),
+            (step, None) if step.starts_with(['+', '-']) => step
+                .parse()
+                .map(VolumeChange::Step)
+                .map_err(|_| format!("bad volume step '{}'", step)),
+            _ => Err(format!("expected +N, -N or `set N`, got '{}'", change)),
+        }
+    }
+
+    fn apply(self, current: u32) -> u32 {
+        match self {
+            VolumeChange::Set(level) => level,
+            VolumeChange::Step(step) => {
+                (current as i64 + step as i64).clamp(0, MAX_VOLUME as i64) as u32
+            }
+        }
+    }
+}
+
+pub async fn run(args: VolumeArgs, config: &Config) -> io::Result<()> {
+    let change = VolumeChange::parse(&args.change, args.level)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let devices = store::load();
+    let macs = match &args.device {
+        Some(query) => vec![
+            crate::bluetooth::link::resolve(&devices, query)?
+                .0
+                .to_string(),
+        ],
+        None => {
+            let mut macs: Vec<String> = devices.keys().cloned().collect();
+            macs.sort();
+            macs
+        }
+    };
+
+    let mut last_err = None;
+    for mac in &macs {
+        match crate::media_controller::change_sink_volume(mac, |v| change.apply(v)).await {
+            Ok(level) => {
+                config::run_template_cmd(&config.volume_osd_command, "+0");
+                println!("{}", level);
+                return Ok(());
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(match last_err {
+        Some(e) if macs.len() == 1 => io::Error::other(e),
+        _ => io::Error::new(io::ErrorKind::NotFound, "no AirPods audio sink found"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_and_set() {
+        assert_eq!(VolumeChange::parse("+5", None), Ok(VolumeChange::Step(5)));
+        assert_eq!(VolumeChange::parse("-5", None), Ok(VolumeChange::Step(-5)));
+        assert_eq!(
+            VolumeChange::parse("set", Some(40)),
+            Ok(VolumeChange::Set(40))
+        );
+        assert_eq!(
+            VolumeChange::parse("set", Some(250)),
+            Ok(VolumeChange::Set(100))
+        );
+        assert!(VolumeChange::parse("set", None).is_err());
+        assert!(VolumeChange::parse("40", None).is_err());
+        assert!(VolumeChange::parse("+x", None).is_err());
+
+        assert_eq!(VolumeChange::Step(-5).apply(3), 0);
+        assert_eq!(VolumeChange::Step(5).apply(98), 100);
+        assert_eq!(VolumeChange::Step(5).apply(40), 45);
+    }
+}
@@ -0,0 +1,304 @@
+//! JSON for waybar's `custom` module (`--waybar` / `--waybar-watch`), and
+//! the `--click` actions its on-click/on-scroll handlers call.
+//!
+//! `text` is the battery view, built from the `[waybar]` icon set; `alt` is
+//! the noise-mode slug, so `format-alt` (toggled by clicking the module) can
+//! switch to a noise-mode view and `format-icons` can key off it. `class`
+//! adds `warning`/`critical` from the `[waybar]` thresholds and `charging`
+//! while any component charges.
+
+use crate::bluetooth::aacp::{BatteryStatus, ControlCommandIdentifiers};
+use crate::config::WaybarConfig;
+use crate::devices::enums::AirPodsNoiseControlMode;
+use crate::status::OUTPUT_VERSION;
+use crate::tui::app::{AirPodsDeviceState, DataSource, DeviceCommand};
+
+/// Volume change per scroll notch, in percent.
+pub const VOLUME_STEP: i8 = 5;
+
+/// Actions for `--click`, sent to the daemon over IPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ClickAction {
+    /// Switch between Noise Cancellation and Transparency
+    ToggleAnc,
+    /// Raise system volume by one step
+    VolumeUp,
+    /// Lower system volume by one step
+    VolumeDown,
+}
+
+/// The command `action` maps to for this device, if it applies.
+pub fn click_command(action: ClickAction, state: &AirPodsDeviceState) -> Option<DeviceCommand> {
+    match action {
+        ClickAction::ToggleAnc if state.has_anc => {
+            let next = if state.listening_mode == AirPodsNoiseControlMode::NoiseCancellation {
+                AirPodsNoiseControlMode::Transparency
+            } else {
+                AirPodsNoiseControlMode::NoiseCancellation
+            };
+            Some(DeviceCommand::ControlCommand(
+                ControlCommandIdentifiers::ListeningMode,
+                vec![next.to_byte()],
+            ))
+        }
+        ClickAction::ToggleAnc => None,
+        ClickAction::VolumeUp => Some(DeviceCommand::AdjustVolume(VOLUME_STEP)),
+        ClickAction::VolumeDown => Some(DeviceCommand::AdjustVolume(-VOLUME_STEP)),
+    }
+}
+
+/// Battery severity for bar styling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// What any bar shows for one device; `render` and the swaybar block are
+/// both built from this.
+pub struct Status {
+    pub text: String,
+    pub tooltip: String,
+    /// Lowest bud/headphone level, 0 when unknown.
+    pub percentage: u8,
+    pub level: Level,
+    /// Components charging right now ("left", "right", "case", "headphone").
+    pub charging: Vec<&'static str>,
+}
+
+pub fn status(s: &AirPodsDeviceState, cfg: &WaybarConfig) -> Status {
+    let model_name = s.model.as_deref().unwrap_or(&s.name);
+    let min_bat = [s.battery_left, s.battery_right, s.battery_headphone]
+        .iter()
+        .filter_map(|b| b.as_ref().map(|(l, _)| *l))
+        .min();
+    let percentage = min_bat.unwrap_or(0);
+
+    let components = [
+        ("left", &cfg.icon_left, "L: ", s.battery_left),
+        ("right", &cfg.icon_right, "R: ", s.battery_right),
+        ("case", &cfg.icon_case, "C: ", case_level(s.battery_case)),
+        ("headphone", &cfg.icon_headphone, "", s.battery_headphone),
+    ];
+    let mut text_parts = Vec::new();
+    let mut tooltip_parts = vec![model_name.to_string()];
+    let mut lowest_discharging: Option<u8> = None;
+    let mut charging = Vec::new();
+    for (name, icon, label, battery) in components {
+        let Some((level, status)) = battery else {
+            continue;
+        };
+        if status == BatteryStatus::Charging {
+            charging.push(name);
+        } else {
+            lowest_discharging = Some(lowest_discharging.map_or(level, |l| l.min(level)));
+        }
+        let charging = if status == BatteryStatus::Charging {
+            cfg.icon_charging.as_str()
+        } else {
+            ""
+        };
+        text_parts.push(format!("{}{}%{}", icon, level, charging));
+        tooltip_parts.push(format!("{}{}%{}", label, level, charging));
+    }
+    if s.has_anc {
+        tooltip_parts.push(s.listening_mode.to_string());
+    }
+    let text = if text_parts.is_empty() {
+        format!("{}%", percentage)
+    } else {
+        text_parts.join(" ")
+    };
+    let level = match lowest_discharging {
+        Some(l) if l <= cfg.critical => Level::Critical,
+        Some(l) if l <= cfg.warning => Level::Warning,
+        _ => Level::Normal,
+    };
+
+    Status {
+        text,
+        tooltip: tooltip_parts.join("\n"),
+        percentage,
+        level,
+        charging,
+    }
+}
+
+/// Render one waybar line for the selected device, or the disconnected
+/// placeholder when there is none.
+pub fn render(state: Option<&AirPodsDeviceState>, cfg: &WaybarConfig) -> String {
+    let Some(s) = state else {
+        return serde_json::json!({
+            "version": OUTPUT_VERSION,
+            "text": "",
+            "alt": "disconnected",
+            "tooltip": "No AirPods",
+            "class": "disconnected",
+            "percentage": 0,
+        })
+        .to_string();
+    };
+
+    let st = status(s, cfg);
+    let mut class = vec!["connected"];
+    match st.level {
+        Level::Critical => class.push("critical"),
+        Level::Warning => class.push("warning"),
+        Level::Normal => {}
+    }
+    if !st.charging.is_empty() {
+        class.push("charging");
+    }
+    let mut tooltip = st.tooltip;
+    if let Some(age) = s.stale_for(DataSource::Aacp, crate::utils::unix_now()) {
+        class.push("stale");
+        tooltip.push_str(&format!("\nNo update for {} min", age / 60));
+    }
+    let level = |b: Option<(u8, BatteryStatus)>| b.map(|(l, _)| l);
+
+    serde_json::json!({
+        "version": OUTPUT_VERSION,
+        "text": st.text,
+        "alt": noise_mode_slug(&s.listening_mode),
+        "tooltip": tooltip,
+        "class": class,
+        "percentage": st.percentage,
+        "left": level(s.battery_left),
+        "right": level(s.battery_right),
+        "case": level(case_level(s.battery_case)),
+        "headphone": level(s.battery_headphone),
+        "charging": st.charging,
+        "noise_mode": s.has_anc.then(|| noise_mode_slug(&s.listening_mode)),
+    })
+    .to_string()
+}
+
+/// A closed case reports Disconnected with a stale level; hide it.
+fn case_level(case: Option<(u8, BatteryStatus)>) -> Option<(u8, BatteryStatus)> {
+    case.filter(|(_, status)| *status != BatteryStatus::Disconnected)
+}
+
+/// Stable key for `format-icons` and `format-alt`.
+pub(crate) fn noise_mode_slug(mode: &AirPodsNoiseControlMode) -> &'static str {
+    match mode {
+        AirPodsNoiseControlMode::Off => "off",
+        AirPodsNoiseControlMode::NoiseCancellation => "anc",
+        AirPodsNoiseControlMode::Transparency => "transparency",
+        AirPodsNoiseControlMode::Adaptive => "adaptive",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> serde_json::Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn pods() -> AirPodsDeviceState {
+        let mut s = AirPodsDeviceState::new("Pods".into());
+        s.battery_left = Some((80, BatteryStatus::NotCharging));
+        s.battery_right = Some((70, BatteryStatus::Charging));
+        s.battery_case = Some((50, BatteryStatus::Disconnected));
+        s
+    }
+
+    #[test]
+    fn text_uses_configured_icons_and_charging_glyph() {
+        let cfg = WaybarConfig {
+            icon_left: "<".into(),
+            icon_right: ">".into(),
+            icon_charging: "+".into(),
+            ..WaybarConfig::default()
+        };
+        let v = parse(&render(Some(&pods()), &cfg));
+        assert_eq!(v["text"], "<80% >70%+");
+        assert_eq!(v["percentage"], 70);
+        assert_eq!(v["class"], serde_json::json!(["connected", "charging"]));
+        assert_eq!(v["charging"], serde_json::json!(["right"]));
+        assert_eq!(v["left"], 80);
+        assert!(v["case"].is_null());
+    }
+
+    #[test]
+    fn low_discharging_component_sets_class() {
+        let mut s = pods();
+        s.battery_left = Some((15, BatteryStatus::NotCharging));
+        s.battery_right = Some((70, BatteryStatus::NotCharging));
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["class"], serde_json::json!(["connected", "warning"]));
+
+        s.battery_left = Some((8, BatteryStatus::NotCharging));
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["class"], serde_json::json!(["connected", "critical"]));
+
+        // Charging components never warn
+        s.battery_left = Some((8, BatteryStatus::Charging));
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["class"], serde_json::json!(["connected", "charging"]));
+    }
+
+    #[test]
+    fn old_aacp_data_is_stale() {
+        let mut s = pods();
+        s.battery_right = Some((70, BatteryStatus::NotCharging));
+        s.last_seen
+            .insert(DataSource::Aacp, crate::utils::unix_now());
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["class"], serde_json::json!(["connected"]));
+        s.last_seen.insert(DataSource::Aacp, 0);
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["class"], serde_json::json!(["connected", "stale"]));
+    }
+
+    #[test]
+    fn alt_carries_noise_mode() {
+        let mut s = pods();
+        s.listening_mode = AirPodsNoiseControlMode::Transparency;
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert_eq!(v["alt"], "transparency");
+        assert_eq!(v["noise_mode"], "transparency");
+        // Only models with noise control report a mode
+        s.has_anc = false;
+        let v = parse(&render(Some(&s), &WaybarConfig::default()));
+        assert!(v["noise_mode"].is_null());
+    }
+
+    #[test]
+    fn toggle_anc_flips_between_anc_and_transparency() {
+        let mut s = pods();
+        s.has_anc = true;
+        s.listening_mode = AirPodsNoiseControlMode::NoiseCancellation;
+        assert!(matches!(
+            click_command(ClickAction::ToggleAnc, &s),
+            Some(DeviceCommand::ControlCommand(ControlCommandIdentifiers::ListeningMode, ref v))
+                if v == &[0x03]
+        ));
+        s.listening_mode = AirPodsNoiseControlMode::Adaptive;
+        assert!(matches!(
+            click_command(ClickAction::ToggleAnc, &s),
+            Some(DeviceCommand::ControlCommand(_, ref v)) if v == &[0x02]
+        ));
+        s.has_anc = false;
+        assert!(click_command(ClickAction::ToggleAnc, &s).is_none());
+    }
+
+    #[test]
+    fn scroll_steps_volume() {
+        assert!(matches!(
+            click_command(ClickAction::VolumeDown, &pods()),
+            Some(DeviceCommand::AdjustVolume(-5))
+        ));
+    }
+
+    #[test]
+    fn disconnected_placeholder() {
+        let v = parse(&render(None, &WaybarConfig::default()));
+        assert_eq!(v["class"], "disconnected");
+        assert_eq!(v["version"], OUTPUT_VERSION);
+        assert_eq!(v["alt"], "disconnected");
+        assert_eq!(v["text"], "");
+    }
+}